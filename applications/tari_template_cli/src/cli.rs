@@ -0,0 +1,130 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+#[clap(propagate_version = true)]
+pub(crate) struct Cli {
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Create a new template crate
+    New(NewArgs),
+    /// Compile a template crate to WASM
+    Build(BuildArgs),
+    /// Print the ABI definition of a compiled template
+    Inspect(InspectArgs),
+    /// Check that a compiled template only uses deterministic instructions
+    Validate(ValidateArgs),
+    /// Call a template function or component method against a local state store
+    #[clap(subcommand)]
+    Run(RunCommand),
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct NewArgs {
+    /// The name of the template crate
+    pub name: String,
+    /// The directory in which to create the crate. Defaults to a directory with the template name.
+    #[clap(long)]
+    pub path: Option<PathBuf>,
+    /// Path to a local tari-dan checkout to use for the template library dependencies instead of git
+    #[clap(long)]
+    pub tari_dan_path: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct BuildArgs {
+    /// The template crate directory
+    #[clap(default_value = ".")]
+    pub path: PathBuf,
+    /// Cargo features to enable
+    #[clap(long)]
+    pub features: Vec<String>,
+    /// Where to write the compiled WASM. Defaults to <crate name>.wasm in the current directory.
+    #[clap(long, short = 'o')]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct InspectArgs {
+    /// Path to a compiled template
+    pub wasm: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ValidateArgs {
+    /// Path to a compiled template
+    pub wasm: PathBuf,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum RunCommand {
+    /// Call a template function, such as a constructor
+    Function(RunFunctionArgs),
+    /// Call a method on an existing component
+    Method(RunMethodArgs),
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct RunOptions {
+    /// Compiled templates (.wasm) or template crate directories to load
+    #[clap(long = "template", short = 't', required = true)]
+    pub templates: Vec<PathBuf>,
+    /// Directory of the local state store. Component state is kept here between runs.
+    #[clap(long, default_value = "./data/template_state")]
+    pub state_dir: PathBuf,
+    /// Execute in a read-only runtime, as a view call would be
+    #[clap(long)]
+    pub read_only: bool,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct RunFunctionArgs {
+    #[clap(flatten)]
+    pub options: RunOptions,
+    /// The name of the template
+    pub template_name: String,
+    /// The name of the function
+    pub function: String,
+    /// Function arguments, parsed according to the template ABI
+    pub args: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct RunMethodArgs {
+    #[clap(flatten)]
+    pub options: RunOptions,
+    /// The hex-encoded id of the component
+    pub component_id: String,
+    /// The name of the method
+    pub method: String,
+    /// Method arguments, parsed according to the template ABI
+    pub args: Vec<String>,
+}
@@ -0,0 +1,226 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use tari_dan_engine::{
+    crypto::create_key_pair,
+    instruction::{Instruction, InstructionBuilder, InstructionProcessor},
+    packager::{Package, PackageModuleLoader},
+    runtime::RuntimeInterface,
+    wasm::{compile::compile_template, validate_determinism, LoadedWasmModule, WasmModule},
+};
+use tari_template_abi::{FunctionDef, ReceiverKind, TemplateDef};
+
+use crate::{
+    cli::{BuildArgs, InspectArgs, NewArgs, RunCommand, ValidateArgs},
+    runtime_interface::{open_local_runtime_interface, LocalRuntimeInterface},
+    scaffold::create_template_crate,
+    value::{format_value, parse_args, parse_component_id},
+};
+
+pub fn new(args: NewArgs) -> anyhow::Result<()> {
+    let dir = args.path.unwrap_or_else(|| PathBuf::from(&args.name));
+    create_template_crate(&args.name, &dir, args.tari_dan_path.as_deref())?;
+    println!("Created template '{}' in {}", args.name, dir.display());
+    Ok(())
+}
+
+pub fn build(args: BuildArgs) -> anyhow::Result<()> {
+    let features = args.features.iter().map(String::as_str).collect::<Vec<_>>();
+    let module = compile_template(&args.path, &features)
+        .with_context(|| format!("Failed to compile template in {}", args.path.display()))?;
+
+    let output = match args.output {
+        Some(output) => output,
+        None => {
+            let path = args.path.canonicalize()?;
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| anyhow!("Unable to determine the crate name of {}", path.display()))?;
+            PathBuf::from(format!("{}.wasm", name.replace('-', "_")))
+        },
+    };
+    fs::write(&output, module.code()).with_context(|| format!("Failed to write {}", output.display()))?;
+    println!("Wrote {} ({} bytes)", output.display(), module.code().len());
+    Ok(())
+}
+
+pub fn inspect(args: InspectArgs) -> anyhow::Result<()> {
+    let module = load_wasm(&args.wasm)?;
+    print_template_def(module.template_def());
+    Ok(())
+}
+
+pub fn validate(args: ValidateArgs) -> anyhow::Result<()> {
+    let code = fs::read(&args.wasm).with_context(|| format!("Failed to read {}", args.wasm.display()))?;
+    validate_determinism(&code)?;
+    println!("{} is deterministic", args.wasm.display());
+    Ok(())
+}
+
+pub fn run(command: RunCommand) -> anyhow::Result<()> {
+    match command {
+        RunCommand::Function(args) => {
+            let package = load_package(&args.options.templates)?;
+            let runtime_interface = open_local_runtime_interface(&args.options.state_dir)
+                .with_context(|| format!("Failed to open state store at {}", args.options.state_dir.display()))?;
+            let module = package
+                .get_module_by_name(&args.template_name)
+                .ok_or_else(|| anyhow!("Template '{}' not found", args.template_name))?;
+            let function_def = find_function(module, &args.function)?;
+            let instruction = Instruction::CallFunction {
+                package_id: package.id(),
+                template: args.template_name,
+                function: args.function,
+                args: parse_args(&function_def.arguments, &args.args)?,
+            };
+            execute(
+                runtime_interface,
+                package,
+                instruction,
+                &function_def,
+                args.options.read_only,
+            )
+        },
+        RunCommand::Method(args) => {
+            let package = load_package(&args.options.templates)?;
+            let runtime_interface = open_local_runtime_interface(&args.options.state_dir)
+                .with_context(|| format!("Failed to open state store at {}", args.options.state_dir.display()))?;
+            let component_id = parse_component_id(&args.component_id)?;
+            let component = runtime_interface
+                .get_component(&component_id)
+                .with_context(|| format!("Failed to load component {}", component_id))?;
+            let module = package
+                .get_module_by_name(&component.module_name)
+                .ok_or_else(|| anyhow!("Template '{}' not found", component.module_name))?;
+            let function_def = find_function(module, &args.method)?;
+            if function_def.receiver == ReceiverKind::None {
+                return Err(anyhow!(
+                    "'{}' is a function of template '{}', use 'run function' to call it",
+                    args.method,
+                    component.module_name
+                ));
+            }
+            // The engine passes the component as the first argument, so it is not given on the command line
+            let method_args = function_def.arguments.get(1..).unwrap_or_default();
+            let instruction = Instruction::CallMethod {
+                package_id: package.id(),
+                component_id,
+                method: args.method,
+                args: parse_args(method_args, &args.args)?,
+            };
+            execute(
+                runtime_interface,
+                package,
+                instruction,
+                &function_def,
+                args.options.read_only,
+            )
+        },
+    }
+}
+
+fn execute(
+    runtime_interface: LocalRuntimeInterface,
+    package: Package,
+    instruction: Instruction,
+    function_def: &FunctionDef,
+    read_only: bool,
+) -> anyhow::Result<()> {
+    let processor = InstructionProcessor::new(runtime_interface, package);
+    let (secret_key, _) = create_key_pair();
+    let instruction_set = InstructionBuilder::new()
+        .add_instruction(instruction)
+        .sign(&secret_key)
+        .build();
+
+    let results = if read_only {
+        processor.execute_read_only(instruction_set)?
+    } else {
+        processor.execute(instruction_set)?
+    };
+    let result = results.first().ok_or_else(|| anyhow!("No result returned"))?;
+    println!("{}", format_value(&function_def.output, &result.raw)?);
+    Ok(())
+}
+
+fn find_function(module: &LoadedWasmModule, name: &str) -> anyhow::Result<FunctionDef> {
+    module
+        .find_func_by_name(name)
+        .cloned()
+        .ok_or_else(|| anyhow!("Function '{}' not found in template '{}'", name, module.template_name()))
+}
+
+/// Reads a compiled template, or compiles it first if the path is a template crate directory
+fn read_module(path: &Path) -> anyhow::Result<WasmModule> {
+    if path.is_dir() {
+        return compile_template(path, &[])
+            .with_context(|| format!("Failed to compile template in {}", path.display()));
+    }
+    let code = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(WasmModule::from_code(code))
+}
+
+fn load_wasm(path: &Path) -> anyhow::Result<LoadedWasmModule> {
+    Ok(read_module(path)?.load_module()?)
+}
+
+fn load_package(paths: &[PathBuf]) -> anyhow::Result<Package> {
+    let mut builder = Package::builder();
+    for path in paths {
+        builder.add_wasm_module(read_module(path)?);
+    }
+    Ok(builder.build()?)
+}
+
+fn print_template_def(template: &TemplateDef) {
+    println!(
+        "template {} (ABI version {})",
+        template.template_name, template.abi_version
+    );
+    for function in &template.functions {
+        let (receiver, arguments) = match function.receiver {
+            ReceiverKind::None => (None, &function.arguments[..]),
+            ReceiverKind::Ref => (
+                Some("&self".to_string()),
+                function.arguments.get(1..).unwrap_or_default(),
+            ),
+            ReceiverKind::Mut => (
+                Some("&mut self".to_string()),
+                function.arguments.get(1..).unwrap_or_default(),
+            ),
+        };
+        let params = receiver
+            .into_iter()
+            .chain(arguments.iter().map(|ty| format!("{:?}", ty)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let view = if function.is_view { "#[view] " } else { "" };
+        println!("  {}fn {}({}) -> {:?}", view, function.name, params, function.output);
+    }
+}
@@ -0,0 +1,146 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Context};
+
+const TARI_DAN_GIT_URL: &str = "https://github.com/tari-project/tari-dan.git";
+const TEMPLATE_CRATES: &[&str] = &["tari_template_abi", "tari_template_lib", "tari_template_macros"];
+
+/// Writes a new template crate to `dir`. The template library crates are taken from `tari_dan_path` if given,
+/// otherwise from git.
+pub fn create_template_crate(name: &str, dir: &Path, tari_dan_path: Option<&Path>) -> anyhow::Result<()> {
+    if dir.exists() {
+        return Err(anyhow!("Destination {} already exists", dir.display()));
+    }
+    fs::create_dir_all(dir.join("src")).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    fs::write(dir.join("Cargo.toml"), cargo_toml(name, tari_dan_path))?;
+    fs::write(dir.join("src").join("lib.rs"), lib_rs(name))?;
+    fs::write(dir.join(".gitignore"), "/target\n*.wasm\n")?;
+    Ok(())
+}
+
+fn cargo_toml(name: &str, tari_dan_path: Option<&Path>) -> String {
+    let dependencies = TEMPLATE_CRATES
+        .iter()
+        .map(|krate| match tari_dan_path {
+            Some(path) => format!(
+                "{} = {{ path = \"{}\" }}",
+                krate,
+                path.join("dan_layer").join(&krate["tari_".len()..]).display()
+            ),
+            None => format!("{} = {{ git = \"{}\" }}", krate, TARI_DAN_GIT_URL),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"[workspace]
+[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+{dependencies}
+
+[profile.release]
+opt-level = 's'     # Optimize for size.
+lto = true          # Enable Link Time Optimization.
+codegen-units = 1   # Reduce number of codegen units to increase optimizations.
+panic = 'abort'     # Abort on panic.
+strip = "debuginfo" # Strip debug info.
+
+[lib]
+crate-type = ["cdylib", "lib"]
+"#,
+        name = name,
+        dependencies = dependencies
+    )
+}
+
+fn lib_rs(name: &str) -> String {
+    let struct_name = to_pascal_case(name);
+    format!(
+        r#"use tari_template_macros::template;
+
+#[template]
+mod {module}_template {{
+    pub struct {struct_name} {{
+        pub value: u32,
+    }}
+
+    impl {struct_name} {{
+        pub fn new() -> Self {{
+            Self {{ value: 0 }}
+        }}
+
+        pub fn set(&mut self, value: u32) {{
+            self.value = value;
+        }}
+
+        #[view]
+        pub fn get(&self) -> u32 {{
+            self.value
+        }}
+    }}
+}}
+"#,
+        module = name.replace('-', "_").to_lowercase(),
+        struct_name = struct_name
+    )
+}
+
+fn to_pascal_case(name: &str) -> String {
+    name.split(['-', '_'])
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut chars = s.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_converts_crate_names_to_struct_names() {
+        assert_eq!(to_pascal_case("my-counter"), "MyCounter");
+        assert_eq!(to_pascal_case("my_counter"), "MyCounter");
+        assert_eq!(to_pascal_case("counter"), "Counter");
+    }
+
+    #[test]
+    fn it_uses_local_paths_when_given() {
+        let toml = cargo_toml("counter", Some(Path::new("/src/tari-dan")));
+        assert!(toml.contains(r#"tari_template_lib = { path = "/src/tari-dan/dan_layer/template_lib" }"#));
+        let toml = cargo_toml("counter", None);
+        assert!(toml.contains(&format!(r#"tari_template_macros = {{ git = "{}" }}"#, TARI_DAN_GIT_URL)));
+    }
+}
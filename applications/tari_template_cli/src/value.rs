@@ -0,0 +1,121 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use tari_template_abi::{decode, encode, Encode, Type};
+use tari_template_lib::models::ComponentId;
+
+/// Parses a command line argument as the given ABI type and returns the encoded value
+pub fn parse_arg(ty: &Type, arg: &str) -> anyhow::Result<Vec<u8>> {
+    fn parse_and_encode<T: FromStr + Encode>(arg: &str) -> anyhow::Result<Vec<u8>>
+    where T::Err: std::error::Error + Send + Sync + 'static {
+        let value = arg.parse::<T>()?;
+        Ok(encode(&value)?)
+    }
+
+    let encoded = match ty {
+        Type::Unit => encode(&())?,
+        Type::Bool => parse_and_encode::<bool>(arg)?,
+        Type::I8 => parse_and_encode::<i8>(arg)?,
+        Type::I16 => parse_and_encode::<i16>(arg)?,
+        Type::I32 => parse_and_encode::<i32>(arg)?,
+        Type::I64 => parse_and_encode::<i64>(arg)?,
+        Type::I128 => parse_and_encode::<i128>(arg)?,
+        Type::U8 => parse_and_encode::<u8>(arg)?,
+        Type::U16 => parse_and_encode::<u16>(arg)?,
+        Type::U32 => parse_and_encode::<u32>(arg)?,
+        Type::U64 => parse_and_encode::<u64>(arg)?,
+        Type::U128 => parse_and_encode::<u128>(arg)?,
+        Type::String => encode(&arg.to_string())?,
+        Type::ComponentId => encode(&parse_component_id(arg)?)?,
+    };
+    Ok(encoded)
+}
+
+pub fn parse_component_id(s: &str) -> anyhow::Result<ComponentId> {
+    ComponentId::from_hex(s).map_err(|_| anyhow!("'{}' is not a valid hex-encoded component id", s))
+}
+
+/// Decodes a value returned from a template and formats it for display
+pub fn format_value(ty: &Type, raw: &[u8]) -> anyhow::Result<String> {
+    let formatted = match ty {
+        Type::Unit => "()".to_string(),
+        Type::Bool => decode::<bool>(raw)?.to_string(),
+        Type::I8 => decode::<i8>(raw)?.to_string(),
+        Type::I16 => decode::<i16>(raw)?.to_string(),
+        Type::I32 => decode::<i32>(raw)?.to_string(),
+        Type::I64 => decode::<i64>(raw)?.to_string(),
+        Type::I128 => decode::<i128>(raw)?.to_string(),
+        Type::U8 => decode::<u8>(raw)?.to_string(),
+        Type::U16 => decode::<u16>(raw)?.to_string(),
+        Type::U32 => decode::<u32>(raw)?.to_string(),
+        Type::U64 => decode::<u64>(raw)?.to_string(),
+        Type::U128 => decode::<u128>(raw)?.to_string(),
+        Type::String => format!("{:?}", decode::<String>(raw)?),
+        Type::ComponentId => decode::<ComponentId>(raw)?.to_string(),
+    };
+    Ok(formatted)
+}
+
+/// Parses the arguments of a call, checking that the number of arguments matches the ABI
+pub fn parse_args(types: &[Type], args: &[String]) -> anyhow::Result<Vec<Vec<u8>>> {
+    if types.len() != args.len() {
+        return Err(anyhow!("Expected {} argument(s) but got {}", types.len(), args.len()));
+    }
+    types
+        .iter()
+        .zip(args)
+        .enumerate()
+        .map(|(i, (ty, arg))| parse_arg(ty, arg).with_context(|| format!("Invalid argument {} ({:?})", i, ty)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_round_trips_values() {
+        let cases = [
+            (Type::U32, "123"),
+            (Type::I64, "-5"),
+            (Type::Bool, "true"),
+            (
+                Type::ComponentId,
+                "0101010101010101010101010101010101010101010101010101010101010101",
+            ),
+        ];
+        for (ty, arg) in &cases {
+            let encoded = parse_arg(ty, arg).unwrap();
+            assert_eq!(format_value(ty, &encoded).unwrap(), *arg);
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_args() {
+        parse_arg(&Type::U8, "256").unwrap_err();
+        parse_arg(&Type::ComponentId, "abc").unwrap_err();
+        parse_args(&[Type::U32], &[]).unwrap_err();
+    }
+}
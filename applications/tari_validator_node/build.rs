@@ -27,5 +27,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .emit_rerun_if_changed_directives()
         .compile()
         .unwrap();
+    tonic_build::configure()
+        .build_client(false)
+        .build_server(true)
+        .compile(&["proto/grpc/misbehaviour.proto", "proto/grpc/state_proof.proto"], &[
+            "proto/grpc",
+        ])?;
     Ok(())
 }
@@ -20,7 +20,9 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
 use tari_app_utilities::common_cli_args::CommonCliArgs;
 use tari_common::configuration::{ConfigOverrideProvider, Network};
 
@@ -36,6 +38,36 @@ pub(crate) struct Cli {
     /// Supply a network (overrides existing configuration)
     #[clap(long, env = "TARI_NETWORK")]
     pub network: Option<String>,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Export the committed state of a contract to a file
+    ExportState(ExportStateArgs),
+    /// Replace the state of a contract with a state export. The validator node must not be running.
+    ImportState(ImportStateArgs),
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ExportStateArgs {
+    /// The contract ID, as hex
+    #[clap(long)]
+    pub contract_id: String,
+    /// The file to write the state export to
+    #[clap(long, short = 'o')]
+    pub output: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ImportStateArgs {
+    /// The contract ID, as hex
+    #[clap(long)]
+    pub contract_id: String,
+    /// The state export file
+    #[clap(long, short = 'i')]
+    pub input: PathBuf,
 }
 
 impl ConfigOverrideProvider for Cli {
@@ -0,0 +1,99 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+};
+
+use tari_common::exit_codes::{ExitCode, ExitError};
+use tari_common_types::types::FixedHash;
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_dan_core::storage::DbFactory;
+use tari_dan_storage_sqlite::SqliteDbFactory;
+
+use crate::{
+    cli::{Command, ExportStateArgs, ImportStateArgs},
+    config::ApplicationConfig,
+    data_dir_lock::DataDirLock,
+};
+
+pub(crate) fn run_command(command: &Command, config: &ApplicationConfig) -> Result<(), ExitError> {
+    match command {
+        Command::ExportState(args) => export_state(args, config),
+        Command::ImportState(args) => import_state(args, config),
+    }
+}
+
+fn export_state(args: &ExportStateArgs, config: &ApplicationConfig) -> Result<(), ExitError> {
+    let contract_id = parse_contract_id(&args.contract_id)?;
+    let db_factory = SqliteDbFactory::new(config.validator_node.data_dir.clone());
+    let state_db = db_factory
+        .get_state_db(&contract_id)
+        .map_err(|e| ExitError::new(ExitCode::DatabaseError, e))?
+        .ok_or_else(|| {
+            ExitError::new(
+                ExitCode::DatabaseError,
+                format!("No state found for contract {}", contract_id.to_hex()),
+            )
+        })?;
+
+    let file = File::create(&args.output).map_err(|e| ExitError::new(ExitCode::IOError, e))?;
+    let header = state_db
+        .export_state(BufWriter::new(file))
+        .map_err(|e| ExitError::new(ExitCode::DatabaseError, e))?;
+    println!(
+        "Exported the state of contract {} at height {} (state root {}) to {}",
+        contract_id.to_hex(),
+        header.height,
+        FixedHash::from(header.state_root).to_hex(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+fn import_state(args: &ImportStateArgs, config: &ApplicationConfig) -> Result<(), ExitError> {
+    let contract_id = parse_contract_id(&args.contract_id)?;
+    // Replacing the state underneath a running node would corrupt it, so refuse to run while the node holds the lock
+    let _data_dir_lock = DataDirLock::acquire(&config.validator_node.data_dir)?;
+    let db_factory = SqliteDbFactory::new(config.validator_node.data_dir.clone());
+    let state_db = db_factory
+        .get_or_create_state_db(&contract_id)
+        .map_err(|e| ExitError::new(ExitCode::DatabaseError, e))?;
+
+    let file = File::open(&args.input).map_err(|e| ExitError::new(ExitCode::IOError, e))?;
+    let header = state_db
+        .import_state(BufReader::new(file))
+        .map_err(|e| ExitError::new(ExitCode::DatabaseError, e))?;
+    println!(
+        "Imported the state of contract {} at height {} (state root {})",
+        contract_id.to_hex(),
+        header.height,
+        FixedHash::from(header.state_root).to_hex()
+    );
+    Ok(())
+}
+
+fn parse_contract_id(contract_id: &str) -> Result<FixedHash, ExitError> {
+    FixedHash::from_hex(contract_id)
+        .map_err(|e| ExitError::new(ExitCode::ConfigError, format!("Invalid contract ID: {}", e)))
+}
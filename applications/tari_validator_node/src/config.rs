@@ -69,6 +69,8 @@ pub struct ValidatorNodeConfig {
     pub public_address: Option<Multiaddr>,
     /// The asset worker will adhere to this phased timeout for the asset
     pub phase_timeout: u64,
+    /// The phase timeout doubles on each consecutive failed view up to this maximum (in seconds)
+    pub max_phase_timeout: u64,
     /// The Tari base node's GRPC address
     pub base_node_grpc_address: SocketAddr,
     /// The Tari console wallet's GRPC address
@@ -93,6 +95,8 @@ pub struct ValidatorNodeConfig {
     pub constitution_management_polling_interval_in_seconds: u64,
     /// GRPC address of the validator node  application
     pub grpc_address: Option<Multiaddr>,
+    /// Pruning of state history and chain data in the contract databases
+    pub pruning: PruningConfig,
 }
 
 impl ValidatorNodeConfig {
@@ -123,6 +127,7 @@ impl Default for ValidatorNodeConfig {
             tor_identity_file: PathBuf::from("validator_node_tor_id.json"),
             public_address: None,
             phase_timeout: 30,
+            max_phase_timeout: 300,
             base_node_grpc_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 18142),
             wallet_grpc_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 18143),
             scan_for_assets: true,
@@ -135,10 +140,44 @@ impl Default for ValidatorNodeConfig {
             constitution_management_polling_interval_in_seconds: 60,
             p2p,
             grpc_address: Some("/ip4/127.0.0.1/tcp/18144".parse().unwrap()),
+            pruning: PruningConfig::default(),
         }
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PruningConfig {
+    /// If set to false, all state history is kept
+    pub enabled: bool,
+    /// How much state history to keep
+    pub retention: RetentionPolicy,
+    /// Also delete committed chain nodes, and their instructions, that are older than the retained history
+    pub prune_chain: bool,
+    /// How often to prune, in seconds
+    pub interval_in_seconds: u64,
+}
+
+impl Default for PruningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention: RetentionPolicy::SinceLastCheckpoint,
+            prune_chain: false,
+            interval_in_seconds: 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionPolicy {
+    /// Keep the history of this many of the most recent heights
+    KeepHeights(u64),
+    /// Keep the history since the last checkpoint
+    SinceLastCheckpoint,
+}
+
 impl SubConfigPath for ValidatorNodeConfig {
     fn main_key_prefix() -> &'static str {
         "validator_node"
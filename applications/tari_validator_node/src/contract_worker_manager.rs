@@ -34,7 +34,7 @@ use tari_comms_dht::Dht;
 use tari_core::transactions::transaction_components::{ContractConstitution, OutputType};
 use tari_crypto::tari_utilities::{hex::Hex, message_format::MessageFormat, ByteArray};
 use tari_dan_core::{
-    models::{AssetDefinition, BaseLayerMetadata, Committee},
+    models::{AssetDefinition, BaseLayerMetadata, Committee, ConsensusMode, LeaderSelection},
     services::{
         AcceptanceManager,
         BaseNodeClient,
@@ -52,7 +52,7 @@ use tari_dan_core::{
         global::{ContractState, GlobalDb, GlobalDbMetadataKey},
         StorageError,
     },
-    workers::ConsensusWorker,
+    workers::{ConsensusWorker, Pacemaker},
     DigitalAssetError,
 };
 use tari_dan_storage_sqlite::{
@@ -445,6 +445,10 @@ impl ContractWorkerManager {
                     .map(|pk| pk.to_hex())
                     .collect(),
                 phase_timeout: self.config.phase_timeout,
+                max_phase_timeout: self.config.max_phase_timeout,
+                // The constitution does not select a consensus mode or leader selection yet
+                consensus_mode: ConsensusMode::default(),
+                leader_selection: LeaderSelection::default(),
                 base_layer_confirmation_time: 0,
                 checkpoint_unique_id: vec![],
                 initial_state: Default::default(),
@@ -476,13 +480,17 @@ impl ContractWorkerManager {
         db_factory: SqliteDbFactory,
         kill: Arc<AtomicBool>,
     ) -> Result<(), DigitalAssetError> {
-        let timeout = Duration::from_secs(asset_definition.phase_timeout);
+        let pacemaker = Pacemaker::new(
+            Duration::from_secs(asset_definition.phase_timeout),
+            Duration::from_secs(asset_definition.max_phase_timeout),
+        );
         let committee = asset_definition
             .committee
             .iter()
             .map(|s| CommsPublicKey::from_hex(s).map_err(|_| DigitalAssetError::InvalidCommitteePublicKeyHex))
             .collect::<Result<Vec<_>, _>>()?;
 
+        let mut inbound = TariCommsInboundConnectionService::new(asset_definition.contract_id, &committee);
         let committee = Committee::new(committee);
         let committee_service = ConcreteCommitteeManager::new(committee);
 
@@ -494,10 +502,9 @@ impl ContractWorkerManager {
         // let _backend = LmdbAssetStore::initialize(data_dir.join("asset_data"), Default::default())
         //     .map_err(|err| ExitCodes::DatabaseError(err.to_string()))?;
         // let data_store = AssetDataStore::new(backend);
-        let asset_processor = ConcreteAssetProcessor::default();
+        let asset_processor = ConcreteAssetProcessor::new(asset_definition.clone())?;
 
         let payload_processor = TariDanPayloadProcessor::new(asset_processor);
-        let mut inbound = TariCommsInboundConnectionService::new(asset_definition.contract_id);
         let receiver = inbound.get_receiver();
 
         let loopback = inbound.clone_sender();
@@ -525,7 +532,7 @@ impl ContractWorkerManager {
             payload_processor,
             asset_definition,
             base_node_client,
-            timeout,
+            pacemaker,
             db_factory,
             chain_storage,
             checkpoint_manager,
@@ -32,11 +32,13 @@ use tari_dan_storage_sqlite::{global::SqliteGlobalDbBackendAdapter, SqliteDbFact
 use tari_p2p::comms_connector::SubscriptionFactory;
 use tari_service_framework::ServiceHandles;
 use tari_shutdown::ShutdownSignal;
+use tokio::task;
 
 use crate::{
     config::ValidatorNodeConfig,
     contract_worker_manager::ContractWorkerManager,
     grpc::services::{base_node_client::GrpcBaseNodeClient, wallet_client::GrpcWalletClient},
+    state_pruner::StatePruner,
 };
 
 const _LOG_TARGET: &str = "tari::validator_node::app";
@@ -69,6 +71,16 @@ impl DanNode {
         handles: ServiceHandles,
         subscription_factory: SubscriptionFactory,
     ) -> Result<(), ExitError> {
+        if self.config.pruning.enabled {
+            let pruner = StatePruner::new(
+                self.config.pruning.clone(),
+                self.global_db.clone(),
+                db_factory.clone(),
+                shutdown.clone(),
+            );
+            task::spawn(pruner.run());
+        }
+
         let base_node_client = GrpcBaseNodeClient::new(self.config.base_node_grpc_address);
         let wallet_client = GrpcWalletClient::new(self.config.wallet_grpc_address);
         let acceptance_manager = ConcreteAcceptanceManager::new(wallet_client, base_node_client.clone());
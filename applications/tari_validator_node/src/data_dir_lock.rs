@@ -0,0 +1,79 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    fs,
+    fs::{File, OpenOptions},
+    path::Path,
+};
+
+use fs2::FileExt;
+use tari_common::exit_codes::{ExitCode, ExitError};
+
+const LOCK_FILE_NAME: &str = "validator_node.lock";
+
+/// An exclusive lock on the data directory, held for as long as the running node or an offline command that writes to
+/// the databases is alive. The lock is released when this is dropped or the process exits.
+#[derive(Debug)]
+pub struct DataDirLock {
+    _file: File,
+}
+
+impl DataDirLock {
+    /// Takes the lock, failing immediately if another process holds it
+    pub fn acquire(data_dir: &Path) -> Result<Self, ExitError> {
+        fs::create_dir_all(data_dir).map_err(|e| ExitError::new(ExitCode::IOError, e))?;
+        let path = data_dir.join(LOCK_FILE_NAME);
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(&path)
+            .map_err(|e| ExitError::new(ExitCode::IOError, e))?;
+        match file.try_lock_exclusive() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(err) if err.raw_os_error() == fs2::lock_contended_error().raw_os_error() => Err(ExitError::new(
+                ExitCode::DatabaseError,
+                format!(
+                    "The data directory {} is in use by another validator node process. Stop the node and try again.",
+                    data_dir.display()
+                ),
+            )),
+            Err(err) => Err(ExitError::new(ExitCode::IOError, err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn second_lock_on_the_same_data_dir_is_refused() {
+        let temp_dir = tempdir().unwrap();
+        let lock = DataDirLock::acquire(temp_dir.path()).unwrap();
+        assert!(DataDirLock::acquire(temp_dir.path()).is_err());
+        drop(lock);
+        DataDirLock::acquire(temp_dir.path()).unwrap();
+    }
+}
@@ -21,7 +21,16 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 use tari_app_grpc::tari_rpc;
 use tari_crypto::tari_utilities::ByteArray;
-use tari_dan_core::models::SidechainMetadata;
+use tari_dan_core::models::{
+    MisbehaviourEvidence,
+    MisbehaviourKind,
+    SidechainMetadata,
+    SignedConsensusMessage,
+    StateValueProof,
+};
+use tari_dan_engine::state::models::StateProof;
+
+use crate::grpc::rpc;
 
 pub struct St(tari_rpc::SidechainMetadata);
 
@@ -34,3 +43,64 @@ impl From<SidechainMetadata> for St {
         })
     }
 }
+
+impl From<MisbehaviourKind> for rpc::MisbehaviourKind {
+    fn from(source: MisbehaviourKind) -> Self {
+        match source {
+            MisbehaviourKind::ConflictingProposals => rpc::MisbehaviourKind::ConflictingProposals,
+            MisbehaviourKind::ConflictingVotes => rpc::MisbehaviourKind::ConflictingVotes,
+        }
+    }
+}
+
+impl From<SignedConsensusMessage> for rpc::SignedConsensusMessage {
+    fn from(source: SignedConsensusMessage) -> Self {
+        Self {
+            message_type: u32::from(source.message_type.as_u8()),
+            view_number: source.view_number.as_u64(),
+            node_hash: source.node_hash.as_bytes().to_vec(),
+            signer: source.signature.signer().as_bytes().to_vec(),
+            public_nonce: source.signature.signature().get_public_nonce().as_bytes().to_vec(),
+            signature: source.signature.signature().get_signature().as_bytes().to_vec(),
+        }
+    }
+}
+
+impl From<MisbehaviourEvidence> for rpc::MisbehaviourEvidence {
+    fn from(source: MisbehaviourEvidence) -> Self {
+        Self {
+            contract_id: source.contract_id.as_slice().to_vec(),
+            kind: rpc::MisbehaviourKind::from(source.kind) as i32,
+            offender: source.offender().as_bytes().to_vec(),
+            view_number: source.view_number().as_u64(),
+            first: Some(source.first.into()),
+            second: Some(source.second.into()),
+        }
+    }
+}
+
+impl From<StateProof> for rpc::StateProof {
+    fn from(source: StateProof) -> Self {
+        let (leaf_key, leaf_value_hash) = source
+            .leaf()
+            .map(|(key, value_hash)| (key.to_vec(), value_hash.to_vec()))
+            .unwrap_or_default();
+        Self {
+            siblings: source.siblings().iter().map(|s| s.to_vec()).collect(),
+            leaf_key,
+            leaf_value_hash,
+        }
+    }
+}
+
+impl From<StateValueProof> for rpc::GetStateProofResponse {
+    fn from(source: StateValueProof) -> Self {
+        Self {
+            state_root: source.state_root().as_bytes().to_vec(),
+            height: source.height(),
+            has_value: source.value().is_some(),
+            value: source.value().map(|v| v.to_vec()).unwrap_or_default(),
+            proof: Some(source.proof().clone().into()),
+        }
+    }
+}
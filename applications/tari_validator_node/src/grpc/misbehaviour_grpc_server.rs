@@ -0,0 +1,63 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::convert::TryFrom;
+
+use tari_common_types::types::FixedHash;
+use tari_dan_core::storage::DbFactory;
+use tonic::{Request, Response, Status};
+
+use crate::grpc::rpc;
+
+/// Serves the evidence of equivocation recorded by the consensus workers
+pub struct MisbehaviourGrpcServer<TDbFactory> {
+    db_factory: TDbFactory,
+}
+
+impl<TDbFactory: DbFactory> MisbehaviourGrpcServer<TDbFactory> {
+    pub fn new(db_factory: TDbFactory) -> Self {
+        Self { db_factory }
+    }
+}
+
+#[tonic::async_trait]
+impl<TDbFactory: DbFactory> rpc::misbehaviour_server::Misbehaviour for MisbehaviourGrpcServer<TDbFactory> {
+    async fn get_misbehaviour_evidence(
+        &self,
+        request: Request<rpc::GetMisbehaviourEvidenceRequest>,
+    ) -> Result<Response<rpc::GetMisbehaviourEvidenceResponse>, Status> {
+        let request = request.into_inner();
+        let contract_id = FixedHash::try_from(request.contract_id)
+            .map_err(|err| Status::invalid_argument(format!("Contract ID was not valid: {}", err)))?;
+        let chain_db = self
+            .db_factory
+            .get_chain_db(&contract_id)
+            .map_err(|e| Status::internal(format!("Could not open chain db: {}", e)))?
+            .ok_or_else(|| Status::not_found(format!("No chain db found for contract {}", contract_id)))?;
+        let evidence = chain_db
+            .get_misbehaviour_evidence()
+            .map_err(|e| Status::internal(format!("Could not read misbehaviour evidence: {}", e)))?;
+        Ok(Response::new(rpc::GetMisbehaviourEvidenceResponse {
+            evidence: evidence.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
@@ -20,5 +20,11 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 mod conversions;
+pub(crate) mod misbehaviour_grpc_server;
 pub mod services;
+pub(crate) mod state_proof_grpc_server;
 pub(crate) mod validator_node_grpc_server;
+
+pub(crate) mod rpc {
+    tonic::include_proto!("tari.dan.rpc");
+}
@@ -30,7 +30,15 @@ use tari_common_types::types::{FixedHash, PublicKey};
 use tari_comms::NodeIdentity;
 use tari_crypto::tari_utilities::ByteArray;
 use tari_dan_core::{
-    services::{AcceptanceManager, AssetProcessor, AssetProxy, ServiceSpecification},
+    services::{
+        decode_template_instructions,
+        encode_template_results,
+        AcceptanceManager,
+        AssetProcessor,
+        AssetProxy,
+        ServiceSpecification,
+        WASM_TEMPLATE_ID,
+    },
     storage::DbFactory,
 };
 use tari_dan_engine::instructions::Instruction;
@@ -65,6 +73,51 @@ impl<TServiceSpecification: ServiceSpecification> ValidatorNodeGrpcServer<TServi
     }
 }
 
+impl<TServiceSpecification: ServiceSpecification + 'static> ValidatorNodeGrpcServer<TServiceSpecification> {
+    /// Serves a read request for WASM templates. The instructions run in a read-only runtime against local state, or
+    /// on a committee member if this node does not process the contract.
+    async fn invoke_view_method(
+        &self,
+        contract_id: FixedHash,
+        instructions: Vec<u8>,
+        sender: &[u8],
+    ) -> Result<Response<rpc::InvokeReadMethodResponse>, Status> {
+        let result = match self
+            .db_factory
+            .get_state_db(&contract_id)
+            .map_err(|e| Status::internal(format!("Could not create state db: {}", e)))?
+        {
+            Some(state) => {
+                let instruction_set =
+                    decode_template_instructions(&instructions).map_err(|e| Status::invalid_argument(e.to_string()))?;
+                let results = self
+                    .asset_processor
+                    .invoke_view_method(instruction_set, &state.reader())
+                    .map_err(|e| Status::internal(format!("Could not invoke view method: {}", e)))?;
+                encode_template_results(&results)
+            },
+            None => self
+                .asset_proxy
+                .invoke_view_method(
+                    &contract_id,
+                    instructions,
+                    PublicKey::from_bytes(sender).map_err(|_| Status::invalid_argument("invalid sender"))?,
+                )
+                .await
+                .map_err(|err| Status::internal(format!("Error calling proxied method:{}", err)))?,
+        };
+        // TODO: Populate authority
+        Ok(Response::new(rpc::InvokeReadMethodResponse {
+            result,
+            authority: Some(rpc::Authority {
+                node_public_key: vec![],
+                signature: vec![],
+                proxied_by: vec![],
+            }),
+        }))
+    }
+}
+
 #[tonic::async_trait]
 impl<TServiceSpecification: ServiceSpecification + 'static> rpc::validator_node_server::ValidatorNode
     for ValidatorNodeGrpcServer<TServiceSpecification>
@@ -170,20 +223,30 @@ impl<TServiceSpecification: ServiceSpecification + 'static> rpc::validator_node_
             .try_into()
             .map_err(|_err| Status::invalid_argument("contract_id was not valid"))?;
 
-        match self
-            .asset_proxy
-            .invoke_method(
-                &contract_id,
-                request
-                    .template_id
-                    .try_into()
-                    .map_err(|_| Status::invalid_argument("invalid template_id"))?,
-                request.method.clone(),
-                request.args.clone(),
-                PublicKey::from_bytes(&request.sender).map_err(|_| Status::invalid_argument("invalid sender"))?,
-            )
-            .await
-        {
+        let result = if request.template_id == WASM_TEMPLATE_ID {
+            self.asset_proxy
+                .submit_template_instructions(
+                    &contract_id,
+                    request.args,
+                    PublicKey::from_bytes(&request.sender).map_err(|_| Status::invalid_argument("invalid sender"))?,
+                )
+                .await
+        } else {
+            self.asset_proxy
+                .invoke_method(
+                    &contract_id,
+                    request
+                        .template_id
+                        .try_into()
+                        .map_err(|_| Status::invalid_argument("invalid template_id"))?,
+                    request.method.clone(),
+                    request.args.clone(),
+                    PublicKey::from_bytes(&request.sender).map_err(|_| Status::invalid_argument("invalid sender"))?,
+                )
+                .await
+        };
+
+        match result {
             Ok(_) => Ok(Response::new(rpc::InvokeMethodResponse {
                 status: "Accepted".to_string(),
                 result: vec![],
@@ -221,6 +284,11 @@ impl<TServiceSpecification: ServiceSpecification + 'static> rpc::validator_node_
             .contract_id
             .try_into()
             .map_err(|err| Status::invalid_argument(format!("Contract ID was not valid: {}", err)))?;
+        if request.template_id == WASM_TEMPLATE_ID {
+            return self
+                .invoke_view_method(contract_id, request.args, &request.sender)
+                .await;
+        }
         let template_id = request
             .template_id
             .try_into()
@@ -23,13 +23,16 @@
 mod asset;
 mod cli;
 mod cmd_args;
+mod commands;
 mod comms;
 mod config;
 mod contract_worker_manager;
 mod dan_node;
+mod data_dir_lock;
 mod default_service_specification;
 mod grpc;
 mod p2p;
+mod state_pruner;
 
 use std::{process, sync::Arc};
 
@@ -71,9 +74,13 @@ use crate::{
     cli::Cli,
     config::{ApplicationConfig, ValidatorNodeConfig},
     dan_node::DanNode,
+    data_dir_lock::DataDirLock,
     default_service_specification::DefaultServiceSpecification,
     grpc::{
+        misbehaviour_grpc_server::MisbehaviourGrpcServer,
+        rpc::{misbehaviour_server::MisbehaviourServer, state_proofs_server::StateProofsServer},
         services::{base_node_client::GrpcBaseNodeClient, wallet_client::GrpcWalletClient},
+        state_proof_grpc_server::StateProofGrpcServer,
         validator_node_grpc_server::ValidatorNodeGrpcServer,
     },
     p2p::services::rpc_client::TariCommsValidatorNodeClientFactory,
@@ -105,6 +112,9 @@ fn main_inner() -> Result<(), ExitError> {
         include_str!("../log4rs_sample.yml"),
     )?;
     let config = ApplicationConfig::load_from(&cfg)?;
+    if let Some(command) = cli.command.as_ref() {
+        return commands::run_command(command, &config);
+    }
     println!("Starting validator node on network {}", config.network);
     let runtime = build_runtime()?;
     runtime.block_on(run_node(&config))?;
@@ -114,6 +124,8 @@ fn main_inner() -> Result<(), ExitError> {
 
 async fn run_node(config: &ApplicationConfig) -> Result<(), ExitError> {
     let shutdown = Shutdown::new();
+    // Held until the node exits so that offline commands cannot write to the databases while it is running
+    let _data_dir_lock = DataDirLock::acquire(&config.validator_node.data_dir)?;
 
     let node_identity = setup_node_identity(
         &config.validator_node.identity_file,
@@ -164,10 +176,18 @@ async fn run_node(config: &ApplicationConfig) -> Result<(), ExitError> {
         asset_proxy,
         acceptance_manager,
     );
+    let misbehaviour_grpc_server = MisbehaviourGrpcServer::new(db_factory.clone());
+    let state_proof_grpc_server = StateProofGrpcServer::new(db_factory.clone());
 
     if let Some(address) = config.validator_node.grpc_address.clone() {
         println!("Started GRPC server on {}", address);
-        task::spawn(run_grpc(grpc_server, address, shutdown.to_signal()));
+        task::spawn(run_grpc(
+            grpc_server,
+            misbehaviour_grpc_server,
+            state_proof_grpc_server,
+            address,
+            shutdown.to_signal(),
+        ));
     }
 
     println!("🚀 Validator node started!");
@@ -219,6 +239,8 @@ async fn run_dan_node(
 
 async fn run_grpc<TServiceSpecification: ServiceSpecification + 'static>(
     grpc_server: ValidatorNodeGrpcServer<TServiceSpecification>,
+    misbehaviour_grpc_server: MisbehaviourGrpcServer<TServiceSpecification::DbFactory>,
+    state_proof_grpc_server: StateProofGrpcServer<TServiceSpecification::DbFactory>,
     grpc_address: Multiaddr,
     shutdown_signal: ShutdownSignal,
 ) -> Result<(), anyhow::Error> {
@@ -229,6 +251,8 @@ async fn run_grpc<TServiceSpecification: ServiceSpecification + 'static>(
 
     Server::builder()
         .add_service(ValidatorNodeServer::new(grpc_server))
+        .add_service(MisbehaviourServer::new(misbehaviour_grpc_server))
+        .add_service(StateProofsServer::new(state_proof_grpc_server))
         .serve_with_shutdown(grpc_address, shutdown_signal.map(|_| ()))
         .await
         .map_err(|err| {
@@ -28,25 +28,29 @@ use std::{
 use tari_common_types::types::{PrivateKey, PublicKey, Signature};
 use tari_core::transactions::transaction_components::SignerSignature;
 use tari_crypto::tari_utilities::ByteArray;
-use tari_dan_common_types::TemplateId;
+use tari_dan_common_types::{state_proof::MAX_PROOF_DEPTH, TemplateId};
 use tari_dan_core::models::{
     CheckpointData,
+    ExecutionResult,
     HotStuffMessage,
     HotStuffMessageType,
     HotStuffTreeNode,
     InstructionSet,
     Node,
     QuorumCertificate,
+    QuorumSignatures,
     SideChainBlock,
+    StateValueProof,
     TariDanPayload,
     TreeNodeHash,
     ValidatorSignature,
     ViewId,
 };
 use tari_dan_engine::{
+    instruction::{Instruction as TemplateInstruction, InstructionSet as TemplateInstructionSet},
     instructions::Instruction,
     state::{
-        models::{KeyValue, StateOpLogEntry, StateRoot},
+        models::{KeyValue, StateOpLogEntry, StateProof, StateRoot},
         DbStateOpLogEntry,
     },
 };
@@ -80,6 +84,7 @@ impl From<HotStuffTreeNode<TariDanPayload>> for proto::consensus::HotStuffTreeNo
             payload: Some(source.payload().clone().into()),
             height: source.height(),
             state_root: Vec::from(source.state_root().as_bytes()),
+            receipts_hash: source.receipts_hash().to_vec(),
         }
     }
 }
@@ -90,23 +95,41 @@ impl From<QuorumCertificate> for proto::consensus::QuorumCertificate {
             message_type: i32::from(source.message_type().as_u8()),
             node_hash: Vec::from(source.node_hash().as_bytes()),
             view_number: source.view_number().as_u64(),
-            signature: source.signature().map(|s| s.clone().into()),
+            signer_bitmap: source.signatures().signer_bitmap().to_vec(),
+            signatures: source.signatures().signatures().iter().map(Into::into).collect(),
         }
     }
 }
 
 impl From<ValidatorSignature> for proto::consensus::ValidatorSignature {
-    fn from(_s: ValidatorSignature) -> Self {
-        Self {}
+    fn from(s: ValidatorSignature) -> Self {
+        Self {
+            signer: s.signer().to_vec(),
+            signature: Some(s.signature().into()),
+        }
     }
 }
 
 impl From<TariDanPayload> for proto::consensus::TariDanPayload {
     fn from(source: TariDanPayload) -> Self {
-        let (instruction_set, checkpoint) = source.destruct();
+        let (instruction_set, template_instructions, checkpoint) = source.destruct();
         Self {
             checkpoint: checkpoint.map(|c| c.into()),
             instruction_set: Some(instruction_set.into()),
+            template_instructions: template_instructions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<TemplateInstructionSet> for proto::consensus::TemplateInstructionSet {
+    fn from(source: TemplateInstructionSet) -> Self {
+        Self {
+            instructions: source
+                .instructions
+                .iter()
+                .map(|instruction| tari_template_abi::encode(instruction).expect("encoding to a Vec cannot fail"))
+                .collect(),
+            signature: Some(source.signature.signature().into()),
         }
     }
 }
@@ -161,7 +184,15 @@ impl TryFrom<proto::consensus::QuorumCertificate> for QuorumCertificate {
             HotStuffMessageType::try_from(u8::try_from(value.message_type).unwrap())?,
             ViewId(value.view_number),
             TreeNodeHash::try_from(value.node_hash).map_err(|err| err.to_string())?,
-            value.signature.map(|s| s.try_into()).transpose()?,
+            QuorumSignatures::try_new(
+                value.signer_bitmap,
+                value
+                    .signatures
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+            )
+            .map_err(|err| err.to_string())?,
         ))
     }
 }
@@ -178,6 +209,10 @@ impl TryFrom<proto::consensus::HotStuffTreeNode> for HotStuffTreeNode<TariDanPay
             .try_into()
             .map(StateRoot::new)
             .map_err(|_| "Incorrect length for state_root")?;
+        let receipts_hash = value
+            .receipts_hash
+            .try_into()
+            .map_err(|_| "Incorrect length for receipts_hash")?;
         Ok(Self::new(
             TreeNodeHash::try_from(value.parent).map_err(|_| "Incorrect length for parent")?,
             value
@@ -185,7 +220,10 @@ impl TryFrom<proto::consensus::HotStuffTreeNode> for HotStuffTreeNode<TariDanPay
                 .map(|p| p.try_into())
                 .transpose()?
                 .ok_or("payload not provided")?,
-            state_root,
+            ExecutionResult {
+                state_root,
+                receipts_hash,
+            },
             value.height,
         ))
     }
@@ -194,8 +232,14 @@ impl TryFrom<proto::consensus::HotStuffTreeNode> for HotStuffTreeNode<TariDanPay
 impl TryFrom<proto::consensus::ValidatorSignature> for ValidatorSignature {
     type Error = String;
 
-    fn try_from(_value: proto::consensus::ValidatorSignature) -> Result<Self, Self::Error> {
-        Ok(Self {})
+    fn try_from(value: proto::consensus::ValidatorSignature) -> Result<Self, Self::Error> {
+        Ok(Self::new(
+            PublicKey::from_bytes(&value.signer).map_err(|err| err.to_string())?,
+            value
+                .signature
+                .map(TryInto::try_into)
+                .ok_or("signature not provided")??,
+        ))
     }
 }
 
@@ -243,8 +287,37 @@ impl TryFrom<proto::consensus::TariDanPayload> for TariDanPayload {
             .ok_or_else(|| "Instructions were not present".to_string())?
             .try_into()?;
         let checkpoint = value.checkpoint.map(|c| c.try_into()).transpose()?;
+        let template_instructions = value
+            .template_instructions
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<_, _>>()?;
 
-        Ok(Self::new(instruction_set, checkpoint))
+        Ok(Self::new(instruction_set, checkpoint).with_template_instructions(template_instructions))
+    }
+}
+
+impl TryFrom<proto::consensus::TemplateInstructionSet> for TemplateInstructionSet {
+    type Error = String;
+
+    fn try_from(value: proto::consensus::TemplateInstructionSet) -> Result<Self, Self::Error> {
+        let instructions = value
+            .instructions
+            .iter()
+            .map(|bytes| {
+                tari_template_abi::decode::<TemplateInstruction>(bytes)
+                    .map_err(|e| format!("Invalid template instruction: {}", e))
+            })
+            .collect::<Result<_, _>>()?;
+        let signature: Signature = value
+            .signature
+            .ok_or_else(|| "Template instruction signature not provided".to_string())?
+            .try_into()?;
+
+        Ok(Self {
+            instructions,
+            signature: signature.into(),
+        })
     }
 }
 
@@ -373,6 +446,84 @@ impl TryFrom<proto::validator_node::StateOpLog> for StateOpLogEntry {
     }
 }
 
+impl From<StateProof> for proto::validator_node::StateProof {
+    fn from(proof: StateProof) -> Self {
+        let (leaf_key, leaf_value_hash) = proof
+            .leaf()
+            .map(|(key, value_hash)| (key.to_vec(), value_hash.to_vec()))
+            .unwrap_or_default();
+        Self {
+            siblings: proof.siblings().iter().map(|s| s.to_vec()).collect(),
+            leaf_key,
+            leaf_value_hash,
+        }
+    }
+}
+
+impl TryFrom<proto::validator_node::StateProof> for StateProof {
+    type Error = String;
+
+    fn try_from(proof: proto::validator_node::StateProof) -> Result<Self, Self::Error> {
+        if proof.siblings.len() > MAX_PROOF_DEPTH {
+            return Err(format!(
+                "StateProof: {} siblings exceeds the maximum of {}",
+                proof.siblings.len(),
+                MAX_PROOF_DEPTH
+            ));
+        }
+        let siblings = proof
+            .siblings
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<_, _>>()
+            .map_err(|_| "StateProof: invalid sibling hash".to_string())?;
+        let leaf = if proof.leaf_key.is_empty() && proof.leaf_value_hash.is_empty() {
+            None
+        } else {
+            let key = proof
+                .leaf_key
+                .try_into()
+                .map_err(|_| "StateProof: invalid leaf key".to_string())?;
+            let value_hash = proof
+                .leaf_value_hash
+                .try_into()
+                .map_err(|_| "StateProof: invalid leaf value hash".to_string())?;
+            Some((key, value_hash))
+        };
+        Ok(Self::new(siblings, leaf))
+    }
+}
+
+impl From<StateValueProof> for proto::validator_node::GetStateProofResponse {
+    fn from(value_proof: StateValueProof) -> Self {
+        Self {
+            state_root: value_proof.state_root().as_bytes().to_vec(),
+            has_value: value_proof.value().is_some(),
+            value: value_proof.value().map(|v| v.to_vec()).unwrap_or_default(),
+            proof: Some(value_proof.proof().clone().into()),
+            height: value_proof.height(),
+        }
+    }
+}
+
+impl TryFrom<proto::validator_node::GetStateProofResponse> for StateValueProof {
+    type Error = String;
+
+    fn try_from(resp: proto::validator_node::GetStateProofResponse) -> Result<Self, Self::Error> {
+        let state_root = resp
+            .state_root
+            .try_into()
+            .map(StateRoot::new)
+            .map_err(|_| "GetStateProofResponse: invalid state root".to_string())?;
+        let proof = resp
+            .proof
+            .ok_or_else(|| "GetStateProofResponse: missing proof".to_string())?
+            .try_into()?;
+        let value = if resp.has_value { Some(resp.value) } else { None };
+        Ok(Self::new(state_root, resp.height, value, proof))
+    }
+}
+
 //---------------------------------- SignerSignature --------------------------------------------//
 impl<B: Borrow<SignerSignature>> From<B> for proto::common::SignerSignature {
     fn from(signature: B) -> Self {
@@ -78,6 +78,12 @@ pub trait ValidatorNodeRpcService: Send + Sync + 'static {
         &self,
         request: Request<proto::GetTipNodeRequest>,
     ) -> Result<Response<proto::GetTipNodeResponse>, RpcStatus>;
+
+    #[rpc(method = 8)]
+    async fn get_state_proof(
+        &self,
+        request: Request<proto::GetStateProofRequest>,
+    ) -> Result<Response<proto::GetStateProofResponse>, RpcStatus>;
 }
 
 pub fn create_validator_node_rpc_service<
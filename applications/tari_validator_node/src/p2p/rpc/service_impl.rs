@@ -29,7 +29,13 @@ use tari_comms::{
 };
 use tari_dan_core::{
     models::TreeNodeHash,
-    services::{AssetProcessor, MempoolService},
+    services::{
+        decode_template_instructions,
+        encode_template_results,
+        AssetProcessor,
+        MempoolService,
+        WASM_TEMPLATE_ID,
+    },
     storage::DbFactory,
 };
 use tari_dan_engine::state::StateDbUnitOfWorkReader;
@@ -98,6 +104,18 @@ where
 
         let unit_of_work = state.reader();
 
+        if request.template_id == WASM_TEMPLATE_ID {
+            let instruction_set =
+                decode_template_instructions(&request.args).map_err(|e| RpcStatus::bad_request(&e.to_string()))?;
+            let results = self
+                .asset_processor
+                .invoke_view_method(instruction_set, &unit_of_work)
+                .map_err(|e| RpcStatus::general(&format!("Could not invoke view method: {}", e)))?;
+            return Ok(Response::new(proto::InvokeReadMethodResponse {
+                result: encode_template_results(&results),
+            }));
+        }
+
         let instruction = Instruction::new(
             request
                 .template_id
@@ -124,6 +142,24 @@ where
     ) -> Result<Response<proto::InvokeMethodResponse>, RpcStatus> {
         println!("{:?}", request);
         let request = request.into_message();
+        if request.template_id == WASM_TEMPLATE_ID {
+            let instruction_set =
+                decode_template_instructions(&request.args).map_err(|e| RpcStatus::bad_request(&e.to_string()))?;
+            debug!(target: LOG_TARGET, "Submitting template instructions to mempool");
+            let mut mempool_service = self.mempool_service.clone();
+            let status = match mempool_service.submit_template_instructions(instruction_set).await {
+                Ok(_) => proto::Status::Accepted,
+                Err(err) => {
+                    debug!(target: LOG_TARGET, "Mempool rejected template instructions: {}", err);
+                    proto::Status::Errored
+                },
+            };
+            return Ok(Response::new(proto::InvokeMethodResponse {
+                result: vec![],
+                status: status as i32,
+            }));
+        }
+
         let instruction = Instruction::new(
             request
                 .template_id
@@ -343,4 +379,29 @@ where
 
         Ok(Response::new(resp))
     }
+
+    async fn get_state_proof(
+        &self,
+        request: Request<proto::GetStateProofRequest>,
+    ) -> Result<Response<proto::GetStateProofResponse>, RpcStatus> {
+        let msg = request.into_message();
+
+        let contract_id = msg
+            .contract_id
+            .try_into()
+            .map_err(|_| RpcStatus::bad_request("Invalid contract_id"))?;
+
+        let db = self
+            .db_factory
+            .get_state_db(&contract_id)
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?
+            .ok_or_else(|| RpcStatus::not_found("Asset not found"))?;
+
+        let value_proof = db
+            .reader()
+            .get_committed_value_proof(&msg.schema, &msg.key)
+            .map_err(RpcStatus::log_internal_error(LOG_TARGET))?;
+
+        Ok(Response::new(value_proof.into()))
+    }
 }
@@ -26,10 +26,11 @@ use tari_common_types::types::FixedHash;
 use tari_comms::{protocol::rpc::mock::RpcRequestMock, test_utils};
 use tari_crypto::tari_utilities::{hex::Hex, ByteArray};
 use tari_dan_core::{
-    models::{Node, TreeNodeHash},
+    models::{Node, StateValueProof, TreeNodeHash},
     services::mocks::{MockAssetProcessor, MockMempoolService},
     storage::{chain::ChainDbUnitOfWork, mocks::MockDbFactory, DbFactory},
 };
+use tari_dan_engine::state::{StateDbUnitOfWork, StateDbUnitOfWorkReader};
 use tari_test_utils::{paths::tempdir, streams::convert_mpsc_to_stream};
 use tokio_stream::StreamExt;
 
@@ -129,3 +130,54 @@ mod get_sidechain_blocks {
         assert!(err.details().starts_with("Block not found"));
     }
 }
+
+mod get_state_proof {
+    use super::*;
+
+    #[tokio::test]
+    async fn it_returns_the_committed_value_with_a_proof_against_the_committed_root() {
+        let (service, mock, db_factory) = setup();
+        let contract_id = FixedHash::zero();
+        let db = db_factory.get_or_create_state_db(&contract_id).unwrap();
+        let mut uow = db.new_unit_of_work(1);
+        uow.set_value("s".to_string(), b"a".to_vec(), b"1".to_vec()).unwrap();
+        uow.set_value("s".to_string(), b"b".to_vec(), b"2".to_vec()).unwrap();
+        uow.commit().unwrap();
+        let committed_root = db.reader().calculate_root().unwrap();
+        // Changes that are not committed are not part of the proof
+        let mut pending = db.new_unit_of_work(2);
+        pending
+            .set_value("s".to_string(), b"a".to_vec(), b"3".to_vec())
+            .unwrap();
+
+        for (key, value) in [(b"a".to_vec(), Some(b"1".to_vec())), (b"c".to_vec(), None)] {
+            let req = proto::validator_node::GetStateProofRequest {
+                contract_id: contract_id.to_vec(),
+                schema: "s".to_string(),
+                key: key.clone(),
+            };
+            let req = mock.request_with_context(Default::default(), req);
+            let resp = service.get_state_proof(req).await.unwrap().into_inner();
+            let value_proof = StateValueProof::try_from(resp).unwrap();
+            assert_eq!(*value_proof.state_root(), committed_root);
+            assert_eq!(value_proof.height(), 1);
+            assert_eq!(value_proof.value(), value.as_deref());
+            assert!(value_proof.verify(&committed_root, "s", &key));
+        }
+    }
+
+    #[tokio::test]
+    async fn it_errors_if_asset_not_found() {
+        let (service, mock, _) = setup();
+
+        let req = proto::validator_node::GetStateProofRequest {
+            contract_id: FixedHash::zero().to_vec(),
+            schema: "s".to_string(),
+            key: b"a".to_vec(),
+        };
+        let req = mock.request_with_context(Default::default(), req);
+        let err = service.get_state_proof(req).await.unwrap_err();
+        assert!(err.as_status_code().is_not_found());
+        assert_eq!(err.details(), "Asset not found");
+    }
+}
@@ -32,8 +32,8 @@ use log::*;
 use tari_common_types::types::FixedHash;
 use tari_comms::types::CommsPublicKey;
 use tari_dan_core::{
-    models::{HotStuffMessage, HotStuffMessageType, TariDanPayload, ViewId},
-    services::infrastructure_services::InboundConnectionService,
+    models::{HotStuffMessage, HotStuffMessageType, MisbehaviourEvidence, TariDanPayload, ViewId},
+    services::infrastructure_services::{EquivocationDetector, InboundConnectionService},
     DigitalAssetError,
 };
 use tari_p2p::comms_connector::PeerMessage;
@@ -51,6 +51,8 @@ const LOG_TARGET: &str = "tari::validator_node::p2p::services::inbound_connectio
 enum WaitForMessageType {
     Message,
     QuorumCertificate,
+    /// A message of the given type for the given view or any later view
+    FromView,
 }
 
 #[derive(Debug)]
@@ -62,6 +64,9 @@ enum TariCommsInboundRequest {
         view_number: ViewId,
         reply_channel: oneshot::Sender<(CommsPublicKey, HotStuffMessage<TariDanPayload>)>,
     },
+    TakeMisbehaviourEvidence {
+        reply_channel: oneshot::Sender<Vec<MisbehaviourEvidence>>,
+    },
 }
 
 #[allow(dead_code)]
@@ -81,11 +86,12 @@ pub struct TariCommsInboundConnectionService {
     )>,
     loopback_sender: Sender<(CommsPublicKey, HotStuffMessage<TariDanPayload>)>,
     loopback_receiver: Receiver<(CommsPublicKey, HotStuffMessage<TariDanPayload>)>,
+    equivocation_detector: EquivocationDetector,
 }
 
 #[allow(dead_code)]
 impl TariCommsInboundConnectionService {
-    pub fn new(contract_id: FixedHash) -> Self {
+    pub fn new(contract_id: FixedHash, committee: &[CommsPublicKey]) -> Self {
         let (sender, receiver) = channel(1000);
         let (loopback_sender, loopback_receiver) = channel(1);
         Self {
@@ -97,6 +103,7 @@ impl TariCommsInboundConnectionService {
             waiters: VecDeque::new(),
             loopback_receiver,
             loopback_sender,
+            equivocation_detector: EquivocationDetector::new(contract_id, committee),
         }
     }
 
@@ -155,6 +162,8 @@ impl TariCommsInboundConnectionService {
                 view_number,
                 reply_channel,
             } => {
+                // The consensus worker waits for the messages of its current view
+                self.equivocation_detector.set_current_view(view_number);
                 // Check for already received messages
                 let mut indexes_to_remove = vec![];
                 let mut result_message = None;
@@ -185,6 +194,13 @@ impl TariCommsInboundConnectionService {
                                     }
                                 }
                             },
+                            WaitForMessageType::FromView => {
+                                if message.message_type() == message_type && message.view_number() >= view_number {
+                                    result_message = Some((from_pk.clone(), message.clone()));
+                                    indexes_to_remove.push(index);
+                                    break;
+                                }
+                            },
                         }
                     }
                 }
@@ -203,6 +219,11 @@ impl TariCommsInboundConnectionService {
                     },
                 }
             },
+            TariCommsInboundRequest::TakeMisbehaviourEvidence { reply_channel } => {
+                if reply_channel.send(self.equivocation_detector.take_evidence()).is_err() {
+                    error!(target: LOG_TARGET, "Failed to send misbehaviour evidence");
+                }
+            },
         }
         Ok(())
     }
@@ -231,6 +252,7 @@ impl TariCommsInboundConnectionService {
     ) -> Result<(), DigitalAssetError> {
         debug!(target: "messages::inbound::validator_node", "Inbound message received:{} {:?}", from, message);
         debug!(target: LOG_TARGET, "Inbound message received:{} {:?}", from, message);
+        self.equivocation_detector.observe(&from, &message);
 
         // Loop until we have sent to a waiting call, or buffer the message
         loop {
@@ -253,6 +275,12 @@ impl TariCommsInboundConnectionService {
                             }
                         }
                     },
+                    WaitForMessageType::FromView => {
+                        if message.message_type() == *message_type && message.view_number() >= *view_number {
+                            waiter_index = Some(index);
+                            break;
+                        }
+                    },
                 }
             }
 
@@ -338,4 +366,68 @@ impl InboundConnectionService for TariCommsInboundReceiverHandle {
         rx.await
             .map_err(|e| DigitalAssetError::FatalError(format!("Error receiving from qc oneshot channel:{}", e)))
     }
+
+    async fn wait_for_timeout(
+        &self,
+        min_view: ViewId,
+    ) -> Result<(CommsPublicKey, HotStuffMessage<TariDanPayload>), DigitalAssetError> {
+        let (tx, rx) = oneshot::channel();
+        self.sender
+            .send(TariCommsInboundRequest::WaitForMessage {
+                wait_for_type: WaitForMessageType::FromView,
+                message_type: HotStuffMessageType::Timeout,
+                view_number: min_view,
+                reply_channel: tx,
+            })
+            .await
+            .map_err(|e| DigitalAssetError::FatalError(format!("Error sending request to channel:{}", e)))?;
+        rx.await
+            .map_err(|e| DigitalAssetError::FatalError(format!("Error receiving from timeout oneshot channel:{}", e)))
+    }
+
+    async fn wait_for_epoch_handover(
+        &self,
+        min_view: ViewId,
+    ) -> Result<(CommsPublicKey, HotStuffMessage<TariDanPayload>), DigitalAssetError> {
+        let (tx, rx) = oneshot::channel();
+        self.sender
+            .send(TariCommsInboundRequest::WaitForMessage {
+                wait_for_type: WaitForMessageType::FromView,
+                message_type: HotStuffMessageType::EpochHandover,
+                view_number: min_view,
+                reply_channel: tx,
+            })
+            .await
+            .map_err(|e| DigitalAssetError::FatalError(format!("Error sending request to channel:{}", e)))?;
+        rx.await
+            .map_err(|e| DigitalAssetError::FatalError(format!("Error receiving from handover oneshot channel:{}", e)))
+    }
+
+    async fn wait_for_epoch_handover_request(
+        &self,
+    ) -> Result<(CommsPublicKey, HotStuffMessage<TariDanPayload>), DigitalAssetError> {
+        let (tx, rx) = oneshot::channel();
+        self.sender
+            .send(TariCommsInboundRequest::WaitForMessage {
+                wait_for_type: WaitForMessageType::FromView,
+                message_type: HotStuffMessageType::EpochHandoverRequest,
+                view_number: ViewId(0),
+                reply_channel: tx,
+            })
+            .await
+            .map_err(|e| DigitalAssetError::FatalError(format!("Error sending request to channel:{}", e)))?;
+        rx.await.map_err(|e| {
+            DigitalAssetError::FatalError(format!("Error receiving from handover request oneshot channel:{}", e))
+        })
+    }
+
+    async fn take_misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>, DigitalAssetError> {
+        let (tx, rx) = oneshot::channel();
+        self.sender
+            .send(TariCommsInboundRequest::TakeMisbehaviourEvidence { reply_channel: tx })
+            .await
+            .map_err(|e| DigitalAssetError::FatalError(format!("Error sending request to channel:{}", e)))?;
+        rx.await
+            .map_err(|e| DigitalAssetError::FatalError(format!("Error receiving from evidence oneshot channel:{}", e)))
+    }
 }
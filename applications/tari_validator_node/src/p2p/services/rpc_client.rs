@@ -30,8 +30,8 @@ use tari_comms_dht::DhtRequester;
 use tari_crypto::tari_utilities::ByteArray;
 use tari_dan_common_types::TemplateId;
 use tari_dan_core::{
-    models::{Node, SideChainBlock, TreeNodeHash},
-    services::{ValidatorNodeClientError, ValidatorNodeClientFactory, ValidatorNodeRpcClient},
+    models::{Node, SideChainBlock, StateValueProof, TreeNodeHash},
+    services::{ValidatorNodeClientError, ValidatorNodeClientFactory, ValidatorNodeRpcClient, WASM_TEMPLATE_ID},
 };
 use tari_dan_engine::state::models::{SchemaState, StateOpLogEntry};
 use tokio_stream::StreamExt;
@@ -84,6 +84,56 @@ impl ValidatorNodeRpcClient for TariCommsValidatorNodeRpcClient {
         })
     }
 
+    async fn invoke_view_method(
+        &mut self,
+        contract_id: &FixedHash,
+        instructions: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<Vec<u8>, ValidatorNodeClientError> {
+        debug!(target: LOG_TARGET, "Invoking template view for asset '{}'", contract_id);
+        let mut connection = self.create_connection().await?;
+        let mut client = connection.connect_rpc::<rpc::ValidatorNodeRpcClient>().await?;
+        let request = proto::InvokeReadMethodRequest {
+            contract_id: contract_id.to_vec(),
+            template_id: WASM_TEMPLATE_ID,
+            method: String::new(),
+            args: instructions,
+            sender: sender.to_vec(),
+        };
+        let response = client.invoke_read_method(request).await?;
+        Ok(response.result)
+    }
+
+    async fn submit_template_instructions(
+        &mut self,
+        contract_id: &FixedHash,
+        instructions: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<(), ValidatorNodeClientError> {
+        debug!(
+            target: LOG_TARGET,
+            "Submitting template instructions for asset '{}'", contract_id
+        );
+        let mut connection = self.create_connection().await?;
+        let mut client = connection.connect_rpc::<rpc::ValidatorNodeRpcClient>().await?;
+        let request = proto::InvokeMethodRequest {
+            contract_id: contract_id.to_vec(),
+            template_id: WASM_TEMPLATE_ID,
+            method: String::new(),
+            args: instructions,
+            sender: sender.to_vec(),
+        };
+        let response = client.invoke_method(request).await?;
+        if response.status == proto::Status::Accepted as i32 {
+            Ok(())
+        } else {
+            Err(ValidatorNodeClientError::InvalidPeerMessage(format!(
+                "Validator node '{}' did not accept the template instructions (status {})",
+                self.address, response.status
+            )))
+        }
+    }
+
     async fn invoke_method(
         &mut self,
         contract_id: &FixedHash,
@@ -243,6 +293,23 @@ impl ValidatorNodeRpcClient for TariCommsValidatorNodeRpcClient {
             .transpose()
             .map_err(ValidatorNodeClientError::InvalidPeerMessage)
     }
+
+    async fn get_state_proof(
+        &mut self,
+        contract_id: &FixedHash,
+        schema: String,
+        key: Vec<u8>,
+    ) -> Result<StateValueProof, ValidatorNodeClientError> {
+        let mut connection = self.create_connection().await?;
+        let mut client = connection.connect_rpc::<rpc::ValidatorNodeRpcClient>().await?;
+        let request = proto::GetStateProofRequest {
+            contract_id: contract_id.to_vec(),
+            schema,
+            key,
+        };
+        let resp = client.get_state_proof(request).await?;
+        resp.try_into().map_err(ValidatorNodeClientError::InvalidPeerMessage)
+    }
 }
 
 #[derive(Clone)]
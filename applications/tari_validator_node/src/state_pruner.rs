@@ -0,0 +1,237 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{convert::TryFrom, time::Duration};
+
+use log::*;
+use tari_common_types::types::{FixedHash, FixedHashSizeError};
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_dan_core::{
+    models::REPUTATION_HISTORY_VIEWS,
+    storage::{
+        chain::ChainPruningStats,
+        global::{ContractState, GlobalDb},
+        DbFactory,
+        StorageError,
+    },
+};
+use tari_dan_engine::state::{error::StateStorageError, StatePruningStats};
+use tari_dan_storage_sqlite::{global::SqliteGlobalDbBackendAdapter, SqliteDbFactory};
+use tari_shutdown::ShutdownSignal;
+use thiserror::Error;
+use tokio::{task, time};
+
+use crate::config::{PruningConfig, RetentionPolicy};
+
+const LOG_TARGET: &str = "tari::validator_node::state_pruner";
+
+/// Periodically prunes the state history and, optionally, the chain data of the active contracts according to the
+/// configured retention policy
+pub struct StatePruner {
+    config: PruningConfig,
+    global_db: GlobalDb<SqliteGlobalDbBackendAdapter>,
+    db_factory: SqliteDbFactory,
+    metrics: PruningMetrics,
+    shutdown: ShutdownSignal,
+}
+
+impl StatePruner {
+    pub fn new(
+        config: PruningConfig,
+        global_db: GlobalDb<SqliteGlobalDbBackendAdapter>,
+        db_factory: SqliteDbFactory,
+        shutdown: ShutdownSignal,
+    ) -> Self {
+        Self {
+            config,
+            global_db,
+            db_factory,
+            metrics: PruningMetrics::default(),
+            shutdown,
+        }
+    }
+
+    pub async fn run(mut self) {
+        info!(
+            target: LOG_TARGET,
+            "State pruning started with retention policy {:?} (prune chain: {})",
+            self.config.retention,
+            self.config.prune_chain
+        );
+        loop {
+            tokio::select! {
+                _ = time::sleep(Duration::from_secs(self.config.interval_in_seconds)) => {},
+                _ = &mut self.shutdown => break,
+            }
+            if let Err(err) = self.prune_active_contracts().await {
+                error!(target: LOG_TARGET, "State pruning failed: {}", err);
+            }
+        }
+        info!(target: LOG_TARGET, "State pruning stopped");
+    }
+
+    async fn prune_active_contracts(&mut self) -> Result<(), PruningError> {
+        let contracts = self.global_db.get_contracts_with_state(ContractState::Active)?;
+        for contract in contracts {
+            let contract_id = FixedHash::try_from(contract.contract_id)?;
+            let db_factory = self.db_factory.clone();
+            let config = self.config.clone();
+            let result = task::spawn_blocking(move || prune_contract(&db_factory, &contract_id, &config))
+                .await
+                .map_err(|err| PruningError::TaskFailed(err.to_string()))?;
+            match result {
+                Ok(Some(stats)) => {
+                    self.metrics.record(&stats);
+                    info!(
+                        target: LOG_TARGET,
+                        "Pruned contract {}: {} op log entries, {} state tree nodes, {} chain nodes and {} \
+                         instructions removed, {} bytes reclaimed. Totals since startup: {}",
+                        contract_id.to_hex(),
+                        stats.state.op_logs_removed,
+                        stats.state.tree_nodes_removed,
+                        stats.chain.nodes_removed,
+                        stats.chain.instructions_removed,
+                        stats.bytes_reclaimed,
+                        self.metrics
+                    );
+                },
+                Ok(None) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Nothing to prune for contract {}",
+                        contract_id.to_hex()
+                    );
+                },
+                Err(err) => {
+                    // Pruning other contracts can still succeed
+                    warn!(
+                        target: LOG_TARGET,
+                        "Failed to prune contract {}: {}",
+                        contract_id.to_hex(),
+                        err
+                    );
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+fn prune_contract(
+    db_factory: &SqliteDbFactory,
+    contract_id: &FixedHash,
+    config: &PruningConfig,
+) -> Result<Option<ContractPruningStats>, PruningError> {
+    let (chain_db, state_db) = match (
+        db_factory.get_chain_db(contract_id)?,
+        db_factory.get_state_db(contract_id)?,
+    ) {
+        (Some(chain_db), Some(state_db)) => (chain_db, state_db),
+        _ => return Ok(None),
+    };
+
+    let (state_height, node_height) = match config.retention {
+        RetentionPolicy::KeepHeights(num_heights) => {
+            let tip_height = chain_db.get_tip_node()?.map(|node| node.height()).unwrap_or(0);
+            (
+                state_db.get_current_height()?.saturating_sub(num_heights),
+                tip_height.saturating_sub(u32::try_from(num_heights).unwrap_or(u32::MAX)),
+            )
+        },
+        RetentionPolicy::SinceLastCheckpoint => match chain_db.get_last_checkpoint_heights()? {
+            Some(heights) => heights,
+            None => return Ok(None),
+        },
+    };
+
+    let free_space_before = db_factory.get_free_space(contract_id)?.unwrap_or(0);
+    let state = state_db.prune(state_height)?;
+    let chain = if config.prune_chain {
+        // Reputation-based leader selection reads which recent views were committed, so those nodes are kept
+        let tip_height = chain_db.get_tip_node()?.map(|node| node.height()).unwrap_or(0);
+        let reputation_history_start =
+            tip_height.saturating_sub(u32::try_from(REPUTATION_HISTORY_VIEWS).unwrap_or(u32::MAX));
+        chain_db.prune_committed_nodes(node_height.min(reputation_history_start))?
+    } else {
+        ChainPruningStats::default()
+    };
+    let free_space_after = db_factory.get_free_space(contract_id)?.unwrap_or(0);
+
+    Ok(Some(ContractPruningStats {
+        state,
+        chain,
+        bytes_reclaimed: free_space_after.saturating_sub(free_space_before),
+    }))
+}
+
+struct ContractPruningStats {
+    state: StatePruningStats,
+    chain: ChainPruningStats,
+    /// The increase in free pages in the database, which sqlite reuses before growing the file
+    bytes_reclaimed: u64,
+}
+
+/// Totals of everything pruned since the validator node started
+#[derive(Debug, Default)]
+struct PruningMetrics {
+    op_logs_removed: u64,
+    tree_nodes_removed: u64,
+    chain_nodes_removed: u64,
+    instructions_removed: u64,
+    bytes_reclaimed: u64,
+}
+
+impl PruningMetrics {
+    fn record(&mut self, stats: &ContractPruningStats) {
+        self.op_logs_removed += stats.state.op_logs_removed;
+        self.tree_nodes_removed += stats.state.tree_nodes_removed;
+        self.chain_nodes_removed += stats.chain.nodes_removed;
+        self.instructions_removed += stats.chain.instructions_removed;
+        self.bytes_reclaimed += stats.bytes_reclaimed;
+    }
+}
+
+impl std::fmt::Display for PruningMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} op log entries, {} state tree nodes, {} chain nodes, {} instructions, {} bytes reclaimed",
+            self.op_logs_removed,
+            self.tree_nodes_removed,
+            self.chain_nodes_removed,
+            self.instructions_removed,
+            self.bytes_reclaimed
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PruningError {
+    #[error(transparent)]
+    FixedHashSizeError(#[from] FixedHashSizeError),
+    #[error("Storage error: {0}")]
+    StorageError(#[from] StorageError),
+    #[error("State storage error: {0}")]
+    StateStorageError(#[from] StateStorageError),
+    #[error("Pruning task failed: {0}")]
+    TaskFailed(String),
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: BSD-3-Clause
 
 pub mod proto;
+pub mod state_proof;
 pub mod storage;
 
 mod template_id;
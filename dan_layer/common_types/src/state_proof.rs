@@ -0,0 +1,272 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! State roots and the proofs that a key has, or does not have, a value in the state tree with a given root.
+//!
+//! These only depend on the hashing of the state tree, so that clients can verify state returned by a validator node
+//! without depending on the engine.
+
+use std::convert::TryFrom;
+
+use digest::Digest;
+use tari_common_types::types::FixedHash;
+use tari_crypto::hash::blake2::Blake256;
+
+const LEAF_DOMAIN: &[u8] = b"tari.dan.state_tree.leaf";
+const INTERNAL_DOMAIN: &[u8] = b"tari.dan.state_tree.internal";
+/// The number of bits in a path. A path has one bit per level of the tree, so no proof has more siblings than this.
+pub const MAX_PROOF_DEPTH: usize = 256;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct StateRoot {
+    root: FixedHash,
+}
+
+impl StateRoot {
+    pub fn new(root: FixedHash) -> Self {
+        Self { root }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.root.as_slice()
+    }
+
+    pub fn initial() -> Self {
+        Self {
+            root: FixedHash::zero(),
+        }
+    }
+}
+
+impl From<StateRoot> for FixedHash {
+    fn from(state_root: StateRoot) -> Self {
+        state_root.root
+    }
+}
+
+/// Returns the path of a (schema, key) pair in the state tree
+pub fn state_tree_key(schema: &str, key: &[u8]) -> FixedHash {
+    let hasher = Blake256::new()
+        .chain((schema.len() as u64).to_le_bytes())
+        .chain(schema.as_bytes())
+        .chain(key);
+    to_fixed_hash(&hasher.finalize())
+}
+
+pub fn state_tree_value_hash(value: &[u8]) -> FixedHash {
+    to_fixed_hash(&Blake256::digest(value))
+}
+
+pub fn hash_leaf(key: &FixedHash, value_hash: &FixedHash) -> FixedHash {
+    let hasher = Blake256::new()
+        .chain(LEAF_DOMAIN)
+        .chain(key.as_slice())
+        .chain(value_hash.as_slice());
+    to_fixed_hash(&hasher.finalize())
+}
+
+pub fn hash_internal(left: &FixedHash, right: &FixedHash) -> FixedHash {
+    let hasher = Blake256::new()
+        .chain(INTERNAL_DOMAIN)
+        .chain(left.as_slice())
+        .chain(right.as_slice());
+    to_fixed_hash(&hasher.finalize())
+}
+
+/// Returns true if the bit at `depth` of the path is set, i.e. the path goes right at that depth
+pub fn path_bit(path: &FixedHash, depth: usize) -> bool {
+    (path.as_slice()[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+fn to_fixed_hash(bytes: &[u8]) -> FixedHash {
+    FixedHash::try_from(bytes).expect("Blake256 output is 32 bytes")
+}
+
+/// A proof that a key has, or does not have, a value in the state tree with a given root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateProof {
+    /// Sibling hashes from the root down to the terminal node of the path
+    siblings: Vec<FixedHash>,
+    /// The leaf (key, value hash) found at the end of the path, or None if the path ends in an empty subtree
+    leaf: Option<(FixedHash, FixedHash)>,
+}
+
+impl StateProof {
+    pub fn new(siblings: Vec<FixedHash>, leaf: Option<(FixedHash, FixedHash)>) -> Self {
+        Self { siblings, leaf }
+    }
+
+    pub fn siblings(&self) -> &[FixedHash] {
+        &self.siblings
+    }
+
+    pub fn leaf(&self) -> Option<&(FixedHash, FixedHash)> {
+        self.leaf.as_ref()
+    }
+
+    /// Returns true if this proves that `key` in `schema` has `value` in the state with the given root
+    pub fn verify_inclusion(&self, root: &StateRoot, schema: &str, key: &[u8], value: &[u8]) -> bool {
+        if self.siblings.len() > MAX_PROOF_DEPTH {
+            return false;
+        }
+        let path = state_tree_key(schema, key);
+        match self.leaf {
+            Some((leaf_key, value_hash)) => {
+                leaf_key == path && value_hash == state_tree_value_hash(value) && self.verify_path(root, &path)
+            },
+            None => false,
+        }
+    }
+
+    /// Returns true if this proves that `key` in `schema` has no value in the state with the given root
+    pub fn verify_exclusion(&self, root: &StateRoot, schema: &str, key: &[u8]) -> bool {
+        if self.siblings.len() > MAX_PROOF_DEPTH {
+            return false;
+        }
+        let path = state_tree_key(schema, key);
+        match self.leaf {
+            // Another leaf occupies the subtree that the key would be in
+            Some((leaf_key, _)) => {
+                leaf_key != path &&
+                    (0..self.siblings.len()).all(|depth| path_bit(&leaf_key, depth) == path_bit(&path, depth)) &&
+                    self.verify_path(root, &path)
+            },
+            None => self.verify_path(root, &path),
+        }
+    }
+
+    fn verify_path(&self, root: &StateRoot, path: &FixedHash) -> bool {
+        let mut current = match self.leaf {
+            Some((key, value_hash)) => hash_leaf(&key, &value_hash),
+            None => FixedHash::zero(),
+        };
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            current = if path_bit(path, depth) {
+                hash_internal(sibling, &current)
+            } else {
+                hash_internal(&current, sibling)
+            };
+        }
+        current.as_slice() == root.as_bytes()
+    }
+}
+
+/// A state value returned by a validator node, together with a proof that it is (or is not) in the committed state
+/// with the given root
+#[derive(Debug, Clone)]
+pub struct StateValueProof {
+    state_root: StateRoot,
+    /// The state height that the root was committed at
+    height: u64,
+    value: Option<Vec<u8>>,
+    proof: StateProof,
+}
+
+impl StateValueProof {
+    pub fn new(state_root: StateRoot, height: u64, value: Option<Vec<u8>>, proof: StateProof) -> Self {
+        Self {
+            state_root,
+            height,
+            value,
+            proof,
+        }
+    }
+
+    pub fn state_root(&self) -> &StateRoot {
+        &self.state_root
+    }
+
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn value(&self) -> Option<&[u8]> {
+        self.value.as_deref()
+    }
+
+    pub fn proof(&self) -> &StateProof {
+        &self.proof
+    }
+
+    /// Returns true if the value is proven against `trusted_root`. The trusted root should come from a source other
+    /// than the validator that sent this proof, e.g. the merkle root of the latest checkpoint on the base layer or a
+    /// root that a quorum of the committee agrees on.
+    pub fn verify(&self, trusted_root: &StateRoot, schema: &str, key: &[u8]) -> bool {
+        if self.state_root != *trusted_root {
+            return false;
+        }
+        match self.value {
+            Some(ref value) => self.proof.verify_inclusion(trusted_root, schema, key, value),
+            None => self.proof.verify_exclusion(trusted_root, schema, key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds the proofs of a tree with two leaves that differ in the first bit of their path
+    fn two_leaf_tree() -> (StateRoot, [(Vec<u8>, Vec<u8>, StateProof); 2]) {
+        let mut leaves = (0u8..)
+            .map(|i| (vec![i], vec![i; 2]))
+            .map(|(key, value)| (state_tree_key("s", &key), key, value))
+            .take(16)
+            .collect::<Vec<_>>();
+        leaves.sort_by_key(|(path, _, _)| path_bit(path, 0));
+        let (left_path, left_key, left_value) = leaves.first().cloned().unwrap();
+        let (right_path, right_key, right_value) = leaves.last().cloned().unwrap();
+        assert!(!path_bit(&left_path, 0) && path_bit(&right_path, 0));
+
+        let left = hash_leaf(&left_path, &state_tree_value_hash(&left_value));
+        let right = hash_leaf(&right_path, &state_tree_value_hash(&right_value));
+        let root = StateRoot::new(hash_internal(&left, &right));
+        let left_proof = StateProof::new(vec![right], Some((left_path, state_tree_value_hash(&left_value))));
+        let right_proof = StateProof::new(vec![left], Some((right_path, state_tree_value_hash(&right_value))));
+        (root, [
+            (left_key, left_value, left_proof),
+            (right_key, right_value, right_proof),
+        ])
+    }
+
+    #[test]
+    fn it_verifies_values_against_the_trusted_root() {
+        let (root, [(key, value, proof), (other_key, _, other_proof)]) = two_leaf_tree();
+
+        let value_proof = StateValueProof::new(root, 1, Some(value.clone()), proof.clone());
+        assert!(value_proof.verify(&root, "s", &key));
+        // The root that the client trusts must be the one that the proof is against
+        assert!(!value_proof.verify(&StateRoot::initial(), "s", &key));
+        // The value must be the one that the proof commits to
+        let wrong_value = StateValueProof::new(root, 1, Some(b"wrong".to_vec()), proof.clone());
+        assert!(!wrong_value.verify(&root, "s", &key));
+        // A proof of another key does not prove this one
+        let wrong_proof = StateValueProof::new(root, 1, Some(value), other_proof);
+        assert!(!wrong_proof.verify(&root, "s", &key));
+        // A key that has a value cannot be proven to be missing
+        let missing = StateValueProof::new(root, 1, None, proof);
+        assert!(!missing.verify(&root, "s", &key));
+        assert!(!missing.verify(&root, "s", &other_key));
+
+        // A key whose path ends at another leaf is proven to be missing by the proof of that leaf
+        let (_, [(key, _, proof), _]) = two_leaf_tree();
+        let missing_key = (0u8..)
+            .map(|i| vec![i, i])
+            .find(|k| !path_bit(&state_tree_key("s", k), 0))
+            .unwrap();
+        assert_ne!(missing_key, key);
+        assert!(StateValueProof::new(root, 1, None, proof).verify(&root, "s", &missing_key));
+    }
+
+    #[test]
+    fn it_rejects_proofs_deeper_than_the_path() {
+        let (root, [(key, value, proof), _]) = two_leaf_tree();
+        let mut siblings = proof.siblings().to_vec();
+        siblings.resize(MAX_PROOF_DEPTH + 1, FixedHash::zero());
+        let oversized = StateProof::new(siblings, proof.leaf().cloned());
+
+        assert!(!oversized.verify_inclusion(&root, "s", &key, &value));
+        assert!(!oversized.verify_exclusion(&root, "s", &key));
+        assert!(!StateProof::new(vec![FixedHash::zero(); MAX_PROOF_DEPTH + 1], None).verify_exclusion(&root, "s", &key));
+    }
+}
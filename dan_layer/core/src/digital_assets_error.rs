@@ -22,8 +22,13 @@
 
 use prost::DecodeError;
 use tari_comms_dht::outbound::DhtOutboundError;
-use tari_crypto::ristretto::RistrettoPublicKey;
-use tari_dan_engine::state::error::StateStorageError;
+use tari_crypto::{ristretto::RistrettoPublicKey, signatures::SchnorrSignatureError};
+use tari_dan_engine::{
+    flow::FlowEngineError,
+    instruction::InstructionError,
+    packager::PackageError,
+    state::error::StateStorageError,
+};
 use thiserror::Error;
 use tokio::sync::mpsc::error::SendError;
 
@@ -108,6 +113,20 @@ pub enum DigitalAssetError {
     InvalidCommitteePublicKeyHex,
     #[error("State storage error:{0}")]
     StateStorageError(#[from] StateStorageError),
+    #[error("Instruction failed: {0}")]
+    InstructionError(#[from] InstructionError),
+    #[error("Invalid template instructions: {0}")]
+    InvalidTemplateInstructions(String),
+    #[error("No template package has been loaded for this contract")]
+    NoTemplatePackage,
+    #[error("Failed to sign: {0}")]
+    SigningError(#[from] SchnorrSignatureError),
+    #[error("Invalid flow function: {0}")]
+    FlowEngineError(#[from] FlowEngineError),
+    #[error("Failed to load template package: {0}")]
+    PackageError(#[from] PackageError),
+    #[error("Failed to read template module '{name}': {source}")]
+    TemplateModuleReadError { name: String, source: std::io::Error },
 }
 
 impl From<lmdb_zero::Error> for DigitalAssetError {
@@ -29,7 +29,10 @@ use tari_dan_engine::{
     wasm::WasmModuleDefinition,
 };
 
-use crate::helpers::deserialize_from_hex;
+use crate::{
+    helpers::deserialize_from_hex,
+    models::{ConsensusMode, LeaderSelection},
+};
 
 #[derive(Deserialize, Clone, Debug)]
 #[serde(default)]
@@ -39,6 +42,10 @@ pub struct AssetDefinition {
     // TODO: remove and read from base layer
     pub committee: Vec<String>,
     pub phase_timeout: u64,
+    /// The view timeout doubles for each consecutive view that times out, up to this many seconds
+    pub max_phase_timeout: u64,
+    pub consensus_mode: ConsensusMode,
+    pub leader_selection: LeaderSelection,
     // TODO: Better name? lock time/peg time? (in number of blocks)
     pub base_layer_confirmation_time: u64,
     // TODO: remove
@@ -58,6 +65,9 @@ impl Default for AssetDefinition {
             contract_id: Default::default(),
             committee: vec![],
             phase_timeout: 30,
+            max_phase_timeout: 300,
+            consensus_mode: ConsensusMode::default(),
+            leader_selection: LeaderSelection::default(),
             initial_state: Default::default(),
             template_parameters: vec![],
             wasm_modules: vec![],
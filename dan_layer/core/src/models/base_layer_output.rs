@@ -56,6 +56,16 @@ impl BaseLayerOutput {
             .map(|cp| cp.merkle_root)
     }
 
+    /// The committee of a contract amendment and the number of blocks after the amendment is mined that the committee
+    /// takes over
+    pub fn get_amendment_committee(&self) -> Option<(&[PublicKey], u64)> {
+        self.features
+            .sidechain_features
+            .as_ref()
+            .and_then(|features| features.amendment.as_ref())
+            .map(|amendment| (amendment.validator_committee.members(), amendment.activation_window))
+    }
+
     pub fn get_parent_public_key(&self) -> Option<&PublicKey> {
         self.features.parent_public_key.as_ref()
     }
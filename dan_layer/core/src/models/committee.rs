@@ -20,21 +20,62 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{models::ViewId, services::infrastructure_services::NodeAddressable};
+use std::sync::Arc;
+
+use crate::{
+    models::{LeaderStrategy, RoundRobinLeaderStrategy, ViewId},
+    services::infrastructure_services::NodeAddressable,
+};
 
 #[derive(Clone)]
 pub struct Committee<TAddr: NodeAddressable> {
     // TODO: encapsulate
     pub members: Vec<TAddr>,
+    leader_strategy: Arc<dyn LeaderStrategy>,
+    /// The committee that handed the contract over to this one, and the last view that it was responsible for
+    handover: Option<Arc<(ViewId, Committee<TAddr>)>>,
 }
 
 impl<TAddr: NodeAddressable> Committee<TAddr> {
     pub fn new(members: Vec<TAddr>) -> Self {
-        Self { members }
+        Self {
+            members,
+            leader_strategy: Arc::new(RoundRobinLeaderStrategy),
+            handover: None,
+        }
+    }
+
+    /// Returns this committee with the leader of each view selected by `leader_strategy` instead of round-robin
+    pub fn with_leader_strategy(mut self, leader_strategy: Arc<dyn LeaderStrategy>) -> Self {
+        self.leader_strategy = leader_strategy;
+        self
+    }
+
+    /// Returns this committee as the successor of `previous`, which remains responsible for the views up to and
+    /// including `handover_view`
+    pub fn with_handover(mut self, handover_view: ViewId, mut previous: Committee<TAddr>) -> Self {
+        // Only the certificates of the latest handover can be verified
+        previous.handover = None;
+        self.handover = Some(Arc::new((handover_view, previous)));
+        self
+    }
+
+    /// The last view of the committee that handed the contract over to this one, if any
+    pub fn handover_view(&self) -> Option<ViewId> {
+        self.handover.as_ref().map(|handover| handover.0)
+    }
+
+    /// The committee that is responsible for `view_id`: the previous committee for views up to and including the
+    /// handover, otherwise this committee
+    pub fn committee_for_view(&self, view_id: ViewId) -> &Committee<TAddr> {
+        match self.handover.as_deref() {
+            Some((handover_view, previous)) if view_id <= *handover_view => previous,
+            _ => self,
+        }
     }
 
     pub fn leader_for_view(&self, view_id: ViewId) -> &TAddr {
-        let pos = view_id.current_leader(self.members.len());
+        let pos = self.leader_strategy.leader_index(self.members.len(), view_id);
         &self.members[pos]
     }
 
@@ -55,6 +96,10 @@ impl<TAddr: NodeAddressable> Committee<TAddr> {
     pub fn contains(&self, member: &TAddr) -> bool {
         self.members.contains(member)
     }
+
+    pub fn index_of(&self, member: &TAddr) -> Option<usize> {
+        self.members.iter().position(|m| m == member)
+    }
 }
 
 impl<TAddr: NodeAddressable> IntoIterator for Committee<TAddr> {
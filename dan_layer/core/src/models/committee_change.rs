@@ -0,0 +1,58 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::convert::TryFrom;
+
+use tari_common_types::types::PublicKey;
+use tari_core::transactions::transaction_components::OutputType;
+
+use crate::{
+    models::{BaseLayerOutput, ModelError},
+    services::infrastructure_services::NodeAddressable,
+};
+
+/// A committee that takes over a contract at a base layer height, as defined by a contract amendment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitteeChange<TAddr: NodeAddressable> {
+    /// The base layer height from which the committee runs the contract
+    pub activation_height: u64,
+    pub members: Vec<TAddr>,
+}
+
+impl TryFrom<BaseLayerOutput> for CommitteeChange<PublicKey> {
+    type Error = ModelError;
+
+    fn try_from(output: BaseLayerOutput) -> Result<Self, Self::Error> {
+        if output.features.output_type != OutputType::ContractAmendment {
+            return Err(ModelError::NotContractAmendmentOutput);
+        }
+
+        let (members, activation_window) = output
+            .get_amendment_committee()
+            .ok_or(ModelError::AmendmentOutputMissingCommittee)?;
+
+        Ok(Self {
+            activation_height: output.height + activation_window,
+            members: members.to_vec(),
+        })
+    }
+}
@@ -22,7 +22,7 @@
 
 use std::{fmt, fmt::Formatter};
 
-use crate::models::{ConsensusWorkerState, Event};
+use crate::models::{ConsensusWorkerState, Event, MisbehaviourKind, ViewId};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConsensusWorkerDomainEvent {
@@ -30,6 +30,15 @@ pub enum ConsensusWorkerDomainEvent {
         from: ConsensusWorkerState,
         to: ConsensusWorkerState,
     },
+    InvalidMessageReceived {
+        sender: String,
+        reason: String,
+    },
+    MisbehaviourDetected {
+        offender: String,
+        kind: MisbehaviourKind,
+        view_number: ViewId,
+    },
 }
 
 impl Event for ConsensusWorkerDomainEvent {}
@@ -40,6 +49,16 @@ impl fmt::Display for ConsensusWorkerDomainEvent {
             ConsensusWorkerDomainEvent::StateChanged { from: old, to: new } => {
                 write!(f, "State changed from {:?} to {:?}", old, new)
             },
+            ConsensusWorkerDomainEvent::InvalidMessageReceived { sender, reason } => {
+                write!(f, "Invalid message received from {}: {}", sender, reason)
+            },
+            ConsensusWorkerDomainEvent::MisbehaviourDetected {
+                offender,
+                kind,
+                view_number,
+            } => {
+                write!(f, "{} signed {} in view {}", offender, kind, view_number)
+            },
         }
     }
 }
@@ -38,4 +38,20 @@ pub enum ModelError {
     NotCommitteeDefinitionOutput,
     #[error("Committee output is missing committee of public keys")]
     CommitteeOutputMissingDefinition,
+    #[error("Output is not flagged as a contract amendment output")]
+    NotContractAmendmentOutput,
+    #[error("Contract amendment output is missing the validator committee")]
+    AmendmentOutputMissingCommittee,
+    #[error("Quorum certificate has {actual} signatures but {required} are required")]
+    InsufficientQuorumSignatures { required: usize, actual: usize },
+    #[error("Quorum certificate is signed by unknown committee member {signer_index}")]
+    UnknownQuorumSigner { signer_index: usize },
+    #[error("Quorum certificate signature of committee member {signer_index} is invalid")]
+    InvalidQuorumSignature { signer_index: usize },
+    #[error("Committee member {signer_index} signed the quorum certificate more than once")]
+    DuplicateQuorumSigner { signer_index: usize },
+    #[error("Genesis quorum certificate must be for view 0 and the genesis parent node, and must not be signed")]
+    InvalidGenesisQuorumCertificate,
+    #[error("Signer bitmap has {signers} signers but there are {signatures} signatures")]
+    InvalidSignerBitmap { signers: usize, signatures: usize },
 }
@@ -0,0 +1,73 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use tari_common_types::types::FixedHash;
+use tari_crypto::hash::blake2::Blake256;
+use tari_dan_engine::state::models::StateRoot;
+
+use crate::models::{dan_layer_models_hasher, hashing::EXECUTION_RECEIPTS_LABEL};
+
+/// The result of executing a payload against the state of a contract. A replica only votes for a node if executing its
+/// payload gives the same result that the leader proposed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionResult {
+    pub state_root: StateRoot,
+    /// Hash of the receipt of each executed instruction, in the order that they were executed
+    pub receipts_hash: FixedHash,
+}
+
+impl ExecutionResult {
+    pub fn new(state_root: StateRoot, receipts: &[Vec<u8>]) -> Self {
+        let mut hasher =
+            dan_layer_models_hasher::<Blake256>(EXECUTION_RECEIPTS_LABEL).chain((receipts.len() as u64).to_le_bytes());
+        for receipt in receipts {
+            hasher = hasher.chain((receipt.len() as u64).to_le_bytes()).chain(receipt);
+        }
+        let mut receipts_hash = [0u8; 32];
+        receipts_hash.copy_from_slice(hasher.finalize().as_ref());
+        Self {
+            state_root,
+            receipts_hash: receipts_hash.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_commits_to_the_order_of_receipts() {
+        let a = vec![1u8];
+        let b = vec![2u8, 3];
+        let root = StateRoot::initial();
+        assert_eq!(
+            ExecutionResult::new(root, &[a.clone(), b.clone()]),
+            ExecutionResult::new(root, &[a.clone(), b.clone()])
+        );
+        assert_ne!(
+            ExecutionResult::new(root, &[a.clone(), b.clone()]),
+            ExecutionResult::new(root, &[b, a])
+        );
+        assert_ne!(ExecutionResult::new(root, &[]), ExecutionResult::new(root, &[vec![]]));
+    }
+}
@@ -34,6 +34,10 @@ hash_domain!(
 
 pub(crate) const HOT_STUFF_MESSAGE_LABEL: &str = "hot_stuff_message";
 pub(crate) const TARI_DAN_PAYLOAD_LABEL: &str = "tari_dan_payload";
+pub(crate) const VALIDATOR_SIGNATURE_LABEL: &str = "validator_signature";
+pub(crate) const LEADER_SELECTION_LABEL: &str = "leader_selection";
+pub(crate) const EXECUTION_RECEIPTS_LABEL: &str = "execution_receipts";
+pub(crate) const TEMPLATE_INSTRUCTIONS_LABEL: &str = "template_instructions";
 
 pub(crate) fn dan_layer_models_hasher<D: Digest + LengthExtensionAttackResistant>(
     label: &'static str,
@@ -22,18 +22,19 @@
 
 use tari_common_types::types::FixedHash;
 use tari_core::transactions::transaction_components::SignerSignature;
-use tari_crypto::hash::blake2::Blake256;
 
-use super::HOT_STUFF_MESSAGE_LABEL;
-use crate::models::{
-    dan_layer_models_hasher,
-    HotStuffMessageType,
-    HotStuffTreeNode,
-    Payload,
-    QuorumCertificate,
-    TreeNodeHash,
-    ValidatorSignature,
-    ViewId,
+use crate::{
+    models::{
+        create_vote_challenge,
+        HotStuffMessageType,
+        HotStuffTreeNode,
+        Payload,
+        QuorumCertificate,
+        TreeNodeHash,
+        ValidatorSignature,
+        ViewId,
+    },
+    services::infrastructure_services::NodeAddressable,
 };
 
 #[derive(Debug, Clone)]
@@ -200,17 +201,115 @@ impl<TPayload: Payload> HotStuffMessage<TPayload> {
         }
     }
 
+    pub fn generic(
+        node: HotStuffTreeNode<TPayload>,
+        justify: QuorumCertificate,
+        view_number: ViewId,
+        contract_id: FixedHash,
+    ) -> Self {
+        Self {
+            message_type: HotStuffMessageType::Generic,
+            node: Some(node),
+            justify: Some(justify),
+            view_number,
+            partial_sig: None,
+            checkpoint_signature: None,
+            node_hash: None,
+            contract_id,
+        }
+    }
+
+    pub fn vote_generic(node_hash: TreeNodeHash, view_number: ViewId, contract_id: FixedHash) -> Self {
+        Self {
+            message_type: HotStuffMessageType::GenericVote,
+            node: None,
+            justify: None,
+            view_number,
+            partial_sig: None,
+            checkpoint_signature: None,
+            node_hash: Some(node_hash),
+            contract_id,
+        }
+    }
+
+    /// A timeout vote for `view_number`, carrying the highest timeout certificate known to the sender so that lagging
+    /// replicas can catch up
+    pub fn timeout(
+        view_number: ViewId,
+        high_timeout_certificate: Option<QuorumCertificate>,
+        contract_id: FixedHash,
+    ) -> Self {
+        Self {
+            message_type: HotStuffMessageType::Timeout,
+            node: None,
+            justify: high_timeout_certificate,
+            view_number,
+            partial_sig: None,
+            checkpoint_signature: None,
+            node_hash: None,
+            contract_id,
+        }
+    }
+
+    /// The final block of an epoch, signed by a member of the outgoing committee and sent to the incoming committee
+    /// along with the certificate of the block that it extends
+    pub fn epoch_handover(
+        final_block: HotStuffTreeNode<TPayload>,
+        high_qc: QuorumCertificate,
+        view_number: ViewId,
+        contract_id: FixedHash,
+    ) -> Self {
+        Self {
+            message_type: HotStuffMessageType::EpochHandover,
+            node: Some(final_block),
+            justify: Some(high_qc),
+            view_number,
+            partial_sig: None,
+            checkpoint_signature: None,
+            node_hash: None,
+            contract_id,
+        }
+    }
+
+    /// Asks the members of the outgoing committee to send their final block to the sender again
+    pub fn epoch_handover_request(view_number: ViewId, contract_id: FixedHash) -> Self {
+        Self {
+            message_type: HotStuffMessageType::EpochHandoverRequest,
+            node: None,
+            justify: None,
+            view_number,
+            partial_sig: None,
+            checkpoint_signature: None,
+            node_hash: None,
+            contract_id,
+        }
+    }
+
     pub fn create_signature_challenge(&self) -> Vec<u8> {
-        let mut b = dan_layer_models_hasher::<Blake256>(HOT_STUFF_MESSAGE_LABEL)
-            .chain(&[self.message_type.as_u8()])
-            .chain(self.view_number.as_u64().to_le_bytes());
-        if let Some(ref node) = self.node {
-            b = b.chain(node.calculate_hash().as_bytes());
-        } else if let Some(ref node_hash) = self.node_hash {
-            b = b.chain(node_hash.as_bytes());
-        } else {
+        let node_hash = match (&self.node, &self.node_hash) {
+            (Some(node), _) => node.calculate_hash(),
+            (None, Some(node_hash)) => *node_hash,
+            (None, None) => TreeNodeHash::zero(),
+        };
+        create_vote_challenge(
+            &self.contract_id,
+            self.view_number,
+            self.message_type.certificate_type(),
+            &node_hash,
+        )
+    }
+
+    /// Returns true if this message is for the given contract and carries a valid vote signature from `signer`
+    pub fn is_signed_by<TAddr: NodeAddressable>(&self, contract_id: &FixedHash, signer: &TAddr) -> bool {
+        if self.contract_id != *contract_id {
+            return false;
+        }
+        match (&self.partial_sig, signer.public_key()) {
+            (Some(sig), Some(public_key)) => {
+                sig.signer() == public_key && sig.verify(&self.create_signature_challenge())
+            },
+            _ => false,
         }
-        b.finalize().as_ref().to_vec()
     }
 
     pub fn view_number(&self) -> ViewId {
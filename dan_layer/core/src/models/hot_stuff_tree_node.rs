@@ -21,26 +21,27 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use digest::{Digest, FixedOutput};
+use tari_common_types::types::FixedHash;
 use tari_crypto::hash::blake2::Blake256;
 use tari_dan_engine::state::models::StateRoot;
 
-use crate::models::{Payload, TreeNodeHash};
+use crate::models::{ExecutionResult, Payload, TreeNodeHash};
 
 #[derive(Debug, Clone)]
 pub struct HotStuffTreeNode<TPayload: Payload> {
     parent: TreeNodeHash,
     payload: TPayload,
-    state_root: StateRoot,
+    execution_result: ExecutionResult,
     hash: TreeNodeHash,
     height: u32,
 }
 
 impl<TPayload: Payload> HotStuffTreeNode<TPayload> {
-    pub fn new(parent: TreeNodeHash, payload: TPayload, state_root: StateRoot, height: u32) -> Self {
+    pub fn new(parent: TreeNodeHash, payload: TPayload, execution_result: ExecutionResult, height: u32) -> Self {
         let mut s = HotStuffTreeNode {
             parent,
             payload,
-            state_root,
+            execution_result,
             hash: TreeNodeHash::zero(),
             height,
         };
@@ -48,12 +49,12 @@ impl<TPayload: Payload> HotStuffTreeNode<TPayload> {
         s
     }
 
-    pub fn genesis(payload: TPayload, state_root: StateRoot) -> HotStuffTreeNode<TPayload> {
+    pub fn genesis(payload: TPayload, execution_result: ExecutionResult) -> HotStuffTreeNode<TPayload> {
         let mut s = Self {
             parent: TreeNodeHash::zero(),
             payload,
             hash: TreeNodeHash::zero(),
-            state_root,
+            execution_result,
             height: 0,
         };
         s.hash = s.calculate_hash();
@@ -63,10 +64,10 @@ impl<TPayload: Payload> HotStuffTreeNode<TPayload> {
     pub fn from_parent(
         parent: TreeNodeHash,
         payload: TPayload,
-        state_root: StateRoot,
+        execution_result: ExecutionResult,
         height: u32,
     ) -> HotStuffTreeNode<TPayload> {
-        Self::new(parent, payload, state_root, height)
+        Self::new(parent, payload, execution_result, height)
     }
 
     pub fn calculate_hash(&self) -> TreeNodeHash {
@@ -74,7 +75,8 @@ impl<TPayload: Payload> HotStuffTreeNode<TPayload> {
             .chain(self.parent.as_bytes())
             .chain(self.payload.consensus_hash())
             .chain(self.height.to_le_bytes())
-            .chain(self.state_root.as_bytes())
+            .chain(self.execution_result.state_root.as_bytes())
+            .chain(self.execution_result.receipts_hash.as_slice())
             .finalize_fixed();
         result.into()
     }
@@ -92,7 +94,16 @@ impl<TPayload: Payload> HotStuffTreeNode<TPayload> {
     }
 
     pub fn state_root(&self) -> &StateRoot {
-        &self.state_root
+        &self.execution_result.state_root
+    }
+
+    pub fn receipts_hash(&self) -> &FixedHash {
+        &self.execution_result.receipts_hash
+    }
+
+    /// The result of executing the payload that the proposer of this node claims
+    pub fn execution_result(&self) -> &ExecutionResult {
+        &self.execution_result
     }
 
     pub fn height(&self) -> u32 {
@@ -0,0 +1,77 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use tari_dan_engine::flow::FlowEvent;
+
+/// The output of executing an instruction, apart from its changes to the state
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstructionResult {
+    /// Events emitted by a flow function, in the order that they were emitted. Template methods do not emit events.
+    pub events: Vec<FlowEvent>,
+}
+
+impl InstructionResult {
+    /// Encodes the result as the receipt of its instruction. Every event field is length-prefixed, so that different
+    /// results never have the same receipt.
+    pub fn to_receipt(&self) -> Vec<u8> {
+        let mut receipt = Vec::new();
+        receipt.extend_from_slice(&(self.events.len() as u64).to_le_bytes());
+        for event in &self.events {
+            for field in [&event.topic, &event.message] {
+                receipt.extend_from_slice(&(field.len() as u64).to_le_bytes());
+                receipt.extend_from_slice(field.as_bytes());
+            }
+        }
+        receipt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(events: &[(&str, &str)]) -> InstructionResult {
+        InstructionResult {
+            events: events
+                .iter()
+                .map(|(topic, message)| FlowEvent {
+                    topic: topic.to_string(),
+                    message: message.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn it_gives_different_results_different_receipts() {
+        let receipts = vec![
+            result(&[]).to_receipt(),
+            result(&[("ab", "c")]).to_receipt(),
+            result(&[("a", "bc")]).to_receipt(),
+            result(&[("a", "bc"), ("", "")]).to_receipt(),
+        ];
+        for (i, receipt) in receipts.iter().enumerate() {
+            assert!(receipts[i + 1..].iter().all(|other| other != receipt));
+        }
+        assert_eq!(result(&[("a", "bc")]).to_receipt(), receipts[2]);
+    }
+}
@@ -0,0 +1,264 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    ops::Range,
+};
+
+use digest::Digest;
+use serde::Deserialize;
+use tari_common_types::types::FixedHash;
+use tari_crypto::hash::blake2::Blake256;
+
+use crate::models::{
+    hashing::{dan_layer_models_hasher, LEADER_SELECTION_LABEL},
+    ViewId,
+};
+
+/// The number of views before `REPUTATION_LAG` that are checked for failed proposals
+pub const REPUTATION_WINDOW: u64 = 100;
+/// The most recent views are ignored, because committee members may not have committed them yet. A chained mode block
+/// is only committed three views after it was proposed.
+pub const REPUTATION_LAG: u64 = 4;
+/// The number of views below the tip whose committed nodes must be kept to compute reputation-based leaders. The
+/// leaders of the views in the reputation window depend on the window before it.
+pub const REPUTATION_HISTORY_VIEWS: u64 = 2 * (REPUTATION_WINDOW + REPUTATION_LAG);
+
+/// Selects the leader of a view from the committee. Every committee member must select the same leader, so
+/// implementations may only depend on data that all members agree on.
+pub trait LeaderStrategy: Send + Sync {
+    /// Returns the index into the committee members of the leader for `view_id`
+    fn leader_index(&self, committee_size: usize, view_id: ViewId) -> usize;
+}
+
+/// How a contract's committee selects the leader for each view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderSelection {
+    /// Members take turns in committee order
+    RoundRobin,
+    /// Round-robin, skipping members that recently failed to get a proposal committed
+    Reputation,
+    /// A pseudo-random member derived from the contract id and view number
+    Hash,
+}
+
+impl Default for LeaderSelection {
+    fn default() -> Self {
+        LeaderSelection::RoundRobin
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoundRobinLeaderStrategy;
+
+impl LeaderStrategy for RoundRobinLeaderStrategy {
+    fn leader_index(&self, committee_size: usize, view_id: ViewId) -> usize {
+        view_id.current_leader(committee_size)
+    }
+}
+
+/// Rotates the leadership in an order that cannot be predicted from the committee order, so that consecutive views
+/// are not led by neighbouring members
+#[derive(Debug, Clone, Copy)]
+pub struct HashLeaderStrategy {
+    contract_id: FixedHash,
+}
+
+impl HashLeaderStrategy {
+    pub fn new(contract_id: FixedHash) -> Self {
+        Self { contract_id }
+    }
+}
+
+impl LeaderStrategy for HashLeaderStrategy {
+    fn leader_index(&self, committee_size: usize, view_id: ViewId) -> usize {
+        if committee_size == 0 {
+            return 0;
+        }
+        let hash = dan_layer_models_hasher::<Blake256>(LEADER_SELECTION_LABEL)
+            .chain(self.contract_id.as_slice())
+            .chain(view_id.as_u64().to_le_bytes())
+            .finalize();
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&hash.as_ref()[..8]);
+        let value = u64::from_le_bytes(buf);
+        // The remainder is less than the committee size, so it always fits in a usize
+        (value % committee_size as u64).try_into().unwrap_or(0)
+    }
+}
+
+/// Round-robin leader selection that skips members that were selected as the leader of a view in the reputation
+/// window that has no committed node. If every member has failed, it falls back to round-robin.
+#[derive(Debug, Clone, Default)]
+pub struct ReputationLeaderStrategy {
+    history_start: u64,
+    committed_views: HashSet<u64>,
+}
+
+impl ReputationLeaderStrategy {
+    /// Creates the strategy from the views of the committed chain from `history_start`. These must include every
+    /// committed view in the history range of the views that leaders are selected for. Views before `history_start`
+    /// are treated as committed.
+    pub fn from_committed_views<I: IntoIterator<Item = u64>>(history_start: u64, committed_views: I) -> Self {
+        Self {
+            history_start,
+            committed_views: committed_views.into_iter().collect(),
+        }
+    }
+
+    /// The views that are checked for failed proposals when selecting the leader for `view_id`
+    pub fn history_window(view_id: ViewId) -> Range<u64> {
+        let end = view_id.as_u64().saturating_sub(REPUTATION_LAG);
+        end.saturating_sub(REPUTATION_WINDOW)..end
+    }
+
+    /// The views whose committed nodes determine the leader for `view_id`: its history window, and the history
+    /// windows that determine the leaders of the views in it
+    pub fn history_range(view_id: ViewId) -> Range<u64> {
+        let window = Self::history_window(view_id);
+        window.start.saturating_sub(REPUTATION_WINDOW + REPUTATION_LAG)..window.end
+    }
+
+    /// The indexes of the members that were selected as the leader of a view in the history window of `view_id` that
+    /// has no committed node
+    pub fn failed_members(&self, committee_size: usize, view_id: ViewId) -> HashSet<usize> {
+        self.failed_members_with(committee_size, view_id, &mut HashMap::new())
+    }
+
+    fn failed_members_with(
+        &self,
+        committee_size: usize,
+        view_id: ViewId,
+        leaders: &mut HashMap<u64, usize>,
+    ) -> HashSet<usize> {
+        Self::history_window(view_id)
+            .filter(|view| *view >= self.history_start && !self.committed_views.contains(view))
+            .map(|view| self.leader_index_with(committee_size, ViewId(view), leaders))
+            .collect()
+    }
+
+    /// Selects the leader for `view_id`, remembering the leaders selected for the failed views in its history
+    fn leader_index_with(&self, committee_size: usize, view_id: ViewId, leaders: &mut HashMap<u64, usize>) -> usize {
+        if let Some(index) = leaders.get(&view_id.as_u64()) {
+            return *index;
+        }
+        let failed_members = self.failed_members_with(committee_size, view_id, leaders);
+        let candidate = view_id.current_leader(committee_size);
+        let index = (0..committee_size)
+            .map(|offset| (candidate + offset) % committee_size)
+            .find(|index| !failed_members.contains(index))
+            .unwrap_or(candidate);
+        leaders.insert(view_id.as_u64(), index);
+        index
+    }
+}
+
+impl LeaderStrategy for ReputationLeaderStrategy {
+    fn leader_index(&self, committee_size: usize, view_id: ViewId) -> usize {
+        self.leader_index_with(committee_size, view_id, &mut HashMap::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_rotates_in_committee_order() {
+        let strategy = RoundRobinLeaderStrategy;
+        let leaders = (0..8).map(|v| strategy.leader_index(4, ViewId(v))).collect::<Vec<_>>();
+        assert_eq!(leaders, vec![0, 1, 2, 3, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn it_selects_the_same_hash_based_leader_for_the_same_view() {
+        let strategy = HashLeaderStrategy::new(FixedHash::zero());
+        let other_contract = HashLeaderStrategy::new([1u8; 32].into());
+        let leaders = (0..100)
+            .map(|v| strategy.leader_index(4, ViewId(v)))
+            .collect::<Vec<_>>();
+        assert!(leaders.iter().all(|index| *index < 4));
+        assert_eq!(
+            leaders,
+            (0..100)
+                .map(|v| strategy.leader_index(4, ViewId(v)))
+                .collect::<Vec<_>>()
+        );
+        // Every member gets a turn
+        assert!((0..4).all(|index| leaders.contains(&index)));
+        assert_ne!(
+            leaders,
+            (0..100)
+                .map(|v| other_contract.leader_index(4, ViewId(v)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn it_skips_members_that_failed_to_propose() {
+        let view_id = ViewId(20);
+        let window = ReputationLeaderStrategy::history_window(view_id);
+        assert_eq!(window, 0..16);
+        // Member 2 was the leader of views 2, 6, 10 and 14, and view 10 was never committed
+        let strategy = ReputationLeaderStrategy::from_committed_views(0, (0..200).filter(|v| *v != 10));
+        assert_eq!(
+            strategy.failed_members(4, view_id).into_iter().collect::<Vec<_>>(),
+            vec![2]
+        );
+
+        let leaders = (20..24)
+            .map(|v| strategy.leader_index(4, ViewId(v)))
+            .collect::<Vec<_>>();
+        assert_eq!(leaders, vec![0, 1, 3, 3]);
+        // Once the failed view has left the window, the member is selected again
+        assert_eq!(strategy.leader_index(4, ViewId(118)), 2);
+    }
+
+    #[test]
+    fn it_blames_the_leader_that_was_selected_for_a_failed_view() {
+        // Member 2 failed view 2, so view 10 was led by member 3 instead of member 2, and it failed as well
+        let strategy = ReputationLeaderStrategy::from_committed_views(0, (0..200).filter(|v| *v != 2 && *v != 10));
+        assert_eq!(strategy.leader_index(4, ViewId(10)), 3);
+        let mut failed_members = strategy.failed_members(4, ViewId(20)).into_iter().collect::<Vec<_>>();
+        failed_members.sort_unstable();
+        assert_eq!(failed_members, vec![2, 3]);
+    }
+
+    #[test]
+    fn it_treats_views_before_the_history_as_committed() {
+        let strategy = ReputationLeaderStrategy::from_committed_views(10, 10..200);
+        assert!(strategy.failed_members(4, ViewId(20)).is_empty());
+        assert_eq!(strategy.leader_index(4, ViewId(22)), 2);
+    }
+
+    #[test]
+    fn it_falls_back_to_round_robin_if_every_member_failed() {
+        let strategy = ReputationLeaderStrategy::from_committed_views(0, vec![]);
+        let leaders = (20..24)
+            .map(|v| strategy.leader_index(4, ViewId(v)))
+            .collect::<Vec<_>>();
+        assert_eq!(leaders, vec![0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,193 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter},
+};
+
+use tari_common_types::types::{FixedHash, PublicKey};
+
+use crate::models::{
+    create_vote_challenge,
+    HotStuffMessage,
+    HotStuffMessageType,
+    Payload,
+    TreeNodeHash,
+    ValidatorSignature,
+    ViewId,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MisbehaviourKind {
+    /// A leader signed two different proposals for the same view
+    ConflictingProposals,
+    /// A replica signed votes for two different nodes in the same view and phase
+    ConflictingVotes,
+}
+
+impl MisbehaviourKind {
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            MisbehaviourKind::ConflictingProposals => 1,
+            MisbehaviourKind::ConflictingVotes => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for MisbehaviourKind {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(MisbehaviourKind::ConflictingProposals),
+            2 => Ok(MisbehaviourKind::ConflictingVotes),
+            _ => Err(format!("Not a valid misbehaviour kind: {}", value)),
+        }
+    }
+}
+
+impl Display for MisbehaviourKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MisbehaviourKind::ConflictingProposals => write!(f, "conflicting proposals"),
+            MisbehaviourKind::ConflictingVotes => write!(f, "conflicting votes"),
+        }
+    }
+}
+
+/// The signed part of a proposal or vote. This is all that is needed to verify the signature, so evidence does not
+/// have to keep the proposed node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedConsensusMessage {
+    pub message_type: HotStuffMessageType,
+    pub view_number: ViewId,
+    pub node_hash: TreeNodeHash,
+    pub signature: ValidatorSignature,
+}
+
+impl SignedConsensusMessage {
+    /// Returns the signed part of a proposal or vote, or None if the message is unsigned or is not for a node
+    pub fn from_message<TPayload: Payload>(message: &HotStuffMessage<TPayload>) -> Option<Self> {
+        let node_hash = match (message.node(), message.node_hash()) {
+            (Some(node), _) => node.calculate_hash(),
+            (None, Some(node_hash)) => *node_hash,
+            (None, None) => return None,
+        };
+        Some(Self {
+            message_type: message.message_type(),
+            view_number: message.view_number(),
+            node_hash,
+            signature: message.partial_sig()?.clone(),
+        })
+    }
+
+    pub fn verify(&self, contract_id: &FixedHash) -> bool {
+        self.signature.verify(&create_vote_challenge(
+            contract_id,
+            self.view_number,
+            self.message_type.certificate_type(),
+            &self.node_hash,
+        ))
+    }
+}
+
+/// Two messages signed by the same validator for different nodes in the same view and phase. Either message is
+/// harmless on its own; together they prove that the signer equivocated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MisbehaviourEvidence {
+    pub contract_id: FixedHash,
+    pub kind: MisbehaviourKind,
+    pub first: SignedConsensusMessage,
+    pub second: SignedConsensusMessage,
+}
+
+impl MisbehaviourEvidence {
+    pub fn offender(&self) -> &PublicKey {
+        self.first.signature.signer()
+    }
+
+    pub fn view_number(&self) -> ViewId {
+        self.first.view_number
+    }
+
+    /// Returns true if both messages are validly signed by the same validator, for the same view and phase but for
+    /// different nodes
+    pub fn verify(&self) -> bool {
+        self.first.signature.signer() == self.second.signature.signer() &&
+            self.first.view_number == self.second.view_number &&
+            self.first.message_type == self.second.message_type &&
+            self.first.node_hash != self.second.node_hash &&
+            self.first.verify(&self.contract_id) &&
+            self.second.verify(&self.contract_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+    use tari_common_types::types::PrivateKey;
+    use tari_crypto::keys::SecretKey;
+
+    use super::*;
+
+    fn signed_vote(secret_key: &PrivateKey, node_hash: TreeNodeHash) -> SignedConsensusMessage {
+        let contract_id = FixedHash::zero();
+        let challenge = create_vote_challenge(&contract_id, ViewId(3), HotStuffMessageType::Prepare, &node_hash);
+        SignedConsensusMessage {
+            message_type: HotStuffMessageType::Prepare,
+            view_number: ViewId(3),
+            node_hash,
+            signature: ValidatorSignature::sign(secret_key, &challenge).unwrap(),
+        }
+    }
+
+    #[test]
+    fn it_verifies_conflicting_votes() {
+        let secret_key = PrivateKey::random(&mut OsRng);
+        let evidence = MisbehaviourEvidence {
+            contract_id: FixedHash::zero(),
+            kind: MisbehaviourKind::ConflictingVotes,
+            first: signed_vote(&secret_key, TreeNodeHash::from([1u8; 32])),
+            second: signed_vote(&secret_key, TreeNodeHash::from([2u8; 32])),
+        };
+        assert!(evidence.verify());
+    }
+
+    #[test]
+    fn it_rejects_evidence_that_does_not_conflict() {
+        let secret_key = PrivateKey::random(&mut OsRng);
+        let same_node = MisbehaviourEvidence {
+            contract_id: FixedHash::zero(),
+            kind: MisbehaviourKind::ConflictingVotes,
+            first: signed_vote(&secret_key, TreeNodeHash::from([1u8; 32])),
+            second: signed_vote(&secret_key, TreeNodeHash::from([1u8; 32])),
+        };
+        assert!(!same_node.verify());
+
+        let different_signers = MisbehaviourEvidence {
+            second: signed_vote(&PrivateKey::random(&mut OsRng), TreeNodeHash::from([2u8; 32])),
+            ..same_node
+        };
+        assert!(!different_signers.verify());
+    }
+}
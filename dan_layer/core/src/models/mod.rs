@@ -22,16 +22,23 @@
 
 use std::{convert::TryFrom, fmt::Debug, hash::Hash};
 
+use serde::Deserialize;
+
 mod asset_definition;
 mod base_layer_metadata;
 mod base_layer_output;
 mod committee;
+mod committee_change;
 pub mod domain_events;
 mod error;
+mod execution_result;
 mod hashing;
 mod hot_stuff_message;
 mod hot_stuff_tree_node;
+mod instruction_result;
 mod instruction_set;
+mod leader_strategy;
+mod misbehaviour_evidence;
 mod node;
 mod payload;
 mod quorum_certificate;
@@ -39,6 +46,7 @@ mod sidechain_block;
 mod sidechain_metadata;
 mod tari_dan_payload;
 mod tree_node_hash;
+mod validator_signature;
 mod view;
 mod view_id;
 
@@ -46,18 +54,32 @@ pub use asset_definition::{AssetDefinition, InitialState};
 pub use base_layer_metadata::BaseLayerMetadata;
 pub use base_layer_output::{BaseLayerOutput, CheckpointOutput, CommitteeOutput};
 pub use committee::Committee;
+pub use committee_change::CommitteeChange;
 pub use error::ModelError;
-pub(crate) use hashing::{dan_layer_models_hasher, HOT_STUFF_MESSAGE_LABEL};
+pub use execution_result::ExecutionResult;
+pub(crate) use hashing::dan_layer_models_hasher;
 pub use hot_stuff_message::HotStuffMessage;
 pub use hot_stuff_tree_node::HotStuffTreeNode;
+pub use instruction_result::InstructionResult;
 pub use instruction_set::InstructionSet;
+pub use leader_strategy::{
+    HashLeaderStrategy,
+    LeaderSelection,
+    LeaderStrategy,
+    ReputationLeaderStrategy,
+    RoundRobinLeaderStrategy,
+    REPUTATION_HISTORY_VIEWS,
+};
+pub use misbehaviour_evidence::{MisbehaviourEvidence, MisbehaviourKind, SignedConsensusMessage};
 pub use node::Node;
 pub use payload::Payload;
-pub use quorum_certificate::QuorumCertificate;
+pub use quorum_certificate::{QuorumCertificate, QuorumSignatures};
 pub use sidechain_block::SideChainBlock;
 pub use sidechain_metadata::SidechainMetadata;
-pub use tari_dan_payload::{CheckpointData, TariDanPayload};
+pub use tari_dan_common_types::state_proof::StateValueProof;
+pub use tari_dan_payload::{hash_template_instructions, CheckpointData, TariDanPayload};
 pub use tree_node_hash::TreeNodeHash;
+pub use validator_signature::{create_vote_challenge, ValidatorSignature};
 pub use view::View;
 pub use view_id::ViewId;
 
@@ -87,13 +109,22 @@ impl AsRef<[u8]> for TokenId {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum HotStuffMessageType {
     NewView,
     Prepare,
     PreCommit,
     Commit,
     Decide,
+    Timeout,
+    /// A proposal in chained mode, justified by the QC of the previous view
+    Generic,
+    /// A vote for a chained mode proposal
+    GenericVote,
+    /// A member of the outgoing committee signing the final block of its epoch for the incoming committee
+    EpochHandover,
+    /// A member of the incoming committee asking the outgoing committee to send its final block again
+    EpochHandoverRequest,
     // Special type
     Genesis,
 }
@@ -106,9 +137,23 @@ impl HotStuffMessageType {
             HotStuffMessageType::PreCommit => 3,
             HotStuffMessageType::Commit => 4,
             HotStuffMessageType::Decide => 5,
+            HotStuffMessageType::Timeout => 6,
+            HotStuffMessageType::Generic => 7,
+            HotStuffMessageType::GenericVote => 8,
+            HotStuffMessageType::EpochHandover => 9,
+            HotStuffMessageType::EpochHandoverRequest => 10,
             HotStuffMessageType::Genesis => 255,
         }
     }
+
+    /// The type of the quorum certificate that a message of this type contributes to. Votes must sign the same
+    /// challenge as the certificate that they are collected into.
+    pub fn certificate_type(&self) -> HotStuffMessageType {
+        match self {
+            HotStuffMessageType::GenericVote => HotStuffMessageType::Generic,
+            message_type => *message_type,
+        }
+    }
 }
 
 impl TryFrom<u8> for HotStuffMessageType {
@@ -121,6 +166,11 @@ impl TryFrom<u8> for HotStuffMessageType {
             3 => Ok(HotStuffMessageType::PreCommit),
             4 => Ok(HotStuffMessageType::Commit),
             5 => Ok(HotStuffMessageType::Decide),
+            6 => Ok(HotStuffMessageType::Timeout),
+            7 => Ok(HotStuffMessageType::Generic),
+            8 => Ok(HotStuffMessageType::GenericVote),
+            9 => Ok(HotStuffMessageType::EpochHandover),
+            10 => Ok(HotStuffMessageType::EpochHandoverRequest),
             255 => Ok(HotStuffMessageType::Genesis),
             _ => Err("Not a value message type".to_string()),
         }
@@ -154,23 +204,25 @@ pub enum ConsensusWorkerState {
     Commit,
     Decide,
     NextView,
+    ViewTimeout,
+    ChainedView,
     Idle,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct ValidatorSignature {}
-
-impl ValidatorSignature {
-    pub fn from_bytes(_source: &[u8]) -> Self {
-        Self {}
-    }
-
-    pub fn combine(&self, other: &ValidatorSignature) -> ValidatorSignature {
-        other.clone()
-    }
+/// How a contract's committee reaches consensus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsensusMode {
+    /// Runs PREPARE, PRE-COMMIT, COMMIT and DECIDE for each block before proposing the next one
+    Basic,
+    /// Chained HotStuff: every view proposes a new block that carries the QC for the previous view, and a block is
+    /// committed once a three-chain of QCs has formed on top of it
+    Chained,
+}
 
-    pub fn to_bytes(&self) -> Vec<u8> {
-        vec![]
+impl Default for ConsensusMode {
+    fn default() -> Self {
+        ConsensusMode::Basic
     }
 }
 
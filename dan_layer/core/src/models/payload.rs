@@ -24,7 +24,8 @@ use std::fmt::Debug;
 
 use crate::models::ConsensusHash;
 
-pub trait Payload: Debug + Clone + Send + Sync + ConsensusHash {}
+/// The content of a proposed node. The default payload is empty.
+pub trait Payload: Debug + Clone + Send + Sync + ConsensusHash + Default {}
 
 impl Payload for &str {}
 
@@ -20,8 +20,21 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::collections::HashSet;
+
+use tari_common_types::types::{FixedHash, Signature};
+
 use crate::{
-    models::{HotStuffMessageType, TreeNodeHash, ValidatorSignature, ViewId},
+    models::{
+        create_vote_challenge,
+        Committee,
+        HotStuffMessageType,
+        ModelError,
+        TreeNodeHash,
+        ValidatorSignature,
+        ViewId,
+    },
+    services::infrastructure_services::NodeAddressable,
     storage::chain::DbQc,
 };
 
@@ -30,7 +43,7 @@ pub struct QuorumCertificate {
     message_type: HotStuffMessageType,
     node_hash: TreeNodeHash,
     view_number: ViewId,
-    signatures: Option<ValidatorSignature>,
+    signatures: QuorumSignatures,
 }
 
 impl QuorumCertificate {
@@ -38,25 +51,35 @@ impl QuorumCertificate {
         message_type: HotStuffMessageType,
         view_number: ViewId,
         node_hash: TreeNodeHash,
-        signature: Option<ValidatorSignature>,
+        signatures: QuorumSignatures,
     ) -> Self {
         Self {
             message_type,
             node_hash,
             view_number,
-            signatures: signature,
+            signatures,
         }
     }
 
-    pub fn genesis(node_hash: TreeNodeHash) -> Self {
+    /// The certificate that justifies the genesis proposal. It certifies the zero hash, which is the parent of the
+    /// genesis node.
+    pub fn genesis() -> Self {
         Self {
             message_type: HotStuffMessageType::Genesis,
-            node_hash,
+            node_hash: TreeNodeHash::zero(),
             view_number: 0.into(),
-            signatures: None,
+            signatures: QuorumSignatures::default(),
         }
     }
 
+    /// Returns true if this is exactly the unsigned genesis certificate
+    pub fn is_genesis(&self) -> bool {
+        self.message_type == HotStuffMessageType::Genesis &&
+            self.view_number.is_genesis() &&
+            self.node_hash == TreeNodeHash::zero() &&
+            self.signatures.is_empty()
+    }
+
     pub fn node_hash(&self) -> &TreeNodeHash {
         &self.node_hash
     }
@@ -69,15 +92,55 @@ impl QuorumCertificate {
         self.message_type
     }
 
-    pub fn signature(&self) -> Option<&ValidatorSignature> {
-        self.signatures.as_ref()
+    pub fn signatures(&self) -> &QuorumSignatures {
+        &self.signatures
+    }
+
+    /// Adds the vote signature of the committee member at `signer_index`
+    pub fn add_signature(&mut self, signer_index: usize, signature: Signature) {
+        self.signatures.insert(signer_index, signature);
     }
 
-    pub fn combine_sig(&mut self, partial_sig: &ValidatorSignature) {
-        self.signatures = match &self.signatures {
-            None => Some(partial_sig.clone()),
-            Some(s) => Some(s.combine(partial_sig)),
-        };
+    /// Checks that the certificate is signed by at least the consensus threshold of the committee and that every
+    /// signature is a valid vote for this certificate's node. The genesis certificate is not signed, so any other
+    /// certificate of the genesis type is rejected. Certificates for views before a committee handover are checked
+    /// against the committee that handed over.
+    pub fn verify<TAddr: NodeAddressable>(
+        &self,
+        contract_id: &FixedHash,
+        committee: &Committee<TAddr>,
+    ) -> Result<(), ModelError> {
+        if self.message_type == HotStuffMessageType::Genesis {
+            if self.is_genesis() {
+                return Ok(());
+            }
+            return Err(ModelError::InvalidGenesisQuorumCertificate);
+        }
+        let committee = committee.committee_for_view(self.view_number);
+        let threshold = committee.consensus_threshold();
+        if self.signatures.len() < threshold {
+            return Err(ModelError::InsufficientQuorumSignatures {
+                required: threshold,
+                actual: self.signatures.len(),
+            });
+        }
+        let challenge = create_vote_challenge(contract_id, self.view_number, self.message_type, &self.node_hash);
+        let mut seen_signers = HashSet::with_capacity(self.signatures.len());
+        for (signer_index, signature) in self.signatures.iter() {
+            let signer = committee
+                .members
+                .get(signer_index)
+                .and_then(NodeAddressable::public_key)
+                .ok_or(ModelError::UnknownQuorumSigner { signer_index })?;
+            // A member listed more than once in the committee must not be counted more than once towards the quorum
+            if !seen_signers.insert(signer) {
+                return Err(ModelError::DuplicateQuorumSigner { signer_index });
+            }
+            if !ValidatorSignature::new(signer.clone(), signature.clone()).verify(&challenge) {
+                return Err(ModelError::InvalidQuorumSignature { signer_index });
+            }
+        }
+        Ok(())
     }
 
     pub fn matches(&self, message_type: HotStuffMessageType, view_id: ViewId) -> bool {
@@ -92,7 +155,249 @@ impl From<DbQc> for QuorumCertificate {
             message_type: rec.message_type,
             node_hash: rec.node_hash,
             view_number: rec.view_number,
-            signatures: rec.signature,
+            signatures: rec.signatures,
+        }
+    }
+}
+
+/// The vote signatures in a quorum certificate. Signers are identified by their position in the committee using a
+/// bitmap, and the signatures are ordered by that position.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuorumSignatures {
+    signer_bitmap: Vec<u8>,
+    signatures: Vec<Signature>,
+}
+
+impl QuorumSignatures {
+    pub fn try_new(signer_bitmap: Vec<u8>, signatures: Vec<Signature>) -> Result<Self, ModelError> {
+        let num_signers = signer_bitmap.iter().map(|b| b.count_ones() as usize).sum::<usize>();
+        if num_signers != signatures.len() {
+            return Err(ModelError::InvalidSignerBitmap {
+                signers: num_signers,
+                signatures: signatures.len(),
+            });
+        }
+        Ok(Self {
+            signer_bitmap,
+            signatures,
+        })
+    }
+
+    pub fn signer_bitmap(&self) -> &[u8] {
+        &self.signer_bitmap
+    }
+
+    pub fn signatures(&self) -> &[Signature] {
+        &self.signatures
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    pub fn contains(&self, signer_index: usize) -> bool {
+        self.signer_bitmap
+            .get(signer_index / 8)
+            .map_or(false, |byte| byte & (1 << (signer_index % 8)) != 0)
+    }
+
+    /// Returns the committee index of each signer along with its signature
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Signature)> + '_ {
+        (0..self.signer_bitmap.len() * 8)
+            .filter(move |i| self.contains(*i))
+            .zip(self.signatures.iter())
+    }
+
+    /// Inserts or replaces the signature of the signer at `signer_index`
+    pub fn insert(&mut self, signer_index: usize, signature: Signature) {
+        let position = self.iter().take_while(|(i, _)| *i < signer_index).count();
+        if self.contains(signer_index) {
+            self.signatures[position] = signature;
+            return;
+        }
+        if self.signer_bitmap.len() <= signer_index / 8 {
+            self.signer_bitmap.resize(signer_index / 8 + 1, 0);
+        }
+        self.signer_bitmap[signer_index / 8] |= 1 << (signer_index % 8);
+        self.signatures.insert(position, signature);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+    use tari_common_types::types::{PrivateKey, PublicKey};
+    use tari_crypto::keys::{PublicKey as PublicKeyT, SecretKey};
+
+    use super::*;
+    use crate::models::HotStuffMessage;
+
+    fn create_committee(n: usize) -> (Vec<PrivateKey>, Committee<PublicKey>) {
+        let secret_keys = (0..n).map(|_| PrivateKey::random(&mut OsRng)).collect::<Vec<_>>();
+        let members = secret_keys.iter().map(PublicKey::from_secret_key).collect();
+        (secret_keys, Committee::new(members))
+    }
+
+    fn create_qc(contract_id: &FixedHash, secret_keys: &[(usize, &PrivateKey)]) -> QuorumCertificate {
+        let node_hash = TreeNodeHash::zero();
+        let mut qc = QuorumCertificate::new(HotStuffMessageType::Prepare, ViewId(5), node_hash, Default::default());
+        let challenge = create_vote_challenge(contract_id, ViewId(5), HotStuffMessageType::Prepare, &node_hash);
+        for (index, secret_key) in secret_keys {
+            let signature = ValidatorSignature::sign(secret_key, &challenge).unwrap();
+            qc.add_signature(*index, signature.signature().clone());
+        }
+        qc
+    }
+
+    #[test]
+    fn it_keeps_signatures_ordered_by_signer() {
+        let (secret_keys, _) = create_committee(10);
+        let contract_id = FixedHash::default();
+        let qc = create_qc(&contract_id, &[
+            (9, &secret_keys[9]),
+            (0, &secret_keys[0]),
+            (3, &secret_keys[3]),
+        ]);
+        let signers = qc.signatures().iter().map(|(i, _)| i).collect::<Vec<_>>();
+        assert_eq!(signers, vec![0, 3, 9]);
+        assert_eq!(qc.signatures().signer_bitmap(), &[0b0000_1001, 0b0000_0010]);
+    }
+
+    #[test]
+    fn it_verifies_a_quorum() {
+        let (secret_keys, committee) = create_committee(4);
+        let contract_id = FixedHash::default();
+        let signers = secret_keys.iter().enumerate().skip(1).collect::<Vec<_>>();
+        let qc = create_qc(&contract_id, &signers);
+        qc.verify(&contract_id, &committee).unwrap();
+
+        let other_contract = FixedHash::from([1u8; 32]);
+        assert!(matches!(
+            qc.verify(&other_contract, &committee),
+            Err(ModelError::InvalidQuorumSignature { .. })
+        ));
+    }
+
+    #[test]
+    fn it_rejects_too_few_signatures() {
+        let (secret_keys, committee) = create_committee(4);
+        let contract_id = FixedHash::default();
+        let qc = create_qc(&contract_id, &[(0, &secret_keys[0]), (1, &secret_keys[1])]);
+        assert!(matches!(
+            qc.verify(&contract_id, &committee),
+            Err(ModelError::InsufficientQuorumSignatures { required: 3, actual: 2 })
+        ));
+    }
+
+    #[test]
+    fn it_rejects_signatures_attributed_to_the_wrong_member() {
+        let (secret_keys, committee) = create_committee(4);
+        let contract_id = FixedHash::default();
+        let qc = create_qc(&contract_id, &[
+            (0, &secret_keys[0]),
+            (1, &secret_keys[1]),
+            (2, &secret_keys[3]),
+        ]);
+        assert!(matches!(
+            qc.verify(&contract_id, &committee),
+            Err(ModelError::InvalidQuorumSignature { signer_index: 2 })
+        ));
+    }
+
+    #[test]
+    fn it_rejects_a_signer_counted_twice() {
+        let (secret_keys, committee) = create_committee(3);
+        let mut members = committee.members.clone();
+        members.push(members[0].clone());
+        let committee = Committee::new(members);
+        let contract_id = FixedHash::default();
+        let qc = create_qc(&contract_id, &[
+            (0, &secret_keys[0]),
+            (1, &secret_keys[1]),
+            (3, &secret_keys[0]),
+        ]);
+        assert!(matches!(
+            qc.verify(&contract_id, &committee),
+            Err(ModelError::DuplicateQuorumSigner { signer_index: 3 })
+        ));
+    }
+
+    #[test]
+    fn it_verifies_a_qc_formed_from_generic_votes() {
+        let (secret_keys, committee) = create_committee(4);
+        let contract_id = FixedHash::default();
+        let node_hash = TreeNodeHash::from([1u8; 32]);
+        let mut qc = QuorumCertificate::new(HotStuffMessageType::Generic, ViewId(7), node_hash, Default::default());
+        for (index, secret_key) in secret_keys.iter().enumerate().take(3) {
+            let vote = HotStuffMessage::<String>::vote_generic(node_hash, ViewId(7), contract_id);
+            let signature = ValidatorSignature::sign(secret_key, &vote.create_signature_challenge()).unwrap();
+            qc.add_signature(index, signature.signature().clone());
+        }
+        qc.verify(&contract_id, &committee).unwrap();
+    }
+
+    #[test]
+    fn it_verifies_a_qc_signed_before_a_handover_with_the_outgoing_committee() {
+        let (outgoing_keys, outgoing) = create_committee(4);
+        let (incoming_keys, incoming) = create_committee(4);
+        let contract_id = FixedHash::default();
+        let outgoing_signers = outgoing_keys.iter().enumerate().take(3).collect::<Vec<_>>();
+        let incoming_signers = incoming_keys.iter().enumerate().take(3).collect::<Vec<_>>();
+        let outgoing_qc = create_qc(&contract_id, &outgoing_signers);
+        let incoming_qc = create_qc(&contract_id, &incoming_signers);
+
+        // The QCs are for view 5
+        let committee = incoming.clone().with_handover(ViewId(5), outgoing.clone());
+        outgoing_qc.verify(&contract_id, &committee).unwrap();
+        assert!(incoming_qc.verify(&contract_id, &committee).is_err());
+
+        let committee = incoming.with_handover(ViewId(4), outgoing);
+        incoming_qc.verify(&contract_id, &committee).unwrap();
+        assert!(outgoing_qc.verify(&contract_id, &committee).is_err());
+    }
+
+    #[test]
+    fn it_only_exempts_the_genesis_certificate_from_signatures() {
+        let (secret_keys, committee) = create_committee(4);
+        let contract_id = FixedHash::default();
+        QuorumCertificate::genesis().verify(&contract_id, &committee).unwrap();
+
+        let not_genesis = [
+            QuorumCertificate::new(
+                HotStuffMessageType::Genesis,
+                ViewId(5),
+                TreeNodeHash::zero(),
+                Default::default(),
+            ),
+            QuorumCertificate::new(
+                HotStuffMessageType::Genesis,
+                ViewId(0),
+                TreeNodeHash::from([1u8; 32]),
+                Default::default(),
+            ),
+            {
+                let mut qc = QuorumCertificate::genesis();
+                let challenge = create_vote_challenge(
+                    &contract_id,
+                    ViewId(0),
+                    HotStuffMessageType::Genesis,
+                    &TreeNodeHash::zero(),
+                );
+                let signature = ValidatorSignature::sign(&secret_keys[0], &challenge).unwrap();
+                qc.add_signature(0, signature.signature().clone());
+                qc
+            },
+        ];
+        for qc in not_genesis {
+            assert!(!qc.is_genesis());
+            assert!(matches!(
+                qc.verify(&contract_id, &committee),
+                Err(ModelError::InvalidGenesisQuorumCertificate)
+            ));
         }
     }
 }
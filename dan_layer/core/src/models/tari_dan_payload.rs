@@ -24,15 +24,20 @@ use std::fmt::Debug;
 
 use tari_common_types::types::FixedHash;
 use tari_crypto::hash::blake2::Blake256;
-use tari_dan_engine::instructions::Instruction;
+use tari_dan_engine::{instruction::InstructionSet as TemplateInstructionSet, instructions::Instruction};
+use tari_template_abi::encode;
 
-use super::{dan_layer_models_hasher, hashing::TARI_DAN_PAYLOAD_LABEL};
+use super::{
+    dan_layer_models_hasher,
+    hashing::{TARI_DAN_PAYLOAD_LABEL, TEMPLATE_INSTRUCTIONS_LABEL},
+};
 use crate::models::{ConsensusHash, InstructionSet, Payload};
 
 #[derive(Debug, Clone)]
 pub struct TariDanPayload {
     hash: FixedHash,
     instruction_set: InstructionSet,
+    template_instructions: Vec<TemplateInstructionSet>,
     checkpoint: Option<CheckpointData>,
 }
 
@@ -41,23 +46,41 @@ impl TariDanPayload {
         let mut result = Self {
             hash: FixedHash::zero(),
             instruction_set,
+            template_instructions: vec![],
             checkpoint,
         };
         result.hash = result.calculate_hash();
         result
     }
 
-    pub fn destruct(self) -> (InstructionSet, Option<CheckpointData>) {
-        (self.instruction_set, self.checkpoint)
+    /// Adds instruction sets to be executed by the WASM template engine, after the legacy instructions
+    pub fn with_template_instructions(mut self, template_instructions: Vec<TemplateInstructionSet>) -> Self {
+        self.template_instructions = template_instructions;
+        self.hash = self.calculate_hash();
+        self
+    }
+
+    pub fn destruct(self) -> (InstructionSet, Vec<TemplateInstructionSet>, Option<CheckpointData>) {
+        (self.instruction_set, self.template_instructions, self.checkpoint)
     }
 
     pub fn instructions(&self) -> &[Instruction] {
         self.instruction_set.instructions()
     }
 
+    pub fn template_instructions(&self) -> &[TemplateInstructionSet] {
+        &self.template_instructions
+    }
+
     fn calculate_hash(&self) -> FixedHash {
-        let result =
+        let mut result =
             dan_layer_models_hasher::<Blake256>(TARI_DAN_PAYLOAD_LABEL).chain(self.instruction_set.consensus_hash());
+        for instruction_set in &self.template_instructions {
+            result = result.chain((instruction_set.instructions.len() as u64).to_le_bytes());
+            for instruction in &instruction_set.instructions {
+                result = result.chain(encode(instruction).expect("encoding to a Vec cannot fail"));
+            }
+        }
 
         let mut out = [0u8; 32];
 
@@ -72,12 +95,30 @@ impl TariDanPayload {
     }
 }
 
+/// Returns the hash that identifies a set of template instructions, e.g. to reserve it in the mempool
+pub fn hash_template_instructions(instruction_set: &TemplateInstructionSet) -> FixedHash {
+    let mut hasher = dan_layer_models_hasher::<Blake256>(TEMPLATE_INSTRUCTIONS_LABEL)
+        .chain((instruction_set.instructions.len() as u64).to_le_bytes());
+    for instruction in &instruction_set.instructions {
+        hasher = hasher.chain(encode(instruction).expect("encoding to a Vec cannot fail"));
+    }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(hasher.finalize().as_ref());
+    out.into()
+}
+
 impl ConsensusHash for TariDanPayload {
     fn consensus_hash(&self) -> &[u8] {
         self.hash.as_slice()
     }
 }
 
+impl Default for TariDanPayload {
+    fn default() -> Self {
+        Self::new(InstructionSet::empty(), None)
+    }
+}
+
 impl Payload for TariDanPayload {}
 
 #[derive(Debug, Clone, Default)]
@@ -0,0 +1,124 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use digest::Digest;
+use rand::rngs::OsRng;
+use tari_common_types::types::{FixedHash, PrivateKey, PublicKey, Signature};
+use tari_crypto::{
+    hash::blake2::Blake256,
+    keys::PublicKey as PublicKeyT,
+    signatures::SchnorrSignatureError,
+    tari_utilities::ByteArray,
+};
+
+use super::{
+    dan_layer_models_hasher,
+    hashing::{HOT_STUFF_MESSAGE_LABEL, VALIDATOR_SIGNATURE_LABEL},
+};
+use crate::models::{HotStuffMessageType, TreeNodeHash, ViewId};
+
+/// A validator's vote signature, made with the validator's comms key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorSignature {
+    signer: PublicKey,
+    signature: Signature,
+}
+
+impl ValidatorSignature {
+    pub fn new(signer: PublicKey, signature: Signature) -> Self {
+        Self { signer, signature }
+    }
+
+    pub fn sign(secret_key: &PrivateKey, message: &[u8]) -> Result<Self, SchnorrSignatureError> {
+        let signer = PublicKey::from_secret_key(secret_key);
+        let (nonce, public_nonce) = PublicKey::random_keypair(&mut OsRng);
+        let challenge = Self::build_challenge(&signer, &public_nonce, message);
+        let signature = Signature::sign(secret_key.clone(), nonce, &challenge)?;
+        Ok(Self { signer, signature })
+    }
+
+    pub fn signer(&self) -> &PublicKey {
+        &self.signer
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Returns true if this is a valid signature of `message` by the signer
+    pub fn verify(&self, message: &[u8]) -> bool {
+        let challenge = Self::build_challenge(&self.signer, self.signature.get_public_nonce(), message);
+        self.signature.verify_challenge(&self.signer, &challenge)
+    }
+
+    fn build_challenge(signer: &PublicKey, public_nonce: &PublicKey, message: &[u8]) -> Vec<u8> {
+        dan_layer_models_hasher::<Blake256>(VALIDATOR_SIGNATURE_LABEL)
+            .chain(signer.as_bytes())
+            .chain(public_nonce.as_bytes())
+            .chain(message)
+            .finalize()
+            .as_ref()
+            .to_vec()
+    }
+}
+
+/// The message validators sign when voting for a node. A quorum certificate is verified by checking the signatures of
+/// its signers over this challenge.
+pub fn create_vote_challenge(
+    contract_id: &FixedHash,
+    view_number: ViewId,
+    message_type: HotStuffMessageType,
+    node_hash: &TreeNodeHash,
+) -> Vec<u8> {
+    dan_layer_models_hasher::<Blake256>(HOT_STUFF_MESSAGE_LABEL)
+        .chain(contract_id.as_slice())
+        .chain(&[message_type.as_u8()])
+        .chain(view_number.as_u64().to_le_bytes())
+        .chain(node_hash.as_bytes())
+        .finalize()
+        .as_ref()
+        .to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use tari_crypto::keys::SecretKey;
+
+    use super::*;
+
+    #[test]
+    fn it_verifies_a_signed_message() {
+        let secret_key = PrivateKey::random(&mut OsRng);
+        let signature = ValidatorSignature::sign(&secret_key, b"vote").unwrap();
+        assert_eq!(*signature.signer(), PublicKey::from_secret_key(&secret_key));
+        assert!(signature.verify(b"vote"));
+        assert!(!signature.verify(b"another vote"));
+    }
+
+    #[test]
+    fn it_rejects_a_signature_from_another_signer() {
+        let signature = ValidatorSignature::sign(&PrivateKey::random(&mut OsRng), b"vote").unwrap();
+        let (_, other) = PublicKey::random_keypair(&mut OsRng);
+        let forged = ValidatorSignature::new(other, signature.signature().clone());
+        assert!(!forged.verify(b"vote"));
+    }
+}
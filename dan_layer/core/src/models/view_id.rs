@@ -26,7 +26,7 @@ use std::{
     ops::{Add, Sub},
 };
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct ViewId(pub u64);
 
 impl ViewId {
@@ -59,7 +59,13 @@ impl ViewId {
 
 impl PartialOrd for ViewId {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.0.partial_cmp(&other.0)
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ViewId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
     }
 }
 
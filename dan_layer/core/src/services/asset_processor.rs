@@ -20,23 +20,58 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::convert::TryInto;
+use std::{convert::TryInto, fs};
 
 use tari_core::transactions::transaction_components::TemplateParameter;
 use tari_dan_engine::{
+    crypto::create_key_pair,
     flow::FlowFactory,
+    instruction::{
+        Instruction as EngineInstruction,
+        InstructionBuilder,
+        InstructionProcessor,
+        InstructionSet as EngineInstructionSet,
+    },
     instructions::Instruction,
+    packager::Package,
     state::{StateDbUnitOfWork, StateDbUnitOfWorkReader},
-    wasm::WasmModuleFactory,
+    wasm::{WasmModule, WasmModuleDefinition},
 };
 
 use crate::{
     digital_assets_error::DigitalAssetError,
-    models::{AssetDefinition, InstructionSet},
+    models::{AssetDefinition, InstructionResult, InstructionSet},
+    services::state_db_runtime_interface::{ReadOnlyStateDbRuntimeInterface, StateDbRuntimeInterface},
     template_command::ExecutionResult,
     templates::{tip002_template, tip004_template, tip721_template},
 };
 
+/// Template id of `invoke_method` and `invoke_read_method` requests that call WASM templates rather than one of the
+/// built-in TIP templates. The args of such a request are the encoded template instructions (see
+/// [`encode_template_instructions`]) and the result of a read is the encoded list of raw instruction results.
+pub const WASM_TEMPLATE_ID: u32 = 0;
+
+pub fn encode_template_instructions(instructions: &[EngineInstruction]) -> Vec<u8> {
+    tari_template_abi::encode(&instructions.to_vec()).expect("encoding to a Vec cannot fail")
+}
+
+/// Decodes the args of a [`WASM_TEMPLATE_ID`] request into an instruction set. Instruction signatures are not checked
+/// by the engine yet, so the set is signed with an ephemeral key.
+pub fn decode_template_instructions(args: &[u8]) -> Result<EngineInstructionSet, DigitalAssetError> {
+    let instructions = tari_template_abi::decode::<Vec<EngineInstruction>>(args)
+        .map_err(|e| DigitalAssetError::InvalidTemplateInstructions(e.to_string()))?;
+    let (secret_key, _) = create_key_pair();
+    let mut builder = InstructionBuilder::new();
+    for instruction in instructions {
+        builder.add_instruction(instruction);
+    }
+    Ok(builder.sign(&secret_key).build())
+}
+
+pub fn encode_template_results(results: &[Vec<u8>]) -> Vec<u8> {
+    tari_template_abi::encode(&results.to_vec()).expect("encoding to a Vec cannot fail")
+}
+
 pub trait AssetProcessor: Sync + Send + 'static {
     // purposefully made sync, because instructions should be run in order, and complete before the
     // next one starts. There may be a better way to enforce this though...
@@ -44,43 +79,74 @@ pub trait AssetProcessor: Sync + Send + 'static {
         &self,
         instruction: &Instruction,
         db: &mut TUnitOfWork,
-    ) -> Result<(), DigitalAssetError>;
+    ) -> Result<InstructionResult, DigitalAssetError>;
 
     fn invoke_read_method<TUnitOfWorkReader: StateDbUnitOfWorkReader>(
         &self,
         instruction: &Instruction,
         state_db: &TUnitOfWorkReader,
     ) -> Result<Option<Vec<u8>>, DigitalAssetError>;
+
+    /// Executes WASM template instructions against the current state without changing it, returning the raw result
+    /// of each instruction
+    fn invoke_view_method<TUnitOfWorkReader: StateDbUnitOfWorkReader>(
+        &self,
+        instruction_set: EngineInstructionSet,
+        state_db: &TUnitOfWorkReader,
+    ) -> Result<Vec<Vec<u8>>, DigitalAssetError>;
+
+    /// Executes WASM template instructions, writing any state changes to the unit of work. Returns the raw result of
+    /// each instruction. Nothing is written if any of the instructions fails.
+    fn execute_template_instructions<TUnitOfWork: StateDbUnitOfWork>(
+        &self,
+        instruction_set: EngineInstructionSet,
+        state_db: &TUnitOfWork,
+    ) -> Result<Vec<Vec<u8>>, DigitalAssetError>;
 }
 
 #[derive(Default, Clone)]
 pub struct ConcreteAssetProcessor {
     _asset_definition: AssetDefinition,
     template_factory: TemplateFactory,
-    _wasm_factory: WasmModuleFactory,
     _function_interface: FunctionInterface,
-    _flow_factory: FlowFactory,
+    flow_factory: FlowFactory,
+    package: Option<Package>,
 }
 
 impl ConcreteAssetProcessor {
-    pub fn new(asset_definition: AssetDefinition) -> Self {
-        Self {
-            _wasm_factory: WasmModuleFactory::new(&asset_definition.wasm_modules, &asset_definition.wasm_functions),
-            _flow_factory: FlowFactory::new(&asset_definition.flow_functions),
+    /// Creates an asset processor for the given asset, loading its WASM template modules into a package
+    pub fn new(asset_definition: AssetDefinition) -> Result<Self, DigitalAssetError> {
+        let package = load_package(&asset_definition.wasm_modules)?;
+        Ok(Self {
+            flow_factory: FlowFactory::new(&asset_definition.flow_functions)?,
             _asset_definition: asset_definition,
             template_factory: Default::default(),
             _function_interface: FunctionInterface {},
-        }
+            package,
+        })
     }
 }
 
 impl AssetProcessor for ConcreteAssetProcessor {
+    /// Executes the instruction with the asset's flow function of the same name as the method, if there is one, or
+    /// otherwise with the method of the instruction's template
     fn execute_instruction<TUnitOfWork: StateDbUnitOfWork>(
         &self,
         instruction: &Instruction,
         state_db: &mut TUnitOfWork,
-    ) -> Result<(), DigitalAssetError> {
-        self.template_factory.invoke_write_method(instruction, state_db)
+    ) -> Result<InstructionResult, DigitalAssetError> {
+        if self.flow_factory.contains_function(instruction.method()) {
+            let output = self.flow_factory.invoke_write_method(
+                instruction.method().to_string(),
+                instruction,
+                state_db.clone(),
+            )?;
+            *state_db = output.state_db;
+            return Ok(InstructionResult { events: output.events });
+        }
+
+        self.template_factory.invoke_write_method(instruction, state_db)?;
+        Ok(InstructionResult::default())
     }
 
     fn invoke_read_method<TUnitOfWork: StateDbUnitOfWorkReader>(
@@ -90,6 +156,46 @@ impl AssetProcessor for ConcreteAssetProcessor {
     ) -> Result<Option<Vec<u8>>, DigitalAssetError> {
         self.template_factory.invoke_read_method(instruction, state_db)
     }
+
+    fn invoke_view_method<TUnitOfWork: StateDbUnitOfWorkReader>(
+        &self,
+        instruction_set: EngineInstructionSet,
+        state_db: &TUnitOfWork,
+    ) -> Result<Vec<Vec<u8>>, DigitalAssetError> {
+        let package = self.package.clone().ok_or(DigitalAssetError::NoTemplatePackage)?;
+        let processor = InstructionProcessor::new(ReadOnlyStateDbRuntimeInterface::new(state_db.clone()), package);
+        let results = processor.execute_read_only(instruction_set)?;
+        Ok(results.into_iter().map(|result| result.raw).collect())
+    }
+
+    fn execute_template_instructions<TUnitOfWork: StateDbUnitOfWork>(
+        &self,
+        instruction_set: EngineInstructionSet,
+        state_db: &TUnitOfWork,
+    ) -> Result<Vec<Vec<u8>>, DigitalAssetError> {
+        let package = self.package.clone().ok_or(DigitalAssetError::NoTemplatePackage)?;
+        let runtime_interface = StateDbRuntimeInterface::new(state_db.clone());
+        let processor = InstructionProcessor::new(runtime_interface.clone(), package);
+        let results = processor.execute(instruction_set)?;
+        runtime_interface.commit_changes()?;
+        Ok(results.into_iter().map(|result| result.raw).collect())
+    }
+}
+
+fn load_package(wasm_modules: &[WasmModuleDefinition]) -> Result<Option<Package>, DigitalAssetError> {
+    if wasm_modules.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = Package::builder();
+    for module in wasm_modules {
+        let code = fs::read(&module.path).map_err(|source| DigitalAssetError::TemplateModuleReadError {
+            name: module.name.clone(),
+            source,
+        })?;
+        builder.add_wasm_module(WasmModule::from_code(code));
+    }
+    Ok(Some(builder.build()?))
 }
 
 #[derive(Clone, Default)]
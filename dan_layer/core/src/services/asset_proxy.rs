@@ -35,6 +35,7 @@ use tokio_stream::StreamExt;
 use crate::{
     models::BaseLayerOutput,
     services::{
+        decode_template_instructions,
         validator_node_rpc_client::ValidatorNodeRpcClient,
         BaseNodeClient,
         MempoolService,
@@ -66,6 +67,24 @@ pub trait AssetProxy: Send + Sync {
         args: Vec<u8>,
         sender: PublicKey,
     ) -> Result<Option<Vec<u8>>, DigitalAssetError>;
+
+    /// Runs encoded WASM template instructions read-only on a member of the contract's committee, returning the
+    /// encoded list of raw instruction results
+    async fn invoke_view_method(
+        &self,
+        contract_id: &FixedHash,
+        instructions: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<Vec<u8>, DigitalAssetError>;
+
+    /// Submits encoded WASM template instructions to the mempool of the contract's committee, or to the local mempool
+    /// if this node is processing the contract
+    async fn submit_template_instructions(
+        &self,
+        contract_id: &FixedHash,
+        instructions: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<(), DigitalAssetError>;
 }
 
 enum InvokeType {
@@ -132,16 +151,7 @@ impl<TServiceSpecification: ServiceSpecification<Addr = PublicKey>> ConcreteAsse
         Ok(resp)
     }
 
-    #[allow(clippy::for_loops_over_fallibles)]
-    async fn forward_to_committee(
-        &self,
-        contract_id: FixedHash,
-        invoke_type: InvokeType,
-        template_id: TemplateId,
-        method: String,
-        args: Vec<u8>,
-        sender: PublicKey,
-    ) -> Result<Option<Vec<u8>>, DigitalAssetError> {
+    async fn get_committee(&self, contract_id: FixedHash) -> Result<Vec<PublicKey>, DigitalAssetError> {
         let mut base_node_client = self.base_node_client.clone();
         let tip = base_node_client.get_tip_info().await?;
         let mut outputs = base_node_client
@@ -164,7 +174,8 @@ impl<TServiceSpecification: ServiceSpecification<Addr = PublicKey>> ConcreteAsse
 
         let committee = constitution
             .get_side_chain_committee()
-            .ok_or(DigitalAssetError::NoCommitteeForAsset)?;
+            .ok_or(DigitalAssetError::NoCommitteeForAsset)?
+            .to_vec();
 
         debug!(
             target: LOG_TARGET,
@@ -173,6 +184,48 @@ impl<TServiceSpecification: ServiceSpecification<Addr = PublicKey>> ConcreteAsse
             committee.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
         );
 
+        Ok(committee)
+    }
+
+    async fn forward_invoke_view_to_node(
+        &self,
+        member: &TServiceSpecification::Addr,
+        contract_id: FixedHash,
+        instructions: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<Vec<u8>, DigitalAssetError> {
+        let mut client = self.validator_node_client_factory.create_client(member);
+        let resp = client.invoke_view_method(&contract_id, instructions, sender).await?;
+        Ok(resp)
+    }
+
+    async fn forward_template_instructions_to_node(
+        &self,
+        member: &TServiceSpecification::Addr,
+        contract_id: FixedHash,
+        instructions: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<(), DigitalAssetError> {
+        debug!(target: LOG_TARGET, "Forwarding template instructions to {}", member);
+        let mut client = self.validator_node_client_factory.create_client(member);
+        client
+            .submit_template_instructions(&contract_id, instructions, sender)
+            .await?;
+        Ok(())
+    }
+
+    #[allow(clippy::for_loops_over_fallibles)]
+    async fn forward_to_committee(
+        &self,
+        contract_id: FixedHash,
+        invoke_type: InvokeType,
+        template_id: TemplateId,
+        method: String,
+        args: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<Option<Vec<u8>>, DigitalAssetError> {
+        let committee = self.get_committee(contract_id).await?;
+
         match invoke_type {
             InvokeType::InvokeReadMethod => {
                 let mut tasks = FuturesUnordered::new();
@@ -284,4 +337,63 @@ impl<TServiceSpecification: ServiceSpecification<Addr = PublicKey>> AssetProxy
         )
         .await
     }
+
+    async fn invoke_view_method(
+        &self,
+        contract_id: &FixedHash,
+        instructions: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<Vec<u8>, DigitalAssetError> {
+        let committee = self.get_committee(*contract_id).await?;
+        let mut tasks = FuturesUnordered::new();
+        for member in committee.iter().take(self.max_clients_to_ask) {
+            tasks.push(self.forward_invoke_view_to_node(member, *contract_id, instructions.clone(), sender.clone()));
+        }
+
+        while let Some(result) = tasks.next().await {
+            match result {
+                Ok(data) => return Ok(data),
+                Err(err) => {
+                    error!(target: LOG_TARGET, "Committee member responded with error:{}", err);
+                },
+            }
+        }
+
+        Err(DigitalAssetError::NoResponsesFromCommittee)
+    }
+
+    async fn submit_template_instructions(
+        &self,
+        contract_id: &FixedHash,
+        instructions: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<(), DigitalAssetError> {
+        if self.db_factory.get_state_db(contract_id)?.is_some() {
+            let instruction_set = decode_template_instructions(&instructions)?;
+            let mut mempool = self.mempool.clone();
+            return mempool.submit_template_instructions(instruction_set).await;
+        }
+
+        let committee = self.get_committee(*contract_id).await?;
+        let mut tasks = FuturesUnordered::new();
+        for member in committee.iter().take(self.max_clients_to_ask) {
+            tasks.push(self.forward_template_instructions_to_node(
+                member,
+                *contract_id,
+                instructions.clone(),
+                sender.clone(),
+            ));
+        }
+
+        while let Some(result) = tasks.next().await {
+            match result {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    error!(target: LOG_TARGET, "Committee member responded with error:{}", err);
+                },
+            }
+        }
+
+        Err(DigitalAssetError::NoResponsesFromCommittee)
+    }
 }
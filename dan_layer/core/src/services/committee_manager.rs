@@ -20,11 +20,13 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::convert::TryFrom;
+
 use tari_common_types::types::PublicKey;
 
 use crate::{
     digital_assets_error::DigitalAssetError,
-    models::{BaseLayerOutput, Committee},
+    models::{BaseLayerOutput, Committee, CommitteeChange, ViewId},
     services::infrastructure_services::NodeAddressable,
 };
 
@@ -32,15 +34,38 @@ pub trait CommitteeManager<TAddr: NodeAddressable> {
     fn current_committee(&self) -> Result<&Committee<TAddr>, DigitalAssetError>;
 
     fn read_from_constitution(&mut self, output: BaseLayerOutput) -> Result<(), DigitalAssetError>;
+
+    /// Schedules the committee of a contract amendment to take over the contract at the amendment's activation height.
+    /// Amendments that do not activate after the current committee are ignored.
+    fn read_from_amendment(&mut self, output: BaseLayerOutput) -> Result<(), DigitalAssetError>;
+
+    /// The committee change that has been scheduled but not yet activated
+    fn pending_change(&self) -> Option<&CommitteeChange<TAddr>>;
+
+    /// Makes the scheduled committee current. The outgoing committee is kept to verify the certificates that it
+    /// signed, up to and including `handover_view`.
+    fn activate_pending_change(&mut self, handover_view: ViewId) -> Result<(), DigitalAssetError>;
 }
 
 pub struct ConcreteCommitteeManager {
     committee: Committee<PublicKey>,
+    pending_change: Option<CommitteeChange<PublicKey>>,
+    activation_height: u64,
 }
 
 impl ConcreteCommitteeManager {
     pub fn new(committee: Committee<PublicKey>) -> Self {
-        Self { committee }
+        Self {
+            committee,
+            pending_change: None,
+            activation_height: 0,
+        }
+    }
+
+    /// Schedules a committee change without reading it from a contract amendment
+    pub fn with_pending_change(mut self, change: CommitteeChange<PublicKey>) -> Self {
+        self.pending_change = Some(change);
+        self
     }
 }
 
@@ -53,6 +78,32 @@ impl CommitteeManager<PublicKey> for ConcreteCommitteeManager {
         // TODO: better error
         let committee = output.get_side_chain_committee().unwrap();
         self.committee = Committee::new(committee.to_vec());
+        self.pending_change = None;
+        self.activation_height = 0;
+        Ok(())
+    }
+
+    fn read_from_amendment(&mut self, output: BaseLayerOutput) -> Result<(), DigitalAssetError> {
+        let change = CommitteeChange::try_from(output)?;
+        if change.activation_height > self.activation_height {
+            self.pending_change = Some(change);
+        }
+        Ok(())
+    }
+
+    fn pending_change(&self) -> Option<&CommitteeChange<PublicKey>> {
+        self.pending_change.as_ref()
+    }
+
+    fn activate_pending_change(&mut self, handover_view: ViewId) -> Result<(), DigitalAssetError> {
+        let change = self
+            .pending_change
+            .take()
+            .ok_or_else(|| DigitalAssetError::InvalidLogicPath {
+                reason: "Tried to activate a committee change, but no change was pending".to_string(),
+            })?;
+        self.committee = Committee::new(change.members).with_handover(handover_view, self.committee.clone());
+        self.activation_height = change.activation_height;
         Ok(())
     }
 }
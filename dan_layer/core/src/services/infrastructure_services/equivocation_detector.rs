@@ -0,0 +1,281 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::collections::{HashMap, HashSet};
+
+use log::*;
+use tari_common_types::types::{FixedHash, PublicKey};
+
+use crate::{
+    models::{
+        HotStuffMessage,
+        HotStuffMessageType,
+        MisbehaviourEvidence,
+        MisbehaviourKind,
+        Payload,
+        SignedConsensusMessage,
+        ViewId,
+    },
+    services::infrastructure_services::NodeAddressable,
+};
+
+const LOG_TARGET: &str = "tari::dan::services::infrastructure_services::equivocation_detector";
+
+/// The number of views below the local view for which signed messages are remembered
+const VIEW_RETENTION: u64 = 100;
+/// The number of views above the local view for which signed messages are checked
+const VIEW_LOOKAHEAD: u64 = 10;
+
+type MessageKey = (MisbehaviourKind, ViewId, HotStuffMessageType, PublicKey);
+
+/// Remembers the first signed proposal and vote of each committee member in each view and phase, and records evidence
+/// when a member signs a message for a different node. Inbound connection services pass every message they receive
+/// through the detector, because a conflicting message is often never taken by a consensus state.
+///
+/// Only messages signed by committee members for views near the local view are remembered, so the memory used is
+/// bounded by the committee size.
+pub struct EquivocationDetector {
+    contract_id: FixedHash,
+    members: HashSet<PublicKey>,
+    first_messages: HashMap<MessageKey, (SignedConsensusMessage, bool)>,
+    current_view: ViewId,
+    evidence: Vec<MisbehaviourEvidence>,
+}
+
+impl EquivocationDetector {
+    pub fn new<TAddr: NodeAddressable>(contract_id: FixedHash, committee: &[TAddr]) -> Self {
+        Self {
+            contract_id,
+            members: committee
+                .iter()
+                .filter_map(|member| member.public_key())
+                .cloned()
+                .collect(),
+            first_messages: HashMap::new(),
+            current_view: ViewId(0),
+            evidence: Vec::new(),
+        }
+    }
+
+    /// Moves the window of checked views to follow the view of the local consensus worker, forgetting the messages of
+    /// views that have left it
+    pub fn set_current_view(&mut self, view_id: ViewId) {
+        if view_id <= self.current_view {
+            return;
+        }
+        self.current_view = view_id;
+        self.first_messages
+            .retain(|(_, view, _, _), _| view.as_u64().saturating_add(VIEW_RETENTION) >= view_id.as_u64());
+    }
+
+    /// Checks a message received from `sender`. Messages that are not signed by the sender, or whose sender is not a
+    /// committee member, are ignored, because they cannot be used as evidence.
+    pub fn observe<TAddr: NodeAddressable, TPayload: Payload>(
+        &mut self,
+        sender: &TAddr,
+        message: &HotStuffMessage<TPayload>,
+    ) {
+        if !message.is_signed_by(&self.contract_id, sender) {
+            return;
+        }
+        let signed = match SignedConsensusMessage::from_message(message) {
+            Some(signed) => signed,
+            None => return,
+        };
+        if !self.members.contains(signed.signature.signer()) {
+            return;
+        }
+        let view = signed.view_number.as_u64();
+        let current_view = self.current_view.as_u64();
+        if view.saturating_add(VIEW_RETENTION) < current_view || view > current_view.saturating_add(VIEW_LOOKAHEAD) {
+            return;
+        }
+
+        let kind = if message.node().is_some() {
+            MisbehaviourKind::ConflictingProposals
+        } else {
+            MisbehaviourKind::ConflictingVotes
+        };
+        let key = (
+            kind,
+            signed.view_number,
+            signed.message_type,
+            signed.signature.signer().clone(),
+        );
+        match self.first_messages.get_mut(&key) {
+            Some((first, reported)) => {
+                if first.node_hash == signed.node_hash || *reported {
+                    return;
+                }
+                warn!(
+                    target: LOG_TARGET,
+                    "{} signed {} in view {}", sender, kind, signed.view_number
+                );
+                *reported = true;
+                self.evidence.push(MisbehaviourEvidence {
+                    contract_id: self.contract_id,
+                    kind,
+                    first: first.clone(),
+                    second: signed,
+                });
+            },
+            None => {
+                self.first_messages.insert(key, (signed, false));
+            },
+        }
+    }
+
+    /// Takes the evidence recorded since the last call
+    pub fn take_evidence(&mut self) -> Vec<MisbehaviourEvidence> {
+        std::mem::take(&mut self.evidence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+    use tari_common_types::types::PrivateKey;
+    use tari_crypto::keys::{PublicKey as PublicKeyT, SecretKey};
+
+    use super::*;
+    use crate::models::{TreeNodeHash, ValidatorSignature};
+
+    fn signed_vote(secret_key: &PrivateKey, node_hash: TreeNodeHash, view_number: ViewId) -> HotStuffMessage<String> {
+        let mut message = HotStuffMessage::vote_prepare(node_hash, view_number, FixedHash::zero());
+        message.add_partial_sig(ValidatorSignature::sign(secret_key, &message.create_signature_challenge()).unwrap());
+        message
+    }
+
+    #[test]
+    fn it_records_conflicting_votes_once() {
+        let secret_key = PrivateKey::random(&mut OsRng);
+        let sender = PublicKey::from_secret_key(&secret_key);
+        let mut detector = EquivocationDetector::new(FixedHash::zero(), &[sender.clone()]);
+        detector.observe(
+            &sender,
+            &signed_vote(&secret_key, TreeNodeHash::from([1u8; 32]), ViewId(1)),
+        );
+        detector.observe(
+            &sender,
+            &signed_vote(&secret_key, TreeNodeHash::from([1u8; 32]), ViewId(1)),
+        );
+        detector.observe(
+            &sender,
+            &signed_vote(&secret_key, TreeNodeHash::from([2u8; 32]), ViewId(2)),
+        );
+        assert!(detector.take_evidence().is_empty());
+
+        detector.observe(
+            &sender,
+            &signed_vote(&secret_key, TreeNodeHash::from([3u8; 32]), ViewId(1)),
+        );
+        detector.observe(
+            &sender,
+            &signed_vote(&secret_key, TreeNodeHash::from([4u8; 32]), ViewId(1)),
+        );
+        let evidence = detector.take_evidence();
+        assert_eq!(evidence.len(), 1);
+        assert_eq!(evidence[0].kind, MisbehaviourKind::ConflictingVotes);
+        assert_eq!(*evidence[0].offender(), sender);
+        assert_eq!(evidence[0].view_number(), ViewId(1));
+        assert!(evidence[0].verify());
+        assert!(detector.take_evidence().is_empty());
+    }
+
+    #[test]
+    fn it_ignores_messages_not_signed_by_the_sender() {
+        let secret_key = PrivateKey::random(&mut OsRng);
+        let (_, other) = PublicKey::random_keypair(&mut OsRng);
+        let mut detector = EquivocationDetector::new(FixedHash::zero(), &[
+            PublicKey::from_secret_key(&secret_key),
+            other.clone(),
+        ]);
+        detector.observe(
+            &other,
+            &signed_vote(&secret_key, TreeNodeHash::from([1u8; 32]), ViewId(1)),
+        );
+        detector.observe(
+            &other,
+            &signed_vote(&secret_key, TreeNodeHash::from([2u8; 32]), ViewId(1)),
+        );
+        detector.observe(
+            &other,
+            &HotStuffMessage::<String>::vote_prepare(TreeNodeHash::from([3u8; 32]), ViewId(1), FixedHash::zero()),
+        );
+        assert!(detector.take_evidence().is_empty());
+    }
+
+    #[test]
+    fn it_ignores_messages_from_non_members() {
+        let secret_key = PrivateKey::random(&mut OsRng);
+        let sender = PublicKey::from_secret_key(&secret_key);
+        let (_, member) = PublicKey::random_keypair(&mut OsRng);
+        let mut detector = EquivocationDetector::new(FixedHash::zero(), &[member]);
+        detector.observe(
+            &sender,
+            &signed_vote(&secret_key, TreeNodeHash::from([1u8; 32]), ViewId(1)),
+        );
+        detector.observe(
+            &sender,
+            &signed_vote(&secret_key, TreeNodeHash::from([2u8; 32]), ViewId(1)),
+        );
+        assert!(detector.take_evidence().is_empty());
+        assert!(detector.first_messages.is_empty());
+    }
+
+    #[test]
+    fn it_only_checks_views_near_the_local_view() {
+        let secret_key = PrivateKey::random(&mut OsRng);
+        let sender = PublicKey::from_secret_key(&secret_key);
+        let mut detector = EquivocationDetector::new(FixedHash::zero(), &[sender.clone()]);
+        detector.set_current_view(ViewId(VIEW_RETENTION + 1));
+        for view in [ViewId(0), ViewId(VIEW_RETENTION + VIEW_LOOKAHEAD + 2), ViewId(u64::MAX)] {
+            detector.observe(&sender, &signed_vote(&secret_key, TreeNodeHash::from([1u8; 32]), view));
+            detector.observe(&sender, &signed_vote(&secret_key, TreeNodeHash::from([2u8; 32]), view));
+        }
+        assert!(detector.take_evidence().is_empty());
+        assert!(detector.first_messages.is_empty());
+
+        let view = ViewId(VIEW_RETENTION + VIEW_LOOKAHEAD + 1);
+        detector.observe(&sender, &signed_vote(&secret_key, TreeNodeHash::from([1u8; 32]), view));
+        detector.observe(&sender, &signed_vote(&secret_key, TreeNodeHash::from([2u8; 32]), view));
+        assert_eq!(detector.take_evidence().len(), 1);
+    }
+
+    #[test]
+    fn it_forgets_old_views() {
+        let secret_key = PrivateKey::random(&mut OsRng);
+        let sender = PublicKey::from_secret_key(&secret_key);
+        let mut detector = EquivocationDetector::new(FixedHash::zero(), &[sender.clone()]);
+        detector.observe(
+            &sender,
+            &signed_vote(&secret_key, TreeNodeHash::from([1u8; 32]), ViewId(1)),
+        );
+        detector.set_current_view(ViewId(VIEW_RETENTION + 2));
+        assert!(detector.first_messages.is_empty());
+        detector.observe(
+            &sender,
+            &signed_vote(&secret_key, TreeNodeHash::from([2u8; 32]), ViewId(1)),
+        );
+        assert!(detector.take_evidence().is_empty());
+    }
+}
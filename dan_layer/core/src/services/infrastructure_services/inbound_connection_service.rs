@@ -23,7 +23,7 @@
 use async_trait::async_trait;
 
 use crate::{
-    models::{HotStuffMessage, HotStuffMessageType, Payload, ViewId},
+    models::{HotStuffMessage, HotStuffMessageType, MisbehaviourEvidence, Payload, ViewId},
     services::infrastructure_services::NodeAddressable,
     DigitalAssetError,
 };
@@ -44,4 +44,24 @@ pub trait InboundConnectionService {
         message_type: HotStuffMessageType,
         for_view: ViewId,
     ) -> Result<(Self::Addr, HotStuffMessage<Self::Payload>), DigitalAssetError>;
+
+    /// Waits for a timeout message for `min_view` or any later view
+    async fn wait_for_timeout(
+        &self,
+        min_view: ViewId,
+    ) -> Result<(Self::Addr, HotStuffMessage<Self::Payload>), DigitalAssetError>;
+
+    /// Waits for an epoch handover message for `min_view` or any later view
+    async fn wait_for_epoch_handover(
+        &self,
+        min_view: ViewId,
+    ) -> Result<(Self::Addr, HotStuffMessage<Self::Payload>), DigitalAssetError>;
+
+    /// Waits for a request for the final block of the outgoing committee, from any view
+    async fn wait_for_epoch_handover_request(
+        &self,
+    ) -> Result<(Self::Addr, HotStuffMessage<Self::Payload>), DigitalAssetError>;
+
+    /// Takes the evidence of equivocation found in the messages received since the last call
+    async fn take_misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>, DigitalAssetError>;
 }
@@ -67,6 +67,30 @@ impl<TAddr: NodeAddressable + Send, TPayload: Payload> InboundConnectionService
     ) -> Result<(TAddr, HotStuffMessage<TPayload>), DigitalAssetError> {
         todo!()
     }
+
+    async fn wait_for_timeout(
+        &self,
+        _min_view: ViewId,
+    ) -> Result<(TAddr, HotStuffMessage<TPayload>), DigitalAssetError> {
+        // The mock never receives timeout messages, so a state selecting on them waits on its other branches
+        futures::future::pending().await
+    }
+
+    async fn wait_for_epoch_handover(
+        &self,
+        _min_view: ViewId,
+    ) -> Result<(TAddr, HotStuffMessage<TPayload>), DigitalAssetError> {
+        // As with timeouts, the mock never receives epoch handover messages
+        futures::future::pending().await
+    }
+
+    async fn wait_for_epoch_handover_request(&self) -> Result<(TAddr, HotStuffMessage<TPayload>), DigitalAssetError> {
+        futures::future::pending().await
+    }
+
+    async fn take_misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>, DigitalAssetError> {
+        Ok(vec![])
+    }
 }
 impl<TAddr: NodeAddressable, TPayload: Payload> Default for MockInboundConnectionService<TAddr, TPayload> {
     fn default() -> Self {
@@ -125,7 +149,7 @@ impl<TAddr: NodeAddressable, TPayload: Payload> MockOutboundService<TAddr, TPayl
 
 use std::fmt::Debug;
 
-use crate::models::{HotStuffMessageType, Payload, ViewId};
+use crate::models::{HotStuffMessageType, MisbehaviourEvidence, Payload, ViewId};
 
 #[async_trait]
 impl<TAddr: NodeAddressable + Send + Sync + Debug, TPayload: Payload> OutboundService
@@ -27,10 +27,19 @@ use std::{
 
 use tari_comms::types::CommsPublicKey;
 
-pub trait NodeAddressable: Eq + Hash + Clone + Debug + Send + Sync + Display {}
+pub trait NodeAddressable: Eq + Hash + Clone + Debug + Send + Sync + Display {
+    /// The public key that this node signs with, if the address is a public key
+    fn public_key(&self) -> Option<&CommsPublicKey> {
+        None
+    }
+}
 
 impl NodeAddressable for String {}
 
 impl NodeAddressable for &str {}
 
-impl NodeAddressable for CommsPublicKey {}
+impl NodeAddressable for CommsPublicKey {
+    fn public_key(&self) -> Option<&CommsPublicKey> {
+        Some(self)
+    }
+}
@@ -24,10 +24,13 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use tari_common_types::types::FixedHash;
-use tari_dan_engine::instructions::Instruction;
+use tari_dan_engine::{instruction::InstructionSet as TemplateInstructionSet, instructions::Instruction};
 use tokio::sync::Mutex;
 
-use crate::{digital_assets_error::DigitalAssetError, models::TreeNodeHash};
+use crate::{
+    digital_assets_error::DigitalAssetError,
+    models::{hash_template_instructions, TreeNodeHash},
+};
 
 #[async_trait]
 pub trait MempoolService: Sync + Send + 'static {
@@ -38,6 +41,17 @@ pub trait MempoolService: Sync + Send + 'static {
         instruction_hash: &FixedHash,
         block_hash: TreeNodeHash,
     ) -> Result<(), DigitalAssetError>;
+    async fn submit_template_instructions(
+        &mut self,
+        instruction_set: TemplateInstructionSet,
+    ) -> Result<(), DigitalAssetError>;
+    async fn read_template_block(&self, limit: usize) -> Result<Vec<TemplateInstructionSet>, DigitalAssetError>;
+    /// Reserves the template instruction set with the given hash (see [hash_template_instructions]) in a block
+    async fn reserve_template_instructions_in_block(
+        &mut self,
+        instruction_set_hash: &FixedHash,
+        block_hash: TreeNodeHash,
+    ) -> Result<(), DigitalAssetError>;
     async fn remove_all_in_block(&mut self, block_hash: &TreeNodeHash) -> Result<(), DigitalAssetError>;
     async fn release_reservations(&mut self, block_hash: &TreeNodeHash) -> Result<(), DigitalAssetError>;
     async fn size(&self) -> usize;
@@ -46,6 +60,7 @@ pub trait MempoolService: Sync + Send + 'static {
 #[derive(Default)]
 pub struct ConcreteMempoolService {
     instructions: Vec<(Instruction, Option<TreeNodeHash>)>,
+    template_instructions: Vec<(TemplateInstructionSet, FixedHash, Option<TreeNodeHash>)>,
 }
 
 #[async_trait]
@@ -83,12 +98,48 @@ impl MempoolService for ConcreteMempoolService {
         Ok(())
     }
 
+    async fn submit_template_instructions(
+        &mut self,
+        instruction_set: TemplateInstructionSet,
+    ) -> Result<(), DigitalAssetError> {
+        let hash = hash_template_instructions(&instruction_set);
+        self.template_instructions.push((instruction_set, hash, None));
+        Ok(())
+    }
+
+    async fn read_template_block(&self, limit: usize) -> Result<Vec<TemplateInstructionSet>, DigitalAssetError> {
+        Ok(self
+            .template_instructions
+            .iter()
+            .filter(|(_, _, block_hash)| block_hash.is_none())
+            .take(limit)
+            .map(|(instruction_set, _, _)| instruction_set.clone())
+            .collect())
+    }
+
+    async fn reserve_template_instructions_in_block(
+        &mut self,
+        instruction_set_hash: &FixedHash,
+        node_hash: TreeNodeHash,
+    ) -> Result<(), DigitalAssetError> {
+        if let Some((_, _, node_hash_mut)) = self
+            .template_instructions
+            .iter_mut()
+            .find(|(_, hash, block_hash)| hash == instruction_set_hash && block_hash.is_none())
+        {
+            *node_hash_mut = Some(node_hash);
+        }
+        Ok(())
+    }
+
     async fn remove_all_in_block(&mut self, block_hash: &TreeNodeHash) -> Result<(), DigitalAssetError> {
         self.instructions = self
             .instructions
             .drain(..)
             .filter(|(_, node_hash)| node_hash.as_ref() != Some(block_hash))
             .collect();
+        self.template_instructions
+            .retain(|(_, _, node_hash)| node_hash.as_ref() != Some(block_hash));
         Ok(())
     }
 
@@ -98,6 +149,11 @@ impl MempoolService for ConcreteMempoolService {
                 *block_hash_mut = None;
             }
         }
+        for (_, _, block_hash_mut) in &mut self.template_instructions {
+            if block_hash_mut.as_ref() == Some(block_hash) {
+                *block_hash_mut = None;
+            }
+        }
         Ok(())
     }
 
@@ -115,7 +171,11 @@ impl MempoolService for ConcreteMempoolService {
     async fn size(&self) -> usize {
         self.instructions
             .iter()
-            .fold(0, |a, b| if b.1.is_none() { a + 1 } else { a })
+            .fold(0, |a, b| if b.1.is_none() { a + 1 } else { a }) +
+            self.template_instructions
+                .iter()
+                .filter(|(_, _, block_hash)| block_hash.is_none())
+                .count()
     }
 }
 
@@ -154,6 +214,33 @@ impl MempoolService for MempoolServiceHandle {
             .await
     }
 
+    async fn submit_template_instructions(
+        &mut self,
+        instruction_set: TemplateInstructionSet,
+    ) -> Result<(), DigitalAssetError> {
+        self.mempool
+            .lock()
+            .await
+            .submit_template_instructions(instruction_set)
+            .await
+    }
+
+    async fn read_template_block(&self, limit: usize) -> Result<Vec<TemplateInstructionSet>, DigitalAssetError> {
+        self.mempool.lock().await.read_template_block(limit).await
+    }
+
+    async fn reserve_template_instructions_in_block(
+        &mut self,
+        instruction_set_hash: &FixedHash,
+        node_hash: TreeNodeHash,
+    ) -> Result<(), DigitalAssetError> {
+        self.mempool
+            .lock()
+            .await
+            .reserve_template_instructions_in_block(instruction_set_hash, node_hash)
+            .await
+    }
+
     async fn remove_all_in_block(&mut self, block_hash: &TreeNodeHash) -> Result<(), DigitalAssetError> {
         self.mempool.lock().await.remove_all_in_block(block_hash).await
     }
@@ -26,19 +26,20 @@ use std::{
 };
 
 use async_trait::async_trait;
-use tari_common_types::types::{FixedHash, PublicKey};
+use tari_common_types::types::{FixedHash, PrivateKey, PublicKey};
 use tari_core::{
     chain_storage::UtxoMinedInfo,
     transactions::transaction_components::{CheckpointChallenge, OutputType, SignerSignature},
 };
-use tari_crypto::ristretto::RistrettoPublicKey;
+use tari_crypto::{keys::SecretKey, ristretto::RistrettoPublicKey};
 use tari_dan_common_types::TemplateId;
 #[cfg(test)]
 use tari_dan_engine::state::mocks::state_db::MockStateDbBackupAdapter;
 use tari_dan_engine::{
+    instruction::InstructionSet as EngineInstructionSet,
     instructions::Instruction,
     state::{
-        models::{SchemaState, StateOpLogEntry, StateRoot},
+        models::{SchemaState, StateOpLogEntry, StateProof, StateRoot},
         StateDbUnitOfWork,
         StateDbUnitOfWorkReader,
     },
@@ -51,16 +52,21 @@ use crate::{
         BaseLayerMetadata,
         BaseLayerOutput,
         Committee,
+        CommitteeChange,
         Event,
+        ExecutionResult,
         HotStuffTreeNode,
+        InstructionResult,
         InstructionSet,
         Node,
         Payload,
         SideChainBlock,
         SidechainMetadata,
+        StateValueProof,
         TariDanPayload,
         TreeNodeHash,
         ValidatorSignature,
+        ViewId,
     },
     services::{
         base_node_client::BaseNodeClient,
@@ -109,6 +115,25 @@ impl MempoolService for MockMempoolService {
         todo!()
     }
 
+    async fn submit_template_instructions(
+        &mut self,
+        _instruction_set: EngineInstructionSet,
+    ) -> Result<(), DigitalAssetError> {
+        Ok(())
+    }
+
+    async fn read_template_block(&self, _limit: usize) -> Result<Vec<EngineInstructionSet>, DigitalAssetError> {
+        Ok(vec![])
+    }
+
+    async fn reserve_template_instructions_in_block(
+        &mut self,
+        _instruction_set_hash: &FixedHash,
+        _block_hash: TreeNodeHash,
+    ) -> Result<(), DigitalAssetError> {
+        Ok(())
+    }
+
     async fn remove_all_in_block(&mut self, _block_hash: &TreeNodeHash) -> Result<(), DigitalAssetError> {
         todo!()
     }
@@ -157,11 +182,11 @@ impl<TPayload: Payload> PayloadProvider<TPayload> for MockStaticPayloadProvider<
         _payload: &TPayload,
         _reservation_key: &TreeNodeHash,
     ) -> Result<(), DigitalAssetError> {
-        todo!()
+        Ok(())
     }
 
     async fn remove_payload(&mut self, _reservation_key: &TreeNodeHash) -> Result<(), DigitalAssetError> {
-        todo!()
+        Ok(())
     }
 }
 
@@ -207,8 +232,9 @@ pub fn mock_signing_service() -> MockSigningService {
 pub struct MockSigningService;
 
 impl SigningService for MockSigningService {
-    fn sign(&self, _challenge: &[u8]) -> Result<ValidatorSignature, DigitalAssetError> {
-        Ok(ValidatorSignature {})
+    fn sign(&self, challenge: &[u8]) -> Result<ValidatorSignature, DigitalAssetError> {
+        let secret_key = PrivateKey::random(&mut rand::thread_rng());
+        Ok(ValidatorSignature::sign(&secret_key, challenge)?)
     }
 
     fn sign_checkpoint(&self, _challenge: &CheckpointChallenge) -> Result<SignerSignature, DigitalAssetError> {
@@ -222,7 +248,10 @@ pub struct MockBaseNodeClient {}
 #[async_trait]
 impl BaseNodeClient for MockBaseNodeClient {
     async fn get_tip_info(&mut self) -> Result<BaseLayerMetadata, DigitalAssetError> {
-        todo!();
+        Ok(BaseLayerMetadata {
+            height_of_longest_chain: 0,
+            tip_hash: FixedHash::zero(),
+        })
     }
 
     async fn get_constitutions(
@@ -261,7 +290,7 @@ impl BaseNodeClient for MockBaseNodeClient {
         _contract_id: FixedHash,
         _output_type: OutputType,
     ) -> Result<Vec<UtxoMinedInfo>, DigitalAssetError> {
-        todo!()
+        Ok(vec![])
     }
 }
 
@@ -282,6 +311,18 @@ impl<TAddr: NodeAddressable> CommitteeManager<TAddr> for MockCommitteeManager {
     fn read_from_constitution(&mut self, _output: BaseLayerOutput) -> Result<(), DigitalAssetError> {
         todo!();
     }
+
+    fn read_from_amendment(&mut self, _output: BaseLayerOutput) -> Result<(), DigitalAssetError> {
+        todo!();
+    }
+
+    fn pending_change(&self) -> Option<&CommitteeChange<TAddr>> {
+        None
+    }
+
+    fn activate_pending_change(&mut self, _handover_view: ViewId) -> Result<(), DigitalAssetError> {
+        todo!();
+    }
 }
 
 // pub fn _mock_template_service() -> MockTemplateService {
@@ -309,9 +350,9 @@ impl<TPayload: Payload> PayloadProcessor<TPayload> for MockPayloadProcessor {
     async fn process_payload<TUnitOfWork: StateDbUnitOfWork>(
         &self,
         _payload: &TPayload,
-        _unit_of_work: TUnitOfWork,
-    ) -> Result<StateRoot, DigitalAssetError> {
-        todo!()
+        unit_of_work: TUnitOfWork,
+    ) -> Result<ExecutionResult, DigitalAssetError> {
+        Ok(ExecutionResult::new(unit_of_work.calculate_root()?, &[]))
     }
 }
 
@@ -323,7 +364,7 @@ impl AssetProcessor for MockAssetProcessor {
         &self,
         _instruction: &Instruction,
         _db: &mut TUnitOfWork,
-    ) -> Result<(), DigitalAssetError> {
+    ) -> Result<InstructionResult, DigitalAssetError> {
         todo!()
     }
 
@@ -332,7 +373,23 @@ impl AssetProcessor for MockAssetProcessor {
         _instruction: &Instruction,
         _state_db: &TUnifOfWork,
     ) -> Result<Option<Vec<u8>>, DigitalAssetError> {
-        todo!()
+        Ok(None)
+    }
+
+    fn invoke_view_method<TUnifOfWork: StateDbUnitOfWorkReader>(
+        &self,
+        instruction_set: EngineInstructionSet,
+        _state_db: &TUnifOfWork,
+    ) -> Result<Vec<Vec<u8>>, DigitalAssetError> {
+        Ok(vec![vec![]; instruction_set.instructions.len()])
+    }
+
+    fn execute_template_instructions<TUnifOfWork: StateDbUnitOfWork>(
+        &self,
+        instruction_set: EngineInstructionSet,
+        _state_db: &TUnifOfWork,
+    ) -> Result<Vec<Vec<u8>>, DigitalAssetError> {
+        Ok(vec![vec![]; instruction_set.instructions.len()])
     }
 }
 
@@ -394,6 +451,24 @@ impl ValidatorNodeRpcClient for MockValidatorNodeClient {
         Ok(None)
     }
 
+    async fn invoke_view_method(
+        &mut self,
+        _contract_id: &FixedHash,
+        _instructions: Vec<u8>,
+        _sender: PublicKey,
+    ) -> Result<Vec<u8>, ValidatorNodeClientError> {
+        Ok(vec![])
+    }
+
+    async fn submit_template_instructions(
+        &mut self,
+        _contract_id: &FixedHash,
+        _instructions: Vec<u8>,
+        _sender: PublicKey,
+    ) -> Result<(), ValidatorNodeClientError> {
+        Ok(())
+    }
+
     async fn invoke_method(
         &mut self,
         _contract_id: &FixedHash,
@@ -432,6 +507,20 @@ impl ValidatorNodeRpcClient for MockValidatorNodeClient {
     async fn get_tip_node(&mut self, _contract_id: &FixedHash) -> Result<Option<Node>, ValidatorNodeClientError> {
         Ok(None)
     }
+
+    async fn get_state_proof(
+        &mut self,
+        _contract_id: &FixedHash,
+        _schema: String,
+        _key: Vec<u8>,
+    ) -> Result<StateValueProof, ValidatorNodeClientError> {
+        Ok(StateValueProof::new(
+            StateRoot::initial(),
+            0,
+            None,
+            StateProof::new(vec![], None),
+        ))
+    }
 }
 
 impl ValidatorNodeClientFactory for MockValidatorNodeClientFactory {
@@ -454,11 +543,21 @@ impl ChainStorageService<TariDanPayload> for MockChainStorageService {
 
     async fn add_node<TUnitOfWork: ChainDbUnitOfWork>(
         &self,
-        _node: &HotStuffTreeNode<TariDanPayload>,
-        _db: TUnitOfWork,
+        node: &HotStuffTreeNode<TariDanPayload>,
+        db: TUnitOfWork,
     ) -> Result<(), StorageError> {
+        let mut db = db;
+        for instruction in node.payload().instructions() {
+            db.add_instruction(*node.hash(), instruction.clone())?;
+        }
+        db.add_node(*node.hash(), *node.parent(), node.height())?;
         Ok(())
     }
+
+    fn payload_from_block(&self, block: SideChainBlock) -> TariDanPayload {
+        let (_, instructions) = block.destruct();
+        TariDanPayload::new(instructions, None)
+    }
 }
 
 pub fn mock_checkpoint_manager() -> ConcreteCheckpointManager<MockWalletClient> {
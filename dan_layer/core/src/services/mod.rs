@@ -30,9 +30,18 @@ mod mempool_service;
 mod payload_processor;
 mod payload_provider;
 mod signing_service;
+mod state_db_runtime_interface;
 
 pub use acceptance_manager::{AcceptanceManager, ConcreteAcceptanceManager};
-pub use asset_processor::{AssetProcessor, ConcreteAssetProcessor, MemoryInstructionLog};
+pub use asset_processor::{
+    decode_template_instructions,
+    encode_template_instructions,
+    encode_template_results,
+    AssetProcessor,
+    ConcreteAssetProcessor,
+    MemoryInstructionLog,
+    WASM_TEMPLATE_ID,
+};
 pub use asset_proxy::{AssetProxy, ConcreteAssetProxy};
 pub use base_node_client::BaseNodeClient;
 pub use committee_manager::{CommitteeManager, ConcreteCommitteeManager};
@@ -41,6 +50,7 @@ pub use mempool_service::{ConcreteMempoolService, MempoolService, MempoolService
 pub use payload_processor::{PayloadProcessor, TariDanPayloadProcessor};
 pub use payload_provider::{PayloadProvider, TariDanPayloadProvider};
 pub use signing_service::{NodeIdentitySigningService, SigningService};
+pub use state_db_runtime_interface::{ReadOnlyStateDbRuntimeInterface, StateDbRuntimeInterface, COMPONENT_SCHEMA};
 mod asset_proxy;
 mod checkpoint_manager;
 pub mod mocks;
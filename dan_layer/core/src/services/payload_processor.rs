@@ -21,21 +21,31 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use async_trait::async_trait;
-use tari_dan_engine::state::{models::StateRoot, StateDbUnitOfWork};
+use log::*;
+use tari_dan_engine::state::StateDbUnitOfWork;
+use tari_utilities::hex::Hex;
 
 use crate::{
     digital_assets_error::DigitalAssetError,
-    models::{Payload, TariDanPayload},
+    models::{ExecutionResult, Payload, TariDanPayload},
     services::AssetProcessor,
 };
 
+const LOG_TARGET: &str = "tari::dan::core::services::payload_processor";
+
+/// Receipts of template instructions start with the outcome of their instruction set. A failed set has its state
+/// changes discarded and gets a single receipt without a result.
+const TEMPLATE_RECEIPT_SUCCEEDED: u8 = 0;
+const TEMPLATE_RECEIPT_FAILED: u8 = 1;
+
 #[async_trait]
 pub trait PayloadProcessor<TPayload: Payload> {
+    /// Executes the payload, writing its state changes to the unit of work
     async fn process_payload<TUnitOfWork: StateDbUnitOfWork>(
         &self,
         payload: &TPayload,
         unit_of_work: TUnitOfWork,
-    ) -> Result<StateRoot, DigitalAssetError>;
+    ) -> Result<ExecutionResult, DigitalAssetError>;
 }
 
 pub struct TariDanPayloadProcessor<TAssetProcessor>
@@ -58,15 +68,156 @@ impl<TAssetProcessor: AssetProcessor + Send + Sync> PayloadProcessor<TariDanPayl
         &self,
         payload: &TariDanPayload,
         state_tx: TUnitOfWork,
-    ) -> Result<StateRoot, DigitalAssetError> {
+    ) -> Result<ExecutionResult, DigitalAssetError> {
         let mut state_tx = state_tx;
+        let mut receipts = Vec::new();
         for instruction in payload.instructions() {
-            println!("Executing instruction");
-            println!("{:?}", instruction);
             // TODO: Should we swallow + log the error instead of propagating it?
-            self.asset_processor.execute_instruction(instruction, &mut state_tx)?;
+            let result = self.asset_processor.execute_instruction(instruction, &mut state_tx)?;
+            for event in &result.events {
+                debug!(
+                    target: LOG_TARGET,
+                    "Instruction {} emitted event '{}': {}",
+                    instruction.hash().to_hex(),
+                    event.topic,
+                    event.message
+                );
+            }
+            receipts.push(result.to_receipt());
+        }
+
+        for instruction_set in payload.template_instructions() {
+            match self
+                .asset_processor
+                .execute_template_instructions(instruction_set.clone(), &state_tx)
+            {
+                Ok(results) => receipts.extend(results.into_iter().map(|result| {
+                    let mut receipt = vec![TEMPLATE_RECEIPT_SUCCEEDED];
+                    receipt.extend(result);
+                    receipt
+                })),
+                // A template failure is part of the payload's outcome on every replica, so it does not abort the
+                // payload. A local storage failure is not, and is returned.
+                Err(DigitalAssetError::InstructionError(err)) if !err.is_storage_error() => {
+                    warn!(target: LOG_TARGET, "Template instruction set failed: {}", err);
+                    receipts.push(vec![TEMPLATE_RECEIPT_FAILED]);
+                },
+                Err(err @ DigitalAssetError::NoTemplatePackage) => {
+                    warn!(target: LOG_TARGET, "Template instruction set failed: {}", err);
+                    receipts.push(vec![TEMPLATE_RECEIPT_FAILED]);
+                },
+                Err(err) => return Err(err),
+            }
         }
 
-        Ok(state_tx.calculate_root()?)
+        Ok(ExecutionResult::new(state_tx.calculate_root()?, &receipts))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tari_common_types::types::FixedHash;
+    use tari_dan_engine::{
+        crypto::create_key_pair,
+        instruction::{Instruction as EngineInstruction, InstructionBuilder, InstructionSet as EngineInstructionSet},
+        packager::Package,
+        state::{mocks::state_db::MockStateDbBackupAdapter, StateDbUnitOfWorkImpl, UnitOfWorkContext},
+        wasm::{compile::compile_template, WasmModule, WasmModuleDefinition},
+    };
+    use tari_template_lib::models::{ComponentInstance, PackageId};
+
+    use super::*;
+    use crate::{
+        models::{AssetDefinition, InstructionSet},
+        services::{state_db_runtime_interface::COMPONENT_SCHEMA, ConcreteAssetProcessor},
+    };
+
+    fn build_instruction_set(instructions: Vec<EngineInstruction>) -> EngineInstructionSet {
+        let (secret_key, _) = create_key_pair();
+        let mut builder = InstructionBuilder::new();
+        for instruction in instructions {
+            builder.add_instruction(instruction);
+        }
+        builder.sign(&secret_key).build()
+    }
+
+    fn call_function(package_id: PackageId, template: &str, function: &str) -> EngineInstruction {
+        EngineInstruction::CallFunction {
+            package_id,
+            template: template.to_string(),
+            function: function.to_string(),
+            args: vec![],
+        }
+    }
+
+    fn new_unit_of_work() -> StateDbUnitOfWorkImpl<MockStateDbBackupAdapter> {
+        StateDbUnitOfWorkImpl::new(
+            UnitOfWorkContext::new(1, FixedHash::zero()),
+            MockStateDbBackupAdapter::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn it_executes_template_instructions_and_records_failed_sets() {
+        let wasm = compile_template("../engine/tests/templates/state", &[]).unwrap();
+        let package_id = Package::builder()
+            .add_wasm_module(WasmModule::from_code(wasm.code().to_vec()))
+            .build()
+            .unwrap()
+            .id();
+        let temp_dir = tari_test_utils::paths::tempdir();
+        let path = temp_dir.path().join("state.wasm");
+        fs::write(&path, wasm.code()).unwrap();
+        let asset_processor = ConcreteAssetProcessor::new(AssetDefinition {
+            wasm_modules: vec![WasmModuleDefinition {
+                name: "state".to_string(),
+                path,
+            }],
+            ..Default::default()
+        })
+        .unwrap();
+        let payload_processor = TariDanPayloadProcessor::new(asset_processor);
+
+        // The second instruction fails, so the component created by the first one must not be written
+        let failing_set = build_instruction_set(vec![
+            call_function(package_id, "State", "new"),
+            call_function(package_id, "Missing", "new"),
+        ]);
+        let payload = TariDanPayload::new(InstructionSet::empty(), None).with_template_instructions(vec![failing_set]);
+        let state_tx = new_unit_of_work();
+        let result = payload_processor
+            .process_payload(&payload, state_tx.clone())
+            .await
+            .unwrap();
+        let empty_result = payload_processor
+            .process_payload(&TariDanPayload::new(InstructionSet::empty(), None), new_unit_of_work())
+            .await
+            .unwrap();
+        assert_eq!(result.state_root, empty_result.state_root);
+        assert_ne!(result.receipts_hash, empty_result.receipts_hash);
+
+        let succeeding_set = build_instruction_set(vec![call_function(package_id, "State", "new")]);
+        let payload =
+            TariDanPayload::new(InstructionSet::empty(), None).with_template_instructions(vec![succeeding_set]);
+        let mut state_tx = new_unit_of_work();
+        let result = payload_processor
+            .process_payload(&payload, state_tx.clone())
+            .await
+            .unwrap();
+        assert_ne!(result.state_root, empty_result.state_root);
+
+        state_tx.commit().unwrap();
+        let components = state_tx
+            .get_all_state()
+            .unwrap()
+            .into_iter()
+            .find(|schema| schema.name == COMPONENT_SCHEMA)
+            .expect("no components were written")
+            .items;
+        assert_eq!(components.len(), 1);
+        let component: ComponentInstance = tari_template_abi::decode(&components[0].value).unwrap();
+        assert_eq!(component.module_name, "State");
     }
 }
@@ -24,7 +24,7 @@ use async_trait::async_trait;
 
 use crate::{
     digital_assets_error::DigitalAssetError,
-    models::{AssetDefinition, InstructionSet, Payload, TariDanPayload, TreeNodeHash},
+    models::{hash_template_instructions, AssetDefinition, InstructionSet, Payload, TariDanPayload, TreeNodeHash},
     services::{asset_processor::TemplateFactory, MempoolService},
 };
 
@@ -60,8 +60,9 @@ impl<TMempoolService: MempoolService> PayloadProvider<TariDanPayload> for TariDa
     async fn create_payload(&self) -> Result<TariDanPayload, DigitalAssetError> {
         let instructions = self.mempool.read_block(100).await?;
         let instruction_set = InstructionSet::from_vec(instructions);
+        let template_instructions = self.mempool.read_template_block(100).await?;
 
-        Ok(TariDanPayload::new(instruction_set, None))
+        Ok(TariDanPayload::new(instruction_set, None).with_template_instructions(template_instructions))
     }
 
     fn create_genesis_payload(&self, asset_definition: &AssetDefinition) -> TariDanPayload {
@@ -88,6 +89,11 @@ impl<TMempoolService: MempoolService> PayloadProvider<TariDanPayload> for TariDa
                 .reserve_instruction_in_block(instruction.hash(), *reservation_key)
                 .await?;
         }
+        for instruction_set in payload.template_instructions() {
+            self.mempool
+                .reserve_template_instructions_in_block(&hash_template_instructions(instruction_set), *reservation_key)
+                .await?;
+        }
         Ok(())
     }
 
@@ -44,9 +44,8 @@ impl NodeIdentitySigningService {
 }
 
 impl SigningService for NodeIdentitySigningService {
-    fn sign(&self, _challenge: &[u8]) -> Result<ValidatorSignature, DigitalAssetError> {
-        // TODO better sig
-        Ok(ValidatorSignature {})
+    fn sign(&self, challenge: &[u8]) -> Result<ValidatorSignature, DigitalAssetError> {
+        Ok(ValidatorSignature::sign(self.node_identity.secret_key(), challenge)?)
     }
 
     fn sign_checkpoint(&self, challenge: &CheckpointChallenge) -> Result<SignerSignature, DigitalAssetError> {
@@ -0,0 +1,266 @@
+// Copyright 2022. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use digest::Digest;
+use log::*;
+use tari_dan_engine::{
+    crypto,
+    runtime::{RuntimeError, RuntimeInterface},
+    state::{error::StateStorageError, StateDbUnitOfWork, StateDbUnitOfWorkReader},
+};
+use tari_template_abi::{decode, encode};
+use tari_template_lib::{
+    args::LogLevel,
+    models::{Component, ComponentId, ComponentInstance},
+};
+
+const LOG_TARGET: &str = "tari::dan::core::services::state_db_runtime_interface";
+
+/// The state schema in which template component instances are stored, keyed by component id
+pub const COMPONENT_SCHEMA: &str = "components";
+/// The state schema holding the counter from which new component ids are derived
+const COMPONENT_COUNTER_SCHEMA: &str = "components.counter";
+const COMPONENT_COUNTER_KEY: &[u8] = b"next_id";
+
+/// A [RuntimeInterface] that reads and writes components through a state database unit of work. Changes are only
+/// persisted when the unit of work is committed, so they contribute to the state root of the node being processed.
+/// Template changes are held back until [`StateDbRuntimeInterface::commit_changes`] is called, so that a failed
+/// instruction set leaves the unit of work untouched.
+#[derive(Clone)]
+pub struct StateDbRuntimeInterface<TStateDb> {
+    state: Arc<RwLock<PendingState<TStateDb>>>,
+}
+
+impl<TStateDb: StateDbUnitOfWork> StateDbRuntimeInterface<TStateDb> {
+    pub fn new(state_db: TStateDb) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(PendingState {
+                state_db,
+                changes: Vec::new(),
+            })),
+        }
+    }
+
+    /// Writes the state changes made by the executed instructions to the unit of work, in the order they were made
+    pub fn commit_changes(&self) -> Result<(), StateStorageError> {
+        let mut state = self.state.write().map_err(|_| StateStorageError::LockError)?;
+        let PendingState { state_db, changes } = &mut *state;
+        for (schema, key, value) in changes.drain(..) {
+            state_db.set_value(schema, key, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl<TStateDb: StateDbUnitOfWork> RuntimeInterface for StateDbRuntimeInterface<TStateDb> {
+    fn emit_log(&self, level: LogLevel, message: &str) {
+        log!(target: LOG_TARGET, to_log_level(level), "{}", message);
+    }
+
+    fn create_component(&self, component: Component) -> Result<ComponentId, RuntimeError> {
+        let mut state = self.state.write().map_err(|_| lock_poisoned())?;
+        let next_id = state
+            .get_value(COMPONENT_COUNTER_SCHEMA, COMPONENT_COUNTER_KEY)?
+            .map(|value| {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&value);
+                u64::from_le_bytes(bytes)
+            })
+            .unwrap_or(0);
+        // Component ids must be the same on every validator, so they are derived from the contract and a counter
+        let component_id: [u8; 32] = crypto::hasher("component")
+            .chain(state.state_db.context().contract_id().as_slice())
+            .chain(next_id.to_le_bytes())
+            .finalize()
+            .into();
+
+        let component = ComponentInstance::new(component_id.into(), component);
+        let value = encode(&component).map_err(|e| RuntimeError::StateDbError(e.into()))?;
+        state.set_value(COMPONENT_SCHEMA, component_id.to_vec(), value);
+        state.set_value(
+            COMPONENT_COUNTER_SCHEMA,
+            COMPONENT_COUNTER_KEY.to_vec(),
+            (next_id + 1).to_le_bytes().to_vec(),
+        );
+
+        Ok(component_id.into())
+    }
+
+    fn get_component(&self, component_id: &ComponentId) -> Result<ComponentInstance, RuntimeError> {
+        let state = self.state.read().map_err(|_| lock_poisoned())?;
+        decode_component(state.get_value(COMPONENT_SCHEMA, component_id)?, component_id)
+    }
+
+    fn get_components_in_range(
+        &self,
+        start: &ComponentId,
+        end: Option<&ComponentId>,
+    ) -> Result<Vec<ComponentInstance>, RuntimeError> {
+        let state = self.state.read().map_err(|_| lock_poisoned())?;
+        decode_components(state.get_values_in_range(COMPONENT_SCHEMA, start, end.map(|end| &end[..]))?)
+    }
+
+    fn set_component_state(&self, component_id: &ComponentId, component_state: Vec<u8>) -> Result<(), RuntimeError> {
+        let mut state = self.state.write().map_err(|_| lock_poisoned())?;
+        let mut component = decode_component(state.get_value(COMPONENT_SCHEMA, component_id)?, component_id)?;
+        component.state = component_state;
+        let value = encode(&component).map_err(|e| RuntimeError::StateDbError(e.into()))?;
+        state.set_value(COMPONENT_SCHEMA, component_id.to_vec(), value);
+        Ok(())
+    }
+}
+
+struct PendingState<TStateDb> {
+    state_db: TStateDb,
+    changes: Vec<(String, Vec<u8>, Vec<u8>)>,
+}
+
+impl<TStateDb: StateDbUnitOfWorkReader> PendingState<TStateDb> {
+    fn get_value(&self, schema: &str, key: &[u8]) -> Result<Option<Vec<u8>>, RuntimeError> {
+        let pending = self
+            .changes
+            .iter()
+            .rev()
+            .find(|(s, k, _)| s == schema && k.as_slice() == key)
+            .map(|(_, _, value)| value.clone());
+        match pending {
+            Some(value) => Ok(Some(value)),
+            None => self
+                .state_db
+                .get_value(schema, key)
+                .map_err(|e| RuntimeError::StateDbError(e.into())),
+        }
+    }
+
+    fn get_values_in_range(
+        &self,
+        schema: &str,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> Result<Vec<Vec<u8>>, RuntimeError> {
+        let mut values = self
+            .state_db
+            .get_values_in_range(schema, start, end)
+            .map_err(|e| RuntimeError::StateDbError(e.into()))?
+            .into_iter()
+            .map(|kv| (kv.key, kv.value))
+            .collect::<BTreeMap<_, _>>();
+        let pending = self
+            .changes
+            .iter()
+            .filter(|(s, k, _)| s == schema && k.as_slice() >= start && end.map_or(true, |end| k.as_slice() < end));
+        for (_, key, value) in pending {
+            values.insert(key.clone(), value.clone());
+        }
+        Ok(values.into_values().collect())
+    }
+
+    fn set_value(&mut self, schema: &str, key: Vec<u8>, value: Vec<u8>) {
+        self.changes.push((schema.to_string(), key, value));
+    }
+}
+
+#[derive(Clone)]
+pub struct ReadOnlyStateDbRuntimeInterface<TStateDbReader> {
+    state_db: TStateDbReader,
+}
+
+impl<TStateDbReader: StateDbUnitOfWorkReader> ReadOnlyStateDbRuntimeInterface<TStateDbReader> {
+    pub fn new(state_db: TStateDbReader) -> Self {
+        Self { state_db }
+    }
+}
+
+impl<TStateDbReader: StateDbUnitOfWorkReader> RuntimeInterface for ReadOnlyStateDbRuntimeInterface<TStateDbReader> {
+    fn emit_log(&self, level: LogLevel, message: &str) {
+        log!(target: LOG_TARGET, to_log_level(level), "{}", message);
+    }
+
+    fn create_component(&self, _component: Component) -> Result<ComponentId, RuntimeError> {
+        Err(RuntimeError::ReadOnlyViolation {
+            operation: "create_component",
+        })
+    }
+
+    fn get_component(&self, component_id: &ComponentId) -> Result<ComponentInstance, RuntimeError> {
+        get_component(&self.state_db, component_id)
+    }
+
+    fn get_components_in_range(
+        &self,
+        start: &ComponentId,
+        end: Option<&ComponentId>,
+    ) -> Result<Vec<ComponentInstance>, RuntimeError> {
+        let values = self
+            .state_db
+            .get_values_in_range(COMPONENT_SCHEMA, start, end.map(|end| &end[..]))
+            .map_err(|e| RuntimeError::StateDbError(e.into()))?;
+        decode_components(values.into_iter().map(|kv| kv.value).collect())
+    }
+
+    fn set_component_state(&self, _component_id: &ComponentId, _state: Vec<u8>) -> Result<(), RuntimeError> {
+        Err(RuntimeError::ReadOnlyViolation {
+            operation: "set_component_state",
+        })
+    }
+}
+
+fn get_component<TStateDbReader: StateDbUnitOfWorkReader>(
+    state_db: &TStateDbReader,
+    component_id: &ComponentId,
+) -> Result<ComponentInstance, RuntimeError> {
+    let value = state_db
+        .get_value(COMPONENT_SCHEMA, component_id)
+        .map_err(|e| RuntimeError::StateDbError(e.into()))?;
+    decode_component(value, component_id)
+}
+
+fn decode_component(value: Option<Vec<u8>>, component_id: &ComponentId) -> Result<ComponentInstance, RuntimeError> {
+    let value = value.ok_or(RuntimeError::ComponentNotFound { id: *component_id })?;
+    let component = decode(&value).map_err(|e| RuntimeError::StateDbError(e.into()))?;
+    Ok(component)
+}
+
+fn decode_components(values: Vec<Vec<u8>>) -> Result<Vec<ComponentInstance>, RuntimeError> {
+    values
+        .iter()
+        .map(|value| decode(value).map_err(|e| RuntimeError::StateDbError(e.into())))
+        .collect()
+}
+
+fn to_log_level(level: LogLevel) -> Level {
+    match level {
+        LogLevel::Error => Level::Error,
+        LogLevel::Warn => Level::Warn,
+        LogLevel::Info => Level::Info,
+        LogLevel::Debug => Level::Debug,
+    }
+}
+
+fn lock_poisoned() -> RuntimeError {
+    RuntimeError::StateDbError(anyhow::anyhow!("state db lock poisoned"))
+}
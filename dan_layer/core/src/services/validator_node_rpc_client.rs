@@ -32,7 +32,7 @@ use tari_dan_common_types::TemplateId;
 use tari_dan_engine::state::models::{SchemaState, StateOpLogEntry};
 
 use crate::{
-    models::{Node, SideChainBlock, TreeNodeHash},
+    models::{Node, SideChainBlock, StateValueProof, TreeNodeHash},
     services::infrastructure_services::NodeAddressable,
 };
 
@@ -53,6 +53,23 @@ pub trait ValidatorNodeRpcClient: Send + Sync {
         sender: PublicKey,
     ) -> Result<Option<Vec<u8>>, ValidatorNodeClientError>;
 
+    /// Runs encoded WASM template instructions against the node's current state without changing it. Returns the
+    /// encoded list of raw instruction results.
+    async fn invoke_view_method(
+        &mut self,
+        contract_id: &FixedHash,
+        instructions: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<Vec<u8>, ValidatorNodeClientError>;
+
+    /// Submits encoded WASM template instructions to the node's mempool
+    async fn submit_template_instructions(
+        &mut self,
+        contract_id: &FixedHash,
+        instructions: Vec<u8>,
+        sender: PublicKey,
+    ) -> Result<(), ValidatorNodeClientError>;
+
     async fn invoke_method(
         &mut self,
         contract_id: &FixedHash,
@@ -81,6 +98,15 @@ pub trait ValidatorNodeRpcClient: Send + Sync {
     ) -> Result<Vec<StateOpLogEntry>, ValidatorNodeClientError>;
 
     async fn get_tip_node(&mut self, contract_id: &FixedHash) -> Result<Option<Node>, ValidatorNodeClientError>;
+
+    /// Returns the committed value of the key together with a proof against the validator node's current state root.
+    /// The result should be checked with [StateValueProof::verify] before it is trusted.
+    async fn get_state_proof(
+        &mut self,
+        contract_id: &FixedHash,
+        schema: String,
+        key: Vec<u8>,
+    ) -> Result<StateValueProof, ValidatorNodeClientError>;
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -22,9 +22,15 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    models::{Node, QuorumCertificate, SideChainBlock, TreeNodeHash},
+    models::{MisbehaviourEvidence, Node, QuorumCertificate, SideChainBlock, TreeNodeHash, ViewId},
     storage::{
-        chain::{chain_db_unit_of_work::ChainDbUnitOfWorkImpl, ChainDbBackendAdapter, ChainDbMetadataKey},
+        chain::{
+            chain_db_unit_of_work::ChainDbUnitOfWorkImpl,
+            ChainDbBackendAdapter,
+            ChainDbMetadataKey,
+            ChainPruningStats,
+            DbVote,
+        },
         MetadataBackendAdapter,
         StorageError,
     },
@@ -49,6 +55,11 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDb<TBackendAdapter> {
         self.adapter.get_locked_qc().map_err(TBackendAdapter::Error::into)
     }
 
+    /// Returns the last vote cast by this node, if it has voted
+    pub fn get_last_vote(&self) -> Result<Option<DbVote>, StorageError> {
+        self.adapter.get_last_vote().map_err(TBackendAdapter::Error::into)
+    }
+
     pub fn is_empty(&self) -> Result<bool, StorageError> {
         self.adapter.is_empty().map_err(TBackendAdapter::Error::into)
     }
@@ -102,10 +113,100 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDb<TBackendAdapter> {
         Ok(Some(SideChainBlock::new(node.into(), instructions)))
     }
 
+    /// Returns the blocks of the nodes that have not been committed, ordered by height
+    pub fn get_uncommitted_sidechain_blocks(&self) -> Result<Vec<SideChainBlock>, StorageError> {
+        let nodes = self
+            .adapter
+            .find_uncommitted_nodes()
+            .map_err(TBackendAdapter::Error::into)?;
+        let mut blocks = Vec::with_capacity(nodes.len());
+        for (block_id, node) in nodes {
+            let instructions = self
+                .adapter
+                .find_all_instructions_by_node(block_id)
+                .map_err(TBackendAdapter::Error::into)?;
+            let instructions = instructions.into_iter().map(|i| i.instruction).collect();
+            blocks.push(SideChainBlock::new(node.into(), instructions));
+        }
+        Ok(blocks)
+    }
+
     pub fn get_tip_node(&self) -> Result<Option<Node>, StorageError> {
         let db_node = self.adapter.get_tip_node().map_err(TBackendAdapter::Error::into)?;
         Ok(db_node.map(Into::into))
     }
+
+    /// Returns the heights of the committed nodes in the range `from_height..to_height`
+    pub fn get_committed_node_heights(&self, from_height: u32, to_height: u32) -> Result<Vec<u32>, StorageError> {
+        self.adapter
+            .get_committed_node_heights(from_height, to_height)
+            .map_err(TBackendAdapter::Error::into)
+    }
+
+    /// Returns the heights in `from_height..to_height` of the ancestors of the lowest committed node at or above
+    /// `to_height`, following parent links. Committed nodes are final, so every member that has committed that node
+    /// gets the same heights. Returns None if no node at or above `to_height` has been committed.
+    pub fn get_committed_chain_heights(
+        &self,
+        from_height: u32,
+        to_height: u32,
+    ) -> Result<Option<Vec<u32>>, StorageError> {
+        let mut node = match self
+            .adapter
+            .find_lowest_committed_node_from(to_height)
+            .map_err(TBackendAdapter::Error::into)?
+        {
+            Some((_, node)) => node,
+            None => return Ok(None),
+        };
+        let mut heights = Vec::new();
+        while let Some((_, parent)) = self
+            .adapter
+            .find_node_by_hash(&node.parent)
+            .map_err(TBackendAdapter::Error::into)?
+        {
+            if parent.height < from_height {
+                break;
+            }
+            heights.push(parent.height);
+            node = parent;
+        }
+        heights.reverse();
+        Ok(Some(heights))
+    }
+
+    /// Deletes the committed nodes below `height` along with their instructions
+    pub fn prune_committed_nodes(&self, height: u32) -> Result<ChainPruningStats, StorageError> {
+        let tx = self
+            .adapter
+            .create_transaction()
+            .map_err(TBackendAdapter::Error::into)?;
+        let stats = self
+            .adapter
+            .delete_committed_nodes_below(height, &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        self.adapter.commit(&tx).map_err(TBackendAdapter::Error::into)?;
+        Ok(stats)
+    }
+
+    pub fn insert_misbehaviour_evidence(&self, evidence: &MisbehaviourEvidence) -> Result<(), StorageError> {
+        let tx = self
+            .adapter
+            .create_transaction()
+            .map_err(TBackendAdapter::Error::into)?;
+        self.adapter
+            .insert_misbehaviour_evidence(evidence, &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        self.adapter.commit(&tx).map_err(TBackendAdapter::Error::into)?;
+        Ok(())
+    }
+
+    /// Returns all recorded misbehaviour evidence, in the order that it was recorded
+    pub fn get_misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>, StorageError> {
+        self.adapter
+            .get_misbehaviour_evidence()
+            .map_err(TBackendAdapter::Error::into)
+    }
 }
 
 impl<TBackendAdapter> ChainDb<TBackendAdapter>
@@ -144,6 +245,75 @@ where TBackendAdapter: MetadataBackendAdapter<ChainDbMetadataKey>
         self.adapter.commit(&tx).map_err(TBackendAdapter::Error::into)?;
         Ok(next)
     }
+
+    /// Records the state height and chain height at which the last checkpoint was created
+    pub fn set_last_checkpoint_heights(&self, state_height: u64, node_height: u32) -> Result<(), StorageError> {
+        let tx = self
+            .adapter
+            .create_transaction()
+            .map_err(TBackendAdapter::Error::into)?;
+        self.adapter
+            .set_metadata(ChainDbMetadataKey::LastCheckpointStateHeight, state_height, &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        self.adapter
+            .set_metadata(ChainDbMetadataKey::LastCheckpointNodeHeight, node_height, &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        self.adapter.commit(&tx).map_err(TBackendAdapter::Error::into)?;
+        Ok(())
+    }
+
+    /// Returns the state height and chain height at which the last checkpoint was created, if a checkpoint has been
+    /// created
+    pub fn get_last_checkpoint_heights(&self) -> Result<Option<(u64, u32)>, StorageError> {
+        let tx = self
+            .adapter
+            .create_transaction()
+            .map_err(TBackendAdapter::Error::into)?;
+        let state_height = self
+            .adapter
+            .get_metadata(&ChainDbMetadataKey::LastCheckpointStateHeight, &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        let node_height = self
+            .adapter
+            .get_metadata(&ChainDbMetadataKey::LastCheckpointNodeHeight, &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        Ok(state_height.zip(node_height))
+    }
+
+    /// Records the activation height of the last committee change and the last view of the committee that handed over
+    pub fn set_last_handover(&self, activation_height: u64, handover_view: ViewId) -> Result<(), StorageError> {
+        let tx = self
+            .adapter
+            .create_transaction()
+            .map_err(TBackendAdapter::Error::into)?;
+        self.adapter
+            .set_metadata(ChainDbMetadataKey::HandoverActivationHeight, activation_height, &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        self.adapter
+            .set_metadata(ChainDbMetadataKey::HandoverView, handover_view.as_u64(), &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        self.adapter.commit(&tx).map_err(TBackendAdapter::Error::into)?;
+        Ok(())
+    }
+
+    /// Returns the activation height of the last committee change and the last view of the committee that handed
+    /// over, if the committee has changed
+    pub fn get_last_handover(&self) -> Result<Option<(u64, ViewId)>, StorageError> {
+        let tx = self
+            .adapter
+            .create_transaction()
+            .map_err(TBackendAdapter::Error::into)?;
+        let activation_height = self
+            .adapter
+            .get_metadata(&ChainDbMetadataKey::HandoverActivationHeight, &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        let handover_view = self
+            .adapter
+            .get_metadata(&ChainDbMetadataKey::HandoverView, &tx)
+            .map_err(TBackendAdapter::Error::into)?
+            .map(ViewId);
+        Ok(activation_height.zip(handover_view))
+    }
 }
 
 impl<TBackendAdapter: ChainDbBackendAdapter + Clone + Send + Sync> ChainDb<TBackendAdapter> {
@@ -23,9 +23,9 @@
 use std::fmt::Debug;
 
 use crate::{
-    models::{Payload, QuorumCertificate, TreeNodeHash},
+    models::{MisbehaviourEvidence, Payload, QuorumCertificate, TreeNodeHash},
     storage::{
-        chain::{DbInstruction, DbNode, DbQc},
+        chain::{ChainPruningStats, DbInstruction, DbNode, DbQc, DbVote},
         AtomicDb,
     },
 };
@@ -37,6 +37,10 @@ pub trait ChainDbBackendAdapter: AtomicDb + Send + Sync + Clone {
     fn is_empty(&self) -> Result<bool, Self::Error>;
     fn node_exists(&self, node_hash: &TreeNodeHash) -> Result<bool, Self::Error>;
     fn get_tip_node(&self) -> Result<Option<DbNode>, Self::Error>;
+    /// Returns the heights of the committed nodes in the range `from_height..to_height`
+    fn get_committed_node_heights(&self, from_height: u32, to_height: u32) -> Result<Vec<u32>, Self::Error>;
+    /// Returns the committed node with the lowest height at or above `height`
+    fn find_lowest_committed_node_from(&self, height: u32) -> Result<Option<(Self::Id, DbNode)>, Self::Error>;
     fn insert_node(&self, item: &DbNode, transaction: &Self::DbTransaction) -> Result<(), Self::Error>;
     fn update_node(&self, id: &Self::Id, item: &DbNode, transaction: &Self::DbTransaction) -> Result<(), Self::Error>;
     fn insert_instruction(&self, item: &DbInstruction, transaction: &Self::DbTransaction) -> Result<(), Self::Error>;
@@ -47,7 +51,25 @@ pub trait ChainDbBackendAdapter: AtomicDb + Send + Sync + Clone {
     fn get_prepare_qc(&self) -> Result<Option<QuorumCertificate>, Self::Error>;
     fn find_node_by_hash(&self, node_hash: &TreeNodeHash) -> Result<Option<(Self::Id, DbNode)>, Self::Error>;
     fn find_node_by_parent_hash(&self, parent_hash: &TreeNodeHash) -> Result<Option<(Self::Id, DbNode)>, Self::Error>;
+    /// Returns the nodes that have not been committed, ordered by height
+    fn find_uncommitted_nodes(&self) -> Result<Vec<(Self::Id, DbNode)>, Self::Error>;
     fn find_all_instructions_by_node(&self, node_id: Self::Id) -> Result<Vec<DbInstruction>, Self::Error>;
     fn update_prepare_qc(&self, item: &DbQc, transaction: &Self::DbTransaction) -> Result<(), Self::Error>;
     fn update_locked_qc(&self, locked_qc: &DbQc, transaction: &Self::DbTransaction) -> Result<(), Self::Error>;
+    /// Returns the last vote cast by this node, if it has voted
+    fn get_last_vote(&self) -> Result<Option<DbVote>, Self::Error>;
+    fn update_last_vote(&self, vote: &DbVote, transaction: &Self::DbTransaction) -> Result<(), Self::Error>;
+    /// Deletes the committed nodes below `height` along with their instructions
+    fn delete_committed_nodes_below(
+        &self,
+        height: u32,
+        transaction: &Self::DbTransaction,
+    ) -> Result<ChainPruningStats, Self::Error>;
+    fn insert_misbehaviour_evidence(
+        &self,
+        evidence: &MisbehaviourEvidence,
+        transaction: &Self::DbTransaction,
+    ) -> Result<(), Self::Error>;
+    /// Returns all recorded misbehaviour evidence, in the order that it was recorded
+    fn get_misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>, Self::Error>;
 }
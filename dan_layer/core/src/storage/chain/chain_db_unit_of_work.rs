@@ -31,9 +31,9 @@ use tari_dan_common_types::storage::UnitOfWorkTracker;
 use tari_dan_engine::instructions::Instruction;
 
 use crate::{
-    models::{Node, QuorumCertificate, TreeNodeHash},
+    models::{HotStuffMessageType, Node, QuorumCertificate, TreeNodeHash, ViewId},
     storage::{
-        chain::{db_node::DbNode, ChainDbBackendAdapter, DbInstruction, DbQc},
+        chain::{db_node::DbNode, ChainDbBackendAdapter, DbInstruction, DbQc, DbVote},
         StorageError,
     },
 };
@@ -42,6 +42,14 @@ const LOG_TARGET: &str = "tari::dan::chain_db::unit_of_work";
 
 pub trait ChainDbUnitOfWork: Clone + Send + Sync {
     fn commit(&mut self) -> Result<(), StorageError>;
+    /// Commits all pending changes along with a record of this node's vote, before the vote is sent. Returns false,
+    /// without writing anything, if the node has already voted in the same or a later view and phase.
+    fn record_vote(
+        &mut self,
+        view_number: ViewId,
+        message_type: HotStuffMessageType,
+        node_hash: TreeNodeHash,
+    ) -> Result<bool, StorageError>;
     fn add_node(&mut self, hash: TreeNodeHash, parent: TreeNodeHash, height: u32) -> Result<(), StorageError>;
     fn add_instruction(&mut self, node_hash: TreeNodeHash, instruction: Instruction) -> Result<(), StorageError>;
     fn get_locked_qc(&mut self) -> Result<QuorumCertificate, StorageError>;
@@ -85,54 +93,50 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWork for ChainDbUnitOf
             .backend_adapter
             .create_transaction()
             .map_err(TBackendAdapter::Error::into)?;
+        inner.write_changes(&tx)?;
+        inner
+            .backend_adapter
+            .commit(&tx)
+            .map_err(TBackendAdapter::Error::into)?;
 
-        for (id, item) in &inner.nodes {
-            if item.is_dirty() {
-                match id {
-                    Some(i) => inner
-                        .backend_adapter
-                        .update_node(i, &*item.get(), &tx)
-                        .map_err(TBackendAdapter::Error::into)?,
-                    None => inner
-                        .backend_adapter
-                        .insert_node(&*item.get(), &tx)
-                        .map_err(TBackendAdapter::Error::into)?,
-                }
-            }
-        }
-
-        for (id, item) in &inner.instructions {
-            if item.is_dirty() {
-                match id {
-                    Some(_i) => {
-                        unimplemented!("Cannot update instructions");
-                    },
-                    None => inner
-                        .backend_adapter
-                        .insert_instruction(&*item.get(), &tx)
-                        .map_err(TBackendAdapter::Error::into)?,
-                }
-            }
-        }
-
-        if let Some(ref locked_qc) = inner.locked_qc {
-            if locked_qc.is_dirty() {
-                inner
-                    .backend_adapter
-                    .update_locked_qc(&*locked_qc.get(), &tx)
-                    .map_err(TBackendAdapter::Error::into)?;
-            }
-        }
+        inner.nodes = vec![];
+        inner.instructions = vec![];
+        Ok(())
+    }
 
-        if let Some(ref prepare_qc) = inner.prepare_qc {
-            if prepare_qc.is_dirty() {
-                inner
-                    .backend_adapter
-                    .update_prepare_qc(&*prepare_qc.get(), &tx)
-                    .map_err(TBackendAdapter::Error::into)?;
+    fn record_vote(
+        &mut self,
+        view_number: ViewId,
+        message_type: HotStuffMessageType,
+        node_hash: TreeNodeHash,
+    ) -> Result<bool, StorageError> {
+        let mut inner = self.inner.write()?;
+        let last_vote = inner
+            .backend_adapter
+            .get_last_vote()
+            .map_err(TBackendAdapter::Error::into)?;
+        if let Some(last_vote) = last_vote {
+            if !last_vote.precedes(view_number, message_type) {
+                return Ok(false);
             }
         }
 
+        let tx = inner
+            .backend_adapter
+            .create_transaction()
+            .map_err(TBackendAdapter::Error::into)?;
+        inner.write_changes(&tx)?;
+        inner
+            .backend_adapter
+            .update_last_vote(
+                &DbVote {
+                    message_type,
+                    view_number,
+                    node_hash,
+                },
+                &tx,
+            )
+            .map_err(TBackendAdapter::Error::into)?;
         inner
             .backend_adapter
             .commit(&tx)
@@ -140,7 +144,7 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWork for ChainDbUnitOf
 
         inner.nodes = vec![];
         inner.instructions = vec![];
-        Ok(())
+        Ok(true)
     }
 
     fn add_node(&mut self, hash: TreeNodeHash, parent: TreeNodeHash, height: u32) -> Result<(), StorageError> {
@@ -176,7 +180,7 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWork for ChainDbUnitOf
                 locked_qc.message_type,
                 locked_qc.view_number,
                 locked_qc.node_hash,
-                locked_qc.signature.clone(),
+                locked_qc.signatures.clone(),
             ));
         }
 
@@ -190,7 +194,7 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWork for ChainDbUnitOf
                 message_type: qc.message_type(),
                 view_number: qc.view_number(),
                 node_hash: *qc.node_hash(),
-                signature: qc.signature().cloned(),
+                signatures: qc.signatures().clone(),
             },
             false,
         ));
@@ -205,14 +209,14 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWork for ChainDbUnitOf
             locked_qc.message_type = qc.message_type();
             locked_qc.view_number = qc.view_number();
             locked_qc.node_hash = *qc.node_hash();
-            locked_qc.signature = qc.signature().cloned();
+            locked_qc.signatures = qc.signatures().clone();
         } else {
             inner.locked_qc = Some(UnitOfWorkTracker::new(
                 DbQc {
                     message_type: qc.message_type(),
                     view_number: qc.view_number(),
                     node_hash: *qc.node_hash(),
-                    signature: qc.signature().cloned(),
+                    signatures: qc.signatures().clone(),
                 },
                 true,
             ));
@@ -239,7 +243,7 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWork for ChainDbUnitOf
                 prepare_qc.message_type,
                 prepare_qc.view_number,
                 prepare_qc.node_hash,
-                prepare_qc.signature.clone(),
+                prepare_qc.signatures.clone(),
             )));
         }
 
@@ -255,7 +259,7 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWork for ChainDbUnitOf
                     message_type: qc.message_type(),
                     view_number: qc.view_number(),
                     node_hash: *qc.node_hash(),
-                    signature: qc.signature().cloned(),
+                    signatures: qc.signatures().clone(),
                 },
                 false,
             )
@@ -274,7 +278,7 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWork for ChainDbUnitOf
                         message_type: qc.message_type(),
                         view_number: qc.view_number(),
                         node_hash: *qc.node_hash(),
-                        signature: qc.signature().cloned(),
+                        signatures: qc.signatures().clone(),
                     },
                     true,
                 ));
@@ -284,7 +288,7 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWork for ChainDbUnitOf
                 db_qc.message_type = qc.message_type();
                 db_qc.view_number = qc.view_number();
                 db_qc.node_hash = *qc.node_hash();
-                db_qc.signature = qc.signature().cloned();
+                db_qc.signatures = qc.signatures().clone();
             },
         }
 
@@ -329,6 +333,55 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWorkInner<TBackendAdap
         }
     }
 
+    /// Writes all dirty items to the backend within `tx`
+    fn write_changes(&self, tx: &TBackendAdapter::DbTransaction) -> Result<(), StorageError> {
+        for (id, item) in &self.nodes {
+            if item.is_dirty() {
+                match id {
+                    Some(i) => self
+                        .backend_adapter
+                        .update_node(i, &*item.get(), tx)
+                        .map_err(TBackendAdapter::Error::into)?,
+                    None => self
+                        .backend_adapter
+                        .insert_node(&*item.get(), tx)
+                        .map_err(TBackendAdapter::Error::into)?,
+                }
+            }
+        }
+
+        for (id, item) in &self.instructions {
+            if item.is_dirty() {
+                match id {
+                    Some(_i) => {
+                        unimplemented!("Cannot update instructions");
+                    },
+                    None => self
+                        .backend_adapter
+                        .insert_instruction(&*item.get(), tx)
+                        .map_err(TBackendAdapter::Error::into)?,
+                }
+            }
+        }
+
+        if let Some(ref locked_qc) = self.locked_qc {
+            if locked_qc.is_dirty() {
+                self.backend_adapter
+                    .update_locked_qc(&*locked_qc.get(), tx)
+                    .map_err(TBackendAdapter::Error::into)?;
+            }
+        }
+
+        if let Some(ref prepare_qc) = self.prepare_qc {
+            if prepare_qc.is_dirty() {
+                self.backend_adapter
+                    .update_prepare_qc(&*prepare_qc.get(), tx)
+                    .map_err(TBackendAdapter::Error::into)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn find_proposed_node(
         &mut self,
         node_hash: &TreeNodeHash,
@@ -357,3 +410,53 @@ impl<TBackendAdapter: ChainDbBackendAdapter> ChainDbUnitOfWorkInner<TBackendAdap
         Ok(node.map(Into::into))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::{chain::ChainDb, mocks::chain_db::MockChainDbBackupAdapter};
+
+    #[test]
+    fn it_records_each_vote_once() {
+        let db = ChainDb::new(MockChainDbBackupAdapter::new());
+        let node_hash = TreeNodeHash::zero();
+        let mut uow = db.new_unit_of_work();
+        assert!(uow
+            .record_vote(ViewId(1), HotStuffMessageType::Prepare, node_hash)
+            .unwrap());
+        assert!(!uow
+            .record_vote(ViewId(1), HotStuffMessageType::Prepare, node_hash)
+            .unwrap());
+
+        // The record survives a new unit of work, as it would a restart
+        let mut uow = db.new_unit_of_work();
+        assert!(!uow
+            .record_vote(ViewId(0), HotStuffMessageType::Commit, node_hash)
+            .unwrap());
+        assert!(uow
+            .record_vote(ViewId(1), HotStuffMessageType::PreCommit, node_hash)
+            .unwrap());
+        let last_vote = db.get_last_vote().unwrap().unwrap();
+        assert_eq!(last_vote.view_number, ViewId(1));
+        assert_eq!(last_vote.message_type, HotStuffMessageType::PreCommit);
+    }
+
+    #[test]
+    fn it_reads_the_committed_chain_below_a_committed_node() {
+        let db = ChainDb::new(MockChainDbBackupAdapter::new());
+        let hash = |height: u8| TreeNodeHash::from([height; 32]);
+        let mut uow = db.new_unit_of_work();
+        // View 3 proposed a node on a fork that was never committed
+        for (height, parent) in [(1, 0), (2, 1), (3, 2), (4, 2), (5, 4), (6, 5)] {
+            uow.add_node(hash(height), hash(parent), u32::from(height)).unwrap();
+        }
+        for height in [1, 2, 4, 5] {
+            uow.commit_node(&hash(height)).unwrap();
+        }
+        uow.commit().unwrap();
+
+        assert_eq!(db.get_committed_chain_heights(0, 5).unwrap(), Some(vec![1, 2, 4]));
+        assert_eq!(db.get_committed_chain_heights(2, 3).unwrap(), Some(vec![2]));
+        assert_eq!(db.get_committed_chain_heights(0, 6).unwrap(), None);
+    }
+}
@@ -0,0 +1,63 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::models::{HotStuffMessageType, TreeNodeHash, ViewId};
+
+/// The last vote cast by this node. It is written before the vote is sent, so that a restarted node never signs a
+/// second vote for a view and phase that it has already voted in.
+#[derive(Debug, Clone)]
+pub struct DbVote {
+    pub message_type: HotStuffMessageType,
+    pub view_number: ViewId,
+    pub node_hash: TreeNodeHash,
+}
+
+impl DbVote {
+    /// Returns true if a vote of `message_type` in `view_number` would come after this vote. Votes within a view are
+    /// ordered by phase.
+    pub fn precedes(&self, view_number: ViewId, message_type: HotStuffMessageType) -> bool {
+        (self.view_number, self.message_type.as_u8()) < (view_number, message_type.as_u8())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vote(view_number: u64, message_type: HotStuffMessageType) -> DbVote {
+        DbVote {
+            message_type,
+            view_number: ViewId(view_number),
+            node_hash: TreeNodeHash::zero(),
+        }
+    }
+
+    #[test]
+    fn it_orders_votes_by_view_then_phase() {
+        let last_vote = vote(5, HotStuffMessageType::PreCommit);
+        assert!(last_vote.precedes(ViewId(5), HotStuffMessageType::Commit));
+        assert!(last_vote.precedes(ViewId(6), HotStuffMessageType::Prepare));
+        assert!(!last_vote.precedes(ViewId(5), HotStuffMessageType::PreCommit));
+        assert!(!last_vote.precedes(ViewId(5), HotStuffMessageType::Prepare));
+        assert!(!last_vote.precedes(ViewId(4), HotStuffMessageType::Commit));
+    }
+}
@@ -27,12 +27,20 @@ use crate::storage::metadata_backend_adapter::AsKeyBytes;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChainDbMetadataKey {
     CheckpointNumber,
+    LastCheckpointStateHeight,
+    LastCheckpointNodeHeight,
+    HandoverActivationHeight,
+    HandoverView,
 }
 
 impl AsKeyBytes for ChainDbMetadataKey {
     fn as_key_bytes(&self) -> &[u8] {
         match self {
             ChainDbMetadataKey::CheckpointNumber => b"checkpoint-number",
+            ChainDbMetadataKey::LastCheckpointStateHeight => b"last-checkpoint-state-height",
+            ChainDbMetadataKey::LastCheckpointNodeHeight => b"last-checkpoint-node-height",
+            ChainDbMetadataKey::HandoverActivationHeight => b"handover-activation-height",
+            ChainDbMetadataKey::HandoverView => b"handover-view",
         }
     }
 }
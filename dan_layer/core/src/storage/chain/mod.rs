@@ -23,15 +23,19 @@
 mod chain_db;
 mod chain_db_backend_adapter;
 mod chain_db_unit_of_work;
+mod chain_pruning_stats;
 mod db_instruction;
 mod db_node;
 mod db_qc;
+mod db_vote;
 mod metadata_key;
 
 pub use chain_db::ChainDb;
 pub use chain_db_backend_adapter::ChainDbBackendAdapter;
 pub use chain_db_unit_of_work::ChainDbUnitOfWork;
+pub use chain_pruning_stats::ChainPruningStats;
 pub use db_instruction::DbInstruction;
 pub use db_node::DbNode;
 pub use db_qc::DbQc;
+pub use db_vote::DbVote;
 pub use metadata_key::ChainDbMetadataKey;
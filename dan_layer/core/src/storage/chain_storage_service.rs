@@ -23,7 +23,7 @@
 use async_trait::async_trait;
 
 use crate::{
-    models::{HotStuffTreeNode, Payload, SidechainMetadata},
+    models::{HotStuffTreeNode, Payload, SideChainBlock, SidechainMetadata},
     storage::{chain::ChainDbUnitOfWork, StorageError},
 };
 
@@ -37,4 +37,7 @@ pub trait ChainStorageService<TPayload: Payload> {
         node: &HotStuffTreeNode<TPayload>,
         db: TUnitOfWork,
     ) -> Result<(), StorageError>;
+    /// Rebuilds the payload of a stored block. Only the instructions of a payload are stored, so the rebuilt payload
+    /// may differ from the proposed payload.
+    fn payload_from_block(&self, block: SideChainBlock) -> TPayload;
 }
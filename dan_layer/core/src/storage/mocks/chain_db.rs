@@ -26,9 +26,9 @@ use tari_utilities::message_format::MessageFormat;
 
 use super::MemoryChainDb;
 use crate::{
-    models::{QuorumCertificate, TreeNodeHash},
+    models::{MisbehaviourEvidence, QuorumCertificate, TreeNodeHash},
     storage::{
-        chain::{ChainDbBackendAdapter, ChainDbMetadataKey, DbInstruction, DbNode, DbQc},
+        chain::{ChainDbBackendAdapter, ChainDbMetadataKey, ChainPruningStats, DbInstruction, DbNode, DbQc, DbVote},
         AtomicDb,
         MetadataBackendAdapter,
         StorageError,
@@ -44,6 +44,19 @@ impl MockChainDbBackupAdapter {
     pub fn new() -> Self {
         Self { db: Default::default() }
     }
+
+    /// Returns the committed nodes, ordered by height
+    pub fn get_committed_nodes(&self) -> Result<Vec<DbNode>, StorageError> {
+        let lock = self.db.read()?;
+        let mut nodes = lock
+            .nodes
+            .rows()
+            .filter(|node| node.is_committed)
+            .cloned()
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|node| node.height);
+        Ok(nodes)
+    }
 }
 
 impl AtomicDb for MockChainDbBackupAdapter {
@@ -113,6 +126,8 @@ impl ChainDbBackendAdapter for MockChainDbBackupAdapter {
                 Some(r) => Some(r),
                 None => Some(rec),
             })
+            // Same as the sqlite backend, the locked QC is the highest until a prepare QC has been set
+            .or_else(|| lock.locked_qc.rows().next())
             .ok_or(StorageError::NotFound)?;
 
         Ok(highest.clone().into())
@@ -152,6 +167,18 @@ impl ChainDbBackendAdapter for MockChainDbBackupAdapter {
         Ok(rec)
     }
 
+    fn find_uncommitted_nodes(&self) -> Result<Vec<(Self::Id, DbNode)>, Self::Error> {
+        let lock = self.db.read()?;
+        let mut recs = lock
+            .nodes
+            .records()
+            .filter(|(_, rec)| !rec.is_committed)
+            .map(|(id, node)| (id, node.clone()))
+            .collect::<Vec<_>>();
+        recs.sort_by_key(|(_, node)| node.height);
+        Ok(recs)
+    }
+
     fn find_all_instructions_by_node(&self, node_id: Self::Id) -> Result<Vec<DbInstruction>, Self::Error> {
         let lock = self.db.read()?;
         let node = lock.nodes.get(node_id).ok_or(StorageError::NotFound)?;
@@ -166,28 +193,74 @@ impl ChainDbBackendAdapter for MockChainDbBackupAdapter {
 
     fn update_prepare_qc(&self, item: &DbQc, _transaction: &Self::DbTransaction) -> Result<(), Self::Error> {
         let mut lock = self.db.write()?;
-        let id = lock
-            .prepare_qc
-            .records()
-            .next()
-            .map(|(id, _)| id)
-            .ok_or(StorageError::NotFound)?;
-        lock.prepare_qc.update(id, item.clone());
+        let id = lock.prepare_qc.records().next().map(|(id, _)| id);
+        match id {
+            Some(id) => {
+                lock.prepare_qc.update(id, item.clone());
+            },
+            None => {
+                lock.prepare_qc.insert(item.clone());
+            },
+        }
         Ok(())
     }
 
     fn update_locked_qc(&self, locked_qc: &DbQc, _transaction: &Self::DbTransaction) -> Result<(), Self::Error> {
         let mut lock = self.db.write()?;
-        let id = lock
-            .locked_qc
-            .records()
-            .next()
-            .map(|(id, _)| id)
-            .ok_or(StorageError::NotFound)?;
-        lock.locked_qc.update(id, locked_qc.clone());
+        let id = lock.locked_qc.records().next().map(|(id, _)| id);
+        match id {
+            Some(id) => {
+                lock.locked_qc.update(id, locked_qc.clone());
+            },
+            None => {
+                lock.locked_qc.insert(locked_qc.clone());
+            },
+        }
         Ok(())
     }
 
+    fn get_last_vote(&self) -> Result<Option<DbVote>, Self::Error> {
+        let lock = self.db.read()?;
+        Ok(lock.last_vote.rows().next().cloned())
+    }
+
+    fn update_last_vote(&self, vote: &DbVote, _transaction: &Self::DbTransaction) -> Result<(), Self::Error> {
+        let mut lock = self.db.write()?;
+        let id = lock.last_vote.records().next().map(|(id, _)| id);
+        match id {
+            Some(id) => {
+                lock.last_vote.update(id, vote.clone());
+            },
+            None => {
+                lock.last_vote.insert(vote.clone());
+            },
+        }
+        Ok(())
+    }
+
+    fn delete_committed_nodes_below(
+        &self,
+        height: u32,
+        _transaction: &Self::DbTransaction,
+    ) -> Result<ChainPruningStats, Self::Error> {
+        let mut lock = self.db.write()?;
+        let is_pruned = |node: &DbNode| node.is_committed && node.height < height;
+        let pruned_hashes = lock
+            .nodes
+            .rows()
+            .filter(|node| is_pruned(node))
+            .map(|node| node.hash)
+            .collect::<Vec<_>>();
+        let nodes_removed = lock.nodes.remove_where(is_pruned);
+        let instructions_removed = lock
+            .instructions
+            .remove_where(|instruction| pruned_hashes.contains(&instruction.node_hash));
+        Ok(ChainPruningStats {
+            nodes_removed,
+            instructions_removed,
+        })
+    }
+
     fn get_tip_node(&self) -> Result<Option<DbNode>, Self::Error> {
         let lock = self.db.read()?;
         let found = lock
@@ -202,6 +275,44 @@ impl ChainDbBackendAdapter for MockChainDbBackupAdapter {
 
         Ok(found)
     }
+
+    fn get_committed_node_heights(&self, from_height: u32, to_height: u32) -> Result<Vec<u32>, Self::Error> {
+        let lock = self.db.read()?;
+        let mut heights = lock
+            .nodes
+            .rows()
+            .filter(|node| node.is_committed && (from_height..to_height).contains(&node.height))
+            .map(|node| node.height)
+            .collect::<Vec<_>>();
+        heights.sort_unstable();
+        Ok(heights)
+    }
+
+    fn find_lowest_committed_node_from(&self, height: u32) -> Result<Option<(Self::Id, DbNode)>, Self::Error> {
+        let lock = self.db.read()?;
+        let rec = lock
+            .nodes
+            .records()
+            .filter(|(_, rec)| rec.is_committed && rec.height >= height)
+            .min_by_key(|(_, rec)| rec.height)
+            .map(|(id, node)| (id, node.clone()));
+        Ok(rec)
+    }
+
+    fn insert_misbehaviour_evidence(
+        &self,
+        evidence: &MisbehaviourEvidence,
+        _transaction: &Self::DbTransaction,
+    ) -> Result<(), Self::Error> {
+        let mut lock = self.db.write()?;
+        lock.misbehaviour_evidence.insert(evidence.clone());
+        Ok(())
+    }
+
+    fn get_misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>, Self::Error> {
+        let lock = self.db.read()?;
+        Ok(lock.misbehaviour_evidence.rows().cloned().collect())
+    }
 }
 
 impl MetadataBackendAdapter<ChainDbMetadataKey> for MockChainDbBackupAdapter {
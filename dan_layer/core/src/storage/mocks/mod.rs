@@ -31,12 +31,15 @@ use std::{
 use tari_common_types::types::FixedHash;
 use tari_dan_engine::state::{mocks::state_db::MockStateDbBackupAdapter, StateDb};
 
-use crate::storage::{
-    chain::{ChainDb, ChainDbMetadataKey, DbInstruction, DbNode, DbQc},
-    global::GlobalDb,
-    mocks::{chain_db::MockChainDbBackupAdapter, global_db::MockGlobalDbBackupAdapter},
-    DbFactory,
-    StorageError,
+use crate::{
+    models::MisbehaviourEvidence,
+    storage::{
+        chain::{ChainDb, ChainDbMetadataKey, DbInstruction, DbNode, DbQc, DbVote},
+        global::GlobalDb,
+        mocks::{chain_db::MockChainDbBackupAdapter, global_db::MockGlobalDbBackupAdapter},
+        DbFactory,
+        StorageError,
+    },
 };
 
 #[derive(Clone, Default)]
@@ -46,6 +49,16 @@ pub struct MockDbFactory {
     _global_db: Arc<RwLock<MockGlobalDbBackupAdapter>>,
 }
 
+impl MockDbFactory {
+    /// Returns the committed nodes of the contract's chain db, ordered by height
+    pub fn get_committed_nodes(&self, contract_id: &FixedHash) -> Result<Vec<DbNode>, StorageError> {
+        match self.chain_db.read().unwrap().get(contract_id) {
+            Some(db) => db.get_committed_nodes(),
+            None => Ok(vec![]),
+        }
+    }
+}
+
 impl DbFactory for MockDbFactory {
     type ChainDbBackendAdapter = MockChainDbBackupAdapter;
     type GlobalDbBackendAdapter = MockGlobalDbBackupAdapter;
@@ -106,7 +119,9 @@ pub(self) struct MemoryChainDb {
     pub instructions: MemoryDbTable<DbInstruction>,
     pub prepare_qc: MemoryDbTable<DbQc>,
     pub locked_qc: MemoryDbTable<DbQc>,
+    pub last_vote: MemoryDbTable<DbVote>,
     pub metadata: MemoryDbTable<(ChainDbMetadataKey, Vec<u8>)>,
+    pub misbehaviour_evidence: MemoryDbTable<MisbehaviourEvidence>,
 }
 
 #[derive(Debug)]
@@ -153,6 +168,13 @@ impl<V> MemoryDbTable<V> {
         self.records.insert(id, v);
     }
 
+    /// Removes the records that match the predicate and returns the number removed
+    pub fn remove_where<F: FnMut(&V) -> bool>(&mut self, mut predicate: F) -> u64 {
+        let len = self.records.len();
+        self.records.retain(|_, v| !predicate(v));
+        (len - self.records.len()) as u64
+    }
+
     pub fn update(&mut self, id: usize, v: V) -> bool {
         match self.records.get_mut(&id) {
             Some(rec) => {
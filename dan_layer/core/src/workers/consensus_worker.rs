@@ -20,26 +20,60 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use std::{
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use log::*;
 use tari_common_types::types::PublicKey;
+use tari_core::transactions::transaction_components::OutputType;
 use tari_dan_engine::state::{models::StateRoot, StateDbUnitOfWork, StateDbUnitOfWorkImpl, StateDbUnitOfWorkReader};
 use tari_shutdown::ShutdownSignal;
-use tokio::time::Duration;
 
 use crate::{
     digital_assets_error::DigitalAssetError,
-    models::{domain_events::ConsensusWorkerDomainEvent, AssetDefinition, ConsensusWorkerState, View, ViewId},
-    services::{CheckpointManager, CommitteeManager, EventsPublisher, PayloadProvider, ServiceSpecification},
+    models::{
+        domain_events::ConsensusWorkerDomainEvent,
+        AssetDefinition,
+        BaseLayerOutput,
+        Committee,
+        ConsensusMode,
+        ConsensusWorkerState,
+        HashLeaderStrategy,
+        HotStuffMessage,
+        HotStuffMessageType,
+        HotStuffTreeNode,
+        LeaderSelection,
+        LeaderStrategy,
+        ReputationLeaderStrategy,
+        RoundRobinLeaderStrategy,
+        View,
+        ViewId,
+    },
+    services::{
+        infrastructure_services::InboundConnectionService,
+        BaseNodeClient,
+        CheckpointManager,
+        CommitteeManager,
+        EventsPublisher,
+        PayloadProcessor,
+        PayloadProvider,
+        ServiceSpecification,
+    },
     storage::{
         chain::{ChainDb, ChainDbUnitOfWork},
+        ChainStorageService,
         DbFactory,
     },
-    workers::{states, states::ConsensusWorkerStateEvent},
+    workers::{
+        states,
+        states::{ConsensusWorkerStateEvent, InvalidMessage, UncommittedNodes},
+        Pacemaker,
+    },
 };
 
 const LOG_TARGET: &str = "tari::dan::consensus_worker";
@@ -50,7 +84,8 @@ pub struct ConsensusWorker<TSpecification: ServiceSpecification> {
     state: ConsensusWorkerState,
     current_view_id: ViewId,
     committee_manager: TSpecification::CommitteeManager,
-    timeout: Duration,
+    pacemaker: Pacemaker<TSpecification::Addr>,
+    leader_strategy: Arc<dyn LeaderStrategy>,
     node_address: TSpecification::Addr,
     payload_provider: TSpecification::PayloadProvider,
     events_publisher: TSpecification::EventsPublisher,
@@ -62,6 +97,9 @@ pub struct ConsensusWorker<TSpecification: ServiceSpecification> {
     chain_storage_service: TSpecification::ChainStorageService,
     state_db_unit_of_work: Option<StateDbUnitOfWorkImpl<TSpecification::StateDbBackendAdapter>>,
     state_db_state_root: Option<StateRoot>,
+    uncommitted_nodes: UncommittedNodes<TSpecification::Payload>,
+    /// The final block that this node signed when its committee last handed the contract over
+    handover_message: Option<HotStuffMessage<TSpecification::Payload>>,
     checkpoint_manager: TSpecification::CheckpointManager,
     validator_node_client_factory: TSpecification::ValidatorNodeClientFactory,
 }
@@ -78,17 +116,24 @@ impl<TSpecification: ServiceSpecification<Addr = PublicKey>> ConsensusWorker<TSp
         payload_processor: TSpecification::PayloadProcessor,
         asset_definition: AssetDefinition,
         base_node_client: TSpecification::BaseNodeClient,
-        timeout: Duration,
+        pacemaker: Pacemaker<TSpecification::Addr>,
         db_factory: TSpecification::DbFactory,
         chain_storage_service: TSpecification::ChainStorageService,
         checkpoint_manager: TSpecification::CheckpointManager,
         validator_node_client_factory: TSpecification::ValidatorNodeClientFactory,
     ) -> Self {
+        let leader_strategy: Arc<dyn LeaderStrategy> = match asset_definition.leader_selection {
+            LeaderSelection::RoundRobin => Arc::new(RoundRobinLeaderStrategy),
+            // Replaced with one built from the chain history before each state is processed
+            LeaderSelection::Reputation => Arc::new(ReputationLeaderStrategy::default()),
+            LeaderSelection::Hash => Arc::new(HashLeaderStrategy::new(asset_definition.contract_id)),
+        };
         Self {
             inbound_connections,
             state: ConsensusWorkerState::Starting,
             current_view_id: ViewId(0),
-            timeout,
+            pacemaker,
+            leader_strategy,
             outbound_service,
             committee_manager,
             node_address: node_id,
@@ -102,22 +147,76 @@ impl<TSpecification: ServiceSpecification<Addr = PublicKey>> ConsensusWorker<TSp
             chain_storage_service,
             state_db_state_root: None,
             state_db_unit_of_work: None,
+            uncommitted_nodes: UncommittedNodes::new(),
+            handover_message: None,
             checkpoint_manager,
             validator_node_client_factory,
         }
     }
 
+    /// Starts the worker in NEXT VIEW, skipping the base layer checks of STARTING and SYNCHRONIZING. The committee
+    /// manager must already know the committee and the contract's state db must exist.
+    #[cfg(test)]
+    pub(crate) fn start_at_next_view(&mut self) {
+        self.state = ConsensusWorkerState::NextView;
+    }
+
     fn get_current_view(&self) -> Result<View, DigitalAssetError> {
         Ok(View {
             view_id: self.current_view_id,
-            is_leader: self
-                .committee_manager
-                .current_committee()?
-                .leader_for_view(self.current_view_id) ==
-                &self.node_address,
+            is_leader: self.committee()?.leader_for_view(self.current_view_id) == &self.node_address,
         })
     }
 
+    /// The current committee, selecting leaders with the contract's leader strategy
+    fn committee(&self) -> Result<Committee<TSpecification::Addr>, DigitalAssetError> {
+        Ok(self
+            .committee_manager
+            .current_committee()?
+            .clone()
+            .with_leader_strategy(self.leader_strategy.clone()))
+    }
+
+    /// Rebuilds a reputation-based leader strategy from the committed chain below the lowest committed node at the end
+    /// of the history range of the next view. Committed nodes are final, so every committee member that has committed
+    /// that node selects the same leaders. Until then, the current strategy is kept.
+    fn update_leader_strategy(
+        &mut self,
+        chain_db: &ChainDb<TSpecification::ChainDbBackendAdapter>,
+    ) -> Result<(), DigitalAssetError> {
+        if self.asset_definition.leader_selection != LeaderSelection::Reputation {
+            return Ok(());
+        }
+        let history_start = ReputationLeaderStrategy::history_range(self.current_view_id).start;
+        let history_end = ReputationLeaderStrategy::history_range(self.current_view_id.next()).end;
+        let committed_views = match chain_db.get_committed_chain_heights(
+            u32::try_from(history_start).unwrap_or(u32::MAX),
+            u32::try_from(history_end).unwrap_or(u32::MAX),
+        )? {
+            Some(committed_views) => committed_views,
+            None => {
+                debug!(
+                    target: LOG_TARGET,
+                    "No node at or above height {} has been committed, keeping the current leader strategy",
+                    history_end
+                );
+                return Ok(());
+            },
+        };
+        let strategy =
+            ReputationLeaderStrategy::from_committed_views(history_start, committed_views.into_iter().map(u64::from));
+        let failed_members =
+            strategy.failed_members(self.committee_manager.current_committee()?.len(), self.current_view_id);
+        if !failed_members.is_empty() {
+            debug!(
+                target: LOG_TARGET,
+                "Skipping committee members {:?} as leaders of view {}", failed_members, self.current_view_id
+            );
+        }
+        self.leader_strategy = Arc::new(strategy);
+        Ok(())
+    }
+
     pub async fn run(
         &mut self,
         shutdown: ShutdownSignal,
@@ -131,6 +230,16 @@ impl<TSpecification: ServiceSpecification<Addr = PublicKey>> ConsensusWorker<TSp
             .get_tip_node()?
             .map(|n| ViewId(u64::from(n.height())))
             .unwrap_or_else(|| ViewId(0));
+        if self.asset_definition.consensus_mode == ConsensusMode::Chained {
+            self.uncommitted_nodes = states::ChainedViewState::<TSpecification>::recover_uncommitted_nodes(
+                &self.asset_definition.contract_id,
+                &chain_db,
+                &self.chain_storage_service,
+                &self.payload_processor,
+                &self.db_factory,
+            )
+            .await?;
+        }
         info!(
             target: LOG_TARGET,
             "🚀 Consensus worker started for asset '{}'. Tip: {}",
@@ -144,12 +253,14 @@ impl<TSpecification: ServiceSpecification<Addr = PublicKey>> ConsensusWorker<TSp
                     break;
                 }
             }
+            self.update_leader_strategy(&chain_db)?;
             let mut processor = ConsensusWorkerProcessor {
                 worker: self,
                 chain_db: &chain_db,
                 shutdown: &shutdown,
             };
             let next_event = processor.next_state_event().await?;
+            self.record_misbehaviour_evidence(&chain_db).await?;
             if next_event.must_shutdown() {
                 info!(
                     target: LOG_TARGET,
@@ -180,7 +291,18 @@ struct ConsensusWorkerProcessor<'a, T: ServiceSpecification> {
 
 impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a, T> {
     async fn next_state_event(&mut self) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
-        use ConsensusWorkerState::{Commit, Decide, Idle, NextView, PreCommit, Prepare, Starting, Synchronizing};
+        use ConsensusWorkerState::{
+            ChainedView,
+            Commit,
+            Decide,
+            Idle,
+            NextView,
+            PreCommit,
+            Prepare,
+            Starting,
+            Synchronizing,
+            ViewTimeout,
+        };
         match &mut self.worker.state {
             Starting => self.starting().await,
             Synchronizing => self.synchronizing().await,
@@ -189,6 +311,8 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
             Commit => self.commit().await,
             Decide => self.decide().await,
             NextView => self.next_view().await,
+            ViewTimeout => self.view_timeout().await,
+            ChainedView => self.chained_view().await,
             Idle => self.idle().await,
         }
     }
@@ -206,7 +330,7 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
     }
 
     async fn synchronizing(&mut self) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
-        states::Synchronizing::<T>::new()
+        let event = states::Synchronizing::<T>::new()
             .next_event(
                 &mut self.worker.base_node_client,
                 &self.worker.asset_definition,
@@ -214,7 +338,73 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
                 &self.worker.validator_node_client_factory,
                 &self.worker.node_address,
             )
-            .await
+            .await?;
+        match event {
+            ConsensusWorkerStateEvent::Synchronized |
+            ConsensusWorkerStateEvent::BaseLayerCheckpointNotFound |
+            ConsensusWorkerStateEvent::BaseLayerAssetRegistrationNotFound => {
+                Ok(self.recover_last_vote().await?.unwrap_or(event))
+            },
+            event => Ok(event),
+        }
+    }
+
+    /// Resumes the view that this node last voted in before it was restarted, rebuilding the pending state by
+    /// re-executing the payload of the node that it voted for. Returns None if there is no view to resume, in which
+    /// case the node moves on to the next view.
+    async fn recover_last_vote(&mut self) -> Result<Option<ConsensusWorkerStateEvent>, DigitalAssetError> {
+        let last_vote = match self.chain_db.get_last_vote()? {
+            Some(vote) => vote,
+            None => return Ok(None),
+        };
+        let state = match last_vote.message_type {
+            HotStuffMessageType::Prepare => ConsensusWorkerState::PreCommit,
+            HotStuffMessageType::PreCommit => ConsensusWorkerState::Commit,
+            HotStuffMessageType::Commit => ConsensusWorkerState::Decide,
+            // A chained mode vote is stored along with the node that it votes for, so the next view follows the tip
+            _ => return Ok(None),
+        };
+        let block = match self.chain_db.find_sidechain_block_by_node_hash(&last_vote.node_hash)? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let parent = *block.node().parent();
+        let height = block.node().height();
+        let payload = self.worker.chain_storage_service.payload_from_block(block);
+        let state_tx = self
+            .worker
+            .db_factory
+            .get_state_db(&self.worker.asset_definition.contract_id)?
+            .ok_or(DigitalAssetError::MissingDatabase)?
+            .new_unit_of_work(last_vote.view_number.as_u64());
+        let execution_result = self
+            .worker
+            .payload_processor
+            .process_payload(&payload, state_tx.clone())
+            .await?;
+
+        // The node hash commits to the payload and its execution result, so a matching hash means that the pending
+        // state was rebuilt exactly and has not been committed yet
+        if HotStuffTreeNode::new(parent, payload, execution_result, height).calculate_hash() != last_vote.node_hash {
+            warn!(
+                target: LOG_TARGET,
+                "Could not rebuild the state of node '{}' voted for in {}, moving on to the next view",
+                last_vote.node_hash,
+                last_vote.view_number
+            );
+            return Ok(None);
+        }
+
+        info!(
+            target: LOG_TARGET,
+            "Resuming {} in {:?} after voting in {:?}", last_vote.view_number, state, last_vote.message_type
+        );
+        self.worker.state_db_state_root = Some(execution_result.state_root);
+        self.worker.state_db_unit_of_work = Some(state_tx);
+        Ok(Some(ConsensusWorkerStateEvent::Recovered {
+            state,
+            view: last_vote.view_number,
+        }))
     }
 
     async fn prepare(&mut self) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
@@ -233,9 +423,9 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
         let res = prepare
             .next_event(
                 &self.worker.get_current_view()?,
-                self.worker.timeout,
+                self.worker.pacemaker.view_timeout(),
                 &self.worker.asset_definition,
-                self.worker.committee_manager.current_committee()?,
+                &self.worker.committee()?,
                 &self.worker.inbound_connections,
                 &mut self.worker.outbound_service,
                 &mut self.worker.payload_provider,
@@ -246,7 +436,9 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
                 &mut state_tx,
                 &self.worker.db_factory,
             )
-            .await?;
+            .await;
+        self.worker.publish_invalid_messages(prepare.take_invalid_messages());
+        let res = res?;
         // Will only be committed in DECIDE
         self.worker.state_db_state_root = Some(state_tx.calculate_root()?);
         self.worker.state_db_unit_of_work = Some(state_tx);
@@ -258,19 +450,21 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
         let mut unit_of_work = self.chain_db.new_unit_of_work();
         let mut state = states::PreCommitState::<T>::new(
             self.worker.node_address.clone(),
-            self.worker.committee_manager.current_committee()?.clone(),
+            self.worker.committee()?,
             self.worker.asset_definition.contract_id,
         );
         let res = state
             .next_event(
-                self.worker.timeout,
+                self.worker.pacemaker.view_timeout(),
                 &self.worker.get_current_view()?,
                 &self.worker.inbound_connections,
                 &mut self.worker.outbound_service,
                 &self.worker.signing_service,
                 unit_of_work.clone(),
             )
-            .await?;
+            .await;
+        self.worker.publish_invalid_messages(state.take_invalid_messages());
+        let res = res?;
         unit_of_work.commit()?;
         Ok(res)
     }
@@ -281,7 +475,7 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
         let mut state = states::CommitState::<T>::new(
             self.worker.node_address.clone(),
             self.worker.asset_definition.contract_id,
-            self.worker.committee_manager.current_committee()?.clone(),
+            self.worker.committee()?,
         );
         let proposed_state_root =
             self.worker
@@ -293,7 +487,7 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
                 })?;
         let res = state
             .next_event(
-                self.worker.timeout,
+                self.worker.pacemaker.view_timeout(),
                 &self.worker.get_current_view()?,
                 &mut self.worker.inbound_connections,
                 &mut self.worker.outbound_service,
@@ -302,7 +496,9 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
                 proposed_state_root,
                 current_checkpoint_num,
             )
-            .await?;
+            .await;
+        self.worker.publish_invalid_messages(state.take_invalid_messages());
+        let res = res?;
         unit_of_work.commit()?;
         Ok(res)
     }
@@ -312,32 +508,40 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
         let mut state = states::DecideState::<T>::new(
             self.worker.node_address.clone(),
             self.worker.asset_definition.contract_id,
-            self.worker.committee_manager.current_committee()?.clone(),
+            self.worker.committee()?,
         );
         let current_view = self.worker.get_current_view()?;
         let res = state
             .next_event(
-                self.worker.timeout,
+                self.worker.pacemaker.view_timeout(),
                 &current_view,
                 &mut self.worker.inbound_connections,
                 &mut self.worker.outbound_service,
                 unit_of_work.clone(),
                 &mut self.worker.payload_provider,
             )
-            .await?;
+            .await;
+        self.worker.publish_invalid_messages(state.take_invalid_messages());
+        let res = res?;
 
         unit_of_work.commit()?;
         if let Some(mut state_tx) = self.worker.state_db_unit_of_work.take() {
             state_tx.commit()?;
             // TODO: Read checkpoint interval from constitution
-            if current_view.is_leader() && current_view.view_id().as_u64() % 50 == 0 {
-                let signatures = state.collected_checkpoint_signatures();
-                let checkpoint_number = self.chain_db.get_current_checkpoint_number()?;
-                self.worker
-                    .checkpoint_manager
-                    .create_checkpoint(checkpoint_number, state_tx.calculate_root()?, &signatures)
-                    .await?;
-                self.chain_db.increment_checkpoint_number()?;
+            if current_view.view_id().as_u64() % 50 == 0 {
+                if current_view.is_leader() {
+                    let signatures = state.collected_checkpoint_signatures();
+                    let checkpoint_number = self.chain_db.get_current_checkpoint_number()?;
+                    self.worker
+                        .checkpoint_manager
+                        .create_checkpoint(checkpoint_number, state_tx.calculate_root()?, &signatures)
+                        .await?;
+                    self.chain_db.increment_checkpoint_number()?;
+                }
+                // Every committee member records where the checkpoint is, so that history before it can be pruned
+                let node_height = self.chain_db.get_tip_node()?.map(|node| node.height()).unwrap_or(0);
+                self.chain_db
+                    .set_last_checkpoint_heights(state_tx.context().height(), node_height)?;
             }
             Ok(res)
         } else {
@@ -357,54 +561,299 @@ impl<'a, T: ServiceSpecification<Addr = PublicKey>> ConsensusWorkerProcessor<'a,
         );
         self.worker.state_db_state_root = None;
         self.worker.state_db_unit_of_work = None;
+        if let Some(event) = self.change_epoch().await? {
+            return Ok(event);
+        }
         let mut state = states::NextViewState::<T>::new();
         state
             .next_event(
                 &self.worker.get_current_view()?,
                 &self.worker.db_factory,
                 &mut self.worker.outbound_service,
-                self.worker.committee_manager.current_committee()?,
+                &self.worker.committee()?,
                 self.worker.node_address.clone(),
                 &self.worker.asset_definition,
-                &self.worker.payload_provider,
                 self.shutdown,
             )
             .await
     }
 
+    /// Hands the contract over to the incoming committee once the base layer reaches the activation height of a
+    /// scheduled committee change. Returns None if the epoch has not ended, or if the outgoing committee has not
+    /// committed the node that it would hand over yet.
+    // TODO: hand over in chained mode, where the highest certified node is never committed
+    async fn change_epoch(&mut self) -> Result<Option<ConsensusWorkerStateEvent>, DigitalAssetError> {
+        let contract_id = self.worker.asset_definition.contract_id;
+        let tip = self.worker.base_node_client.get_tip_info().await?;
+        let height = tip
+            .height_of_longest_chain
+            .saturating_sub(self.worker.asset_definition.base_layer_confirmation_time);
+        if self.worker.committee_manager.pending_change().is_none() {
+            let mut amendments = self
+                .worker
+                .base_node_client
+                .get_current_contract_outputs(height, contract_id, OutputType::ContractAmendment)
+                .await?;
+            if let Some(utxo) = amendments.pop() {
+                self.worker
+                    .committee_manager
+                    .read_from_amendment(BaseLayerOutput::try_from(utxo)?)?;
+            }
+        }
+
+        let outgoing = self.worker.committee()?;
+        let (activation_height, incoming) = match self.worker.committee_manager.pending_change() {
+            Some(change) if change.activation_height <= height => (
+                change.activation_height,
+                Committee::new(change.members.clone()).with_leader_strategy(self.worker.leader_strategy.clone()),
+            ),
+            // Members of the incoming committee wait for the activation height
+            _ if !outgoing.contains(&self.worker.node_address) => {
+                return Ok(Some(ConsensusWorkerStateEvent::NotPartOfCommittee))
+            },
+            _ => return Ok(None),
+        };
+        if outgoing.contains(&self.worker.node_address) && !self.is_quiescent()? {
+            debug!(
+                target: LOG_TARGET,
+                "Epoch ended at height {}, waiting for a committed node to hand over", activation_height
+            );
+            return Ok(None);
+        }
+
+        info!(
+            target: LOG_TARGET,
+            "Epoch ended at height {}, handing contract '{}' over to the incoming committee",
+            activation_height,
+            contract_id
+        );
+        let mut unit_of_work = self.chain_db.new_unit_of_work();
+        let mut state =
+            states::EpochChangeState::<T>::new(self.worker.node_address.clone(), contract_id, outgoing, incoming);
+        let res = state
+            .next_event(
+                self.worker.pacemaker.view_timeout(),
+                &self.worker.get_current_view()?,
+                &self.worker.inbound_connections,
+                &mut self.worker.outbound_service,
+                &self.worker.signing_service,
+                unit_of_work.clone(),
+                &self.worker.db_factory,
+                &self.worker.validator_node_client_factory,
+            )
+            .await;
+        self.worker.publish_invalid_messages(state.take_invalid_messages());
+        if let Some(handover_message) = state.take_handover_message() {
+            self.worker.handover_message = Some(handover_message);
+        }
+        let res = res?;
+        if let Some(handover_view) = state.handover_view() {
+            unit_of_work.commit()?;
+            self.chain_db.set_last_handover(activation_height, handover_view)?;
+            self.worker.committee_manager.activate_pending_change(handover_view)?;
+        }
+        Ok(Some(res))
+    }
+
+    /// Returns true if the highest certified node has been committed, so that the committee has no node in flight
+    fn is_quiescent(&self) -> Result<bool, DigitalAssetError> {
+        let tip = match self.chain_db.get_tip_node()? {
+            Some(tip) => tip,
+            None => return Ok(false),
+        };
+        Ok(tip.is_committed() && self.chain_db.find_highest_prepared_qc()?.node_hash() == tip.hash())
+    }
+
+    async fn chained_view(&mut self) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
+        let mut unit_of_work = self.chain_db.new_unit_of_work();
+        let mut state = states::ChainedViewState::<T>::new(
+            self.worker.node_address.clone(),
+            self.worker.asset_definition.contract_id,
+            self.worker.committee()?,
+        );
+        let res = state
+            .next_event(
+                self.worker.pacemaker.view_timeout(),
+                &self.worker.get_current_view()?,
+                &self.worker.asset_definition,
+                &self.worker.inbound_connections,
+                &mut self.worker.outbound_service,
+                &mut self.worker.payload_provider,
+                &self.worker.signing_service,
+                &mut self.worker.payload_processor,
+                &self.worker.chain_storage_service,
+                unit_of_work.clone(),
+                &self.worker.db_factory,
+                &mut self.worker.uncommitted_nodes,
+            )
+            .await;
+        self.worker.publish_invalid_messages(state.take_invalid_messages());
+        let res = res?;
+        unit_of_work.commit()?;
+        Ok(res)
+    }
+
+    async fn view_timeout(&mut self) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
+        let mut state = states::ViewTimeoutState::<T>::new(
+            self.worker.node_address.clone(),
+            self.worker.asset_definition.contract_id,
+            self.worker.committee()?,
+        );
+        let res = state
+            .next_event(
+                self.worker.pacemaker.view_timeout(),
+                &self.worker.get_current_view()?,
+                &self.worker.inbound_connections,
+                &mut self.worker.outbound_service,
+                &self.worker.signing_service,
+                &mut self.worker.pacemaker,
+            )
+            .await;
+        self.worker.publish_invalid_messages(state.take_invalid_messages());
+        res
+    }
+
     async fn idle(&mut self) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
         info!(target: LOG_TARGET, "No work to do, idling");
         let state = states::IdleState::default();
-        state.next_event().await
+        let handover_message = match self.worker.handover_message.clone() {
+            Some(message) => message,
+            None => return state.next_event().await,
+        };
+        // Members of an outgoing committee keep sending their final block to incoming members that missed it, whether
+        // or not they saw the handover complete
+        let incoming = match self.worker.committee_manager.pending_change() {
+            Some(change) => Committee::new(change.members.clone()),
+            None => self.worker.committee()?,
+        };
+        tokio::select! {
+            res = state.next_event() => res,
+            res = states::EpochChangeState::<T>::answer_handover_requests(
+                &self.worker.node_address,
+                &incoming,
+                &handover_message,
+                &self.worker.inbound_connections,
+                &mut self.worker.outbound_service,
+            ) => res.map(|_| ConsensusWorkerStateEvent::TimedOut),
+        }
     }
 }
 
 impl<TSpecification: ServiceSpecification<Addr = PublicKey>> ConsensusWorker<TSpecification> {
+    fn publish_invalid_messages(&mut self, invalid_messages: Vec<InvalidMessage<TSpecification::Addr>>) {
+        for invalid_message in invalid_messages {
+            self.events_publisher
+                .publish(ConsensusWorkerDomainEvent::InvalidMessageReceived {
+                    sender: invalid_message.sender.to_string(),
+                    reason: invalid_message.reason.to_string(),
+                });
+        }
+    }
+
+    /// Stores the evidence of equivocation by committee members that the inbound connection service has found, and
+    /// publishes an event for each offence
+    async fn record_misbehaviour_evidence(
+        &mut self,
+        chain_db: &ChainDb<TSpecification::ChainDbBackendAdapter>,
+    ) -> Result<(), DigitalAssetError> {
+        // Until the committee is known, the evidence is left with the inbound connection service
+        let committee = match self.committee_manager.current_committee() {
+            Ok(committee) => committee.clone(),
+            Err(_) => return Ok(()),
+        };
+        for evidence in self.inbound_connections.take_misbehaviour_evidence().await? {
+            if !committee.contains(evidence.offender()) {
+                debug!(
+                    target: LOG_TARGET,
+                    "Ignoring evidence against {} who is not a committee member",
+                    evidence.offender()
+                );
+                continue;
+            }
+            warn!(
+                target: LOG_TARGET,
+                "Committee member {} signed {} in view {}",
+                evidence.offender(),
+                evidence.kind,
+                evidence.view_number()
+            );
+            chain_db.insert_misbehaviour_evidence(&evidence)?;
+            self.events_publisher
+                .publish(ConsensusWorkerDomainEvent::MisbehaviourDetected {
+                    offender: evidence.offender().to_string(),
+                    kind: evidence.kind,
+                    view_number: evidence.view_number(),
+                });
+        }
+        Ok(())
+    }
+
     fn transition(
         &mut self,
         event: ConsensusWorkerStateEvent,
     ) -> Result<(ConsensusWorkerState, ConsensusWorkerState), DigitalAssetError> {
-        use ConsensusWorkerState::{Commit, Decide, Idle, NextView, PreCommit, Prepare, Starting, Synchronizing};
+        use ConsensusWorkerState::{
+            ChainedView,
+            Commit,
+            Decide,
+            Idle,
+            NextView,
+            PreCommit,
+            Prepare,
+            Starting,
+            Synchronizing,
+            ViewTimeout,
+        };
         #[allow(clippy::enum_glob_use)]
         use ConsensusWorkerStateEvent::*;
         let from = self.state;
         self.state = match (&self.state, event) {
             (Starting, Initialized) => Synchronizing,
             (Synchronizing, Synchronized) => NextView,
+            (Synchronizing, Recovered { state, view }) => {
+                self.current_view_id = view;
+                state
+            },
             (_, NotPartOfCommittee) => Idle,
             (Idle, TimedOut) => Starting,
+            (Starting, BaseLayerCheckopintNotFound) => {
+                info!(target: LOG_TARGET, "No constitution found for the contract");
+                Idle
+            },
             (_, TimedOut) => {
-                warn!(target: LOG_TARGET, "State timed out for {}", self.current_view_id);
+                self.pacemaker.record_timeout();
+                warn!(
+                    target: LOG_TARGET,
+                    "State timed out for {} ({} consecutive timeouts, next timeout {:.2?})",
+                    self.current_view_id,
+                    self.pacemaker.consecutive_timeouts(),
+                    self.pacemaker.view_timeout()
+                );
+                ViewTimeout
+            },
+            (ViewTimeout, TimeoutCertified { view }) => {
+                self.current_view_id = view;
                 NextView
             },
             (NextView, NewView { new_view }) => {
                 self.current_view_id = new_view;
-                Prepare
+                match self.asset_definition.consensus_mode {
+                    ConsensusMode::Basic => Prepare,
+                    ConsensusMode::Chained => ChainedView,
+                }
+            },
+            (ChainedView, NewView { new_view }) => {
+                self.pacemaker.record_progress(self.current_view_id);
+                self.current_view_id = new_view;
+                ChainedView
             },
             (Prepare, Prepared) => PreCommit,
             (PreCommit, PreCommitted) => Commit,
             (Commit, Committed) => Decide,
-            (Decide, Decided) => NextView,
+            (Decide, Decided) => {
+                self.pacemaker.record_progress(self.current_view_id);
+                NextView
+            },
             (Synchronizing, BaseLayerCheckpointNotFound | BaseLayerAssetRegistrationNotFound) => {
                 info!(target: LOG_TARGET, "No initial checkpoint.");
                 NextView
@@ -424,6 +873,8 @@ impl<TSpecification: ServiceSpecification<Addr = PublicKey>> ConsensusWorker<TSp
 
 #[cfg(test)]
 mod test {
+    use std::time::Duration;
+
     use tari_crypto::ristretto::RistrettoPublicKey;
     use tari_shutdown::Shutdown;
     use tokio::task::JoinHandle;
@@ -468,7 +919,7 @@ mod test {
         let payload_processor = mock_payload_processor();
         let asset_definition = AssetDefinition::default();
         let base_node_client = mock_base_node_client();
-        let timeout = Duration::from_secs(5);
+        let pacemaker = Pacemaker::new(Duration::from_secs(5), Duration::from_secs(60));
         let db_factory = MockDbFactory::default();
         let chain_storage_service = MockChainStorageService::default();
         let checkpoint_manager = mock_checkpoint_manager();
@@ -484,7 +935,7 @@ mod test {
             payload_processor,
             asset_definition,
             base_node_client,
-            timeout,
+            pacemaker,
             db_factory,
             chain_storage_service,
             checkpoint_manager,
@@ -577,11 +1028,13 @@ mod test {
 
     fn assert_state_change(events: &[ConsensusWorkerDomainEvent], states: Vec<ConsensusWorkerState>) {
         println!("{:?}", events);
-        let mapped_events = events.iter().map(|e| match e {
+        let mapped_events = events.iter().filter_map(|e| match e {
             ConsensusWorkerDomainEvent::StateChanged { from: _, to: new } => Some(new),
+            ConsensusWorkerDomainEvent::InvalidMessageReceived { .. } |
+            ConsensusWorkerDomainEvent::MisbehaviourDetected { .. } => None,
         });
         for (state, event) in states.iter().zip(mapped_events) {
-            assert_eq!(state, event.unwrap())
+            assert_eq!(state, event)
         }
     }
 }
@@ -21,9 +21,14 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 mod consensus_worker;
+mod pacemaker;
 pub mod states;
 
 pub use consensus_worker::ConsensusWorker;
+pub use pacemaker::Pacemaker;
+
+#[cfg(test)]
+pub mod simulation;
 
 mod state_sync;
 pub use state_sync::StateSyncError;
@@ -0,0 +1,217 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use tari_common_types::types::Signature;
+
+use crate::{
+    models::{Committee, HotStuffMessageType, QuorumCertificate, TreeNodeHash, ViewId},
+    services::infrastructure_services::NodeAddressable,
+};
+
+/// Keeps the committee's views in step. The view timeout doubles for every consecutive view that fails (up to a
+/// maximum) and resets once a view makes progress. Timeout votes from the committee are collected into timeout
+/// certificates, which allow replicas that have fallen behind to jump to the highest certified view.
+#[derive(Debug)]
+pub struct Pacemaker<TAddr> {
+    base_timeout: Duration,
+    max_timeout: Duration,
+    consecutive_timeouts: u32,
+    timeout_votes: BTreeMap<ViewId, HashMap<TAddr, Signature>>,
+    highest_timeout_certificate: Option<QuorumCertificate>,
+}
+
+impl<TAddr: NodeAddressable> Pacemaker<TAddr> {
+    pub fn new(base_timeout: Duration, max_timeout: Duration) -> Self {
+        Self {
+            base_timeout,
+            max_timeout: max_timeout.max(base_timeout),
+            consecutive_timeouts: 0,
+            timeout_votes: BTreeMap::new(),
+            highest_timeout_certificate: None,
+        }
+    }
+
+    /// The time to wait in each phase of the current view
+    pub fn view_timeout(&self) -> Duration {
+        let factor = 1u32.checked_shl(self.consecutive_timeouts).unwrap_or(u32::MAX);
+        self.base_timeout
+            .checked_mul(factor)
+            .map_or(self.max_timeout, |timeout| timeout.min(self.max_timeout))
+    }
+
+    pub fn consecutive_timeouts(&self) -> u32 {
+        self.consecutive_timeouts
+    }
+
+    pub fn record_timeout(&mut self) {
+        self.consecutive_timeouts = self.consecutive_timeouts.saturating_add(1);
+    }
+
+    /// Resets the view timeout once a view has been decided, and discards timeout votes for views before `view_id`
+    pub fn record_progress(&mut self, view_id: ViewId) {
+        self.consecutive_timeouts = 0;
+        self.timeout_votes = self.timeout_votes.split_off(&view_id);
+    }
+
+    pub fn highest_timeout_certificate(&self) -> Option<&QuorumCertificate> {
+        self.highest_timeout_certificate.as_ref()
+    }
+
+    /// The highest view that a timeout certificate has been formed or received for
+    pub fn highest_certified_view(&self) -> Option<ViewId> {
+        self.highest_timeout_certificate.as_ref().map(|tc| tc.view_number())
+    }
+
+    /// Records a timeout certificate received from another member. The certificate must already be verified.
+    /// Returns true if it is higher than any certificate seen so far.
+    pub fn update_highest_timeout_certificate(&mut self, timeout_certificate: QuorumCertificate) -> bool {
+        if timeout_certificate.message_type() != HotStuffMessageType::Timeout {
+            return false;
+        }
+        if self
+            .highest_certified_view()
+            .map_or(false, |view| view >= timeout_certificate.view_number())
+        {
+            return false;
+        }
+        let view_id = timeout_certificate.view_number();
+        self.timeout_votes = self.timeout_votes.split_off(&view_id.next());
+        self.highest_timeout_certificate = Some(timeout_certificate);
+        true
+    }
+
+    /// Adds a verified timeout vote from a committee member. Once a quorum of the committee has timed out in a view,
+    /// the timeout certificate for that view is returned.
+    pub fn add_timeout_vote(
+        &mut self,
+        committee: &Committee<TAddr>,
+        sender: TAddr,
+        view_id: ViewId,
+        signature: Signature,
+    ) -> Option<QuorumCertificate> {
+        if self.highest_certified_view().map_or(false, |view| view >= view_id) {
+            return None;
+        }
+        let votes = self.timeout_votes.entry(view_id).or_insert_with(HashMap::new);
+        votes.insert(sender, signature);
+        if votes.len() < committee.consensus_threshold() {
+            return None;
+        }
+
+        let mut timeout_certificate = QuorumCertificate::new(
+            HotStuffMessageType::Timeout,
+            view_id,
+            TreeNodeHash::zero(),
+            Default::default(),
+        );
+        for (signer, signature) in votes.iter() {
+            if let Some(index) = committee.index_of(signer) {
+                timeout_certificate.add_signature(index, signature.clone());
+            }
+        }
+        self.update_highest_timeout_certificate(timeout_certificate.clone());
+        Some(timeout_certificate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+    use tari_common_types::types::{FixedHash, PrivateKey, PublicKey};
+    use tari_crypto::keys::{PublicKey as PublicKeyT, SecretKey};
+
+    use super::*;
+    use crate::models::{HotStuffMessage, TariDanPayload, ValidatorSignature};
+
+    #[test]
+    fn it_doubles_the_view_timeout_up_to_the_maximum() {
+        let mut pacemaker = Pacemaker::<String>::new(Duration::from_secs(5), Duration::from_secs(30));
+        assert_eq!(pacemaker.view_timeout(), Duration::from_secs(5));
+        pacemaker.record_timeout();
+        assert_eq!(pacemaker.view_timeout(), Duration::from_secs(10));
+        pacemaker.record_timeout();
+        assert_eq!(pacemaker.view_timeout(), Duration::from_secs(20));
+        pacemaker.record_timeout();
+        assert_eq!(pacemaker.view_timeout(), Duration::from_secs(30));
+        for _ in 0..100 {
+            pacemaker.record_timeout();
+        }
+        assert_eq!(pacemaker.view_timeout(), Duration::from_secs(30));
+
+        pacemaker.record_progress(ViewId(1));
+        assert_eq!(pacemaker.consecutive_timeouts(), 0);
+        assert_eq!(pacemaker.view_timeout(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn it_forms_a_timeout_certificate_from_a_quorum_of_votes() {
+        let contract_id = FixedHash::default();
+        let secret_keys = (0..4).map(|_| PrivateKey::random(&mut OsRng)).collect::<Vec<_>>();
+        let committee = Committee::new(secret_keys.iter().map(PublicKey::from_secret_key).collect());
+        let mut pacemaker = Pacemaker::new(Duration::from_secs(5), Duration::from_secs(30));
+
+        let view_id = ViewId(3);
+        let mut timeout_certificate = None;
+        for secret_key in &secret_keys[..3] {
+            assert!(timeout_certificate.is_none());
+            let message = HotStuffMessage::<TariDanPayload>::timeout(view_id, None, contract_id);
+            let vote = ValidatorSignature::sign(secret_key, &message.create_signature_challenge()).unwrap();
+            timeout_certificate =
+                pacemaker.add_timeout_vote(&committee, vote.signer().clone(), view_id, vote.signature().clone());
+        }
+
+        let timeout_certificate = timeout_certificate.unwrap();
+        assert_eq!(timeout_certificate.message_type(), HotStuffMessageType::Timeout);
+        assert_eq!(timeout_certificate.view_number(), view_id);
+        timeout_certificate.verify(&contract_id, &committee).unwrap();
+        assert_eq!(pacemaker.highest_certified_view(), Some(view_id));
+
+        // Late votes for a certified view are ignored
+        let message = HotStuffMessage::<TariDanPayload>::timeout(view_id, None, contract_id);
+        let vote = ValidatorSignature::sign(&secret_keys[3], &message.create_signature_challenge()).unwrap();
+        assert!(pacemaker
+            .add_timeout_vote(&committee, vote.signer().clone(), view_id, vote.signature().clone())
+            .is_none());
+    }
+
+    #[test]
+    fn it_keeps_the_highest_timeout_certificate() {
+        let mut pacemaker = Pacemaker::<String>::new(Duration::from_secs(5), Duration::from_secs(30));
+        let certificate = |view| {
+            QuorumCertificate::new(
+                HotStuffMessageType::Timeout,
+                ViewId(view),
+                TreeNodeHash::zero(),
+                Default::default(),
+            )
+        };
+        assert!(pacemaker.update_highest_timeout_certificate(certificate(4)));
+        assert!(!pacemaker.update_highest_timeout_certificate(certificate(2)));
+        assert!(pacemaker.update_highest_timeout_certificate(certificate(7)));
+        assert_eq!(pacemaker.highest_certified_view(), Some(ViewId(7)));
+    }
+}
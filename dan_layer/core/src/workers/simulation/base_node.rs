@@ -0,0 +1,90 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tari_common_types::types::{FixedHash, PublicKey};
+use tari_core::{chain_storage::UtxoMinedInfo, transactions::transaction_components::OutputType};
+use tokio::time::Instant;
+
+use crate::{
+    digital_assets_error::DigitalAssetError,
+    models::{BaseLayerMetadata, BaseLayerOutput},
+    services::BaseNodeClient,
+};
+
+/// A base layer that mines a block every `block_time` from the start of the simulation and has no contract outputs.
+/// Committee changes are scheduled with the committee managers of the simulated nodes instead.
+#[derive(Debug, Clone)]
+pub struct SimulatedBaseNodeClient {
+    started_at: Instant,
+    block_time: Duration,
+}
+
+impl SimulatedBaseNodeClient {
+    pub fn new(started_at: Instant, block_time: Duration) -> Self {
+        Self { started_at, block_time }
+    }
+}
+
+#[async_trait]
+impl BaseNodeClient for SimulatedBaseNodeClient {
+    async fn get_tip_info(&mut self) -> Result<BaseLayerMetadata, DigitalAssetError> {
+        Ok(BaseLayerMetadata {
+            height_of_longest_chain: self.started_at.elapsed().as_secs() / self.block_time.as_secs().max(1),
+            tip_hash: FixedHash::zero(),
+        })
+    }
+
+    async fn get_current_contract_outputs(
+        &mut self,
+        _height: u64,
+        _contract_id: FixedHash,
+        _output_type: OutputType,
+    ) -> Result<Vec<UtxoMinedInfo>, DigitalAssetError> {
+        Ok(vec![])
+    }
+
+    async fn get_constitutions(
+        &mut self,
+        _start_block_hash: Option<FixedHash>,
+        _dan_node_public_key: &PublicKey,
+    ) -> Result<Vec<UtxoMinedInfo>, DigitalAssetError> {
+        Ok(vec![])
+    }
+
+    async fn check_if_in_committee(
+        &mut self,
+        _asset_public_key: PublicKey,
+        _dan_node_public_key: PublicKey,
+    ) -> Result<(bool, u64), DigitalAssetError> {
+        unimplemented!()
+    }
+
+    async fn get_asset_registration(
+        &mut self,
+        _asset_public_key: PublicKey,
+    ) -> Result<Option<BaseLayerOutput>, DigitalAssetError> {
+        Ok(None)
+    }
+}
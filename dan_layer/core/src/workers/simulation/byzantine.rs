@@ -0,0 +1,79 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::models::{HotStuffMessage, HotStuffMessageType, HotStuffTreeNode, Payload};
+
+/// How a faulty simulated node deviates from the protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByzantineBehaviour {
+    /// Proposes a different node to every second committee member when it is the leader
+    Equivocate,
+    /// Never sends proposals or quorum certificates when it is the leader, but still votes as a replica
+    SilentLeader,
+}
+
+impl ByzantineBehaviour {
+    /// Returns true if the node does not send the message at all
+    pub fn withholds<TPayload: Payload>(self, message: &HotStuffMessage<TPayload>) -> bool {
+        self == ByzantineBehaviour::SilentLeader && is_leader_message(message)
+    }
+
+    /// Returns the message that the node sends to the committee member at `recipient_index` instead of `message`
+    pub fn message_for<TPayload: Payload>(
+        self,
+        message: HotStuffMessage<TPayload>,
+        recipient_index: usize,
+    ) -> HotStuffMessage<TPayload> {
+        if self != ByzantineBehaviour::Equivocate || recipient_index % 2 == 0 {
+            return message;
+        }
+        match message.node() {
+            Some(node) => {
+                // Only the height differs, so that replicas accept either node as extending the justified node
+                let conflicting_node = HotStuffTreeNode::new(
+                    *node.parent(),
+                    node.payload().clone(),
+                    *node.execution_result(),
+                    node.height().wrapping_add(1),
+                );
+                HotStuffMessage::new(
+                    message.view_number(),
+                    message.message_type(),
+                    message.justify().cloned(),
+                    Some(conflicting_node),
+                    None,
+                    None,
+                    None,
+                    *message.contract_id(),
+                )
+            },
+            None => message,
+        }
+    }
+}
+
+/// Returns true if the message is a proposal or a quorum certificate, which only the leader of a view sends
+fn is_leader_message<TPayload: Payload>(message: &HotStuffMessage<TPayload>) -> bool {
+    use HotStuffMessageType::{Commit, Decide, Generic, PreCommit, Prepare};
+    matches!(message.message_type(), Prepare | PreCommit | Commit | Decide | Generic) &&
+        (message.node().is_some() || message.justify().is_some())
+}
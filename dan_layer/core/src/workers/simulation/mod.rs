@@ -0,0 +1,522 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Runs a committee of consensus workers in-process over a simulated network. Node keys and the network schedule are
+//! derived from a seed, and time is virtual when run on a paused tokio runtime, so a simulation with the same seed and
+//! config always plays out the same way.
+
+mod base_node;
+mod byzantine;
+mod network;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+pub use base_node::SimulatedBaseNodeClient;
+pub use byzantine::ByzantineBehaviour;
+pub use network::{
+    NetworkConfig,
+    NetworkStats,
+    Partition,
+    SimulatedInboundConnectionService,
+    SimulatedNetwork,
+    SimulatedOutboundService,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_core::transactions::transaction_components::{CheckpointChallenge, SignerSignature};
+use tari_crypto::keys::{PublicKey as PublicKeyT, SecretKey};
+use tari_dan_engine::state::mocks::state_db::MockStateDbBackupAdapter;
+use tari_shutdown::Shutdown;
+use tokio::time::{sleep, Instant};
+
+use crate::{
+    digital_assets_error::DigitalAssetError,
+    models::{
+        domain_events::ConsensusWorkerDomainEvent,
+        AssetDefinition,
+        Committee,
+        CommitteeChange,
+        TariDanPayload,
+        ValidatorSignature,
+        ViewId,
+    },
+    services::{
+        mocks::{
+            mock_checkpoint_manager,
+            mock_events_publisher,
+            mock_payload_processor,
+            mock_static_payload_provider,
+            MockAssetProcessor,
+            MockChainStorageService,
+            MockEventsPublisher,
+            MockMempoolService,
+            MockPayloadProcessor,
+            MockStaticPayloadProvider,
+            MockValidatorNodeClientFactory,
+            MockWalletClient,
+        },
+        ConcreteAcceptanceManager,
+        ConcreteAssetProxy,
+        ConcreteCheckpointManager,
+        ConcreteCommitteeManager,
+        ServiceSpecification,
+        SigningService,
+    },
+    storage::{
+        chain::DbNode,
+        mocks::{chain_db::MockChainDbBackupAdapter, global_db::MockGlobalDbBackupAdapter, MockDbFactory},
+        DbFactory,
+    },
+    workers::{ConsensusWorker, Pacemaker},
+};
+
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// Seeds the node keys and the network schedule
+    pub seed: u64,
+    pub num_nodes: usize,
+    pub network: NetworkConfig,
+    /// The misbehaving nodes, by committee index
+    pub byzantine_nodes: HashMap<usize, ByzantineBehaviour>,
+    pub asset_definition: AssetDefinition,
+    /// How long the committee runs for, in virtual time when the runtime is paused
+    pub duration: Duration,
+    /// The time between base layer blocks
+    pub base_layer_block_time: Duration,
+    pub committee_change: Option<CommitteeChangeConfig>,
+}
+
+/// Hands the contract over to the nodes at `members`, by committee index, once the base layer reaches
+/// `activation_height`. The incoming committee is drawn from the initial committee, because nodes outside of the
+/// committee would need a constitution on the base layer to join.
+#[derive(Debug, Clone)]
+pub struct CommitteeChangeConfig {
+    pub activation_height: u64,
+    pub members: Vec<usize>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            num_nodes: 4,
+            network: NetworkConfig::default(),
+            byzantine_nodes: HashMap::new(),
+            asset_definition: AssetDefinition::default(),
+            duration: Duration::from_secs(10 * 60),
+            base_layer_block_time: Duration::from_secs(2 * 60),
+            committee_change: None,
+        }
+    }
+}
+
+pub struct Simulation {
+    config: SimulationConfig,
+    secret_keys: Vec<PrivateKey>,
+}
+
+impl Simulation {
+    pub fn new(config: SimulationConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let secret_keys = (0..config.num_nodes).map(|_| PrivateKey::random(&mut rng)).collect();
+        Self { config, secret_keys }
+    }
+
+    pub fn committee(&self) -> Vec<PublicKey> {
+        self.secret_keys.iter().map(PublicKey::from_secret_key).collect()
+    }
+
+    /// Runs every node from an empty chain for the configured duration and returns what each of them committed
+    pub async fn run(self) -> Result<SimulationResult, DigitalAssetError> {
+        let members = self.committee();
+        let contract_id = self.config.asset_definition.contract_id;
+        let network = SimulatedNetwork::new(
+            members.clone(),
+            contract_id,
+            self.config.network.clone(),
+            self.config.seed,
+        );
+        let network_task = tokio::spawn({
+            let network = network.clone();
+            async move { network.run().await }
+        });
+
+        let started_at = Instant::now();
+        let committee_change = self.config.committee_change.as_ref().map(|change| CommitteeChange {
+            activation_height: change.activation_height,
+            members: change.members.iter().map(|index| members[*index].clone()).collect(),
+        });
+
+        let mut shutdown = Shutdown::new();
+        let mut nodes = Vec::with_capacity(members.len());
+        let mut tasks = Vec::with_capacity(members.len());
+        for (index, secret_key) in self.secret_keys.into_iter().enumerate() {
+            let address = members[index].clone();
+            let behaviour = self.config.byzantine_nodes.get(&index).copied();
+            let db_factory = MockDbFactory::default();
+            db_factory.get_or_create_state_db(&contract_id)?;
+            let inbound = network
+                .inbound(&address)
+                .ok_or_else(|| DigitalAssetError::FatalError(format!("No inbound for {}", address)))?;
+            let mut committee_manager = ConcreteCommitteeManager::new(Committee::new(members.clone()));
+            if let Some(change) = committee_change.clone() {
+                committee_manager = committee_manager.with_pending_change(change);
+            }
+            let mut worker = ConsensusWorker::<SimulationServiceSpecification>::new(
+                inbound,
+                network.outbound(behaviour),
+                committee_manager,
+                address.clone(),
+                mock_static_payload_provider(),
+                mock_events_publisher(),
+                SimulatedSigningService::new(secret_key),
+                mock_payload_processor(),
+                self.config.asset_definition.clone(),
+                SimulatedBaseNodeClient::new(started_at, self.config.base_layer_block_time),
+                Pacemaker::new(
+                    Duration::from_secs(self.config.asset_definition.phase_timeout),
+                    Duration::from_secs(self.config.asset_definition.max_phase_timeout),
+                ),
+                db_factory.clone(),
+                MockChainStorageService::default(),
+                mock_checkpoint_manager(),
+                MockValidatorNodeClientFactory::default(),
+            );
+            worker.start_at_next_view();
+            let shutdown_signal = shutdown.to_signal();
+            tasks.push(tokio::spawn(async move {
+                worker
+                    .run(shutdown_signal, None, Arc::new(AtomicBool::new(false)))
+                    .await
+            }));
+            nodes.push((address, behaviour, db_factory));
+        }
+
+        let healed_after = self
+            .config
+            .network
+            .partitions
+            .iter()
+            .map(|partition| partition.until)
+            .max()
+            .unwrap_or_default()
+            .min(self.config.duration);
+        sleep(healed_after).await;
+        let mut committed_when_healed = Vec::with_capacity(nodes.len());
+        for (_, _, db_factory) in &nodes {
+            committed_when_healed.push(db_factory.get_committed_nodes(&contract_id)?.len());
+        }
+        sleep(self.config.duration - healed_after).await;
+
+        shutdown.trigger();
+        network_task.abort();
+        let mut outcomes = Vec::with_capacity(nodes.len());
+        for (((address, behaviour, db_factory), task), committed_when_healed) in
+            nodes.into_iter().zip(tasks).zip(committed_when_healed)
+        {
+            task.abort();
+            let error = match task.await {
+                Ok(Err(err)) => Some(err.to_string()),
+                _ => None,
+            };
+            let handover_view = match db_factory.get_chain_db(&contract_id)? {
+                Some(chain_db) => chain_db.get_last_handover()?.map(|(_, view)| view),
+                None => None,
+            };
+            outcomes.push(NodeOutcome {
+                address,
+                behaviour,
+                committed: db_factory.get_committed_nodes(&contract_id)?,
+                handover_view,
+                committed_when_healed,
+                error,
+            });
+        }
+
+        Ok(SimulationResult {
+            nodes: outcomes,
+            network_stats: network.stats(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct NodeOutcome {
+    pub address: PublicKey,
+    pub behaviour: Option<ByzantineBehaviour>,
+    /// The committed nodes, ordered by height
+    pub committed: Vec<DbNode>,
+    /// The number of nodes that had been committed when the last partition healed
+    pub committed_when_healed: usize,
+    /// The last view of the outgoing committee, if this node saw the contract handed over
+    pub handover_view: Option<ViewId>,
+    /// The error that stopped the node's worker, if it stopped
+    pub error: Option<String>,
+}
+
+impl NodeOutcome {
+    pub fn is_honest(&self) -> bool {
+        self.behaviour.is_none()
+    }
+}
+
+#[derive(Debug)]
+pub struct SimulationResult {
+    pub nodes: Vec<NodeOutcome>,
+    pub network_stats: NetworkStats,
+}
+
+impl SimulationResult {
+    pub fn honest_nodes(&self) -> impl Iterator<Item = &NodeOutcome> + '_ {
+        self.nodes.iter().filter(|node| node.is_honest())
+    }
+
+    /// Panics if honest nodes committed conflicting nodes, i.e. different nodes at the same height
+    pub fn assert_safety(&self) {
+        let mut committed_by_height = BTreeMap::new();
+        for node in self.honest_nodes() {
+            for committed in &node.committed {
+                let (hash, committed_by) = committed_by_height
+                    .entry(committed.height)
+                    .or_insert((committed.hash, &node.address));
+                assert_eq!(
+                    *hash, committed.hash,
+                    "{} committed {} at height {}, but {} committed {}",
+                    committed_by, hash, committed.height, node.address, committed.hash
+                );
+            }
+        }
+    }
+
+    /// Panics unless every honest node committed at least `min_commits` nodes after the last partition healed
+    pub fn assert_liveness(&self, min_commits: usize) {
+        for node in self.honest_nodes() {
+            let commits = node.committed.len() - node.committed_when_healed;
+            assert!(
+                commits >= min_commits,
+                "{} committed {} nodes after the network healed, expected at least {} (error: {:?})",
+                node.address,
+                commits,
+                min_commits,
+                node.error
+            );
+        }
+    }
+}
+
+/// Signs votes with the simulated node's own key, so that they verify against its committee address
+pub struct SimulatedSigningService {
+    secret_key: PrivateKey,
+}
+
+impl SimulatedSigningService {
+    pub fn new(secret_key: PrivateKey) -> Self {
+        Self { secret_key }
+    }
+}
+
+impl SigningService for SimulatedSigningService {
+    fn sign(&self, challenge: &[u8]) -> Result<ValidatorSignature, DigitalAssetError> {
+        Ok(ValidatorSignature::sign(&self.secret_key, challenge)?)
+    }
+
+    fn sign_checkpoint(&self, challenge: &CheckpointChallenge) -> Result<SignerSignature, DigitalAssetError> {
+        Ok(SignerSignature::sign(&self.secret_key, challenge))
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct SimulationServiceSpecification;
+
+impl ServiceSpecification for SimulationServiceSpecification {
+    type AcceptanceManager = ConcreteAcceptanceManager<Self::WalletClient, Self::BaseNodeClient>;
+    type Addr = PublicKey;
+    type AssetProcessor = MockAssetProcessor;
+    type AssetProxy = ConcreteAssetProxy<Self>;
+    type BaseNodeClient = SimulatedBaseNodeClient;
+    type ChainDbBackendAdapter = MockChainDbBackupAdapter;
+    type ChainStorageService = MockChainStorageService;
+    type CheckpointManager = ConcreteCheckpointManager<Self::WalletClient>;
+    type CommitteeManager = ConcreteCommitteeManager;
+    type DbFactory = MockDbFactory;
+    type EventsPublisher = MockEventsPublisher<ConsensusWorkerDomainEvent>;
+    type GlobalDbAdapter = MockGlobalDbBackupAdapter;
+    type InboundConnectionService = SimulatedInboundConnectionService<Self::Addr, Self::Payload>;
+    type MempoolService = MockMempoolService;
+    type OutboundService = SimulatedOutboundService<Self::Addr, Self::Payload>;
+    type Payload = TariDanPayload;
+    type PayloadProcessor = MockPayloadProcessor;
+    type PayloadProvider = MockStaticPayloadProvider<Self::Payload>;
+    type SigningService = SimulatedSigningService;
+    type StateDbBackendAdapter = MockStateDbBackupAdapter;
+    type ValidatorNodeClientFactory = MockValidatorNodeClientFactory;
+    type WalletClient = MockWalletClient;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConsensusMode, TreeNodeHash};
+
+    async fn run(config: SimulationConfig) -> SimulationResult {
+        Simulation::new(config).run().await.unwrap()
+    }
+
+    fn committed_hashes(result: &SimulationResult) -> Vec<Vec<TreeNodeHash>> {
+        result
+            .nodes
+            .iter()
+            .map(|node| node.committed.iter().map(|n| n.hash).collect())
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_commits_with_an_honest_committee() {
+        let result = run(SimulationConfig::default()).await;
+        result.assert_safety();
+        result.assert_liveness(3);
+        assert_eq!(result.network_stats.dropped, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_commits_in_chained_mode() {
+        let mut config = SimulationConfig::default();
+        config.asset_definition.consensus_mode = ConsensusMode::Chained;
+        let result = run(config).await;
+        result.assert_safety();
+        result.assert_liveness(3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_hands_the_contract_over_to_the_incoming_committee() {
+        let config = SimulationConfig {
+            num_nodes: 5,
+            // Replicas finish each view at the same time, so that they reach the end of the epoch together
+            network: NetworkConfig {
+                min_latency: Duration::from_millis(50),
+                max_latency: Duration::from_millis(50),
+                ..Default::default()
+            },
+            base_layer_block_time: Duration::from_secs(30),
+            committee_change: Some(CommitteeChangeConfig {
+                activation_height: 10,
+                members: vec![0, 1, 2, 3],
+            }),
+            duration: Duration::from_secs(20 * 60),
+            ..Default::default()
+        };
+        let result = run(config).await;
+        result.assert_safety();
+
+        let handover_view = result
+            .nodes
+            .iter()
+            .find_map(|node| node.handover_view)
+            .expect("the contract was not handed over");
+        for node in &result.nodes {
+            assert!(node.handover_view.map_or(true, |view| view == handover_view));
+        }
+        let committed_after_handover = |node: &NodeOutcome| {
+            node.committed
+                .iter()
+                .filter(|n| u64::from(n.height) > handover_view.as_u64())
+                .count()
+        };
+        // The outgoing member stops at the final block
+        assert_eq!(committed_after_handover(&result.nodes[4]), 0);
+        // A leader that moved on to the next view just before the epoch ended misses the handover, but the rest of
+        // the incoming committee carries on from the final block
+        let carried_on = result.nodes[..4]
+            .iter()
+            .filter(|node| committed_after_handover(node) >= 3)
+            .count();
+        assert!(
+            carried_on >= 3,
+            "Only {} members of the incoming committee committed after the handover",
+            carried_on
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_replays_the_same_run_for_a_seed() {
+        let config = SimulationConfig {
+            seed: 7,
+            network: NetworkConfig {
+                drop_rate: 0.05,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let first = run(config.clone()).await;
+        let second = run(config).await;
+        assert_eq!(first.network_stats, second.network_stats);
+        assert_eq!(committed_hashes(&first), committed_hashes(&second));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_stays_safe_with_an_equivocating_leader() {
+        let config = SimulationConfig {
+            byzantine_nodes: vec![(0, ByzantineBehaviour::Equivocate)].into_iter().collect(),
+            duration: Duration::from_secs(30 * 60),
+            ..Default::default()
+        };
+        let result = run(config).await;
+        result.assert_safety();
+        result.assert_liveness(1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_makes_progress_past_a_silent_leader() {
+        let config = SimulationConfig {
+            byzantine_nodes: vec![(1, ByzantineBehaviour::SilentLeader)].into_iter().collect(),
+            duration: Duration::from_secs(30 * 60),
+            ..Default::default()
+        };
+        let result = run(config).await;
+        result.assert_safety();
+        result.assert_liveness(1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_recovers_after_a_partition_heals() {
+        let config = SimulationConfig {
+            network: NetworkConfig {
+                partitions: vec![Partition {
+                    nodes: vec![0, 1],
+                    from: Duration::from_secs(60),
+                    until: Duration::from_secs(5 * 60),
+                }],
+                ..Default::default()
+            },
+            duration: Duration::from_secs(30 * 60),
+            ..Default::default()
+        };
+        let result = run(config).await;
+        assert!(result.network_stats.dropped > 0);
+        result.assert_safety();
+        result.assert_liveness(1);
+    }
+}
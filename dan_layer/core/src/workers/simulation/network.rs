@@ -0,0 +1,478 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use log::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tari_common_types::types::FixedHash;
+use tokio::{
+    sync::Notify,
+    time::{sleep_until, Instant},
+};
+
+use crate::{
+    digital_assets_error::DigitalAssetError,
+    models::{HotStuffMessage, HotStuffMessageType, MisbehaviourEvidence, Payload, ViewId},
+    services::infrastructure_services::{
+        EquivocationDetector,
+        InboundConnectionService,
+        NodeAddressable,
+        OutboundService,
+    },
+    workers::simulation::ByzantineBehaviour,
+};
+
+const LOG_TARGET: &str = "tari::dan::workers::simulation::network";
+
+/// The conditions of the links between simulated nodes
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    /// Every message is delivered after a latency chosen uniformly between `min_latency` and `max_latency`
+    pub min_latency: Duration,
+    pub max_latency: Duration,
+    /// The probability that a message between two different nodes is lost
+    pub drop_rate: f64,
+    pub partitions: Vec<Partition>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            min_latency: Duration::from_millis(10),
+            max_latency: Duration::from_millis(100),
+            drop_rate: 0.0,
+            partitions: vec![],
+        }
+    }
+}
+
+/// Cuts the committee members at `nodes` off from the rest of the committee from `from` until `until`, measured from
+/// the start of the simulation
+#[derive(Debug, Clone)]
+pub struct Partition {
+    pub nodes: Vec<usize>,
+    pub from: Duration,
+    pub until: Duration,
+}
+
+impl Partition {
+    fn separates(&self, a: usize, b: usize, elapsed: Duration) -> bool {
+        (self.from..self.until).contains(&elapsed) && self.nodes.contains(&a) != self.nodes.contains(&b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    pub sent: u64,
+    pub dropped: u64,
+    pub delivered: u64,
+}
+
+/// An in-process network between committee members. Messages are queued with a latency and drop decision taken
+/// from a seeded RNG, and delivered in order of delivery time by [SimulatedNetwork::run], so the same seed always
+/// produces the same message schedule.
+pub struct SimulatedNetwork<TAddr: NodeAddressable, TPayload: Payload> {
+    state: Arc<Mutex<NetworkState<TAddr, TPayload>>>,
+    scheduled: Arc<Notify>,
+}
+
+struct NetworkState<TAddr: NodeAddressable, TPayload: Payload> {
+    config: NetworkConfig,
+    rng: StdRng,
+    started_at: Instant,
+    members: Vec<TAddr>,
+    inbounds: HashMap<TAddr, SimulatedInboundConnectionService<TAddr, TPayload>>,
+    queue: BinaryHeap<ScheduledMessage<TAddr, TPayload>>,
+    next_sequence: u64,
+    stats: NetworkStats,
+}
+
+impl<TAddr: NodeAddressable, TPayload: Payload> NetworkState<TAddr, TPayload> {
+    fn is_partitioned(&self, from: &TAddr, to: &TAddr) -> bool {
+        let elapsed = self.started_at.elapsed();
+        match (self.index_of(from), self.index_of(to)) {
+            (Some(a), Some(b)) => self.config.partitions.iter().any(|p| p.separates(a, b, elapsed)),
+            // Nodes outside of the committee are unreachable
+            _ => true,
+        }
+    }
+
+    fn index_of(&self, member: &TAddr) -> Option<usize> {
+        self.members.iter().position(|m| m == member)
+    }
+
+    fn sample_latency(&mut self) -> Duration {
+        if self.config.max_latency <= self.config.min_latency {
+            return self.config.min_latency;
+        }
+        let min = self.config.min_latency.as_micros() as u64;
+        let max = self.config.max_latency.as_micros() as u64;
+        Duration::from_micros(self.rng.gen_range(min..=max))
+    }
+}
+
+impl<TAddr: NodeAddressable, TPayload: Payload> SimulatedNetwork<TAddr, TPayload> {
+    pub fn new(members: Vec<TAddr>, contract_id: FixedHash, config: NetworkConfig, seed: u64) -> Self {
+        let inbounds = members
+            .iter()
+            .map(|member| {
+                (
+                    member.clone(),
+                    SimulatedInboundConnectionService::new(contract_id, &members),
+                )
+            })
+            .collect();
+        Self {
+            state: Arc::new(Mutex::new(NetworkState {
+                config,
+                rng: StdRng::seed_from_u64(seed),
+                started_at: Instant::now(),
+                members,
+                inbounds,
+                queue: BinaryHeap::new(),
+                next_sequence: 0,
+                stats: NetworkStats::default(),
+            })),
+            scheduled: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn inbound(&self, member: &TAddr) -> Option<SimulatedInboundConnectionService<TAddr, TPayload>> {
+        self.state.lock().unwrap().inbounds.get(member).cloned()
+    }
+
+    /// Returns an outbound service that sends through this network, misbehaving as `behaviour` if given
+    pub fn outbound(&self, behaviour: Option<ByzantineBehaviour>) -> SimulatedOutboundService<TAddr, TPayload> {
+        SimulatedOutboundService {
+            network: self.clone(),
+            behaviour,
+        }
+    }
+
+    pub fn stats(&self) -> NetworkStats {
+        self.state.lock().unwrap().stats
+    }
+
+    /// Queues the message for delivery, unless it is dropped or the nodes are partitioned. Messages that a node sends
+    /// to itself are always delivered.
+    fn schedule(&self, from: TAddr, to: TAddr, message: HotStuffMessage<TPayload>) {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        state.stats.sent += 1;
+        let latency = if from == to {
+            Duration::ZERO
+        } else {
+            if state.is_partitioned(&from, &to) || state.rng.gen_bool(state.config.drop_rate) {
+                debug!(
+                    target: LOG_TARGET,
+                    "Dropping {:?} message for view {} from {} to {}",
+                    message.message_type(),
+                    message.view_number(),
+                    from,
+                    to
+                );
+                state.stats.dropped += 1;
+                return;
+            }
+            state.sample_latency()
+        };
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        state.queue.push(ScheduledMessage {
+            deliver_at: Instant::now() + latency,
+            sequence,
+            from,
+            to,
+            message,
+        });
+        drop(guard);
+        self.scheduled.notify_one();
+    }
+
+    /// Delivers queued messages to the inbound services of their recipients at their delivery times. Messages with the
+    /// same delivery time are delivered in the order that they were sent.
+    pub async fn run(&self) {
+        loop {
+            let next_delivery = self.state.lock().unwrap().queue.peek().map(|m| m.deliver_at);
+            match next_delivery {
+                Some(deliver_at) if deliver_at <= Instant::now() => self.deliver_next(),
+                Some(deliver_at) => {
+                    tokio::select! {
+                        _ = sleep_until(deliver_at) => {},
+                        _ = self.scheduled.notified() => {},
+                    }
+                },
+                None => self.scheduled.notified().await,
+            }
+        }
+    }
+
+    fn deliver_next(&self) {
+        let mut state = self.state.lock().unwrap();
+        let scheduled = match state.queue.pop() {
+            Some(scheduled) => scheduled,
+            None => return,
+        };
+        let inbound = state.inbounds.get(&scheduled.to).cloned();
+        if inbound.is_some() {
+            state.stats.delivered += 1;
+        }
+        drop(state);
+        if let Some(inbound) = inbound {
+            inbound.deliver(scheduled.from, scheduled.message);
+        }
+    }
+}
+
+impl<TAddr: NodeAddressable, TPayload: Payload> Clone for SimulatedNetwork<TAddr, TPayload> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            scheduled: self.scheduled.clone(),
+        }
+    }
+}
+
+struct ScheduledMessage<TAddr: NodeAddressable, TPayload: Payload> {
+    deliver_at: Instant,
+    sequence: u64,
+    from: TAddr,
+    to: TAddr,
+    message: HotStuffMessage<TPayload>,
+}
+
+impl<TAddr: NodeAddressable, TPayload: Payload> PartialEq for ScheduledMessage<TAddr, TPayload> {
+    fn eq(&self, other: &Self) -> bool {
+        self.deliver_at == other.deliver_at && self.sequence == other.sequence
+    }
+}
+
+impl<TAddr: NodeAddressable, TPayload: Payload> Eq for ScheduledMessage<TAddr, TPayload> {}
+
+impl<TAddr: NodeAddressable, TPayload: Payload> PartialOrd for ScheduledMessage<TAddr, TPayload> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Reversed, so that the max-heap pops the earliest message first
+impl<TAddr: NodeAddressable, TPayload: Payload> Ord for ScheduledMessage<TAddr, TPayload> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .deliver_at
+            .cmp(&self.deliver_at)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// Buffers the messages delivered to a node until a consensus state waits for them
+pub struct SimulatedInboundConnectionService<TAddr: NodeAddressable, TPayload: Payload> {
+    inbox: Arc<Inbox<TAddr, TPayload>>,
+}
+
+struct Inbox<TAddr: NodeAddressable, TPayload: Payload> {
+    messages: Mutex<VecDeque<(TAddr, HotStuffMessage<TPayload>)>>,
+    arrived: Notify,
+    detector: Mutex<EquivocationDetector>,
+}
+
+impl<TAddr: NodeAddressable, TPayload: Payload> SimulatedInboundConnectionService<TAddr, TPayload> {
+    fn new(contract_id: FixedHash, committee: &[TAddr]) -> Self {
+        Self {
+            inbox: Arc::new(Inbox {
+                messages: Mutex::new(VecDeque::new()),
+                arrived: Notify::new(),
+                detector: Mutex::new(EquivocationDetector::new(contract_id, committee)),
+            }),
+        }
+    }
+
+    fn deliver(&self, from: TAddr, message: HotStuffMessage<TPayload>) {
+        self.inbox.detector.lock().unwrap().observe(&from, &message);
+        self.inbox.messages.lock().unwrap().push_back((from, message));
+        self.inbox.arrived.notify_waiters();
+    }
+
+    /// Waits for the first message that matches the predicate and removes it from the inbox. Consensus states wait
+    /// for the messages of the node's current view, so `view_id` moves the view window of the equivocation detector.
+    async fn wait_for<F>(&self, view_id: ViewId, predicate: F) -> (TAddr, HotStuffMessage<TPayload>)
+    where F: Fn(&HotStuffMessage<TPayload>) -> bool + Send + Sync {
+        self.inbox.detector.lock().unwrap().set_current_view(view_id);
+        loop {
+            // Registered before checking the inbox so that a message delivered in between is not missed
+            let arrived = self.inbox.arrived.notified();
+            if let Some(message) = self.take_first(&predicate) {
+                return message;
+            }
+            arrived.await;
+        }
+    }
+
+    fn take_first<F>(&self, predicate: &F) -> Option<(TAddr, HotStuffMessage<TPayload>)>
+    where F: Fn(&HotStuffMessage<TPayload>) -> bool {
+        let mut messages = self.inbox.messages.lock().unwrap();
+        let index = messages.iter().position(|(_, message)| predicate(message))?;
+        messages.remove(index)
+    }
+}
+
+impl<TAddr: NodeAddressable, TPayload: Payload> Clone for SimulatedInboundConnectionService<TAddr, TPayload> {
+    fn clone(&self) -> Self {
+        Self {
+            inbox: self.inbox.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl<TAddr: NodeAddressable, TPayload: Payload> InboundConnectionService
+    for SimulatedInboundConnectionService<TAddr, TPayload>
+{
+    type Addr = TAddr;
+    type Payload = TPayload;
+
+    async fn wait_for_message(
+        &self,
+        message_type: HotStuffMessageType,
+        for_view: ViewId,
+    ) -> Result<(TAddr, HotStuffMessage<TPayload>), DigitalAssetError> {
+        Ok(self
+            .wait_for(for_view, |message| {
+                message.message_type() == message_type && message.view_number() == for_view
+            })
+            .await)
+    }
+
+    async fn wait_for_qc(
+        &self,
+        message_type: HotStuffMessageType,
+        for_view: ViewId,
+    ) -> Result<(TAddr, HotStuffMessage<TPayload>), DigitalAssetError> {
+        Ok(self
+            .wait_for(for_view, |message| {
+                message
+                    .justify()
+                    .map(|qc| qc.message_type() == message_type && qc.view_number() == for_view)
+                    .unwrap_or(false)
+            })
+            .await)
+    }
+
+    async fn wait_for_timeout(
+        &self,
+        min_view: ViewId,
+    ) -> Result<(TAddr, HotStuffMessage<TPayload>), DigitalAssetError> {
+        Ok(self
+            .wait_for(min_view, |message| {
+                message.message_type() == HotStuffMessageType::Timeout && message.view_number() >= min_view
+            })
+            .await)
+    }
+
+    async fn wait_for_epoch_handover(
+        &self,
+        min_view: ViewId,
+    ) -> Result<(TAddr, HotStuffMessage<TPayload>), DigitalAssetError> {
+        Ok(self
+            .wait_for(min_view, |message| {
+                message.message_type() == HotStuffMessageType::EpochHandover && message.view_number() >= min_view
+            })
+            .await)
+    }
+
+    async fn wait_for_epoch_handover_request(&self) -> Result<(TAddr, HotStuffMessage<TPayload>), DigitalAssetError> {
+        Ok(self
+            .wait_for(ViewId(0), |message| {
+                message.message_type() == HotStuffMessageType::EpochHandoverRequest
+            })
+            .await)
+    }
+
+    async fn take_misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>, DigitalAssetError> {
+        Ok(self.inbox.detector.lock().unwrap().take_evidence())
+    }
+}
+
+/// Sends messages through a [SimulatedNetwork], applying the node's Byzantine behaviour if it has one
+pub struct SimulatedOutboundService<TAddr: NodeAddressable, TPayload: Payload> {
+    network: SimulatedNetwork<TAddr, TPayload>,
+    behaviour: Option<ByzantineBehaviour>,
+}
+
+impl<TAddr: NodeAddressable, TPayload: Payload> SimulatedOutboundService<TAddr, TPayload> {
+    fn withholds(&self, message: &HotStuffMessage<TPayload>) -> bool {
+        self.behaviour.map_or(false, |behaviour| behaviour.withholds(message))
+    }
+}
+
+impl<TAddr: NodeAddressable, TPayload: Payload> Clone for SimulatedOutboundService<TAddr, TPayload> {
+    fn clone(&self) -> Self {
+        Self {
+            network: self.network.clone(),
+            behaviour: self.behaviour,
+        }
+    }
+}
+
+#[async_trait]
+impl<TAddr: NodeAddressable, TPayload: Payload> OutboundService for SimulatedOutboundService<TAddr, TPayload> {
+    type Addr = TAddr;
+    type Payload = TPayload;
+
+    async fn send(
+        &mut self,
+        from: TAddr,
+        to: TAddr,
+        message: HotStuffMessage<TPayload>,
+    ) -> Result<(), DigitalAssetError> {
+        if !self.withholds(&message) {
+            self.network.schedule(from, to, message);
+        }
+        Ok(())
+    }
+
+    async fn broadcast(
+        &mut self,
+        from: TAddr,
+        committee: &[TAddr],
+        message: HotStuffMessage<TPayload>,
+    ) -> Result<(), DigitalAssetError> {
+        if self.withholds(&message) {
+            return Ok(());
+        }
+        for (index, member) in committee.iter().enumerate() {
+            let message = match self.behaviour {
+                Some(behaviour) => behaviour.message_for(message.clone(), index),
+                None => message.clone(),
+            };
+            self.network.schedule(from.clone(), member.clone(), message);
+        }
+        Ok(())
+    }
+}
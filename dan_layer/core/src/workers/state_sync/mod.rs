@@ -24,7 +24,7 @@ mod error;
 pub use error::StateSyncError;
 use log::*;
 use rand::{rngs::OsRng, seq::SliceRandom};
-use tari_common_types::types::PublicKey;
+use tari_common_types::types::{FixedHash, PublicKey};
 use tari_dan_engine::state::{
     error::StateStorageError,
     StateDb,
@@ -34,15 +34,14 @@ use tari_dan_engine::state::{
 };
 use tari_utilities::hex::Hex;
 
-use crate::{
-    models::CheckpointOutput,
-    services::{ValidatorNodeClientFactory, ValidatorNodeRpcClient},
-};
+use crate::services::{ValidatorNodeClientFactory, ValidatorNodeRpcClient};
 
 const LOG_TARGET: &str = "tari::dan::workers::state_sync";
 
+/// Downloads the state of a contract from a committee member, checking it against the expected merkle root
 pub struct StateSynchronizer<'a, TStateDbBackendAdapter, TValidatorNodeClientFactory: ValidatorNodeClientFactory> {
-    last_checkpoint: &'a CheckpointOutput,
+    contract_id: FixedHash,
+    merkle_root: FixedHash,
     state_db: &'a mut StateDb<TStateDbBackendAdapter>,
     validator_node_client_factory: &'a TValidatorNodeClientFactory,
     our_address: &'a TValidatorNodeClientFactory::Addr,
@@ -56,14 +55,16 @@ where
     TValidatorNodeClientFactory: ValidatorNodeClientFactory<Addr = PublicKey>,
 {
     pub fn new(
-        last_checkpoint: &'a CheckpointOutput,
+        contract_id: FixedHash,
+        merkle_root: FixedHash,
         state_db: &'a mut StateDb<TStateDbBackendAdapter>,
         validator_node_client_factory: &'a TValidatorNodeClientFactory,
         our_address: &'a TValidatorNodeClientFactory::Addr,
         committee: &'a [TValidatorNodeClientFactory::Addr],
     ) -> Self {
         Self {
-            last_checkpoint,
+            contract_id,
+            merkle_root,
             state_db,
             validator_node_client_factory,
             our_address,
@@ -103,16 +104,16 @@ where
     async fn try_sync_from(&self, member: &TValidatorNodeClientFactory::Addr) -> Result<(), StateSyncError> {
         info!(
             target: LOG_TARGET,
-            "Attempting to sync asset '{}' from peer '{}'", self.last_checkpoint.contract_id, member
+            "Attempting to sync asset '{}' from peer '{}'", self.contract_id, member
         );
         let mut client = self.validator_node_client_factory.create_client(member);
         let tip_node = client
-            .get_tip_node(&self.last_checkpoint.contract_id)
+            .get_tip_node(&self.contract_id)
             .await?
             .ok_or(StateSyncError::RemotePeerDoesNotHaveTipNode)?;
 
         // TODO: should rather download the op logs for a checkpoint and reply over initial/current state
-        let state_schemas = client.get_sidechain_state(&self.last_checkpoint.contract_id).await?;
+        let state_schemas = client.get_sidechain_state(&self.contract_id).await?;
 
         let mut uow = self.state_db.new_unit_of_work(u64::from(tip_node.height()));
 
@@ -135,7 +136,7 @@ where
         uow.commit().map_err(StateStorageError::from)?;
 
         let merkle_root = uow.calculate_root()?;
-        if self.last_checkpoint.merkle_root.as_slice() != merkle_root.as_bytes() {
+        if self.merkle_root.as_slice() != merkle_root.as_bytes() {
             return Err(StateSyncError::InvalidStateMerkleRoot);
         }
 
@@ -0,0 +1,796 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::collections::{HashMap, HashSet};
+
+use log::*;
+use tari_common_types::types::{FixedHash, Signature};
+use tari_dan_engine::state::StateDbUnitOfWork;
+use tari_utilities::hex::Hex;
+use tokio::time::{sleep, Duration};
+
+use crate::{
+    digital_assets_error::DigitalAssetError,
+    models::{
+        AssetDefinition,
+        Committee,
+        ExecutionResult,
+        HotStuffMessage,
+        HotStuffMessageType,
+        HotStuffTreeNode,
+        Payload,
+        QuorumCertificate,
+        TreeNodeHash,
+        View,
+        ViewId,
+    },
+    services::{
+        infrastructure_services::{InboundConnectionService, OutboundService},
+        PayloadProcessor,
+        PayloadProvider,
+        ServiceSpecification,
+        SigningService,
+    },
+    storage::{
+        chain::{ChainDb, ChainDbUnitOfWork},
+        ChainStorageService,
+        DbFactory,
+    },
+    workers::states::{ConsensusWorkerStateEvent, InvalidMessage, InvalidMessageReason, InvalidMessages},
+};
+
+const LOG_TARGET: &str = "tari::dan::workers::states::chained_view";
+
+/// Nodes that have been voted for in chained mode but have not been committed yet, keyed by their hash
+pub type UncommittedNodes<TPayload> = HashMap<TreeNodeHash, HotStuffTreeNode<TPayload>>;
+
+/// A single view of chained HotStuff. The leader proposes a node extending the highest QC, which it forms from the
+/// votes for the previous view's proposal (or from NEW-VIEW messages after a view change). Replicas vote for the
+/// proposal by sending a vote to the leader of the next view, and commit a node once a three-chain of QCs has formed
+/// on top of it.
+///
+/// The state changes of uncommitted nodes are not persisted. Instead, each proposal is executed on top of its
+/// uncommitted ancestors, and a node's payload is only written to the state database once the node is committed.
+pub struct ChainedViewState<TSpecification: ServiceSpecification> {
+    node_id: TSpecification::Addr,
+    contract_id: FixedHash,
+    committee: Committee<TSpecification::Addr>,
+    received_new_view_messages: HashMap<TSpecification::Addr, HotStuffMessage<TSpecification::Payload>>,
+    received_votes: HashMap<TreeNodeHash, HashMap<TSpecification::Addr, Signature>>,
+    has_proposed: bool,
+    invalid_messages: InvalidMessages<TSpecification::Addr>,
+}
+
+impl<TSpecification: ServiceSpecification> ChainedViewState<TSpecification> {
+    pub fn new(
+        node_id: TSpecification::Addr,
+        contract_id: FixedHash,
+        committee: Committee<TSpecification::Addr>,
+    ) -> Self {
+        Self {
+            node_id,
+            contract_id,
+            committee,
+            received_new_view_messages: HashMap::new(),
+            received_votes: HashMap::new(),
+            has_proposed: false,
+            invalid_messages: InvalidMessages::new(),
+        }
+    }
+
+    /// Takes the messages that were dropped because they failed validation
+    pub fn take_invalid_messages(&mut self) -> Vec<InvalidMessage<TSpecification::Addr>> {
+        self.invalid_messages.take()
+    }
+
+    pub async fn next_event<TUnitOfWork: ChainDbUnitOfWork>(
+        &mut self,
+        timeout: Duration,
+        current_view: &View,
+        asset_definition: &AssetDefinition,
+        inbound_services: &TSpecification::InboundConnectionService,
+        outbound_service: &mut TSpecification::OutboundService,
+        payload_provider: &mut TSpecification::PayloadProvider,
+        signing_service: &TSpecification::SigningService,
+        payload_processor: &mut TSpecification::PayloadProcessor,
+        chain_storage_service: &TSpecification::ChainStorageService,
+        mut chain_tx: TUnitOfWork,
+        db_factory: &TSpecification::DbFactory,
+        uncommitted_nodes: &mut UncommittedNodes<TSpecification::Payload>,
+    ) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
+        let view_id = current_view.view_id();
+        let previous_view = ViewId(view_id.as_u64().saturating_sub(1));
+        let is_leader = current_view.is_leader();
+        let timeout = sleep(timeout);
+        futures::pin_mut!(timeout);
+        loop {
+            tokio::select! {
+                r = inbound_services.wait_for_message(HotStuffMessageType::NewView, view_id), if is_leader && !self.has_proposed => {
+                    let (from, message) = r?;
+                    if !self.invalid_messages.check(&self.contract_id, &self.committee, &from, &message) {
+                        continue;
+                    }
+                    if let Some(high_qc) = self.process_new_view_message(&from, message) {
+                        self.propose(high_qc, current_view, asset_definition, outbound_service, signing_service, payload_provider, payload_processor, db_factory, uncommitted_nodes).await?;
+                    }
+                },
+                r = inbound_services.wait_for_message(HotStuffMessageType::GenericVote, previous_view), if is_leader && !self.has_proposed && !view_id.is_genesis() => {
+                    let (from, message) = r?;
+                    if !self.invalid_messages.check(&self.contract_id, &self.committee, &from, &message) {
+                        continue;
+                    }
+                    if let Some(high_qc) = self.process_vote(&from, &message, previous_view) {
+                        self.propose(high_qc, current_view, asset_definition, outbound_service, signing_service, payload_provider, payload_processor, db_factory, uncommitted_nodes).await?;
+                    }
+                },
+                r = inbound_services.wait_for_message(HotStuffMessageType::Generic, view_id) => {
+                    let (from, message) = r?;
+                    if !self.invalid_messages.check(&self.contract_id, &self.committee, &from, &message) {
+                        continue;
+                    }
+                    if let Some(event) = self.process_proposal(
+                        &message,
+                        &from,
+                        current_view,
+                        outbound_service,
+                        payload_provider,
+                        signing_service,
+                        payload_processor,
+                        chain_storage_service,
+                        &mut chain_tx,
+                        db_factory,
+                        uncommitted_nodes,
+                    ).await? {
+                        break Ok(event);
+                    }
+                },
+                _ = &mut timeout => {
+                    break Ok(ConsensusWorkerStateEvent::TimedOut);
+                }
+            }
+        }
+    }
+
+    fn process_new_view_message(
+        &mut self,
+        sender: &TSpecification::Addr,
+        message: HotStuffMessage<TSpecification::Payload>,
+    ) -> Option<QuorumCertificate> {
+        if self.received_new_view_messages.contains_key(sender) {
+            return None;
+        }
+        self.received_new_view_messages.insert(sender.clone(), message);
+        if self.received_new_view_messages.len() < self.committee.consensus_threshold() {
+            return None;
+        }
+        self.received_new_view_messages
+            .values()
+            .filter_map(|message| message.justify())
+            .max_by_key(|qc| qc.view_number())
+            .cloned()
+    }
+
+    /// Collects a vote for the previous view's proposal, returning the QC for it once a quorum has voted
+    fn process_vote(
+        &mut self,
+        sender: &TSpecification::Addr,
+        message: &HotStuffMessage<TSpecification::Payload>,
+        previous_view: ViewId,
+    ) -> Option<QuorumCertificate> {
+        if !message.is_signed_by(&self.contract_id, sender) {
+            warn!(
+                target: LOG_TARGET,
+                "Ignoring vote with an invalid signature from {}", sender
+            );
+            return None;
+        }
+        let node_hash = *message.node_hash()?;
+        let signature = message.partial_sig()?.signature().clone();
+        let votes = self.received_votes.entry(node_hash).or_insert_with(HashMap::new);
+        votes.insert(sender.clone(), signature);
+        if votes.len() < self.committee.consensus_threshold() {
+            debug!(
+                target: LOG_TARGET,
+                "Consensus has NOT YET been reached with {} out of {} votes",
+                votes.len(),
+                self.committee.len()
+            );
+            return None;
+        }
+
+        let mut qc = QuorumCertificate::new(
+            HotStuffMessageType::Generic,
+            previous_view,
+            node_hash,
+            Default::default(),
+        );
+        for (signer, signature) in votes.iter() {
+            if let Some(index) = self.committee.index_of(signer) {
+                qc.add_signature(index, signature.clone());
+            }
+        }
+        Some(qc)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    async fn propose(
+        &mut self,
+        high_qc: QuorumCertificate,
+        current_view: &View,
+        asset_definition: &AssetDefinition,
+        outbound: &mut TSpecification::OutboundService,
+        signing_service: &TSpecification::SigningService,
+        payload_provider: &TSpecification::PayloadProvider,
+        payload_processor: &TSpecification::PayloadProcessor,
+        db_factory: &TSpecification::DbFactory,
+        uncommitted_nodes: &UncommittedNodes<TSpecification::Payload>,
+    ) -> Result<(), DigitalAssetError> {
+        let view_id = current_view.view_id();
+        debug!(target: LOG_TARGET, "Creating new proposal for {}", view_id);
+        let parent = *high_qc.node_hash();
+        let node = if view_id.is_genesis() {
+            let payload = payload_provider.create_genesis_payload(asset_definition);
+            let execution_result = Self::execute(
+                &self.contract_id,
+                &parent,
+                &payload,
+                view_id,
+                payload_processor,
+                db_factory,
+                uncommitted_nodes,
+            )
+            .await?;
+            HotStuffTreeNode::genesis(payload, execution_result)
+        } else {
+            let payload = payload_provider.create_payload().await?;
+            let execution_result = Self::execute(
+                &self.contract_id,
+                &parent,
+                &payload,
+                view_id,
+                payload_processor,
+                db_factory,
+                uncommitted_nodes,
+            )
+            .await?;
+            HotStuffTreeNode::from_parent(parent, payload, execution_result, view_id.as_u64() as u32)
+        };
+        let mut message = HotStuffMessage::generic(node, high_qc, view_id, self.contract_id);
+        message.add_partial_sig(signing_service.sign(&message.create_signature_challenge())?);
+        outbound
+            .broadcast(self.node_id.clone(), self.committee.members.as_slice(), message)
+            .await?;
+        self.has_proposed = true;
+        Ok(())
+    }
+
+    async fn process_proposal<TUnitOfWork: ChainDbUnitOfWork>(
+        &mut self,
+        message: &HotStuffMessage<TSpecification::Payload>,
+        from: &TSpecification::Addr,
+        current_view: &View,
+        outbound: &mut TSpecification::OutboundService,
+        payload_provider: &mut TSpecification::PayloadProvider,
+        signing_service: &TSpecification::SigningService,
+        payload_processor: &TSpecification::PayloadProcessor,
+        chain_storage_service: &TSpecification::ChainStorageService,
+        chain_tx: &mut TUnitOfWork,
+        db_factory: &TSpecification::DbFactory,
+        uncommitted_nodes: &mut UncommittedNodes<TSpecification::Payload>,
+    ) -> Result<Option<ConsensusWorkerStateEvent>, DigitalAssetError> {
+        let view_id = current_view.view_id();
+        if from != self.committee.leader_for_view(view_id) {
+            warn!(target: LOG_TARGET, "Proposal not from leader");
+            return Ok(None);
+        }
+        let (node, justify) = match (message.node(), message.justify()) {
+            (Some(node), Some(justify)) => (node, justify),
+            _ => {
+                warn!(target: LOG_TARGET, "Proposal from {} is missing the node or QC", from);
+                return Ok(None);
+            },
+        };
+
+        // The genesis does not extend any node
+        if !view_id.is_genesis() {
+            if node.parent() != justify.node_hash() {
+                warn!(
+                    target: LOG_TARGET,
+                    "Proposal for {} does not extend the node certified by its QC", view_id
+                );
+                return Ok(None);
+            }
+            if !self.is_safe_node(node, justify, chain_tx, uncommitted_nodes)? {
+                warn!(target: LOG_TARGET, "Proposal for {} is not safe", view_id);
+                return Ok(None);
+            }
+        }
+
+        let execution_result = Self::execute(
+            &self.contract_id,
+            node.parent(),
+            node.payload(),
+            view_id,
+            payload_processor,
+            db_factory,
+            uncommitted_nodes,
+        )
+        .await?;
+        if execution_result != *node.execution_result() {
+            warn!(
+                target: LOG_TARGET,
+                "Calculated execution result did not match the result provided by the leader: Expected: {:?} Leader \
+                 provided:{:?}",
+                execution_result,
+                node.execution_result()
+            );
+            self.invalid_messages
+                .record(from, message, InvalidMessageReason::ExecutionMismatch {
+                    node_hash: *node.hash(),
+                });
+            return Ok(None);
+        }
+
+        chain_storage_service
+            .add_node::<TUnitOfWork>(node, chain_tx.clone())
+            .await?;
+        payload_provider.reserve_payload(node.payload(), node.hash()).await?;
+        uncommitted_nodes.insert(*node.hash(), node.clone());
+
+        self.update_chain(
+            justify,
+            chain_tx,
+            payload_provider,
+            payload_processor,
+            db_factory,
+            uncommitted_nodes,
+        )
+        .await?;
+
+        // A node that has already voted in this view (before a restart) moves on without voting again
+        if chain_tx.record_vote(view_id, HotStuffMessageType::GenericVote, *node.hash())? {
+            let mut vote = HotStuffMessage::vote_generic(*node.hash(), view_id, self.contract_id);
+            vote.add_partial_sig(signing_service.sign(&vote.create_signature_challenge())?);
+            let next_leader = self.committee.leader_for_view(view_id.next()).clone();
+            outbound.send(self.node_id.clone(), next_leader, vote).await?;
+        } else {
+            warn!(target: LOG_TARGET, "Already voted in {}, not voting again", view_id);
+        }
+
+        Ok(Some(ConsensusWorkerStateEvent::NewView {
+            new_view: view_id.next(),
+        }))
+    }
+
+    /// Applies the chained HotStuff rules for the QC carried by a proposal: the QC becomes the highest prepared QC, the
+    /// QC of its parent (a two-chain) becomes the locked QC, and the grandparent is committed once the certified node,
+    /// its parent and its grandparent form a three-chain of consecutive heights.
+    async fn update_chain<TUnitOfWork: ChainDbUnitOfWork>(
+        &self,
+        justify: &QuorumCertificate,
+        chain_tx: &mut TUnitOfWork,
+        payload_provider: &mut TSpecification::PayloadProvider,
+        payload_processor: &TSpecification::PayloadProcessor,
+        db_factory: &TSpecification::DbFactory,
+        uncommitted_nodes: &mut UncommittedNodes<TSpecification::Payload>,
+    ) -> Result<(), DigitalAssetError> {
+        let prepare_qc = chain_tx.get_prepare_qc()?;
+        let certified_parent = uncommitted_nodes.get(justify.node_hash()).map(|node| *node.parent());
+        if let (Some(certified_parent), Some(parent_qc)) = (certified_parent, prepare_qc.as_ref()) {
+            // The previous prepare QC certifies the parent of the newly certified node, forming a two-chain
+            if *parent_qc.node_hash() == certified_parent &&
+                parent_qc.view_number() > chain_tx.get_locked_qc()?.view_number()
+            {
+                chain_tx.set_locked_qc(parent_qc)?;
+            }
+        }
+        if let Some(node_hash) = find_three_chain_commit(justify, uncommitted_nodes) {
+            self.commit_nodes(
+                node_hash,
+                chain_tx,
+                payload_provider,
+                payload_processor,
+                db_factory,
+                uncommitted_nodes,
+            )
+            .await?;
+        }
+        if prepare_qc.map_or(true, |qc| justify.view_number() > qc.view_number()) {
+            chain_tx.set_prepare_qc(justify)?;
+        }
+        Ok(())
+    }
+
+    /// Commits the node and any of its uncommitted ancestors, oldest first
+    async fn commit_nodes<TUnitOfWork: ChainDbUnitOfWork>(
+        &self,
+        node_hash: TreeNodeHash,
+        chain_tx: &mut TUnitOfWork,
+        payload_provider: &mut TSpecification::PayloadProvider,
+        payload_processor: &TSpecification::PayloadProcessor,
+        db_factory: &TSpecification::DbFactory,
+        uncommitted_nodes: &mut UncommittedNodes<TSpecification::Payload>,
+    ) -> Result<(), DigitalAssetError> {
+        let mut to_commit = Vec::new();
+        let mut hash = node_hash;
+        while let Some(node) = uncommitted_nodes.remove(&hash) {
+            hash = *node.parent();
+            to_commit.push(node);
+        }
+
+        let state_db = db_factory.get_or_create_state_db(&self.contract_id)?;
+        for node in to_commit.iter().rev() {
+            let mut state_tx = state_db.new_unit_of_work(u64::from(node.height()));
+            payload_processor
+                .process_payload(node.payload(), state_tx.clone())
+                .await?;
+            state_tx.commit()?;
+            chain_tx.commit_node(node.hash())?;
+            payload_provider.remove_payload(node.hash()).await?;
+            info!(target: LOG_TARGET, "Committed node: {}", node.hash().to_hex());
+        }
+
+        // Nodes on other branches at or below the committed height can no longer be committed
+        if let Some(committed_height) = to_commit.first().map(|node| node.height()) {
+            uncommitted_nodes.retain(|_, node| node.height() > committed_height);
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the uncommitted nodes from the chain database after a restart. Only the instructions of a payload are
+    /// stored, so each node is rebuilt by re-executing its payload on top of its rebuilt ancestors, and a node is only
+    /// recovered if this reproduces its hash and its parent was recovered or committed.
+    pub async fn recover_uncommitted_nodes(
+        contract_id: &FixedHash,
+        chain_db: &ChainDb<TSpecification::ChainDbBackendAdapter>,
+        chain_storage_service: &TSpecification::ChainStorageService,
+        payload_processor: &TSpecification::PayloadProcessor,
+        db_factory: &TSpecification::DbFactory,
+    ) -> Result<UncommittedNodes<TSpecification::Payload>, DigitalAssetError> {
+        let mut uncommitted_nodes = UncommittedNodes::new();
+        let mut unrecovered = HashSet::new();
+        for block in chain_db.get_uncommitted_sidechain_blocks()? {
+            let hash = *block.node().hash();
+            let parent = *block.node().parent();
+            let height = block.node().height();
+            if unrecovered.contains(&parent) {
+                unrecovered.insert(hash);
+                continue;
+            }
+            let payload = chain_storage_service.payload_from_block(block);
+            let execution_result = Self::execute(
+                contract_id,
+                &parent,
+                &payload,
+                ViewId(u64::from(height)),
+                payload_processor,
+                db_factory,
+                &uncommitted_nodes,
+            )
+            .await?;
+            let node = HotStuffTreeNode::new(parent, payload, execution_result, height);
+            if *node.hash() != hash {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not rebuild uncommitted node '{}' at height {}", hash, height
+                );
+                unrecovered.insert(hash);
+                continue;
+            }
+            uncommitted_nodes.insert(hash, node);
+        }
+        info!(
+            target: LOG_TARGET,
+            "Recovered {} uncommitted node(s) from the chain database",
+            uncommitted_nodes.len()
+        );
+        Ok(uncommitted_nodes)
+    }
+
+    /// Executes the payload on top of the uncommitted ancestors of `parent`, returning the result of the payload
+    async fn execute(
+        contract_id: &FixedHash,
+        parent: &TreeNodeHash,
+        payload: &TSpecification::Payload,
+        view_id: ViewId,
+        payload_processor: &TSpecification::PayloadProcessor,
+        db_factory: &TSpecification::DbFactory,
+        uncommitted_nodes: &UncommittedNodes<TSpecification::Payload>,
+    ) -> Result<ExecutionResult, DigitalAssetError> {
+        let mut ancestors = Vec::new();
+        let mut hash = parent;
+        while let Some(node) = uncommitted_nodes.get(hash) {
+            ancestors.push(node);
+            hash = node.parent();
+        }
+
+        let state_tx = db_factory
+            .get_or_create_state_db(contract_id)?
+            .new_unit_of_work(view_id.as_u64());
+        for node in ancestors.iter().rev() {
+            payload_processor
+                .process_payload(node.payload(), state_tx.clone())
+                .await?;
+        }
+        payload_processor.process_payload(payload, state_tx).await
+    }
+
+    fn is_safe_node<TUnitOfWork: ChainDbUnitOfWork>(
+        &self,
+        node: &HotStuffTreeNode<TSpecification::Payload>,
+        justify: &QuorumCertificate,
+        chain_tx: &mut TUnitOfWork,
+        uncommitted_nodes: &UncommittedNodes<TSpecification::Payload>,
+    ) -> Result<bool, DigitalAssetError> {
+        let locked_qc = chain_tx.get_locked_qc()?;
+        if justify.view_number() > locked_qc.view_number() {
+            return Ok(true);
+        }
+        // Otherwise the node must extend the locked node
+        let mut hash = node.parent();
+        loop {
+            if hash == locked_qc.node_hash() {
+                return Ok(true);
+            }
+            match uncommitted_nodes.get(hash) {
+                Some(ancestor) => hash = ancestor.parent(),
+                None => return Ok(false),
+            }
+        }
+    }
+}
+
+/// Returns the node committed by a QC: the grandparent of the certified node, if the certified node, its parent and its
+/// grandparent are uncommitted and were proposed at consecutive heights. A node's height is the view it was proposed
+/// in.
+fn find_three_chain_commit<TPayload: Payload>(
+    justify: &QuorumCertificate,
+    uncommitted_nodes: &UncommittedNodes<TPayload>,
+) -> Option<TreeNodeHash> {
+    let certified = uncommitted_nodes.get(justify.node_hash())?;
+    let parent = uncommitted_nodes.get(certified.parent())?;
+    let grandparent = uncommitted_nodes.get(parent.parent())?;
+    let is_consecutive = |child: &HotStuffTreeNode<TPayload>, ancestor: &HotStuffTreeNode<TPayload>| {
+        ancestor.height().checked_add(1) == Some(child.height())
+    };
+    if u64::from(certified.height()) == justify.view_number().as_u64() &&
+        is_consecutive(certified, parent) &&
+        is_consecutive(parent, grandparent)
+    {
+        Some(*grandparent.hash())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tari_common_types::types::PublicKey;
+
+    use super::*;
+    use crate::{
+        models::TariDanPayload,
+        services::{
+            infrastructure_services::mocks::mock_outbound,
+            mocks::{
+                mock_payload_processor,
+                mock_signing_service,
+                mock_static_payload_provider,
+                MockChainStorageService,
+                MockServiceSpecification,
+            },
+        },
+        storage::mocks::MockDbFactory,
+    };
+
+    type TestState = ChainedViewState<MockServiceSpecification>;
+
+    fn new_state() -> TestState {
+        ChainedViewState::new(
+            PublicKey::default(),
+            FixedHash::zero(),
+            Committee::new(vec![PublicKey::default()]),
+        )
+    }
+
+    /// Creates a node extending `parent` at `height` and stores it, as is done for a valid proposal
+    async fn add_node<TUnitOfWork: ChainDbUnitOfWork>(
+        parent: TreeNodeHash,
+        height: u32,
+        db_factory: &MockDbFactory,
+        chain_tx: &TUnitOfWork,
+        uncommitted_nodes: &mut UncommittedNodes<TariDanPayload>,
+    ) -> TreeNodeHash {
+        let payload = TariDanPayload::default();
+        let execution_result = TestState::execute(
+            &FixedHash::zero(),
+            &parent,
+            &payload,
+            ViewId(u64::from(height)),
+            &mock_payload_processor(),
+            db_factory,
+            uncommitted_nodes,
+        )
+        .await
+        .unwrap();
+        let node = HotStuffTreeNode::new(parent, payload, execution_result, height);
+        MockChainStorageService.add_node(&node, chain_tx.clone()).await.unwrap();
+        uncommitted_nodes.insert(*node.hash(), node.clone());
+        *node.hash()
+    }
+
+    async fn update_chain<TUnitOfWork: ChainDbUnitOfWork>(
+        certified: TreeNodeHash,
+        view: u64,
+        db_factory: &MockDbFactory,
+        chain_tx: &mut TUnitOfWork,
+        uncommitted_nodes: &mut UncommittedNodes<TariDanPayload>,
+    ) {
+        let justify = QuorumCertificate::new(
+            HotStuffMessageType::Generic,
+            ViewId(view),
+            certified,
+            Default::default(),
+        );
+        new_state()
+            .update_chain(
+                &justify,
+                chain_tx,
+                &mut mock_static_payload_provider(),
+                &mock_payload_processor(),
+                db_factory,
+                uncommitted_nodes,
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_commits_a_three_chain_at_consecutive_heights() {
+        let db_factory = MockDbFactory::default();
+        let chain_db = db_factory.get_or_create_chain_db(&FixedHash::zero()).unwrap();
+        let mut chain_tx = chain_db.new_unit_of_work();
+        let mut uncommitted_nodes = UncommittedNodes::new();
+        let b1 = add_node(TreeNodeHash::zero(), 1, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+        let b2 = add_node(b1, 2, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+        let b3 = add_node(b2, 3, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+
+        update_chain(b3, 3, &db_factory, &mut chain_tx, &mut uncommitted_nodes).await;
+        chain_tx.commit().unwrap();
+
+        assert_eq!(chain_db.get_committed_node_heights(0, 10).unwrap(), vec![1]);
+        assert!(!uncommitted_nodes.contains_key(&b1));
+        assert!(uncommitted_nodes.contains_key(&b2));
+        assert!(uncommitted_nodes.contains_key(&b3));
+    }
+
+    #[tokio::test]
+    async fn it_does_not_commit_a_three_chain_with_a_gap_in_its_heights() {
+        let db_factory = MockDbFactory::default();
+        let chain_db = db_factory.get_or_create_chain_db(&FixedHash::zero()).unwrap();
+        let mut chain_tx = chain_db.new_unit_of_work();
+        let mut uncommitted_nodes = UncommittedNodes::new();
+        let b1 = add_node(TreeNodeHash::zero(), 1, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+        let b2 = add_node(b1, 2, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+        // View 3 timed out, so the proposal of view 4 extends the node of view 2
+        let b4 = add_node(b2, 4, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+
+        update_chain(b4, 4, &db_factory, &mut chain_tx, &mut uncommitted_nodes).await;
+        chain_tx.commit().unwrap();
+        assert!(chain_db.get_committed_node_heights(0, 10).unwrap().is_empty());
+        assert_eq!(uncommitted_nodes.len(), 3);
+
+        // A three-chain at consecutive heights on top of it commits it along with its ancestors
+        let b5 = add_node(b4, 5, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+        let b6 = add_node(b5, 6, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+        update_chain(b6, 6, &db_factory, &mut chain_tx, &mut uncommitted_nodes).await;
+        chain_tx.commit().unwrap();
+        assert_eq!(chain_db.get_committed_node_heights(0, 10).unwrap(), vec![1, 2, 4]);
+    }
+
+    #[tokio::test]
+    async fn it_recovers_the_uncommitted_nodes_after_a_restart() {
+        let db_factory = MockDbFactory::default();
+        let chain_db = db_factory.get_or_create_chain_db(&FixedHash::zero()).unwrap();
+        let mut chain_tx = chain_db.new_unit_of_work();
+        let mut uncommitted_nodes = UncommittedNodes::new();
+        let b1 = add_node(TreeNodeHash::zero(), 1, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+        let b2 = add_node(b1, 2, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+        let b3 = add_node(b2, 3, &db_factory, &chain_tx, &mut uncommitted_nodes).await;
+        chain_tx.commit().unwrap();
+
+        let mut uncommitted_nodes = TestState::recover_uncommitted_nodes(
+            &FixedHash::zero(),
+            &chain_db,
+            &MockChainStorageService,
+            &mock_payload_processor(),
+            &db_factory,
+        )
+        .await
+        .unwrap();
+        assert_eq!(uncommitted_nodes.len(), 3);
+        assert_eq!(uncommitted_nodes[&b3].parent(), &b2);
+
+        let mut chain_tx = chain_db.new_unit_of_work();
+        update_chain(b3, 3, &db_factory, &mut chain_tx, &mut uncommitted_nodes).await;
+        chain_tx.commit().unwrap();
+        assert_eq!(chain_db.get_committed_node_heights(0, 10).unwrap(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn it_refuses_a_proposal_with_a_different_execution_result() {
+        let db_factory = MockDbFactory::default();
+        let chain_db = db_factory.get_or_create_chain_db(&FixedHash::zero()).unwrap();
+        let mut chain_tx = chain_db.new_unit_of_work();
+        let mut uncommitted_nodes = UncommittedNodes::new();
+        let payload = TariDanPayload::default();
+        let execution_result = TestState::execute(
+            &FixedHash::zero(),
+            &TreeNodeHash::zero(),
+            &payload,
+            ViewId(0),
+            &mock_payload_processor(),
+            &db_factory,
+            &uncommitted_nodes,
+        )
+        .await
+        .unwrap();
+        // The leader claims a receipt that executing the payload does not give
+        let node = HotStuffTreeNode::genesis(payload, ExecutionResult::new(execution_result.state_root, &[vec![1]]));
+        let node_hash = *node.hash();
+        let message = HotStuffMessage::generic(node, QuorumCertificate::genesis(), ViewId(0), FixedHash::zero());
+
+        let leader = PublicKey::default();
+        let mut state = new_state();
+        let event = state
+            .process_proposal(
+                &message,
+                &leader,
+                &View {
+                    view_id: ViewId(0),
+                    is_leader: true,
+                },
+                &mut mock_outbound(vec![leader.clone()]),
+                &mut mock_static_payload_provider(),
+                &mock_signing_service(),
+                &mock_payload_processor(),
+                &MockChainStorageService,
+                &mut chain_tx,
+                &db_factory,
+                &mut uncommitted_nodes,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(event, None);
+        let invalid_messages = state.take_invalid_messages();
+        assert_eq!(invalid_messages.len(), 1);
+        assert!(matches!(
+            invalid_messages[0].reason,
+            InvalidMessageReason::ExecutionMismatch { node_hash: hash } if hash == node_hash
+        ));
+        assert!(uncommitted_nodes.is_empty());
+        chain_tx.commit().unwrap();
+        assert!(!chain_db.sidechain_block_exists(&node_hash).unwrap());
+        assert!(chain_db.get_last_vote().unwrap().is_none());
+    }
+}
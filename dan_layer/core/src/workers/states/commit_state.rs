@@ -37,7 +37,7 @@ use crate::{
         SigningService,
     },
     storage::chain::ChainDbUnitOfWork,
-    workers::states::ConsensusWorkerStateEvent,
+    workers::states::{ConsensusWorkerStateEvent, InvalidMessage, InvalidMessages},
 };
 
 const LOG_TARGET: &str = "tari::dan::workers::states::commit";
@@ -48,6 +48,7 @@ pub struct CommitState<TSpecification: ServiceSpecification> {
     contract_id: FixedHash,
     committee: Committee<TSpecification::Addr>,
     received_new_view_messages: HashMap<TSpecification::Addr, HotStuffMessage<TSpecification::Payload>>,
+    invalid_messages: InvalidMessages<TSpecification::Addr>,
 }
 
 impl<TSpecification: ServiceSpecification> CommitState<TSpecification> {
@@ -61,9 +62,15 @@ impl<TSpecification: ServiceSpecification> CommitState<TSpecification> {
             contract_id,
             committee,
             received_new_view_messages: HashMap::new(),
+            invalid_messages: InvalidMessages::new(),
         }
     }
 
+    /// Takes the messages that were dropped because they failed validation
+    pub fn take_invalid_messages(&mut self) -> Vec<InvalidMessage<TSpecification::Addr>> {
+        self.invalid_messages.take()
+    }
+
     pub async fn next_event<TUnitOfWork: ChainDbUnitOfWork>(
         &mut self,
         timeout: Duration,
@@ -82,6 +89,9 @@ impl<TSpecification: ServiceSpecification> CommitState<TSpecification> {
             tokio::select! {
                r  = inbound_services.wait_for_message(HotStuffMessageType::PreCommit, current_view.view_id()) => {
                    let (from, message) = r?;
+                   if !self.invalid_messages.check(&self.contract_id, &self.committee, &from, &message) {
+                       continue;
+                   }
                    if current_view.is_leader() {
                       if let Some(result) = self.process_leader_message(current_view, message.clone(), &from, outbound_service).await?{
                           break Ok(result);
@@ -90,6 +100,9 @@ impl<TSpecification: ServiceSpecification> CommitState<TSpecification> {
                },
                r =  inbound_services.wait_for_qc(HotStuffMessageType::PreCommit, current_view.view_id()) => {
                    let (from, message) = r?;
+                   if !self.invalid_messages.check(&self.contract_id, &self.committee, &from, &message) {
+                       continue;
+                   }
                    let leader = self.committee.leader_for_view(current_view.view_id).clone();
                    if let Some(result) = self.process_replica_message(
                         &message,
@@ -129,6 +142,14 @@ impl<TSpecification: ServiceSpecification> CommitState<TSpecification> {
             return Ok(None);
         }
 
+        if !message.is_signed_by(&self.contract_id, sender) {
+            warn!(
+                target: LOG_TARGET,
+                "Ignoring vote with an invalid signature from {}", sender
+            );
+            return Ok(None);
+        }
+
         self.received_new_view_messages.insert(sender.clone(), message);
 
         if self.received_new_view_messages.len() >= self.committee.consensus_threshold() {
@@ -168,29 +189,29 @@ impl<TSpecification: ServiceSpecification> CommitState<TSpecification> {
             .await
     }
 
+    /// Creates a QC for the node that a quorum of the received votes are for. Votes for any other node, e.g. after
+    /// an equivocating proposal, are not counted.
     fn create_qc(&self, current_view: &View) -> Option<QuorumCertificate> {
-        // TODO: This can be done in one loop instead of two
-        let mut node_hash = None;
-        for message in self.received_new_view_messages.values() {
-            node_hash = match node_hash {
-                None => message.node_hash().copied(),
-                Some(n) => {
-                    if let Some(m_node) = message.node_hash() {
-                        if &n != m_node {
-                            unimplemented!("Nodes did not match");
-                        }
-                        Some(*m_node)
-                    } else {
-                        Some(n)
-                    }
-                },
-            };
+        let mut votes_by_node = HashMap::<TreeNodeHash, Vec<_>>::new();
+        for (sender, message) in &self.received_new_view_messages {
+            if let Some(node_hash) = message.node_hash() {
+                votes_by_node.entry(*node_hash).or_default().push((sender, message));
+            }
         }
+        let (node_hash, votes) = votes_by_node
+            .into_iter()
+            .find(|(_, votes)| votes.len() >= self.committee.consensus_threshold())?;
 
-        let node_hash = node_hash.unwrap();
-        let mut qc = QuorumCertificate::new(HotStuffMessageType::PreCommit, current_view.view_id, node_hash, None);
-        for message in self.received_new_view_messages.values() {
-            qc.combine_sig(message.partial_sig().unwrap())
+        let mut qc = QuorumCertificate::new(
+            HotStuffMessageType::PreCommit,
+            current_view.view_id,
+            node_hash,
+            Default::default(),
+        );
+        for (sender, message) in votes {
+            if let (Some(index), Some(sig)) = (self.committee.index_of(sender), message.partial_sig()) {
+                qc.add_signature(index, sig.signature().clone());
+            }
         }
         Some(qc)
     }
@@ -228,6 +249,13 @@ impl<TSpecification: ServiceSpecification> CommitState<TSpecification> {
             }
 
             unit_of_work.set_locked_qc(justify)?;
+            if !unit_of_work.record_vote(current_view.view_id, HotStuffMessageType::Commit, *justify.node_hash())? {
+                warn!(
+                    target: LOG_TARGET,
+                    "Already voted in COMMIT for {}, not voting again", current_view.view_id
+                );
+                return Ok(None);
+            }
             self.send_vote_to_leader(
                 *justify.node_hash(),
                 outbound,
@@ -30,14 +30,14 @@ use tokio::time::{sleep, Duration};
 
 use crate::{
     digital_assets_error::DigitalAssetError,
-    models::{Committee, HotStuffMessage, HotStuffMessageType, QuorumCertificate, View, ViewId},
+    models::{Committee, HotStuffMessage, HotStuffMessageType, QuorumCertificate, TreeNodeHash, View, ViewId},
     services::{
         infrastructure_services::{InboundConnectionService, OutboundService},
         PayloadProvider,
         ServiceSpecification,
     },
     storage::chain::ChainDbUnitOfWork,
-    workers::states::ConsensusWorkerStateEvent,
+    workers::states::{ConsensusWorkerStateEvent, InvalidMessage, InvalidMessages},
 };
 
 const LOG_TARGET: &str = "tari::dan::workers::states::decide";
@@ -48,6 +48,7 @@ pub struct DecideState<TSpecification: ServiceSpecification> {
     contract_id: FixedHash,
     committee: Committee<TSpecification::Addr>,
     received_new_view_messages: HashMap<TSpecification::Addr, HotStuffMessage<TSpecification::Payload>>,
+    invalid_messages: InvalidMessages<TSpecification::Addr>,
 }
 
 impl<TSpecification: ServiceSpecification> DecideState<TSpecification> {
@@ -61,9 +62,15 @@ impl<TSpecification: ServiceSpecification> DecideState<TSpecification> {
             contract_id,
             committee,
             received_new_view_messages: HashMap::new(),
+            invalid_messages: InvalidMessages::new(),
         }
     }
 
+    /// Takes the messages that were dropped because they failed validation
+    pub fn take_invalid_messages(&mut self) -> Vec<InvalidMessage<TSpecification::Addr>> {
+        self.invalid_messages.take()
+    }
+
     pub async fn next_event<TUnitOfWork: ChainDbUnitOfWork>(
         &mut self,
         timeout: Duration,
@@ -80,6 +87,9 @@ impl<TSpecification: ServiceSpecification> DecideState<TSpecification> {
             tokio::select! {
                 r = inbound_services.wait_for_message(HotStuffMessageType::Commit, current_view.view_id()) => {
                     let (from, message) = r?;
+                    if !self.invalid_messages.check(&self.contract_id, &self.committee, &from, &message) {
+                        continue;
+                    }
                     if current_view.is_leader() {
                         if let Some(event) = self.process_leader_message(current_view, message.clone(), &from, outbound_service).await?{
                           break Ok(event);
@@ -88,6 +98,9 @@ impl<TSpecification: ServiceSpecification> DecideState<TSpecification> {
                 },
               r = inbound_services.wait_for_qc(HotStuffMessageType::Commit, current_view.view_id()) => {
                     let (from, message) = r?;
+                    if !self.invalid_messages.check(&self.contract_id, &self.committee, &from, &message) {
+                        continue;
+                    }
                     let leader= self.committee.leader_for_view(current_view.view_id).clone();
                       if let Some(event) = self.process_replica_message(&message, current_view, &from, &leader, &mut unit_of_work, payload_provider).await? {
                           break Ok(event);
@@ -117,6 +130,14 @@ impl<TSpecification: ServiceSpecification> DecideState<TSpecification> {
         }
         debug!(target: LOG_TARGET, "MSG={:?}", message);
 
+        if !message.is_signed_by(&self.contract_id, sender) {
+            warn!(
+                target: LOG_TARGET,
+                "Ignoring vote with an invalid signature from {}", sender
+            );
+            return Ok(None);
+        }
+
         self.received_new_view_messages.insert(sender.clone(), message);
 
         if self.received_new_view_messages.len() >= self.committee.consensus_threshold() {
@@ -156,28 +177,29 @@ impl<TSpecification: ServiceSpecification> DecideState<TSpecification> {
             .await
     }
 
+    /// Creates a QC for the node that a quorum of the received votes are for. Votes for any other node, e.g. after
+    /// an equivocating proposal, are not counted.
     fn create_qc(&self, current_view: &View) -> Option<QuorumCertificate> {
-        let mut node_hash = None;
-        for message in self.received_new_view_messages.values() {
-            node_hash = match node_hash {
-                None => message.node_hash().copied(),
-                Some(n) => {
-                    if let Some(m_node) = message.node_hash() {
-                        if &n != m_node {
-                            unimplemented!("Nodes did not match");
-                        }
-                        Some(*m_node)
-                    } else {
-                        Some(n)
-                    }
-                },
-            };
+        let mut votes_by_node = HashMap::<TreeNodeHash, Vec<_>>::new();
+        for (sender, message) in &self.received_new_view_messages {
+            if let Some(node_hash) = message.node_hash() {
+                votes_by_node.entry(*node_hash).or_default().push((sender, message));
+            }
         }
-
-        let node_hash = node_hash.unwrap();
-        let mut qc = QuorumCertificate::new(HotStuffMessageType::Commit, current_view.view_id, node_hash, None);
-        for message in self.received_new_view_messages.values() {
-            qc.combine_sig(message.partial_sig().unwrap())
+        let (node_hash, votes) = votes_by_node
+            .into_iter()
+            .find(|(_, votes)| votes.len() >= self.committee.consensus_threshold())?;
+
+        let mut qc = QuorumCertificate::new(
+            HotStuffMessageType::Commit,
+            current_view.view_id,
+            node_hash,
+            Default::default(),
+        );
+        for (sender, message) in votes {
+            if let (Some(index), Some(sig)) = (self.committee.index_of(sender), message.partial_sig()) {
+                qc.add_signature(index, sig.signature().clone());
+            }
         }
         Some(qc)
     }
@@ -0,0 +1,419 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::collections::HashMap;
+
+use log::*;
+use tari_common_types::types::{FixedHash, PublicKey};
+use tari_dan_engine::state::StateDbUnitOfWorkReader;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::{
+    digital_assets_error::DigitalAssetError,
+    models::{
+        Committee,
+        ExecutionResult,
+        HotStuffMessage,
+        HotStuffMessageType,
+        HotStuffTreeNode,
+        QuorumCertificate,
+        TreeNodeHash,
+        View,
+        ViewId,
+    },
+    services::{
+        infrastructure_services::{InboundConnectionService, OutboundService},
+        ServiceSpecification,
+        SigningService,
+    },
+    storage::{chain::ChainDbUnitOfWork, DbFactory},
+    workers::{
+        state_sync::StateSynchronizer,
+        states::{ConsensusWorkerStateEvent, InvalidMessage, InvalidMessageReason, InvalidMessages},
+    },
+};
+
+const LOG_TARGET: &str = "tari::dan::workers::states::epoch_change";
+/// The number of times that an incoming member asks the outgoing committee for its final block again before it
+/// retries the handover from STARTING
+const MAX_HANDOVER_REQUESTS: usize = 3;
+
+/// Entered at the end of an epoch, once the base layer has reached the activation height of a committee change.
+/// Members of the outgoing committee sign a final block that extends their highest certified node and send it to the
+/// incoming committee. Members of the incoming committee wait until a quorum of the outgoing committee has signed the
+/// same final block, sync their state to it if needed, and start the next view from the certificate formed by those
+/// signatures. Incoming members that time out ask the outgoing committee to send its final block again, and outgoing
+/// members answer those requests for as long as they wait in this state and once they are idle.
+pub struct EpochChangeState<TSpecification: ServiceSpecification> {
+    node_id: TSpecification::Addr,
+    contract_id: FixedHash,
+    outgoing: Committee<TSpecification::Addr>,
+    incoming: Committee<TSpecification::Addr>,
+    handover_certificates: HashMap<TreeNodeHash, QuorumCertificate>,
+    handover_view: Option<ViewId>,
+    handover_message: Option<HotStuffMessage<TSpecification::Payload>>,
+    invalid_messages: InvalidMessages<TSpecification::Addr>,
+}
+
+impl<TSpecification: ServiceSpecification<Addr = PublicKey>> EpochChangeState<TSpecification> {
+    pub fn new(
+        node_id: TSpecification::Addr,
+        contract_id: FixedHash,
+        outgoing: Committee<TSpecification::Addr>,
+        incoming: Committee<TSpecification::Addr>,
+    ) -> Self {
+        Self {
+            node_id,
+            contract_id,
+            outgoing,
+            incoming,
+            handover_certificates: HashMap::new(),
+            handover_view: None,
+            handover_message: None,
+            invalid_messages: InvalidMessages::new(),
+        }
+    }
+
+    /// Takes the messages that were dropped because they failed validation
+    pub fn take_invalid_messages(&mut self) -> Vec<InvalidMessage<TSpecification::Addr>> {
+        self.invalid_messages.take()
+    }
+
+    /// The last view of the outgoing committee, once the contract has been handed over
+    pub fn handover_view(&self) -> Option<ViewId> {
+        self.handover_view
+    }
+
+    /// Takes the final block that this node signed as a member of the outgoing committee, if it signed one
+    pub fn take_handover_message(&mut self) -> Option<HotStuffMessage<TSpecification::Payload>> {
+        self.handover_message.take()
+    }
+
+    pub async fn next_event<TUnitOfWork: ChainDbUnitOfWork>(
+        &mut self,
+        timeout: Duration,
+        current_view: &View,
+        inbound_services: &TSpecification::InboundConnectionService,
+        outbound_service: &mut TSpecification::OutboundService,
+        signing_service: &TSpecification::SigningService,
+        chain_tx: TUnitOfWork,
+        db_factory: &TSpecification::DbFactory,
+        validator_node_client_factory: &TSpecification::ValidatorNodeClientFactory,
+    ) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
+        let mut min_view = current_view.view_id;
+        if self.outgoing.contains(&self.node_id) {
+            let handover_view = self
+                .send_final_block(outbound_service, signing_service, db_factory)
+                .await?;
+            if !self.incoming.contains(&self.node_id) {
+                info!(
+                    target: LOG_TARGET,
+                    "Handed the contract over to the incoming committee after {}", handover_view
+                );
+                self.handover_view = Some(handover_view);
+                return Ok(ConsensusWorkerStateEvent::NotPartOfCommittee);
+            }
+            min_view = handover_view;
+        }
+
+        let timer = sleep(timeout);
+        futures::pin_mut!(timer);
+        let mut requests = 0;
+        loop {
+            tokio::select! {
+                r = inbound_services.wait_for_epoch_handover(min_view) => {
+                    let (from, message) = r?;
+                    if !self.invalid_messages.check(&self.contract_id, &self.outgoing, &from, &message) {
+                        continue;
+                    }
+                    if let Some(final_qc) = self.process_handover_message(&message, &from) {
+                        break self.start_epoch(
+                            current_view,
+                            &message,
+                            final_qc,
+                            outbound_service,
+                            chain_tx,
+                            db_factory,
+                            validator_node_client_factory,
+                        ).await;
+                    }
+                },
+                r = inbound_services.wait_for_epoch_handover_request(), if self.handover_message.is_some() => {
+                    let (from, _) = r?;
+                    if let Some(handover_message) = &self.handover_message {
+                        Self::answer_handover_request(
+                            &self.node_id,
+                            &self.incoming,
+                            handover_message,
+                            &from,
+                            outbound_service,
+                        ).await?;
+                    }
+                },
+                _ = &mut timer => {
+                    if requests == MAX_HANDOVER_REQUESTS {
+                        // Neither committee runs the contract for this node until the handover completes, so it is
+                        // retried from STARTING
+                        warn!(
+                            target: LOG_TARGET,
+                            "Timed out waiting for the outgoing committee to hand over the contract"
+                        );
+                        break Ok(ConsensusWorkerStateEvent::NotPartOfCommittee);
+                    }
+                    requests += 1;
+                    debug!(
+                        target: LOG_TARGET,
+                        "Asking the outgoing committee for its final block again ({}/{})",
+                        requests,
+                        MAX_HANDOVER_REQUESTS
+                    );
+                    self.request_handover(min_view, outbound_service).await?;
+                    timer.as_mut().reset(Instant::now() + timeout);
+                }
+            }
+        }
+    }
+
+    /// Signs a final block on top of the highest certified node and sends it to the incoming committee. Returns the
+    /// view of the final block, which is the last view of the outgoing committee.
+    async fn send_final_block(
+        &mut self,
+        outbound_service: &mut TSpecification::OutboundService,
+        signing_service: &TSpecification::SigningService,
+        db_factory: &TSpecification::DbFactory,
+    ) -> Result<ViewId, DigitalAssetError> {
+        let chain_db = db_factory.get_or_create_chain_db(&self.contract_id)?;
+        let high_qc = chain_db.find_highest_prepared_qc()?;
+        let parent = chain_db
+            .find_sidechain_block_by_node_hash(high_qc.node_hash())?
+            .ok_or_else(|| DigitalAssetError::InvalidLogicPath {
+                reason: "The node certified by the highest QC was not found".to_string(),
+            })?;
+        let state_root = db_factory
+            .get_state_db(&self.contract_id)?
+            .ok_or(DigitalAssetError::MissingDatabase)?
+            .reader()
+            .calculate_root()?;
+
+        // Every member that has committed the certified node builds the same final block, so that the incoming
+        // committee can collect a quorum of signatures for it
+        let final_block = HotStuffTreeNode::from_parent(
+            *high_qc.node_hash(),
+            TSpecification::Payload::default(),
+            ExecutionResult::new(state_root, &[]),
+            parent.node().height() + 1,
+        );
+        let handover_view = high_qc.view_number().next();
+        debug!(
+            target: LOG_TARGET,
+            "Sending final block '{}' for {} to the incoming committee",
+            final_block.hash(),
+            handover_view
+        );
+        let mut message = HotStuffMessage::epoch_handover(final_block, high_qc, handover_view, self.contract_id);
+        message.add_partial_sig(signing_service.sign(&message.create_signature_challenge())?);
+        outbound_service
+            .broadcast(self.node_id.clone(), self.incoming.members.as_slice(), message.clone())
+            .await?;
+        self.handover_message = Some(message);
+        Ok(handover_view)
+    }
+
+    /// Asks the other members of the outgoing committee to send their final block to this node again
+    async fn request_handover(
+        &self,
+        view_id: ViewId,
+        outbound_service: &mut TSpecification::OutboundService,
+    ) -> Result<(), DigitalAssetError> {
+        let outgoing = self
+            .outgoing
+            .members
+            .iter()
+            .filter(|member| **member != self.node_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        let message = HotStuffMessage::epoch_handover_request(view_id, self.contract_id);
+        outbound_service
+            .broadcast(self.node_id.clone(), outgoing.as_slice(), message)
+            .await
+    }
+
+    /// Sends the final block that this node signed to each member of the incoming committee that asks for it again.
+    /// Only returns if an error occurs.
+    pub async fn answer_handover_requests(
+        node_id: &TSpecification::Addr,
+        incoming: &Committee<TSpecification::Addr>,
+        handover_message: &HotStuffMessage<TSpecification::Payload>,
+        inbound_services: &TSpecification::InboundConnectionService,
+        outbound_service: &mut TSpecification::OutboundService,
+    ) -> Result<(), DigitalAssetError> {
+        loop {
+            let (from, _) = inbound_services.wait_for_epoch_handover_request().await?;
+            Self::answer_handover_request(node_id, incoming, handover_message, &from, outbound_service).await?;
+        }
+    }
+
+    async fn answer_handover_request(
+        node_id: &TSpecification::Addr,
+        incoming: &Committee<TSpecification::Addr>,
+        handover_message: &HotStuffMessage<TSpecification::Payload>,
+        requester: &TSpecification::Addr,
+        outbound_service: &mut TSpecification::OutboundService,
+    ) -> Result<(), DigitalAssetError> {
+        if !incoming.contains(requester) {
+            debug!(
+                target: LOG_TARGET,
+                "Ignoring epoch handover request from {}, who is not in the incoming committee", requester
+            );
+            return Ok(());
+        }
+        debug!(
+            target: LOG_TARGET,
+            "Sending the final block for {} to {} again",
+            handover_message.view_number(),
+            requester
+        );
+        outbound_service
+            .send(node_id.clone(), requester.clone(), handover_message.clone())
+            .await
+    }
+
+    /// Adds the sender's signature of the final block to the handover certificate for that block. Returns the
+    /// certificate once a quorum of the outgoing committee has signed it.
+    fn process_handover_message(
+        &mut self,
+        message: &HotStuffMessage<TSpecification::Payload>,
+        sender: &TSpecification::Addr,
+    ) -> Option<QuorumCertificate> {
+        let (final_block, high_qc) = match (message.node(), message.justify()) {
+            (Some(final_block), Some(high_qc))
+                if final_block.parent() == high_qc.node_hash() &&
+                    message.view_number() == high_qc.view_number().next() =>
+            {
+                (final_block, high_qc)
+            },
+            _ => {
+                self.invalid_messages
+                    .record(sender, message, InvalidMessageReason::InvalidEpochHandover);
+                return None;
+            },
+        };
+        if !message.is_signed_by(&self.contract_id, sender) {
+            warn!(
+                target: LOG_TARGET,
+                "Ignoring epoch handover with an invalid signature from {}", sender
+            );
+            return None;
+        }
+        let signer_index = self.outgoing.index_of(sender)?;
+        let signature = message.partial_sig()?.signature().clone();
+        let final_hash = final_block.calculate_hash();
+        debug!(
+            target: LOG_TARGET,
+            "{} signed final block '{}' extending '{}'",
+            sender,
+            final_hash,
+            high_qc.node_hash()
+        );
+        let certificate = self.handover_certificates.entry(final_hash).or_insert_with(|| {
+            QuorumCertificate::new(
+                HotStuffMessageType::EpochHandover,
+                message.view_number(),
+                final_hash,
+                Default::default(),
+            )
+        });
+        certificate.add_signature(signer_index, signature);
+        if certificate.signatures().len() >= self.outgoing.consensus_threshold() {
+            Some(certificate.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Syncs the state to the final block of the outgoing committee if needed, stores the final block with its
+    /// certificate as the highest certified node, and asks the first leader of the incoming committee for a new view
+    async fn start_epoch<TUnitOfWork: ChainDbUnitOfWork>(
+        &mut self,
+        current_view: &View,
+        message: &HotStuffMessage<TSpecification::Payload>,
+        final_qc: QuorumCertificate,
+        outbound_service: &mut TSpecification::OutboundService,
+        mut chain_tx: TUnitOfWork,
+        db_factory: &TSpecification::DbFactory,
+        validator_node_client_factory: &TSpecification::ValidatorNodeClientFactory,
+    ) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
+        let final_block = message.node().ok_or_else(|| DigitalAssetError::InvalidLogicPath {
+            reason: "Epoch handover message has no final block".to_string(),
+        })?;
+        let expected_root = *final_block.state_root();
+        let mut state_db = db_factory.get_or_create_state_db(&self.contract_id)?;
+        if state_db.reader().calculate_root()? != expected_root {
+            info!(
+                target: LOG_TARGET,
+                "Our state is behind the final block of the outgoing committee. Attempting to sync from an outgoing \
+                 committee member"
+            );
+            let synchronizer = StateSynchronizer::new(
+                self.contract_id,
+                expected_root.into(),
+                &mut state_db,
+                validator_node_client_factory,
+                &self.node_id,
+                &self.outgoing.members,
+            );
+            if let Err(err) = synchronizer.sync().await {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not sync the state of the outgoing committee: {}", err
+                );
+            }
+            // The incoming committee must not vote until its state matches the final block
+            if state_db.reader().calculate_root()? != expected_root {
+                warn!(
+                    target: LOG_TARGET,
+                    "Our state does not match the final block of the outgoing committee, retrying the handover"
+                );
+                return Ok(ConsensusWorkerStateEvent::NotPartOfCommittee);
+            }
+        }
+
+        let final_hash = *final_qc.node_hash();
+        chain_tx.add_node(final_hash, *final_block.parent(), final_block.height())?;
+        chain_tx.commit_node(&final_hash)?;
+        chain_tx.set_prepare_qc(&final_qc)?;
+
+        let handover_view = final_qc.view_number();
+        let new_view = current_view.view_id.max(handover_view).next();
+        info!(
+            target: LOG_TARGET,
+            "The incoming committee takes over the contract from {} with final block '{}'", new_view, final_hash
+        );
+        let leader = self.incoming.leader_for_view(new_view);
+        let message = HotStuffMessage::new_view(final_qc, new_view, self.contract_id);
+        outbound_service
+            .send(self.node_id.clone(), leader.clone(), message)
+            .await?;
+        self.handover_view = Some(handover_view);
+        Ok(ConsensusWorkerStateEvent::NewView { new_view })
+    }
+}
@@ -0,0 +1,194 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use log::*;
+use tari_common_types::types::FixedHash;
+
+use crate::{
+    models::{Committee, HotStuffMessage, HotStuffMessageType, ModelError, Payload, TreeNodeHash, ViewId},
+    services::infrastructure_services::NodeAddressable,
+};
+
+const LOG_TARGET: &str = "tari::dan::workers::states::message_validation";
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidMessageReason {
+    #[error("Sender is not a member of the committee")]
+    SenderNotInCommittee,
+    #[error("Message is for contract {actual} but expected contract {expected}")]
+    ContractMismatch { expected: FixedHash, actual: FixedHash },
+    #[error("Invalid justify quorum certificate: {0}")]
+    InvalidJustify(#[from] ModelError),
+    #[error("Executing the payload of proposed node {node_hash} did not give the proposed state root and receipts")]
+    ExecutionMismatch { node_hash: TreeNodeHash },
+    #[error("Justify is a {actual:?} quorum certificate but a {expected:?} quorum certificate was expected")]
+    UnexpectedJustifyType {
+        expected: HotStuffMessageType,
+        actual: HotStuffMessageType,
+    },
+    #[error("Epoch handover does not carry a final block extending its quorum certificate")]
+    InvalidEpochHandover,
+}
+
+/// Checks that an inbound message was sent by a committee member for this contract, and that its justify quorum
+/// certificate (if any) is signed by a quorum of the committee.
+pub fn validate_inbound_message<TAddr: NodeAddressable, TPayload: Payload>(
+    contract_id: &FixedHash,
+    committee: &Committee<TAddr>,
+    sender: &TAddr,
+    message: &HotStuffMessage<TPayload>,
+) -> Result<(), InvalidMessageReason> {
+    if !committee.contains(sender) {
+        return Err(InvalidMessageReason::SenderNotInCommittee);
+    }
+    if message.contract_id() != contract_id {
+        return Err(InvalidMessageReason::ContractMismatch {
+            expected: *contract_id,
+            actual: *message.contract_id(),
+        });
+    }
+    if let Some(justify) = message.justify() {
+        justify.verify(contract_id, committee)?;
+    }
+    Ok(())
+}
+
+/// A message that was dropped because it failed validation
+#[derive(Debug)]
+pub struct InvalidMessage<TAddr> {
+    pub sender: TAddr,
+    pub message_type: HotStuffMessageType,
+    pub view_number: ViewId,
+    pub reason: InvalidMessageReason,
+}
+
+/// Records the senders of messages dropped by a consensus state so that they can be reported once the state is done
+#[derive(Debug)]
+pub struct InvalidMessages<TAddr> {
+    messages: Vec<InvalidMessage<TAddr>>,
+}
+
+impl<TAddr: NodeAddressable> InvalidMessages<TAddr> {
+    pub fn new() -> Self {
+        Self { messages: Vec::new() }
+    }
+
+    /// Validates the message, recording and returning false if it is invalid
+    pub fn check<TPayload: Payload>(
+        &mut self,
+        contract_id: &FixedHash,
+        committee: &Committee<TAddr>,
+        sender: &TAddr,
+        message: &HotStuffMessage<TPayload>,
+    ) -> bool {
+        match validate_inbound_message(contract_id, committee, sender, message) {
+            Ok(()) => true,
+            Err(reason) => {
+                self.record(sender, message, reason);
+                false
+            },
+        }
+    }
+
+    /// Records a message that was dropped for the given reason
+    pub fn record<TPayload: Payload>(
+        &mut self,
+        sender: &TAddr,
+        message: &HotStuffMessage<TPayload>,
+        reason: InvalidMessageReason,
+    ) {
+        warn!(
+            target: LOG_TARGET,
+            "Dropping {:?} message for view {} from {}: {}",
+            message.message_type(),
+            message.view_number(),
+            sender,
+            reason
+        );
+        self.messages.push(InvalidMessage {
+            sender: sender.clone(),
+            message_type: message.message_type(),
+            view_number: message.view_number(),
+            reason,
+        });
+    }
+
+    pub fn take(&mut self) -> Vec<InvalidMessage<TAddr>> {
+        std::mem::take(&mut self.messages)
+    }
+}
+
+impl<TAddr: NodeAddressable> Default for InvalidMessages<TAddr> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::QuorumCertificate;
+
+    fn committee() -> Committee<String> {
+        Committee::new(vec!["A".to_string(), "B".to_string(), "C".to_string(), "D".to_string()])
+    }
+
+    #[test]
+    fn it_drops_messages_from_non_members() {
+        let contract_id = FixedHash::default();
+        let message = HotStuffMessage::<String>::vote_prepare(TreeNodeHash::zero(), ViewId(1), contract_id);
+        let mut invalid_messages = InvalidMessages::new();
+        assert!(invalid_messages.check(&contract_id, &committee(), &"A".to_string(), &message));
+        assert!(!invalid_messages.check(&contract_id, &committee(), &"E".to_string(), &message));
+        let recorded = invalid_messages.take();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].sender, "E");
+        assert!(matches!(recorded[0].reason, InvalidMessageReason::SenderNotInCommittee));
+        assert!(invalid_messages.take().is_empty());
+    }
+
+    #[test]
+    fn it_drops_messages_for_other_contracts() {
+        let message = HotStuffMessage::<String>::vote_prepare(TreeNodeHash::zero(), ViewId(1), FixedHash::zero());
+        let result = validate_inbound_message(&FixedHash::from([1u8; 32]), &committee(), &"A".to_string(), &message);
+        assert!(matches!(result, Err(InvalidMessageReason::ContractMismatch { .. })));
+    }
+
+    #[test]
+    fn it_drops_messages_with_an_unsigned_justify() {
+        let contract_id = FixedHash::default();
+        let qc = QuorumCertificate::new(
+            HotStuffMessageType::Prepare,
+            ViewId(1),
+            TreeNodeHash::zero(),
+            Default::default(),
+        );
+        let message = HotStuffMessage::<String>::new_view(qc, ViewId(1), contract_id);
+        let result = validate_inbound_message(&contract_id, &committee(), &"A".to_string(), &message);
+        assert!(matches!(
+            result,
+            Err(InvalidMessageReason::InvalidJustify(
+                ModelError::InsufficientQuorumSignatures { .. }
+            ))
+        ));
+    }
+}
@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::models::ViewId;
+use crate::models::{ConsensusWorkerState, ViewId};
 
 // #[async_trait]
 // pub trait State {
@@ -31,23 +31,31 @@ use crate::models::ViewId;
 //     ) -> Result<ConsensusWorkerStateEvent, DigitalAssetError>;
 // }
 
+mod chained_view;
 mod commit_state;
 mod decide_state;
+mod epoch_change;
 mod idle_state;
+mod message_validation;
 mod next_view;
 mod pre_commit_state;
 mod prepare;
 mod starting;
 mod synchronizing;
+mod view_timeout;
 
+pub use chained_view::{ChainedViewState, UncommittedNodes};
 pub use commit_state::CommitState;
 pub use decide_state::DecideState;
+pub use epoch_change::EpochChangeState;
 pub use idle_state::IdleState;
+pub use message_validation::{validate_inbound_message, InvalidMessage, InvalidMessageReason, InvalidMessages};
 pub use next_view::NextViewState;
 pub use pre_commit_state::PreCommitState;
 pub use prepare::Prepare;
 pub use starting::Starting;
 pub use synchronizing::Synchronizing;
+pub use view_timeout::ViewTimeoutState;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ConsensusWorkerStateEvent {
@@ -63,7 +71,10 @@ pub enum ConsensusWorkerStateEvent {
     Committed,
     Decided,
     TimedOut,
+    TimeoutCertified { view: ViewId },
     NewView { new_view: ViewId },
+    /// The node resumes the given state of a view that it voted in before it was restarted
+    Recovered { state: ConsensusWorkerState, view: ViewId },
 }
 
 impl ConsensusWorkerStateEvent {
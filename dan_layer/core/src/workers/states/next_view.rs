@@ -23,13 +23,12 @@
 use std::marker::PhantomData;
 
 use log::*;
-use tari_dan_engine::state::models::StateRoot;
 use tari_shutdown::ShutdownSignal;
 
 use crate::{
     digital_assets_error::DigitalAssetError,
-    models::{AssetDefinition, Committee, HotStuffMessage, HotStuffTreeNode, QuorumCertificate, View},
-    services::{infrastructure_services::OutboundService, PayloadProvider, ServiceSpecification},
+    models::{AssetDefinition, Committee, HotStuffMessage, QuorumCertificate, View},
+    services::{infrastructure_services::OutboundService, ServiceSpecification},
     storage::DbFactory,
     workers::states::ConsensusWorkerStateEvent,
 };
@@ -54,17 +53,12 @@ impl<TSpecification: ServiceSpecification> NextViewState<TSpecification> {
         committee: &Committee<TSpecification::Addr>,
         node_id: TSpecification::Addr,
         asset_definition: &AssetDefinition,
-        payload_provider: &TSpecification::PayloadProvider,
         _shutdown: &ShutdownSignal,
     ) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
         let chain_db = db_factory.get_or_create_chain_db(&asset_definition.contract_id)?;
         if chain_db.is_empty()? {
             info!(target: LOG_TARGET, "Database is empty. Proposing genesis block");
-            let node = HotStuffTreeNode::genesis(
-                payload_provider.create_genesis_payload(asset_definition),
-                StateRoot::initial(),
-            );
-            let genesis_qc = QuorumCertificate::genesis(*node.hash());
+            let genesis_qc = QuorumCertificate::genesis();
             let genesis_view_no = genesis_qc.view_number();
             let leader = committee.leader_for_view(genesis_view_no);
             let message = HotStuffMessage::new_view(genesis_qc, genesis_view_no, asset_definition.contract_id);
@@ -35,7 +35,7 @@ use crate::{
         SigningService,
     },
     storage::chain::ChainDbUnitOfWork,
-    workers::states::ConsensusWorkerStateEvent,
+    workers::states::{ConsensusWorkerStateEvent, InvalidMessage, InvalidMessages},
 };
 
 const LOG_TARGET: &str = "tari::dan::workers::states::precommit";
@@ -45,6 +45,7 @@ pub struct PreCommitState<TSpecification: ServiceSpecification> {
     contract_id: FixedHash,
     committee: Committee<TSpecification::Addr>,
     received_prepare_messages: HashMap<TSpecification::Addr, HotStuffMessage<TSpecification::Payload>>,
+    invalid_messages: InvalidMessages<TSpecification::Addr>,
 }
 
 impl<TSpecification: ServiceSpecification> PreCommitState<TSpecification> {
@@ -58,9 +59,15 @@ impl<TSpecification: ServiceSpecification> PreCommitState<TSpecification> {
             contract_id,
             committee,
             received_prepare_messages: HashMap::new(),
+            invalid_messages: InvalidMessages::new(),
         }
     }
 
+    /// Takes the messages that were dropped because they failed validation
+    pub fn take_invalid_messages(&mut self) -> Vec<InvalidMessage<TSpecification::Addr>> {
+        self.invalid_messages.take()
+    }
+
     pub async fn next_event<TUnitOfWork: ChainDbUnitOfWork>(
         &mut self,
         timeout: Duration,
@@ -78,6 +85,9 @@ impl<TSpecification: ServiceSpecification> PreCommitState<TSpecification> {
             tokio::select! {
                 r = inbound_services.wait_for_message(HotStuffMessageType::Prepare, current_view.view_id()) => {
                     let (from, message) = r?;
+                    if !self.invalid_messages.check(&self.contract_id, &self.committee, &from, &message) {
+                        continue;
+                    }
                     debug!(target: LOG_TARGET, "Received message: {:?} view:{}",  message.message_type(), message.view_number());
                      if current_view.is_leader() {
                          if let Some(event) = self.process_leader_message(current_view, message.clone(), &from, outbound_service).await? {
@@ -87,6 +97,9 @@ impl<TSpecification: ServiceSpecification> PreCommitState<TSpecification> {
                 },
                 r = inbound_services.wait_for_qc(HotStuffMessageType::Prepare, current_view.view_id()) => {
                    let (from, message) = r?;
+                   if !self.invalid_messages.check(&self.contract_id, &self.committee, &from, &message) {
+                       continue;
+                   }
                    let leader = self.committee.leader_for_view(current_view.view_id).clone();
                    if let Some(event) = self.process_replica_message(&message, current_view, &from, &leader,  outbound_service, signing_service, &mut unit_of_work).await? {
                        break Ok(event);
@@ -120,6 +133,14 @@ impl<TSpecification: ServiceSpecification> PreCommitState<TSpecification> {
             return Ok(None);
         }
 
+        if !message.is_signed_by(&self.contract_id, sender) {
+            warn!(
+                target: LOG_TARGET,
+                "Ignoring vote with an invalid signature from {}", sender
+            );
+            return Ok(None);
+        }
+
         self.received_prepare_messages.insert(sender.clone(), message);
 
         if self.received_prepare_messages.len() >= self.committee.consensus_threshold() {
@@ -162,28 +183,29 @@ impl<TSpecification: ServiceSpecification> PreCommitState<TSpecification> {
             .await
     }
 
+    /// Creates a QC for the node that a quorum of the received votes are for. Votes for any other node, e.g. after
+    /// an equivocating proposal, are not counted.
     fn create_qc(&self, current_view: &View) -> Option<QuorumCertificate> {
-        let mut node_hash = None;
-        for message in self.received_prepare_messages.values() {
-            node_hash = match node_hash {
-                None => message.node_hash().copied(),
-                Some(n) => {
-                    if let Some(m_node) = message.node_hash() {
-                        if &n != m_node {
-                            unimplemented!("Nodes did not match");
-                        }
-                        Some(*m_node)
-                    } else {
-                        Some(n)
-                    }
-                },
-            };
+        let mut votes_by_node = HashMap::<TreeNodeHash, Vec<_>>::new();
+        for (sender, message) in &self.received_prepare_messages {
+            if let Some(node_hash) = message.node_hash() {
+                votes_by_node.entry(*node_hash).or_default().push((sender, message));
+            }
         }
+        let (node_hash, votes) = votes_by_node
+            .into_iter()
+            .find(|(_, votes)| votes.len() >= self.committee.consensus_threshold())?;
 
-        let node_hash = node_hash.unwrap();
-        let mut qc = QuorumCertificate::new(HotStuffMessageType::Prepare, current_view.view_id, node_hash, None);
-        for message in self.received_prepare_messages.values() {
-            qc.combine_sig(message.partial_sig().unwrap())
+        let mut qc = QuorumCertificate::new(
+            HotStuffMessageType::Prepare,
+            current_view.view_id,
+            node_hash,
+            Default::default(),
+        );
+        for (sender, message) in votes {
+            if let (Some(index), Some(sig)) = (self.committee.index_of(sender), message.partial_sig()) {
+                qc.add_signature(index, sig.signature().clone());
+            }
         }
         Some(qc)
     }
@@ -222,6 +244,17 @@ impl<TSpecification: ServiceSpecification> PreCommitState<TSpecification> {
             }
 
             unit_of_work.set_prepare_qc(justify)?;
+            if !unit_of_work.record_vote(
+                current_view.view_id,
+                HotStuffMessageType::PreCommit,
+                *justify.node_hash(),
+            )? {
+                warn!(
+                    target: LOG_TARGET,
+                    "Already voted in PRECOMMIT for {}, not voting again", current_view.view_id
+                );
+                return Ok(None);
+            }
             self.send_vote_to_leader(
                 *justify.node_hash(),
                 outbound,
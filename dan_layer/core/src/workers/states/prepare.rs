@@ -48,7 +48,7 @@ use crate::{
         SigningService,
     },
     storage::{chain::ChainDbUnitOfWork, ChainStorageService, DbFactory, StorageError},
-    workers::states::ConsensusWorkerStateEvent,
+    workers::states::{ConsensusWorkerStateEvent, InvalidMessage, InvalidMessageReason, InvalidMessages},
 };
 
 const LOG_TARGET: &str = "tari::dan::workers::states::prepare";
@@ -57,6 +57,7 @@ pub struct Prepare<TSpecification: ServiceSpecification> {
     node_id: TSpecification::Addr,
     contract_id: FixedHash,
     received_new_view_messages: HashMap<TSpecification::Addr, HotStuffMessage<TSpecification::Payload>>,
+    invalid_messages: InvalidMessages<TSpecification::Addr>,
 }
 
 impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
@@ -65,9 +66,15 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
             node_id,
             contract_id,
             received_new_view_messages: HashMap::new(),
+            invalid_messages: InvalidMessages::new(),
         }
     }
 
+    /// Takes the messages that were dropped because they failed validation
+    pub fn take_invalid_messages(&mut self) -> Vec<InvalidMessage<TSpecification::Addr>> {
+        self.invalid_messages.take()
+    }
+
     pub async fn next_event<TChainDbUnitOfWork: ChainDbUnitOfWork, TStateDbUnitOfWork: StateDbUnitOfWork>(
         &mut self,
         current_view: &View,
@@ -107,6 +114,9 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
                 r = inbound_services.wait_for_message(HotStuffMessageType::NewView, current_view.view_id())  => {
                     let (from, message) = r?;
                     debug!(target: LOG_TARGET, "Received leader message (is_leader = {:?})", current_view.is_leader());
+                    if !self.invalid_messages.check(&self.contract_id, committee, &from, &message) {
+                        continue;
+                    }
                     if current_view.is_leader() {
                         if let Some(event) = self.process_leader_message(
                             current_view,
@@ -117,6 +127,7 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
                             payload_provider,
                             payload_processor,
                             outbound_service,
+                            signing_service,
                             db_factory,
                         ).await? {
                             break Ok(event)
@@ -126,11 +137,14 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
                 r = inbound_services.wait_for_message(HotStuffMessageType::Prepare, current_view.view_id()) => {
                     let (from, message) = r?;
                     debug!(target: LOG_TARGET, "Received replica message");
+                    if !self.invalid_messages.check(&self.contract_id, committee, &from, &message) {
+                        continue;
+                    }
                     if let Some(event) = self.process_replica_message(
                         &message,
                         current_view,
                         &from,
-                        committee.leader_for_view(current_view.view_id),
+                        committee,
                         outbound_service,
                         signing_service,
                         payload_processor,
@@ -160,6 +174,7 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
         payload_provider: &TSpecification::PayloadProvider,
         payload_processor: &mut TSpecification::PayloadProcessor,
         outbound: &mut TSpecification::OutboundService,
+        signing_service: &TSpecification::SigningService,
         db_factory: &TSpecification::DbFactory,
     ) -> Result<Option<ConsensusWorkerStateEvent>, DigitalAssetError> {
         debug!(
@@ -199,8 +214,15 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
                     temp_state_tx,
                 )
                 .await?;
-            self.broadcast_proposal(outbound, committee, proposal, high_qc, current_view.view_id)
-                .await?;
+            self.broadcast_proposal(
+                outbound,
+                committee,
+                proposal,
+                high_qc,
+                current_view.view_id,
+                signing_service,
+            )
+            .await?;
             Ok(None) // Will move to pre-commit when it receives the message as a replica
         } else {
             debug!(
@@ -214,11 +236,11 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
     }
 
     async fn process_replica_message<TChainDbUnitOfWork: ChainDbUnitOfWork, TStateDbUnitOfWork: StateDbUnitOfWork>(
-        &self,
+        &mut self,
         message: &HotStuffMessage<TSpecification::Payload>,
         current_view: &View,
         from: &TSpecification::Addr,
-        view_leader: &TSpecification::Addr,
+        committee: &Committee<TSpecification::Addr>,
         outbound: &mut TSpecification::OutboundService,
         signing_service: &TSpecification::SigningService,
         payload_processor: &mut TSpecification::PayloadProcessor,
@@ -236,6 +258,7 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
         if message.node().is_none() {
             unimplemented!("Empty message");
         }
+        let view_leader = committee.leader_for_view(current_view.view_id);
         if from != view_leader {
             warn!("Message not from leader");
             return Ok(None);
@@ -244,6 +267,20 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
         let justify = message
             .justify()
             .ok_or(DigitalAssetError::PreparePhaseNoQuorumCertificate)?;
+        // A proposal is justified by the highest PREPARE QC, or by the genesis QC for the first proposal
+        let expected_justify_type = if current_view.view_id().is_genesis() {
+            HotStuffMessageType::Genesis
+        } else {
+            HotStuffMessageType::Prepare
+        };
+        if justify.message_type() != expected_justify_type {
+            self.invalid_messages
+                .record(from, message, InvalidMessageReason::UnexpectedJustifyType {
+                    expected: expected_justify_type,
+                    actual: justify.message_type(),
+                });
+            return Ok(None);
+        }
 
         // The genesis does not extend any node
         if !current_view.view_id().is_genesis() {
@@ -262,18 +299,22 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
             current_view.view_id()
         );
 
-        let state_root = payload_processor
+        let execution_result = payload_processor
             .process_payload(node.payload(), state_tx.clone())
             .await?;
 
-        if state_root != *node.state_root() {
+        if execution_result != *node.execution_result() {
             warn!(
                 target: LOG_TARGET,
-                "Calculated state root did not match the state root provided by the leader: Expected: {:?} Leader \
+                "Calculated execution result did not match the result provided by the leader: Expected: {:?} Leader \
                  provided:{:?}",
-                state_root,
-                node.state_root()
+                execution_result,
+                node.execution_result()
             );
+            self.invalid_messages
+                .record(from, message, InvalidMessageReason::ExecutionMismatch {
+                    node_hash: *node.hash(),
+                });
             return Ok(None);
         }
 
@@ -289,6 +330,15 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
             .await?;
 
         payload_provider.reserve_payload(node.payload(), node.hash()).await?;
+        if !chain_tx.record_vote(current_view.view_id, HotStuffMessageType::Prepare, *node.hash())? {
+            warn!(
+                target: LOG_TARGET,
+                "Already voted in PREPARE for {}, not voting for node '{}'",
+                current_view.view_id(),
+                node.hash()
+            );
+            return Ok(None);
+        }
         self.send_vote_to_leader(
             *node.hash(),
             outbound,
@@ -335,16 +385,16 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
 
         if view_id.is_genesis() {
             let payload = payload_provider.create_genesis_payload(asset_definition);
-            let state_root = payload_processor.process_payload(&payload, state_db).await?;
-            Ok(HotStuffTreeNode::genesis(payload, state_root))
+            let execution_result = payload_processor.process_payload(&payload, state_db).await?;
+            Ok(HotStuffTreeNode::genesis(payload, execution_result))
         } else {
             let payload = payload_provider.create_payload().await?;
 
-            let state_root = payload_processor.process_payload(&payload, state_db).await?;
+            let execution_result = payload_processor.process_payload(&payload, state_db).await?;
             Ok(HotStuffTreeNode::from_parent(
                 parent,
                 payload,
-                state_root,
+                execution_result,
                 view_id.as_u64() as u32,
             ))
         }
@@ -357,8 +407,11 @@ impl<TSpecification: ServiceSpecification> Prepare<TSpecification> {
         proposal: HotStuffTreeNode<TSpecification::Payload>,
         high_qc: QuorumCertificate,
         view_number: ViewId,
+        signing_service: &TSpecification::SigningService,
     ) -> Result<(), DigitalAssetError> {
-        let message = HotStuffMessage::prepare(proposal, Some(high_qc), view_number, self.contract_id);
+        // Signed, so that a conflicting proposal for the same view can be used as evidence against the leader
+        let mut message = HotStuffMessage::prepare(proposal, Some(high_qc), view_number, self.contract_id);
+        message.add_partial_sig(signing_service.sign(&message.create_signature_challenge())?);
         outbound
             .broadcast(self.node_id.clone(), committee.members.as_slice(), message)
             .await
@@ -400,7 +453,17 @@ mod test {
     use tari_common_types::types::FixedHash;
 
     use crate::{
-        models::{AssetDefinition, Committee, HotStuffMessage, QuorumCertificate, TreeNodeHash, View, ViewId},
+        models::{
+            AssetDefinition,
+            Committee,
+            ExecutionResult,
+            HotStuffMessage,
+            HotStuffTreeNode,
+            QuorumCertificate,
+            TariDanPayload,
+            View,
+            ViewId,
+        },
         services::{
             infrastructure_services::{mocks::mock_outbound, OutboundService},
             mocks::{
@@ -411,9 +474,10 @@ mod test {
                 MockChainStorageService,
                 MockServiceSpecification,
             },
+            PayloadProcessor,
         },
-        storage::{mocks::MockDbFactory, DbFactory},
-        workers::states::{ConsensusWorkerStateEvent, Prepare},
+        storage::{chain::ChainDbUnitOfWork, mocks::MockDbFactory, DbFactory},
+        workers::states::{ConsensusWorkerStateEvent, InvalidMessageReason, Prepare},
     };
 
     #[tokio::test(flavor = "multi_thread")]
@@ -421,7 +485,7 @@ mod test {
     async fn basic_test_as_leader() {
         // let mut inbound = mock_inbound();
         // let mut sender = inbound.create_sender();
-        let locked_qc = QuorumCertificate::genesis(TreeNodeHash::zero());
+        let locked_qc = QuorumCertificate::genesis();
         let contract_id = FixedHash::default();
         let address_a = create_public_key();
         let address_b = create_public_key();
@@ -502,4 +566,63 @@ mod test {
         let event = task.await.unwrap();
         assert_eq!(event, ConsensusWorkerStateEvent::Prepared);
     }
+
+    #[tokio::test]
+    async fn it_refuses_a_proposal_with_a_different_execution_result() {
+        let contract_id = FixedHash::default();
+        let leader = create_public_key();
+        let replica = create_public_key();
+        let committee = Committee::new(vec![leader.clone(), replica.clone()]);
+        let current_view = View {
+            view_id: ViewId(0),
+            is_leader: false,
+        };
+        let mut payload_processor = mock_payload_processor();
+        let db_factory = MockDbFactory::default();
+        let chain_db = db_factory.get_or_create_chain_db(&contract_id).unwrap();
+        let mut chain_tx = chain_db.new_unit_of_work();
+        let mut state_tx = db_factory
+            .get_or_create_state_db(&contract_id)
+            .unwrap()
+            .new_unit_of_work(current_view.view_id.as_u64());
+
+        let payload = TariDanPayload::default();
+        let execution_result = payload_processor
+            .process_payload(&payload, state_tx.clone())
+            .await
+            .unwrap();
+        // The leader claims a receipt that executing the payload does not give
+        let node = HotStuffTreeNode::genesis(payload, ExecutionResult::new(execution_result.state_root, &[vec![1]]));
+        let node_hash = *node.hash();
+        let message = HotStuffMessage::prepare(node, Some(QuorumCertificate::genesis()), ViewId(0), contract_id);
+
+        let mut state = Prepare::<MockServiceSpecification>::new(replica, contract_id);
+        let event = state
+            .process_replica_message(
+                &message,
+                &current_view,
+                &leader,
+                &committee,
+                &mut mock_outbound(committee.members.clone()),
+                &mock_signing_service(),
+                &mut payload_processor,
+                &mut mock_static_payload_provider(),
+                &mut chain_tx,
+                &MockChainStorageService::default(),
+                &mut state_tx,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(event, None);
+        let invalid_messages = state.take_invalid_messages();
+        assert_eq!(invalid_messages.len(), 1);
+        assert_eq!(invalid_messages[0].sender, leader);
+        assert!(matches!(
+            invalid_messages[0].reason,
+            InvalidMessageReason::ExecutionMismatch { node_hash: hash } if hash == node_hash
+        ));
+        chain_tx.commit().unwrap();
+        assert!(!chain_db.sidechain_block_exists(&node_hash).unwrap());
+    }
 }
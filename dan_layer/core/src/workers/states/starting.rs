@@ -20,14 +20,17 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::{convert::TryInto, marker::PhantomData};
+use std::{
+    convert::{TryFrom, TryInto},
+    marker::PhantomData,
+};
 
 use log::*;
 use tari_core::transactions::transaction_components::OutputType;
 
 use crate::{
     digital_assets_error::DigitalAssetError,
-    models::AssetDefinition,
+    models::{AssetDefinition, BaseLayerOutput},
     services::{BaseNodeClient, CommitteeManager, ServiceSpecification},
     storage::DbFactory,
     workers::states::ConsensusWorkerStateEvent,
@@ -78,7 +81,36 @@ impl<TSpecification: ServiceSpecification> Starting<TSpecification> {
 
         committee_manager.read_from_constitution(output)?;
 
-        if !committee_manager.current_committee()?.contains(node_id) {
+        let mut amendments = base_node_client
+            .get_current_contract_outputs(
+                tip.height_of_longest_chain
+                    .saturating_sub(asset_definition.base_layer_confirmation_time),
+                asset_definition.contract_id,
+                OutputType::ContractAmendment,
+            )
+            .await?;
+        if let Some(utxo) = amendments.pop() {
+            committee_manager.read_from_amendment(BaseLayerOutput::try_from(utxo)?)?;
+        }
+
+        // Resume the committee that this node has already seen take over
+        let last_handover = match db_factory.get_chain_db(&asset_definition.contract_id)? {
+            Some(chain_db) => chain_db.get_last_handover()?,
+            None => None,
+        };
+        if let Some((activation_height, handover_view)) = last_handover {
+            let is_handed_over = committee_manager
+                .pending_change()
+                .map_or(false, |change| change.activation_height == activation_height);
+            if is_handed_over {
+                committee_manager.activate_pending_change(handover_view)?;
+            }
+        }
+
+        let is_incoming_member = committee_manager
+            .pending_change()
+            .map_or(false, |change| change.members.contains(node_id));
+        if !committee_manager.current_committee()?.contains(node_id) && !is_incoming_member {
             info!(
                 target: LOG_TARGET,
                 "Validator node not part of committee for asset public key '{}'", asset_definition.contract_id
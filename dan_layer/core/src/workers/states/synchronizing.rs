@@ -115,7 +115,8 @@ impl<TSpecification: ServiceSpecification<Addr = CommsPublicKey>> Synchronizing<
         );
 
         let synchronizer = StateSynchronizer::new(
-            &last_checkpoint,
+            last_checkpoint.contract_id,
+            last_checkpoint.merkle_root,
             &mut state_db,
             validator_node_client_factory,
             our_address,
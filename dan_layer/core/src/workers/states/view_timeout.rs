@@ -0,0 +1,159 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use log::*;
+use tari_common_types::types::FixedHash;
+use tokio::time::{sleep, Duration};
+
+use crate::{
+    digital_assets_error::DigitalAssetError,
+    models::{Committee, HotStuffMessage, HotStuffMessageType, View},
+    services::{
+        infrastructure_services::{InboundConnectionService, OutboundService},
+        ServiceSpecification,
+        SigningService,
+    },
+    workers::{
+        states::{ConsensusWorkerStateEvent, InvalidMessage, InvalidMessages},
+        Pacemaker,
+    },
+};
+
+const LOG_TARGET: &str = "tari::dan::workers::states::view_timeout";
+
+/// Entered when a view times out. The node broadcasts a signed timeout vote for the view and waits until a quorum of
+/// the committee has timed out in this or a later view, at which point all nodes move on from the certified view.
+pub struct ViewTimeoutState<TSpecification: ServiceSpecification> {
+    node_id: TSpecification::Addr,
+    contract_id: FixedHash,
+    committee: Committee<TSpecification::Addr>,
+    invalid_messages: InvalidMessages<TSpecification::Addr>,
+}
+
+impl<TSpecification: ServiceSpecification> ViewTimeoutState<TSpecification> {
+    pub fn new(
+        node_id: TSpecification::Addr,
+        contract_id: FixedHash,
+        committee: Committee<TSpecification::Addr>,
+    ) -> Self {
+        Self {
+            node_id,
+            contract_id,
+            committee,
+            invalid_messages: InvalidMessages::new(),
+        }
+    }
+
+    /// Takes the messages that were dropped because they failed validation
+    pub fn take_invalid_messages(&mut self) -> Vec<InvalidMessage<TSpecification::Addr>> {
+        self.invalid_messages.take()
+    }
+
+    pub async fn next_event(
+        &mut self,
+        timeout: Duration,
+        current_view: &View,
+        inbound_services: &TSpecification::InboundConnectionService,
+        outbound_service: &mut TSpecification::OutboundService,
+        signing_service: &TSpecification::SigningService,
+        pacemaker: &mut Pacemaker<TSpecification::Addr>,
+    ) -> Result<ConsensusWorkerStateEvent, DigitalAssetError> {
+        let mut message = HotStuffMessage::timeout(
+            current_view.view_id,
+            pacemaker.highest_timeout_certificate().cloned(),
+            self.contract_id,
+        );
+        message.add_partial_sig(signing_service.sign(&message.create_signature_challenge())?);
+        debug!(
+            target: LOG_TARGET,
+            "Broadcasting timeout for {}, next timeout is {:.2?}", current_view.view_id, timeout
+        );
+        outbound_service
+            .broadcast(self.node_id.clone(), self.committee.members.as_slice(), message)
+            .await?;
+
+        let timeout = sleep(timeout);
+        futures::pin_mut!(timeout);
+        loop {
+            tokio::select! {
+                r = inbound_services.wait_for_timeout(current_view.view_id()) => {
+                    let (from, message) = r?;
+                    if !self.invalid_messages.check(&self.contract_id, &self.committee, &from, &message) {
+                        continue;
+                    }
+                    if let Some(event) = self.process_timeout_message(current_view, &message, &from, pacemaker) {
+                        break Ok(event);
+                    }
+                },
+                _ = &mut timeout => {
+                    break Ok(ConsensusWorkerStateEvent::TimedOut);
+                }
+            }
+        }
+    }
+
+    fn process_timeout_message(
+        &self,
+        current_view: &View,
+        message: &HotStuffMessage<TSpecification::Payload>,
+        sender: &TSpecification::Addr,
+        pacemaker: &mut Pacemaker<TSpecification::Addr>,
+    ) -> Option<ConsensusWorkerStateEvent> {
+        // The sender has already seen a timeout certificate at least as high as this view, so we can catch up
+        // without waiting for the votes. The certificate was verified when the message was validated.
+        if let Some(timeout_certificate) = message.justify() {
+            if timeout_certificate.message_type() == HotStuffMessageType::Timeout &&
+                timeout_certificate.view_number() >= current_view.view_id &&
+                pacemaker.update_highest_timeout_certificate(timeout_certificate.clone())
+            {
+                info!(
+                    target: LOG_TARGET,
+                    "Synchronizing to {} certified by {}",
+                    timeout_certificate.view_number(),
+                    sender
+                );
+                return Some(ConsensusWorkerStateEvent::TimeoutCertified {
+                    view: timeout_certificate.view_number(),
+                });
+            }
+        }
+
+        if !message.is_signed_by(&self.contract_id, sender) {
+            warn!(
+                target: LOG_TARGET,
+                "Ignoring timeout with an invalid signature from {}", sender
+            );
+            return None;
+        }
+        let signature = message.partial_sig()?.signature().clone();
+        let timeout_certificate =
+            pacemaker.add_timeout_vote(&self.committee, sender.clone(), message.view_number(), signature)?;
+        info!(
+            target: LOG_TARGET,
+            "Timeout certificate formed for {}",
+            timeout_certificate.view_number()
+        );
+        Some(ConsensusWorkerStateEvent::TimeoutCertified {
+            view: timeout_certificate.view_number(),
+        })
+    }
+}
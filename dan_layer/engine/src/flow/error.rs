@@ -6,4 +6,43 @@ use thiserror::Error;
 pub enum FlowEngineError {
     #[error("The instruction execution failed: Inner error:{inner}")]
     InstructionFailed { inner: String },
+    #[error("Flow function '{name}' does not exist")]
+    FunctionNotFound { name: String },
+    #[error("Flow '{function}' is invalid: {details}")]
+    InvalidFlow { function: String, details: String },
+    #[error("Flow '{function}' must have exactly one core::start node but has {count}")]
+    InvalidStartNodeCount { function: String, count: usize },
+    #[error("Node {node_id} in flow '{function}' uses unknown worker '{worker}'")]
+    UnknownWorker {
+        function: String,
+        node_id: i64,
+        worker: String,
+    },
+    #[error("Node {node_id} in flow '{function}' is connected to node {target}, which does not exist")]
+    UnknownConnection {
+        function: String,
+        node_id: i64,
+        target: i64,
+    },
+    #[error("Node {node_id} ({worker}) in flow '{function}' is missing required field '{field}'")]
+    MissingField {
+        function: String,
+        node_id: i64,
+        worker: String,
+        field: String,
+    },
+    #[error("Node {node_id} in flow '{function}' uses argument '{arg}', which the function does not declare")]
+    UnknownArgument {
+        function: String,
+        node_id: i64,
+        arg: String,
+    },
+    #[error("Flow '{function}' contains a cycle through node {node_id}")]
+    CyclicFlow { function: String, node_id: i64 },
+    #[error("Missing argument '{name}' at position {position}")]
+    MissingArgument { name: String, position: usize },
+    #[error("Argument '{name}' is invalid: {details}")]
+    InvalidArgument { name: String, details: String },
+    #[error("{remaining} unexpected bytes remain after decoding the arguments")]
+    TrailingArgumentBytes { remaining: usize },
 }
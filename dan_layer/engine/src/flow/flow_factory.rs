@@ -3,10 +3,8 @@
 
 use std::collections::HashMap;
 
-use d3ne::WorkersBuilder;
-
 use crate::{
-    flow::{FlowEngineError, FlowInstance},
+    flow::{FlowEngineError, FlowInstance, FlowOutput},
     function_definitions::{FlowFunctionDefinition, FunctionArgDefinition},
     instructions::Instruction,
     state::StateDbUnitOfWork,
@@ -17,20 +15,21 @@ pub struct FlowFactory {
     flows: HashMap<String, (Vec<FunctionArgDefinition>, FlowInstance)>,
 }
 impl FlowFactory {
-    pub fn new(flow_functions: &[FlowFunctionDefinition]) -> Self {
+    /// Validates and loads the flows of the given functions, failing if any of them is invalid
+    pub fn new(flow_functions: &[FlowFunctionDefinition]) -> Result<Self, FlowEngineError> {
         let mut flows = HashMap::new();
         for func_def in flow_functions {
-            // build_instance(&mut instance, &func_def);
             flows.insert(
                 func_def.name.clone(),
-                (
-                    func_def.args.clone(),
-                    FlowInstance::try_build(func_def.flow.clone(), WorkersBuilder::new().build())
-                        .expect("Could not build flow"),
-                ),
+                (func_def.args.clone(), FlowInstance::try_build(func_def)?),
             );
         }
-        Self { flows }
+        Ok(Self { flows })
+    }
+
+    /// Returns true if a flow function with the given name was loaded
+    pub fn contains_function(&self, name: &str) -> bool {
+        self.flows.contains_key(name)
     }
 
     pub fn invoke_write_method<TUnitOfWork: StateDbUnitOfWork + 'static>(
@@ -38,11 +37,11 @@ impl FlowFactory {
         name: String,
         instruction: &Instruction,
         state_db: TUnitOfWork,
-    ) -> Result<TUnitOfWork, FlowEngineError> {
-        if let Some((args, engine)) = self.flows.get(&name) {
-            engine.process(instruction.args(), args, instruction.sender(), state_db)
-        } else {
-            todo!("could not find engine")
-        }
+    ) -> Result<FlowOutput<TUnitOfWork>, FlowEngineError> {
+        let (args, engine) = self
+            .flows
+            .get(&name)
+            .ok_or(FlowEngineError::FunctionNotFound { name })?;
+        engine.process(instruction.args(), args, instruction.sender(), state_db)
     }
 }
@@ -3,31 +3,43 @@
 
 use std::{
     collections::HashMap,
+    convert::TryFrom,
     ops::Deref,
     sync::{Arc, RwLock},
 };
 
 use d3ne::{Engine, Node, Workers, WorkersBuilder};
-use serde_json::Value as JsValue;
 use tari_common_types::types::PublicKey;
 use tari_utilities::ByteArray;
 
 use crate::{
     flow::{
+        flow_validation::validate_flow,
         workers::{
             ArgWorker,
+            ArithmeticOp,
+            ArithmeticWorker,
+            CompareOp,
+            CompareWorker,
             CreateBucketWorker,
+            EmitEventWorker,
+            GetStateTextWorker,
+            GetStateU64Worker,
             HasRoleWorker,
+            IfWorker,
             MintBucketWorker,
             SenderWorker,
+            SetStateTextWorker,
+            SetStateU64Worker,
             StartWorker,
             StoreBucketWorker,
             TextWorker,
         },
         ArgValue,
         FlowEngineError,
+        FlowEvent,
     },
-    function_definitions::{ArgType, FunctionArgDefinition},
+    function_definitions::{ArgType, FlowFunctionDefinition, FunctionArgDefinition},
     state::StateDbUnitOfWork,
 };
 
@@ -40,16 +52,24 @@ pub struct FlowInstance {
     nodes: HashMap<i64, Node>,
 }
 
+/// The result of processing a flow
+pub struct FlowOutput<TUnitOfWork> {
+    pub state_db: TUnitOfWork,
+    pub events: Vec<FlowEvent>,
+}
+
 impl FlowInstance {
-    pub fn try_build(value: JsValue, workers: Workers) -> Result<Self, FlowEngineError> {
-        let engine = Engine::new("tari@0.1.0", workers);
-        // dbg!(&value);
-        let nodes = engine.parse_value(value).expect("could not create engine");
-        Ok(FlowInstance {
-            // process: value,
-            nodes,
-            start_node: 1,
-        })
+    /// Validates and parses the flow of the given function
+    pub fn try_build(func_def: &FlowFunctionDefinition) -> Result<Self, FlowEngineError> {
+        let start_node = validate_flow(func_def)?;
+        let engine = Engine::new("tari@0.1.0", WorkersBuilder::new().build());
+        let nodes = engine
+            .parse_value(func_def.flow.clone())
+            .map_err(|err| FlowEngineError::InvalidFlow {
+                function: func_def.name.clone(),
+                details: format!("{:?}", err),
+            })?;
+        Ok(FlowInstance { nodes, start_node })
     }
 
     pub fn process<TUnitOfWork: StateDbUnitOfWork + 'static>(
@@ -58,56 +78,91 @@ impl FlowInstance {
         arg_defs: &[FunctionArgDefinition],
         sender: PublicKey,
         state_db: TUnitOfWork,
-    ) -> Result<TUnitOfWork, FlowEngineError> {
-        let mut engine_args = HashMap::new();
-
-        let mut remaining_args = Vec::from(args);
-        for ad in arg_defs {
-            let value = match ad.arg_type {
-                ArgType::String => {
-                    let length = remaining_args.pop().expect("no more args: len") as usize;
-                    let s_bytes: Vec<u8> = remaining_args.drain(0..length).collect();
-                    let s = String::from_utf8(s_bytes).expect("could not convert string");
-                    ArgValue::String(s)
-                },
-                ArgType::Byte => ArgValue::Byte(remaining_args.pop().expect("No byte to read")),
-                ArgType::PublicKey => {
-                    let bytes: Vec<u8> = remaining_args.drain(0..32).collect();
-                    let pk = PublicKey::from_bytes(&bytes).expect("Not a valid public key");
-                    ArgValue::PublicKey(pk)
-                },
-                ArgType::Uint => {
-                    let bytes: Vec<u8> = remaining_args.drain(0..8).collect();
-                    let mut fixed: [u8; 8] = [0u8; 8];
-                    fixed.copy_from_slice(&bytes);
-                    let value = u64::from_le_bytes(fixed);
-                    ArgValue::Uint(value)
-                },
-            };
-            engine_args.insert(ad.name.clone(), value);
-        }
+    ) -> Result<FlowOutput<TUnitOfWork>, FlowEngineError> {
+        let engine_args = decode_args(args, arg_defs)?;
 
         let state_db = Arc::new(RwLock::new(state_db));
-        let engine = Engine::new("tari@0.1.0", load_workers(engine_args, sender, state_db.clone()));
-        let output = engine.process(&self.nodes, self.start_node);
-        let _od = output.expect("engine process failed");
-        // if let Some(err) = od.get("error") {
-        //     match err {
-        //         Ok(_) => todo!("Unexpected Ok result returned from error"),
-        //         Err(e) => {
-        //             return Err(FlowEngineError::InstructionFailed { inner: e.to_string() });
-        //         },
-        //     }
-        // }
-        let inner = state_db.read().map(|s| s.deref().clone()).unwrap();
-        Ok(inner)
+        let events = Arc::new(RwLock::new(Vec::new()));
+        let engine = Engine::new(
+            "tari@0.1.0",
+            load_workers(engine_args, sender, state_db.clone(), events.clone()),
+        );
+        engine
+            .process(&self.nodes, self.start_node)
+            .map_err(|err| FlowEngineError::InstructionFailed {
+                inner: format!("{:?}", err),
+            })?;
+        let state_db = state_db
+            .read()
+            .map(|s| s.deref().clone())
+            .map_err(|_| FlowEngineError::InstructionFailed {
+                inner: "State lock poisoned".to_string(),
+            })?;
+        let events = events
+            .read()
+            .map(|e| e.deref().clone())
+            .map_err(|_| FlowEngineError::InstructionFailed {
+                inner: "Event lock poisoned".to_string(),
+            })?;
+        Ok(FlowOutput { state_db, events })
     }
 }
 
+/// Decodes the instruction arguments in the order they are defined. See [`encode_args`](crate::flow::encode_args) for
+/// the format.
+fn decode_args(args: &[u8], arg_defs: &[FunctionArgDefinition]) -> Result<HashMap<String, ArgValue>, FlowEngineError> {
+    let mut decoded = HashMap::with_capacity(arg_defs.len());
+    let mut remaining = args;
+    for (position, ad) in arg_defs.iter().enumerate() {
+        let mut take = |len: usize| {
+            if remaining.len() < len {
+                return Err(FlowEngineError::MissingArgument {
+                    name: ad.name.clone(),
+                    position,
+                });
+            }
+            let (bytes, rest) = remaining.split_at(len);
+            remaining = rest;
+            Ok(bytes)
+        };
+        let invalid = |details: String| FlowEngineError::InvalidArgument {
+            name: ad.name.clone(),
+            details,
+        };
+        let value = match ad.arg_type {
+            ArgType::String => {
+                let mut len = [0u8; 4];
+                len.copy_from_slice(take(4)?);
+                let len = usize::try_from(u32::from_le_bytes(len)).map_err(|err| invalid(err.to_string()))?;
+                let s = String::from_utf8(take(len)?.to_vec()).map_err(|err| invalid(err.to_string()))?;
+                ArgValue::String(s)
+            },
+            ArgType::Byte => ArgValue::Byte(take(1)?[0]),
+            ArgType::PublicKey => {
+                let pk = PublicKey::from_bytes(take(32)?).map_err(|err| invalid(err.to_string()))?;
+                ArgValue::PublicKey(pk)
+            },
+            ArgType::Uint => {
+                let mut fixed = [0u8; 8];
+                fixed.copy_from_slice(take(8)?);
+                ArgValue::Uint(u64::from_le_bytes(fixed))
+            },
+        };
+        decoded.insert(ad.name.clone(), value);
+    }
+    if !remaining.is_empty() {
+        return Err(FlowEngineError::TrailingArgumentBytes {
+            remaining: remaining.len(),
+        });
+    }
+    Ok(decoded)
+}
+
 fn load_workers<TUnitOfWork: StateDbUnitOfWork + 'static>(
     args: HashMap<String, ArgValue>,
     sender: PublicKey,
     state_db: Arc<RwLock<TUnitOfWork>>,
+    events: Arc<RwLock<Vec<FlowEvent>>>,
 ) -> Workers {
     let mut workers = WorkersBuilder::new();
     workers.add(StartWorker {});
@@ -117,11 +172,150 @@ fn load_workers<TUnitOfWork: StateDbUnitOfWork + 'static>(
     workers.add(StoreBucketWorker {
         state_db: state_db.clone(),
     });
-    workers.add(ArgWorker { args: args.clone() });
     workers.add(ArgWorker { args });
     workers.add(SenderWorker { sender });
     workers.add(TextWorker {});
-    workers.add(HasRoleWorker { state_db });
+    workers.add(HasRoleWorker {
+        state_db: state_db.clone(),
+    });
     workers.add(MintBucketWorker {});
+    for op in [
+        ArithmeticOp::Add,
+        ArithmeticOp::Subtract,
+        ArithmeticOp::Multiply,
+        ArithmeticOp::Divide,
+    ] {
+        workers.add(ArithmeticWorker { op });
+    }
+    for op in [
+        CompareOp::Equal,
+        CompareOp::NotEqual,
+        CompareOp::LessThan,
+        CompareOp::LessThanOrEqual,
+        CompareOp::GreaterThan,
+        CompareOp::GreaterThanOrEqual,
+    ] {
+        workers.add(CompareWorker { op });
+    }
+    workers.add(IfWorker {});
+    workers.add(GetStateU64Worker {
+        state_db: state_db.clone(),
+    });
+    workers.add(SetStateU64Worker {
+        state_db: state_db.clone(),
+    });
+    workers.add(GetStateTextWorker {
+        state_db: state_db.clone(),
+    });
+    workers.add(SetStateTextWorker { state_db });
+    workers.add(EmitEventWorker { events });
     workers.build()
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        flow::{
+            encode_args,
+            test_flow::{new_state_db, TestFlow, START},
+        },
+        state::StateDbUnitOfWorkReader,
+    };
+
+    fn arg(name: &str, arg_type: ArgType) -> FunctionArgDefinition {
+        FunctionArgDefinition {
+            name: name.to_string(),
+            arg_type,
+        }
+    }
+
+    #[test]
+    fn it_decodes_args_in_order() {
+        let defs = [
+            arg("label", ArgType::String),
+            arg("flag", ArgType::Byte),
+            arg("amount", ArgType::Uint),
+        ];
+        let bytes = encode_args(&[
+            ArgValue::String("hello".to_string()),
+            ArgValue::Byte(7),
+            ArgValue::Uint(1000),
+        ])
+        .unwrap();
+        let mut expected = 5u32.to_le_bytes().to_vec();
+        expected.extend_from_slice(b"hello");
+        expected.push(7);
+        expected.extend_from_slice(&1000u64.to_le_bytes());
+        assert_eq!(bytes, expected);
+
+        let args = decode_args(&bytes, &defs).unwrap();
+        assert!(matches!(args.get("label"), Some(ArgValue::String(s)) if s == "hello"));
+        assert!(matches!(args.get("flag"), Some(ArgValue::Byte(7))));
+        assert!(matches!(args.get("amount"), Some(ArgValue::Uint(1000))));
+    }
+
+    #[test]
+    fn it_errors_on_missing_args() {
+        let defs = [arg("flag", ArgType::Byte), arg("amount", ArgType::Uint)];
+        let err = decode_args(&[1, 2, 3], &defs).unwrap_err();
+        assert!(matches!(err, FlowEngineError::MissingArgument { position: 1, .. }));
+    }
+
+    #[test]
+    fn it_errors_on_trailing_bytes() {
+        let defs = [arg("flag", ArgType::Byte)];
+        let err = decode_args(&[1, 2], &defs).unwrap_err();
+        assert!(matches!(err, FlowEngineError::TrailingArgumentBytes { remaining: 1 }));
+    }
+
+    #[test]
+    fn it_only_processes_the_branch_selected_by_if() {
+        // amount > 10 ? (status = "large", emit "large") : (status = "small")
+        let flow = TestFlow::new()
+            .arg("amount", ArgType::Uint)
+            .node(2, "tari::arg", json!({"name": "amount"}))
+            .node(3, "core::greater_than", json!({"b": 10}))
+            .node(4, "core::if", json!({}))
+            .node(
+                5,
+                "tari::set_state_text",
+                json!({"schema": "s", "key": "status", "value": "large"}),
+            )
+            .node(6, "tari::emit_event", json!({"topic": "status", "message": "large"}))
+            .node(
+                7,
+                "tari::set_state_text",
+                json!({"schema": "s", "key": "status", "value": "small"}),
+            )
+            .connect(START, "default", 4, "default")
+            .connect(2, "default", 3, "a")
+            .connect(3, "default", 4, "condition")
+            .connect(4, "true", 5, "default")
+            .connect(5, "default", 6, "default")
+            .connect(4, "false", 7, "default");
+
+        let output = flow
+            .run(&encode_args(&[ArgValue::Uint(50)]).unwrap(), new_state_db())
+            .unwrap();
+        assert_eq!(
+            output.state_db.get_value("s", b"status").unwrap(),
+            Some(b"large".to_vec())
+        );
+        assert_eq!(output.events, vec![FlowEvent {
+            topic: "status".to_string(),
+            message: "large".to_string(),
+        }]);
+
+        let output = flow
+            .run(&encode_args(&[ArgValue::Uint(5)]).unwrap(), new_state_db())
+            .unwrap();
+        assert_eq!(
+            output.state_db.get_value("s", b"status").unwrap(),
+            Some(b"small".to_vec())
+        );
+        assert!(output.events.is_empty());
+    }
+}
@@ -0,0 +1,294 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Value as JsValue};
+
+use crate::{
+    flow::{
+        workers::{find_worker_spec, ARG_WORKER, START_WORKER},
+        FlowEngineError,
+    },
+    function_definitions::FlowFunctionDefinition,
+};
+
+/// Checks that the flow of a function is well formed before it is loaded: every node must use a known worker and
+/// provide the fields that worker requires, connections must refer to existing nodes, arguments must be declared by
+/// the function and the flow must have a single start node and no cycles. Returns the id of the start node.
+pub fn validate_flow(func_def: &FlowFunctionDefinition) -> Result<i64, FlowEngineError> {
+    let function = func_def.name.as_str();
+    let invalid = |details: String| FlowEngineError::InvalidFlow {
+        function: function.to_string(),
+        details,
+    };
+
+    let nodes = func_def
+        .flow
+        .get("nodes")
+        .and_then(JsValue::as_object)
+        .ok_or_else(|| invalid("expected a `nodes` object".to_string()))?;
+
+    let mut parsed = HashMap::with_capacity(nodes.len());
+    for (key, node) in nodes {
+        let node = node
+            .as_object()
+            .ok_or_else(|| invalid(format!("node '{}' is not an object", key)))?;
+        let id = node
+            .get("id")
+            .and_then(JsValue::as_i64)
+            .ok_or_else(|| invalid(format!("node '{}' does not have a numeric id", key)))?;
+        if key.parse::<i64>().ok() != Some(id) {
+            return Err(invalid(format!("node '{}' has mismatched id {}", key, id)));
+        }
+        let name = node
+            .get("name")
+            .and_then(JsValue::as_str)
+            .ok_or_else(|| invalid(format!("node {} does not have a worker name", id)))?;
+        parsed.insert(id, (name, node));
+    }
+
+    let start_nodes = parsed
+        .iter()
+        .filter(|(_, (name, _))| *name == START_WORKER)
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+    if start_nodes.len() != 1 {
+        return Err(FlowEngineError::InvalidStartNodeCount {
+            function: function.to_string(),
+            count: start_nodes.len(),
+        });
+    }
+
+    let declared_args = func_def
+        .args
+        .iter()
+        .map(|arg| arg.name.as_str())
+        .collect::<HashSet<_>>();
+    let mut edges = HashMap::with_capacity(parsed.len());
+    for (id, (name, node)) in &parsed {
+        let spec = find_worker_spec(name).ok_or_else(|| FlowEngineError::UnknownWorker {
+            function: function.to_string(),
+            node_id: *id,
+            worker: name.to_string(),
+        })?;
+
+        let inputs = connections(node, "inputs").map_err(|details| invalid(format!("node {}: {}", id, details)))?;
+        let outputs = connections(node, "outputs").map_err(|details| invalid(format!("node {}: {}", id, details)))?;
+        for (_, target) in inputs.iter().chain(outputs.iter()) {
+            if !parsed.contains_key(target) {
+                return Err(FlowEngineError::UnknownConnection {
+                    function: function.to_string(),
+                    node_id: *id,
+                    target: *target,
+                });
+            }
+        }
+
+        let data = node.get("data").and_then(JsValue::as_object);
+        for field in spec.required_fields {
+            let has_data = matches!(data.and_then(|d| d.get(*field)), Some(v) if !v.is_null());
+            let has_input = inputs.iter().any(|(input, _)| input == field);
+            if !has_data && !has_input {
+                return Err(FlowEngineError::MissingField {
+                    function: function.to_string(),
+                    node_id: *id,
+                    worker: name.to_string(),
+                    field: field.to_string(),
+                });
+            }
+        }
+
+        if *name == ARG_WORKER {
+            if let Some(arg) = data.and_then(|d| d.get("name")).and_then(JsValue::as_str) {
+                if !declared_args.contains(arg) {
+                    return Err(FlowEngineError::UnknownArgument {
+                        function: function.to_string(),
+                        node_id: *id,
+                        arg: arg.to_string(),
+                    });
+                }
+            }
+        }
+
+        edges.insert(*id, outputs.into_iter().map(|(_, target)| target).collect::<Vec<_>>());
+    }
+
+    if let Some(node_id) = find_cycle(&edges) {
+        return Err(FlowEngineError::CyclicFlow {
+            function: function.to_string(),
+            node_id,
+        });
+    }
+
+    Ok(start_nodes[0])
+}
+
+/// Returns the (port, connected node id) pairs of the `inputs` or `outputs` of a node
+fn connections(node: &Map<String, JsValue>, direction: &str) -> Result<Vec<(String, i64)>, String> {
+    let ports = match node.get(direction) {
+        Some(JsValue::Object(ports)) => ports,
+        Some(JsValue::Null) | None => return Ok(vec![]),
+        Some(_) => return Err(format!("`{}` is not an object", direction)),
+    };
+    let mut result = vec![];
+    for (port, value) in ports {
+        let conns = value
+            .get("connections")
+            .and_then(JsValue::as_array)
+            .ok_or_else(|| format!("{} '{}' does not have a `connections` array", direction, port))?;
+        for conn in conns {
+            let target = conn
+                .get("node")
+                .and_then(JsValue::as_i64)
+                .ok_or_else(|| format!("{} '{}' has a connection without a node id", direction, port))?;
+            result.push((port.clone(), target));
+        }
+    }
+    Ok(result)
+}
+
+/// Returns a node that is part of a cycle, if the graph has one
+fn find_cycle(edges: &HashMap<i64, Vec<i64>>) -> Option<i64> {
+    let mut finished = HashSet::new();
+    let mut on_path = HashSet::new();
+    for start in edges.keys() {
+        if finished.contains(start) {
+            continue;
+        }
+        // Each stack entry is a node and the index of the next edge to visit
+        let mut stack = vec![(*start, 0)];
+        on_path.insert(*start);
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            match edges.get(&node).and_then(|targets| targets.get(*next)) {
+                Some(target) => {
+                    *next += 1;
+                    if on_path.contains(target) {
+                        return Some(*target);
+                    }
+                    if !finished.contains(target) {
+                        on_path.insert(*target);
+                        stack.push((*target, 0));
+                    }
+                },
+                None => {
+                    stack.pop();
+                    on_path.remove(&node);
+                    finished.insert(node);
+                },
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::function_definitions::{ArgType, FunctionArgDefinition};
+
+    fn function(flow: JsValue) -> FlowFunctionDefinition {
+        FlowFunctionDefinition {
+            name: "transfer".to_string(),
+            args: vec![FunctionArgDefinition {
+                name: "amount".to_string(),
+                arg_type: ArgType::Uint,
+            }],
+            flow,
+        }
+    }
+
+    fn valid_flow() -> JsValue {
+        json!({
+            "id": "tari@0.1.0",
+            "nodes": {
+                "1": {
+                    "id": 1,
+                    "name": "core::start",
+                    "data": {},
+                    "inputs": {},
+                    "outputs": {"default": {"connections": [{"node": 3, "input": "default", "data": {}}]}},
+                    "position": [0, 0]
+                },
+                "2": {
+                    "id": 2,
+                    "name": "tari::arg",
+                    "data": {"name": "amount"},
+                    "inputs": {},
+                    "outputs": {"default": {"connections": [{"node": 3, "input": "a", "data": {}}]}},
+                    "position": [0, 0]
+                },
+                "3": {
+                    "id": 3,
+                    "name": "core::greater_than",
+                    "data": {"b": 10},
+                    "inputs": {
+                        "default": {"connections": [{"node": 1, "output": "default", "data": {}}]},
+                        "a": {"connections": [{"node": 2, "output": "default", "data": {}}]}
+                    },
+                    "outputs": {},
+                    "position": [0, 0]
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn it_accepts_a_valid_flow() {
+        let start = validate_flow(&function(valid_flow())).unwrap();
+        assert_eq!(start, 1);
+    }
+
+    #[test]
+    fn it_rejects_unknown_workers() {
+        let mut flow = valid_flow();
+        flow["nodes"]["3"]["name"] = json!("core::launch_rockets");
+        let err = validate_flow(&function(flow)).unwrap_err();
+        assert!(matches!(err, FlowEngineError::UnknownWorker { node_id: 3, .. }));
+    }
+
+    #[test]
+    fn it_rejects_missing_fields() {
+        let mut flow = valid_flow();
+        flow["nodes"]["3"]["data"] = json!({});
+        let err = validate_flow(&function(flow)).unwrap_err();
+        assert!(matches!(err, FlowEngineError::MissingField { node_id: 3, ref field, .. } if field == "b"));
+    }
+
+    #[test]
+    fn it_rejects_undeclared_args() {
+        let mut flow = valid_flow();
+        flow["nodes"]["2"]["data"]["name"] = json!("price");
+        let err = validate_flow(&function(flow)).unwrap_err();
+        assert!(matches!(err, FlowEngineError::UnknownArgument { ref arg, .. } if arg == "price"));
+    }
+
+    #[test]
+    fn it_rejects_connections_to_missing_nodes() {
+        let mut flow = valid_flow();
+        flow["nodes"]["1"]["outputs"]["default"]["connections"][0]["node"] = json!(42);
+        let err = validate_flow(&function(flow)).unwrap_err();
+        assert!(matches!(err, FlowEngineError::UnknownConnection { target: 42, .. }));
+    }
+
+    #[test]
+    fn it_requires_a_single_start_node() {
+        let mut flow = valid_flow();
+        flow["nodes"]["1"]["name"] = json!("core::text");
+        flow["nodes"]["1"]["data"] = json!({"txt": "hello"});
+        let err = validate_flow(&function(flow)).unwrap_err();
+        assert!(matches!(err, FlowEngineError::InvalidStartNodeCount { count: 0, .. }));
+    }
+
+    #[test]
+    fn it_rejects_cycles() {
+        let mut flow = valid_flow();
+        flow["nodes"]["3"]["outputs"] =
+            json!({"default": {"connections": [{"node": 1, "input": "default", "data": {}}]}});
+        let err = validate_flow(&function(flow)).unwrap_err();
+        assert!(matches!(err, FlowEngineError::CyclicFlow { .. }));
+    }
+}
@@ -4,14 +4,18 @@
 pub mod error;
 mod flow_factory;
 mod flow_instance;
+mod flow_validation;
+#[cfg(test)]
+mod test_flow;
 pub mod workers;
 
-use std::any::Any;
+use std::{any::Any, convert::TryFrom};
 
 pub use error::FlowEngineError;
 pub use flow_factory::FlowFactory;
-pub use flow_instance::FlowInstance;
+pub use flow_instance::{FlowInstance, FlowOutput};
 use tari_common_types::types::PublicKey;
+use tari_utilities::ByteArray;
 
 #[derive(Clone, Debug)]
 pub enum ArgValue {
@@ -22,6 +26,24 @@ pub enum ArgValue {
 }
 
 impl ArgValue {
+    /// Appends the encoding of the value to `buf`, in the format that [`FlowInstance::process`] decodes
+    fn encode_into(&self, position: usize, buf: &mut Vec<u8>) -> Result<(), FlowEngineError> {
+        match self {
+            ArgValue::String(s) => {
+                let len = u32::try_from(s.len()).map_err(|_| FlowEngineError::InvalidArgument {
+                    name: format!("#{}", position),
+                    details: format!("String of {} bytes is too long", s.len()),
+                })?;
+                buf.extend_from_slice(&len.to_le_bytes());
+                buf.extend_from_slice(s.as_bytes());
+            },
+            ArgValue::Byte(b) => buf.push(*b),
+            ArgValue::PublicKey(k) => buf.extend_from_slice(k.as_bytes()),
+            ArgValue::Uint(u) => buf.extend_from_slice(&u.to_le_bytes()),
+        }
+        Ok(())
+    }
+
     pub fn into_any(self) -> Box<dyn Any> {
         match self {
             ArgValue::String(s) => Box::new(s),
@@ -31,3 +53,24 @@ impl ArgValue {
         }
     }
 }
+
+/// Encodes the arguments of an instruction that calls a flow function, in the order that the function defines them.
+/// Bytes are a single byte, public keys are 32 bytes, unsigned integers are 8 bytes little-endian and strings are
+/// prefixed with their length as 4 bytes little-endian.
+///
+/// This format replaced one in which the length of each string was a single byte taken from the end of the args.
+/// Args in the old format are not decoded correctly and must be re-encoded with this function.
+pub fn encode_args(args: &[ArgValue]) -> Result<Vec<u8>, FlowEngineError> {
+    let mut buf = Vec::new();
+    for (position, arg) in args.iter().enumerate() {
+        arg.encode_into(position, &mut buf)?;
+    }
+    Ok(buf)
+}
+
+/// An event emitted by a flow using the `tari::emit_event` worker
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlowEvent {
+    pub topic: String,
+    pub message: String,
+}
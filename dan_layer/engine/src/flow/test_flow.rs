@@ -0,0 +1,103 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Builds and runs small flows so that workers can be tested through the flow engine
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map, Value as JsValue};
+use tari_common_types::types::{FixedHash, PublicKey};
+
+use crate::{
+    flow::{FlowEngineError, FlowInstance, FlowOutput},
+    function_definitions::{ArgType, FlowFunctionDefinition, FunctionArgDefinition},
+    state::{mocks::state_db::MockStateDbBackupAdapter, StateDbUnitOfWorkImpl, UnitOfWorkContext},
+};
+
+pub type TestUnitOfWork = StateDbUnitOfWorkImpl<MockStateDbBackupAdapter>;
+
+/// The id of the `core::start` node that every test flow starts with
+pub const START: i64 = 1;
+
+pub fn new_state_db() -> TestUnitOfWork {
+    StateDbUnitOfWorkImpl::new(
+        UnitOfWorkContext::new(1, FixedHash::zero()),
+        MockStateDbBackupAdapter::default(),
+    )
+}
+
+pub struct TestFlow {
+    args: Vec<FunctionArgDefinition>,
+    nodes: BTreeMap<i64, JsValue>,
+}
+
+impl TestFlow {
+    pub fn new() -> Self {
+        Self {
+            args: vec![],
+            nodes: BTreeMap::new(),
+        }
+        .node(START, "core::start", json!({}))
+    }
+
+    pub fn arg(mut self, name: &str, arg_type: ArgType) -> Self {
+        self.args.push(FunctionArgDefinition {
+            name: name.to_string(),
+            arg_type,
+        });
+        self
+    }
+
+    pub fn node(mut self, id: i64, worker: &str, data: JsValue) -> Self {
+        self.nodes.insert(
+            id,
+            json!({
+                "id": id,
+                "name": worker,
+                "data": data,
+                "inputs": {},
+                "outputs": {},
+                "position": [0, 0]
+            }),
+        );
+        self
+    }
+
+    /// Connects `output` of node `from` to `input` of node `to`
+    pub fn connect(mut self, from: i64, output: &str, to: i64, input: &str) -> Self {
+        push_connection(
+            &mut self.nodes.get_mut(&from).expect("unknown node")["outputs"][output],
+            json!({"node": to, "input": input, "data": {}}),
+        );
+        push_connection(
+            &mut self.nodes.get_mut(&to).expect("unknown node")["inputs"][input],
+            json!({"node": from, "output": output, "data": {}}),
+        );
+        self
+    }
+
+    pub fn build(&self) -> FlowFunctionDefinition {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(id, node)| (id.to_string(), node.clone()))
+            .collect::<Map<_, _>>();
+        FlowFunctionDefinition {
+            name: "test".to_string(),
+            args: self.args.clone(),
+            flow: json!({"id": "tari@0.1.0", "nodes": nodes}),
+        }
+    }
+
+    pub fn run(&self, args: &[u8], state_db: TestUnitOfWork) -> Result<FlowOutput<TestUnitOfWork>, FlowEngineError> {
+        let func_def = self.build();
+        FlowInstance::try_build(&func_def)?.process(args, &func_def.args, PublicKey::default(), state_db)
+    }
+}
+
+fn push_connection(slot: &mut JsValue, connection: JsValue) {
+    match slot["connections"].as_array_mut() {
+        Some(connections) => connections.push(connection),
+        None => slot["connections"] = json!([connection]),
+    }
+}
@@ -1,8 +1,9 @@
 // Copyright 2022 The Tari Project
 // SPDX-License-Identifier: BSD-3-Clause
 
-use std::collections::HashMap;
+use std::{collections::HashMap, convert::TryFrom};
 
+use anyhow::anyhow;
 use d3ne::{InputData, Node, OutputData, OutputDataBuilder, Worker};
 use tari_utilities::hex::Hex;
 
@@ -42,11 +43,16 @@ impl Worker for ArgWorker {
 
     fn work(&self, node: &Node, input_data: InputData) -> anyhow::Result<OutputData> {
         let name = node.get_string_field("name", &input_data)?;
-        let value = self.args.get(&name).cloned().expect("could not find arg");
+        let value = self
+            .args
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Argument '{}' was not provided", name))?;
         let output = match value {
-            ArgValue::Uint(x) => OutputDataBuilder::new().data("default", Box::new(x as i64)),
+            ArgValue::String(s) => OutputDataBuilder::new().data("default", Box::new(s)),
+            ArgValue::Byte(b) => OutputDataBuilder::new().data("default", Box::new(i64::from(b))),
             ArgValue::PublicKey(pk) => OutputDataBuilder::new().data("default", Box::new(pk.to_hex())),
-            _ => todo!(),
+            ArgValue::Uint(x) => OutputDataBuilder::new().data("default", Box::new(i64::try_from(x)?)),
         };
         Ok(output.build())
     }
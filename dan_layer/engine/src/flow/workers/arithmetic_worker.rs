@@ -0,0 +1,98 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use anyhow::anyhow;
+use d3ne::{InputData, Node, OutputData, OutputDataBuilder, Worker};
+
+#[derive(Clone, Copy, Debug)]
+pub enum ArithmeticOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl ArithmeticOp {
+    fn worker_name(self) -> &'static str {
+        match self {
+            ArithmeticOp::Add => "core::add",
+            ArithmeticOp::Subtract => "core::subtract",
+            ArithmeticOp::Multiply => "core::multiply",
+            ArithmeticOp::Divide => "core::divide",
+        }
+    }
+
+    fn apply(self, a: i64, b: i64) -> Option<i64> {
+        match self {
+            ArithmeticOp::Add => a.checked_add(b),
+            ArithmeticOp::Subtract => a.checked_sub(b),
+            ArithmeticOp::Multiply => a.checked_mul(b),
+            ArithmeticOp::Divide => a.checked_div(b),
+        }
+    }
+}
+
+/// Applies an arithmetic operation to the numbers `a` and `b`, failing the flow on overflow or division by zero
+pub struct ArithmeticWorker {
+    pub op: ArithmeticOp,
+}
+
+impl Worker for ArithmeticWorker {
+    fn name(&self) -> &str {
+        self.op.worker_name()
+    }
+
+    fn work(&self, node: &Node, input_data: InputData) -> anyhow::Result<OutputData> {
+        let a = node.get_number_field("a", &input_data)?;
+        let b = node.get_number_field("b", &input_data)?;
+        let result = self
+            .op
+            .apply(a, b)
+            .ok_or_else(|| anyhow!("{} of {} and {} is out of range", self.name(), a, b))?;
+        Ok(OutputDataBuilder::new().data("default", Box::new(result)).build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        flow::{
+            test_flow::{new_state_db, TestFlow, START},
+            FlowEngineError,
+        },
+        state::StateDbUnitOfWorkReader,
+    };
+
+    /// Stores the result of `worker` applied to `a` and `b` in the state
+    fn apply(worker: &str, a: i64, b: i64) -> Result<Option<u64>, FlowEngineError> {
+        let output = TestFlow::new()
+            .node(2, worker, json!({"a": a, "b": b}))
+            .node(3, "tari::set_state_u64", json!({"schema": "s", "key": "result"}))
+            .connect(START, "default", 3, "default")
+            .connect(2, "default", 3, "value")
+            .run(&[], new_state_db())?;
+        Ok(output.state_db.get_u64("s", b"result").unwrap())
+    }
+
+    #[test]
+    fn it_applies_each_operation() {
+        assert_eq!(apply("core::add", 7, 3).unwrap(), Some(10));
+        assert_eq!(apply("core::subtract", 7, 3).unwrap(), Some(4));
+        assert_eq!(apply("core::multiply", 7, 3).unwrap(), Some(21));
+        assert_eq!(apply("core::divide", 7, 3).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn it_fails_the_flow_on_overflow_and_division_by_zero() {
+        assert!(matches!(
+            apply("core::add", i64::MAX, 1),
+            Err(FlowEngineError::InstructionFailed { .. })
+        ));
+        assert!(matches!(
+            apply("core::divide", 1, 0),
+            Err(FlowEngineError::InstructionFailed { .. })
+        ));
+    }
+}
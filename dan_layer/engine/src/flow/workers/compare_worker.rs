@@ -0,0 +1,95 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use d3ne::{InputData, Node, OutputData, OutputDataBuilder, Worker};
+
+#[derive(Clone, Copy, Debug)]
+pub enum CompareOp {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
+
+impl CompareOp {
+    fn worker_name(self) -> &'static str {
+        match self {
+            CompareOp::Equal => "core::equal",
+            CompareOp::NotEqual => "core::not_equal",
+            CompareOp::LessThan => "core::less_than",
+            CompareOp::LessThanOrEqual => "core::less_than_or_equal",
+            CompareOp::GreaterThan => "core::greater_than",
+            CompareOp::GreaterThanOrEqual => "core::greater_than_or_equal",
+        }
+    }
+
+    fn apply(self, a: i64, b: i64) -> bool {
+        match self {
+            CompareOp::Equal => a == b,
+            CompareOp::NotEqual => a != b,
+            CompareOp::LessThan => a < b,
+            CompareOp::LessThanOrEqual => a <= b,
+            CompareOp::GreaterThan => a > b,
+            CompareOp::GreaterThanOrEqual => a >= b,
+        }
+    }
+}
+
+/// Compares the numbers `a` and `b`. The result is output as a number (1 if true, 0 if false) so that it can be
+/// used as the condition of a `core::if` node or in further arithmetic.
+pub struct CompareWorker {
+    pub op: CompareOp,
+}
+
+impl Worker for CompareWorker {
+    fn name(&self) -> &str {
+        self.op.worker_name()
+    }
+
+    fn work(&self, node: &Node, input_data: InputData) -> anyhow::Result<OutputData> {
+        let a = node.get_number_field("a", &input_data)?;
+        let b = node.get_number_field("b", &input_data)?;
+        let result = i64::from(self.op.apply(a, b));
+        Ok(OutputDataBuilder::new().data("default", Box::new(result)).build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        flow::test_flow::{new_state_db, TestFlow, START},
+        state::StateDbUnitOfWorkReader,
+    };
+
+    fn compare(worker: &str, a: i64, b: i64) -> u64 {
+        let output = TestFlow::new()
+            .node(2, worker, json!({"a": a, "b": b}))
+            .node(3, "tari::set_state_u64", json!({"schema": "s", "key": "result"}))
+            .connect(START, "default", 3, "default")
+            .connect(2, "default", 3, "value")
+            .run(&[], new_state_db())
+            .unwrap();
+        output.state_db.get_u64("s", b"result").unwrap().unwrap()
+    }
+
+    #[test]
+    fn it_outputs_one_if_the_comparison_holds_and_zero_otherwise() {
+        let cases = [
+            ("core::equal", [0, 1, 0]),
+            ("core::not_equal", [1, 0, 1]),
+            ("core::less_than", [1, 0, 0]),
+            ("core::less_than_or_equal", [1, 1, 0]),
+            ("core::greater_than", [0, 0, 1]),
+            ("core::greater_than_or_equal", [0, 1, 1]),
+        ];
+        for (worker, [less, equal, greater]) in cases {
+            assert_eq!(compare(worker, 1, 2), less, "{} with a < b", worker);
+            assert_eq!(compare(worker, 2, 2), equal, "{} with a == b", worker);
+            assert_eq!(compare(worker, 3, 2), greater, "{} with a > b", worker);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::sync::{Arc, RwLock};
+
+use anyhow::anyhow;
+use d3ne::{InputData, Node, OutputData, OutputDataBuilder, Worker};
+
+use crate::flow::FlowEvent;
+
+/// Emits an event with the given `topic` and `message`, returned to the caller once the flow completes
+pub struct EmitEventWorker {
+    pub events: Arc<RwLock<Vec<FlowEvent>>>,
+}
+
+impl Worker for EmitEventWorker {
+    fn name(&self) -> &str {
+        "tari::emit_event"
+    }
+
+    fn work(&self, node: &Node, input_data: InputData) -> anyhow::Result<OutputData> {
+        let topic = node.get_string_field("topic", &input_data)?;
+        let message = node.get_string_field("message", &input_data)?;
+        self.events
+            .write()
+            .map_err(|_| anyhow!("Event lock poisoned"))?
+            .push(FlowEvent { topic, message });
+        Ok(OutputDataBuilder::new().data("default", Box::new(())).build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::flow::{
+        test_flow::{new_state_db, TestFlow, START},
+        FlowEvent,
+    };
+
+    #[test]
+    fn it_records_events_in_the_order_they_are_emitted() {
+        let output = TestFlow::new()
+            .node(2, "tari::emit_event", json!({"topic": "first", "message": "one"}))
+            .node(3, "core::text", json!({"txt": "two"}))
+            .node(4, "tari::emit_event", json!({"topic": "second"}))
+            .connect(START, "default", 2, "default")
+            .connect(2, "default", 4, "default")
+            .connect(3, "default", 4, "message")
+            .run(&[], new_state_db())
+            .unwrap();
+        assert_eq!(output.events, vec![
+            FlowEvent {
+                topic: "first".to_string(),
+                message: "one".to_string(),
+            },
+            FlowEvent {
+                topic: "second".to_string(),
+                message: "two".to_string(),
+            },
+        ]);
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use d3ne::{InputData, Node, OutputData, OutputDataBuilder, Worker};
+
+/// Continues the flow from the `true` output if `condition` is non-zero, otherwise from the `false` output. Only
+/// the nodes connected to the selected output are processed.
+pub struct IfWorker {}
+
+impl Worker for IfWorker {
+    fn name(&self) -> &str {
+        "core::if"
+    }
+
+    fn work(&self, node: &Node, input_data: InputData) -> anyhow::Result<OutputData> {
+        let condition = node.get_number_field("condition", &input_data)?;
+        let branch = if condition == 0 { "false" } else { "true" };
+        Ok(OutputDataBuilder::new().data(branch, Box::new(())).build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::flow::{
+        test_flow::{new_state_db, TestFlow, START},
+        FlowEvent,
+    };
+
+    fn branch_taken(condition: i64) -> Vec<FlowEvent> {
+        TestFlow::new()
+            .node(2, "core::if", json!({ "condition": condition }))
+            .node(3, "tari::emit_event", json!({"topic": "branch", "message": "true"}))
+            .node(4, "tari::emit_event", json!({"topic": "branch", "message": "false"}))
+            .connect(START, "default", 2, "default")
+            .connect(2, "true", 3, "default")
+            .connect(2, "false", 4, "default")
+            .run(&[], new_state_db())
+            .unwrap()
+            .events
+    }
+
+    #[test]
+    fn it_follows_the_branch_selected_by_the_condition() {
+        let event = |message: &str| FlowEvent {
+            topic: "branch".to_string(),
+            message: message.to_string(),
+        };
+        assert_eq!(branch_taken(1), vec![event("true")]);
+        assert_eq!(branch_taken(-5), vec![event("true")]);
+        assert_eq!(branch_taken(0), vec![event("false")]);
+    }
+}
@@ -1,19 +1,142 @@
 // Copyright 2022 The Tari Project
 // SPDX-License-Identifier: BSD-3-Clause
 mod arg_worker;
+mod arithmetic_worker;
+mod compare_worker;
 mod create_bucket_worker;
+mod emit_event_worker;
 mod has_role_worker;
+mod if_worker;
 mod mint_bucket_worker;
 mod sender_worker;
 mod start_worker;
+mod state_workers;
 mod store_bucket_worker;
 mod text_worker;
 
 pub use arg_worker::ArgWorker;
+pub use arithmetic_worker::{ArithmeticOp, ArithmeticWorker};
+pub use compare_worker::{CompareOp, CompareWorker};
 pub use create_bucket_worker::CreateBucketWorker;
+pub use emit_event_worker::EmitEventWorker;
 pub use has_role_worker::HasRoleWorker;
+pub use if_worker::IfWorker;
 pub use mint_bucket_worker::MintBucketWorker;
 pub use sender_worker::SenderWorker;
 pub use start_worker::StartWorker;
+pub use state_workers::{GetStateTextWorker, GetStateU64Worker, SetStateTextWorker, SetStateU64Worker};
 pub use store_bucket_worker::StoreBucketWorker;
 pub use text_worker::TextWorker;
+
+/// The name of a worker that can be used in a flow, and the fields each node using it must provide either as node
+/// data or through an input connection
+pub struct WorkerSpec {
+    pub name: &'static str,
+    pub required_fields: &'static [&'static str],
+}
+
+pub const START_WORKER: &str = "core::start";
+pub const ARG_WORKER: &str = "tari::arg";
+
+pub const WORKER_SPECS: &[WorkerSpec] = &[
+    WorkerSpec {
+        name: START_WORKER,
+        required_fields: &[],
+    },
+    WorkerSpec {
+        name: ARG_WORKER,
+        required_fields: &["name"],
+    },
+    WorkerSpec {
+        name: "core::sender",
+        required_fields: &[],
+    },
+    WorkerSpec {
+        name: "core::text",
+        required_fields: &["txt"],
+    },
+    WorkerSpec {
+        name: "core::add",
+        required_fields: &["a", "b"],
+    },
+    WorkerSpec {
+        name: "core::subtract",
+        required_fields: &["a", "b"],
+    },
+    WorkerSpec {
+        name: "core::multiply",
+        required_fields: &["a", "b"],
+    },
+    WorkerSpec {
+        name: "core::divide",
+        required_fields: &["a", "b"],
+    },
+    WorkerSpec {
+        name: "core::equal",
+        required_fields: &["a", "b"],
+    },
+    WorkerSpec {
+        name: "core::not_equal",
+        required_fields: &["a", "b"],
+    },
+    WorkerSpec {
+        name: "core::less_than",
+        required_fields: &["a", "b"],
+    },
+    WorkerSpec {
+        name: "core::less_than_or_equal",
+        required_fields: &["a", "b"],
+    },
+    WorkerSpec {
+        name: "core::greater_than",
+        required_fields: &["a", "b"],
+    },
+    WorkerSpec {
+        name: "core::greater_than_or_equal",
+        required_fields: &["a", "b"],
+    },
+    WorkerSpec {
+        name: "core::if",
+        required_fields: &["condition"],
+    },
+    WorkerSpec {
+        name: "tari::has_role",
+        required_fields: &["role"],
+    },
+    WorkerSpec {
+        name: "tari::create_bucket",
+        required_fields: &["amount", "vault_id", "token_id", "from"],
+    },
+    WorkerSpec {
+        name: "tari::mint_bucket",
+        required_fields: &["amount", "token_id", "vault_id"],
+    },
+    WorkerSpec {
+        name: "tari::store_bucket",
+        required_fields: &["bucket", "to"],
+    },
+    WorkerSpec {
+        name: "tari::get_state_u64",
+        required_fields: &["schema", "key"],
+    },
+    WorkerSpec {
+        name: "tari::set_state_u64",
+        required_fields: &["schema", "key", "value"],
+    },
+    WorkerSpec {
+        name: "tari::get_state_text",
+        required_fields: &["schema", "key"],
+    },
+    WorkerSpec {
+        name: "tari::set_state_text",
+        required_fields: &["schema", "key", "value"],
+    },
+    WorkerSpec {
+        name: "tari::emit_event",
+        required_fields: &["topic", "message"],
+    },
+];
+
+pub fn find_worker_spec(name: &str) -> Option<&'static WorkerSpec> {
+    WORKER_SPECS.iter().find(|spec| spec.name == name)
+}
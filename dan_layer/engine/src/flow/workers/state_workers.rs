@@ -0,0 +1,151 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{
+    convert::TryFrom,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::anyhow;
+use d3ne::{InputData, Node, OutputData, OutputDataBuilder, Worker};
+
+use crate::state::StateDbUnitOfWork;
+
+/// Reads a number stored under `schema`/`key`, outputting 0 if the key has not been set
+pub struct GetStateU64Worker<TUnitOfWork: StateDbUnitOfWork> {
+    pub state_db: Arc<RwLock<TUnitOfWork>>,
+}
+
+impl<TUnitOfWork: StateDbUnitOfWork> Worker for GetStateU64Worker<TUnitOfWork> {
+    fn name(&self) -> &str {
+        "tari::get_state_u64"
+    }
+
+    fn work(&self, node: &Node, input_data: InputData) -> anyhow::Result<OutputData> {
+        let schema = node.get_string_field("schema", &input_data)?;
+        let key = node.get_string_field("key", &input_data)?;
+        let state = self.state_db.read().map_err(|_| anyhow!("State lock poisoned"))?;
+        let value = state.get_u64(&schema, key.as_bytes())?.unwrap_or(0);
+        let value = i64::try_from(value)?;
+        Ok(OutputDataBuilder::new().data("default", Box::new(value)).build())
+    }
+}
+
+/// Stores the number `value` under `schema`/`key`
+pub struct SetStateU64Worker<TUnitOfWork: StateDbUnitOfWork> {
+    pub state_db: Arc<RwLock<TUnitOfWork>>,
+}
+
+impl<TUnitOfWork: StateDbUnitOfWork> Worker for SetStateU64Worker<TUnitOfWork> {
+    fn name(&self) -> &str {
+        "tari::set_state_u64"
+    }
+
+    fn work(&self, node: &Node, input_data: InputData) -> anyhow::Result<OutputData> {
+        let schema = node.get_string_field("schema", &input_data)?;
+        let key = node.get_string_field("key", &input_data)?;
+        let value = u64::try_from(node.get_number_field("value", &input_data)?)?;
+        let mut state = self.state_db.write().map_err(|_| anyhow!("State lock poisoned"))?;
+        state.set_u64(&schema, key.as_bytes(), value)?;
+        Ok(OutputDataBuilder::new().data("default", Box::new(())).build())
+    }
+}
+
+/// Reads the UTF-8 text stored under `schema`/`key`, outputting an empty string if the key has not been set
+pub struct GetStateTextWorker<TUnitOfWork: StateDbUnitOfWork> {
+    pub state_db: Arc<RwLock<TUnitOfWork>>,
+}
+
+impl<TUnitOfWork: StateDbUnitOfWork> Worker for GetStateTextWorker<TUnitOfWork> {
+    fn name(&self) -> &str {
+        "tari::get_state_text"
+    }
+
+    fn work(&self, node: &Node, input_data: InputData) -> anyhow::Result<OutputData> {
+        let schema = node.get_string_field("schema", &input_data)?;
+        let key = node.get_string_field("key", &input_data)?;
+        let state = self.state_db.read().map_err(|_| anyhow!("State lock poisoned"))?;
+        let value = match state.get_value(&schema, key.as_bytes())? {
+            Some(bytes) => String::from_utf8(bytes)?,
+            None => String::new(),
+        };
+        Ok(OutputDataBuilder::new().data("default", Box::new(value)).build())
+    }
+}
+
+/// Stores the text `value` under `schema`/`key`
+pub struct SetStateTextWorker<TUnitOfWork: StateDbUnitOfWork> {
+    pub state_db: Arc<RwLock<TUnitOfWork>>,
+}
+
+impl<TUnitOfWork: StateDbUnitOfWork> Worker for SetStateTextWorker<TUnitOfWork> {
+    fn name(&self) -> &str {
+        "tari::set_state_text"
+    }
+
+    fn work(&self, node: &Node, input_data: InputData) -> anyhow::Result<OutputData> {
+        let schema = node.get_string_field("schema", &input_data)?;
+        let key = node.get_string_field("key", &input_data)?;
+        let value = node.get_string_field("value", &input_data)?;
+        let mut state = self.state_db.write().map_err(|_| anyhow!("State lock poisoned"))?;
+        state.set_value(schema, key.into_bytes(), value.into_bytes())?;
+        Ok(OutputDataBuilder::new().data("default", Box::new(())).build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        flow::{
+            test_flow::{new_state_db, TestFlow, START},
+            FlowEvent,
+        },
+        state::{StateDbUnitOfWork, StateDbUnitOfWorkReader},
+    };
+
+    #[test]
+    fn it_reads_and_writes_u64_state() {
+        let flow = TestFlow::new()
+            .node(2, "tari::get_state_u64", json!({"schema": "s", "key": "counter"}))
+            .node(3, "core::add", json!({"b": 1}))
+            .node(4, "tari::set_state_u64", json!({"schema": "s", "key": "counter"}))
+            .connect(START, "default", 4, "default")
+            .connect(2, "default", 3, "a")
+            .connect(3, "default", 4, "value");
+
+        // A missing value reads as zero
+        let output = flow.run(&[], new_state_db()).unwrap();
+        assert_eq!(output.state_db.get_u64("s", b"counter").unwrap(), Some(1));
+
+        let output = flow.run(&[], output.state_db).unwrap();
+        assert_eq!(output.state_db.get_u64("s", b"counter").unwrap(), Some(2));
+    }
+
+    #[test]
+    fn it_reads_and_writes_text_state() {
+        let mut state_db = new_state_db();
+        state_db
+            .set_value("s".to_string(), b"greeting".to_vec(), b"hello".to_vec())
+            .unwrap();
+        let output = TestFlow::new()
+            .node(2, "tari::get_state_text", json!({"schema": "s", "key": "greeting"}))
+            .node(3, "tari::emit_event", json!({"topic": "greeting"}))
+            .node(
+                4,
+                "tari::set_state_text",
+                json!({"schema": "s", "key": "reply", "value": "hi"}),
+            )
+            .connect(START, "default", 3, "default")
+            .connect(2, "default", 3, "message")
+            .connect(3, "default", 4, "default")
+            .run(&[], state_db)
+            .unwrap();
+        assert_eq!(output.events, vec![FlowEvent {
+            topic: "greeting".to_string(),
+            message: "hello".to_string(),
+        }]);
+        assert_eq!(output.state_db.get_value("s", b"reply").unwrap(), Some(b"hi".to_vec()));
+    }
+}
@@ -34,4 +34,18 @@ pub enum InstructionError {
     TemplateNameNotFound { name: String },
     #[error(transparent)]
     RuntimeError(#[from] RuntimeError),
+    #[error("Function '{name}' mutates component state and cannot be called read-only")]
+    FunctionNotReadOnly { name: String },
+}
+
+impl InstructionError {
+    /// Returns true if the instruction failed because of the local state storage rather than the template, in which
+    /// case it may succeed on other nodes
+    pub fn is_storage_error(&self) -> bool {
+        match self {
+            InstructionError::RuntimeError(err) |
+            InstructionError::WasmExecutionError(WasmExecutionError::RuntimeError(err)) => err.is_storage_error(),
+            _ => false,
+        }
+    }
 }
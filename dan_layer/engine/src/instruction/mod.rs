@@ -24,15 +24,17 @@ mod builder;
 pub use builder::InstructionBuilder;
 
 mod error;
+pub use error::InstructionError;
 
 mod processor;
 pub use processor::InstructionProcessor;
 
 mod signature;
 pub use signature::InstructionSignature;
+use tari_template_abi::{Decode, Encode};
 use tari_template_lib::models::{ComponentId, PackageId};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Encode, Decode)]
 pub enum Instruction {
     CallFunction {
         package_id: PackageId,
@@ -29,7 +29,7 @@ use crate::{
     packager::Package,
     runtime::{Runtime, RuntimeInterface},
     traits::Invokable,
-    wasm::{ExecutionResult, Process},
+    wasm::{ExecutionResult, LoadedWasmModule, Process},
 };
 
 #[derive(Debug, Clone)]
@@ -49,10 +49,25 @@ where TRuntimeInterface: RuntimeInterface + Clone + 'static
     }
 
     pub fn execute(&self, instruction_set: InstructionSet) -> Result<Vec<ExecutionResult>, InstructionError> {
-        let mut results = Vec::with_capacity(instruction_set.instructions.len());
-
         // TODO: implement engine
         let state = Runtime::new(Arc::new(self.runtime_interface.clone()));
+        self.execute_with_runtime(state, instruction_set)
+    }
+
+    /// Executes the instructions in a read-only runtime. Calls to `&mut self` methods are rejected and any attempt to
+    /// change state from within a template fails, so the result can be served without going through consensus.
+    pub fn execute_read_only(&self, instruction_set: InstructionSet) -> Result<Vec<ExecutionResult>, InstructionError> {
+        let state = Runtime::new_read_only(Arc::new(self.runtime_interface.clone()));
+        self.execute_with_runtime(state, instruction_set)
+    }
+
+    fn execute_with_runtime(
+        &self,
+        state: Runtime,
+        instruction_set: InstructionSet,
+    ) -> Result<Vec<ExecutionResult>, InstructionError> {
+        let mut results = Vec::with_capacity(instruction_set.instructions.len());
+
         for instruction in instruction_set.instructions {
             let result = match instruction {
                 Instruction::CallFunction {
@@ -69,6 +84,7 @@ where TRuntimeInterface: RuntimeInterface + Clone + 'static
                         .package
                         .get_module_by_name(&template)
                         .ok_or(InstructionError::TemplateNameNotFound { name: template })?;
+                    Self::check_read_only_permitted(&state, module, &function)?;
 
                     // TODO: implement intelligent instance caching
                     let process = Process::start(module.clone(), state.clone(), package_id)?;
@@ -89,6 +105,7 @@ where TRuntimeInterface: RuntimeInterface + Clone + 'static
                             name: component.module_name.clone(),
                         }
                     })?;
+                    Self::check_read_only_permitted(&state, module, &method)?;
 
                     let mut final_args = Vec::with_capacity(args.len() + 1);
                     final_args.push(encode(&component).unwrap());
@@ -105,4 +122,20 @@ where TRuntimeInterface: RuntimeInterface + Clone + 'static
 
         Ok(results)
     }
+
+    fn check_read_only_permitted(
+        state: &Runtime,
+        module: &LoadedWasmModule,
+        function: &str,
+    ) -> Result<(), InstructionError> {
+        if !state.is_read_only() {
+            return Ok(());
+        }
+        match module.find_func_by_name(function) {
+            Some(func_def) if func_def.is_mutable() => Err(InstructionError::FunctionNotReadOnly {
+                name: function.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
 }
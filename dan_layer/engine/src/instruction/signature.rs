@@ -34,4 +34,14 @@ impl InstructionSignature {
         let challenge = [0u8; 32];
         Self(Signature::sign(secret_key.clone(), nonce, &challenge).unwrap())
     }
+
+    pub fn signature(&self) -> &Signature {
+        &self.0
+    }
+}
+
+impl From<Signature> for InstructionSignature {
+    fn from(signature: Signature) -> Self {
+        Self(signature)
+    }
 }
@@ -36,4 +36,6 @@ pub enum PackageError {
     HostEnvInitError(#[from] wasmer::HostEnvInitError),
     #[error("Runtime error: {0}")]
     RuntimeError(#[from] wasmer::RuntimeError),
+    #[error("Template ABI version {version} is not supported. Supported versions are {min} to {max}")]
+    UnsupportedAbiVersion { version: u16, min: u16, max: u16 },
 }
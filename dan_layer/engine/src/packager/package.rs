@@ -23,7 +23,6 @@
 use std::collections::HashMap;
 
 use digest::Digest;
-use rand::{rngs::OsRng, RngCore};
 use tari_template_lib::models::PackageId;
 
 use crate::{
@@ -71,7 +70,7 @@ impl PackageBuilder {
 
     pub fn build(&self) -> Result<Package, PackageError> {
         let mut wasm_modules = HashMap::with_capacity(self.wasm_modules.len());
-        let id = new_package_id();
+        let id = new_package_id(&self.wasm_modules);
         for wasm in &self.wasm_modules {
             let loaded = wasm.load_module()?;
             wasm_modules.insert(loaded.template_name().to_string(), loaded);
@@ -81,11 +80,13 @@ impl PackageBuilder {
     }
 }
 
-fn new_package_id() -> PackageId {
-    let v = OsRng.next_u32();
-    let hash: [u8; 32] = crypto::hasher("package")
-          // TODO: Proper package id
-        .chain(&v.to_le_bytes())
-        .finalize().into();
+/// The package id is derived from the module code, so that every validator that loads the same modules agrees on it
+fn new_package_id(wasm_modules: &[WasmModule]) -> PackageId {
+    let hasher = wasm_modules.iter().fold(crypto::hasher("package"), |hasher, wasm| {
+        hasher
+            .chain(&(wasm.code().len() as u64).to_le_bytes())
+            .chain(wasm.code())
+    });
+    let hash: [u8; 32] = hasher.finalize().into();
     hash.into()
 }
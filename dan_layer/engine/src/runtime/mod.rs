@@ -23,7 +23,7 @@
 use std::{
     collections::HashMap,
     fmt::Debug,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 
 use tari_common_types::types::FixedHash;
@@ -34,10 +34,16 @@ use tari_template_lib::{
 
 use crate::{models::Bucket, state_store::StateStoreError};
 
+mod state_store_interface;
+pub use state_store_interface::StateStoreRuntimeInterface;
+
 #[derive(Clone)]
 pub struct Runtime {
     tracker: Arc<RwLock<ChangeTracker>>,
     interface: Arc<dyn RuntimeInterface>,
+    is_read_only: bool,
+    /// A storage error raised by the interface during a host call, which cannot be returned to the template
+    storage_error: Arc<Mutex<Option<RuntimeError>>>,
 }
 
 impl Runtime {
@@ -45,12 +51,47 @@ impl Runtime {
         Self {
             tracker: Arc::new(RwLock::new(ChangeTracker::default())),
             interface: engine,
+            is_read_only: false,
+            storage_error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Creates a runtime in which any host operation that mutates state fails
+    pub fn new_read_only(engine: Arc<dyn RuntimeInterface>) -> Self {
+        Self {
+            is_read_only: true,
+            ..Self::new(engine)
         }
     }
 
     pub fn interface(&self) -> &dyn RuntimeInterface {
         &*self.interface
     }
+
+    pub fn is_read_only(&self) -> bool {
+        self.is_read_only
+    }
+
+    /// Returns an error if the runtime is read-only, otherwise Ok
+    pub fn check_write_permitted(&self, operation: &'static str) -> Result<(), RuntimeError> {
+        if self.is_read_only {
+            return Err(RuntimeError::ReadOnlyViolation { operation });
+        }
+        Ok(())
+    }
+
+    /// Records a storage error that occurred in a host call. The template only sees that the call failed, so the
+    /// error is kept to be returned by the execution instead of the template's failure.
+    pub fn set_storage_error(&self, err: RuntimeError) {
+        let mut storage_error = self.storage_error.lock().unwrap();
+        if storage_error.is_none() {
+            *storage_error = Some(err);
+        }
+    }
+
+    pub fn take_storage_error(&self) -> Option<RuntimeError> {
+        self.storage_error.lock().unwrap().take()
+    }
 }
 
 impl Debug for Runtime {
@@ -58,6 +99,8 @@ impl Debug for Runtime {
         f.debug_struct("Runtime")
             .field("tracker", &self.tracker)
             .field("engine", &"dyn RuntimeEngine")
+            .field("is_read_only", &self.is_read_only)
+            .field("storage_error", &self.storage_error)
             .finish()
     }
 }
@@ -75,11 +118,28 @@ pub enum RuntimeError {
     StateStoreError(#[from] StateStoreError),
     #[error("Component not found with id '{id}'")]
     ComponentNotFound { id: ComponentId },
+    #[error("Operation '{operation}' is not permitted in a read-only runtime")]
+    ReadOnlyViolation { operation: &'static str },
+}
+
+impl RuntimeError {
+    /// Returns true if the error was caused by the local state storage rather than by the template, so executing the
+    /// same instructions on another node may not fail
+    pub fn is_storage_error(&self) -> bool {
+        matches!(self, RuntimeError::StateDbError(_) | RuntimeError::StateStoreError(_))
+    }
 }
 
 pub trait RuntimeInterface: Send + Sync {
     fn emit_log(&self, level: LogLevel, message: &str);
     fn create_component(&self, component: Component) -> Result<ComponentId, RuntimeError>;
     fn get_component(&self, component_id: &ComponentId) -> Result<ComponentInstance, RuntimeError>;
+    /// Returns the components with ids from `start` (inclusive) to `end` (exclusive), in ascending id order. If `end`
+    /// is None, all components from `start` onwards are returned.
+    fn get_components_in_range(
+        &self,
+        start: &ComponentId,
+        end: Option<&ComponentId>,
+    ) -> Result<Vec<ComponentInstance>, RuntimeError>;
     fn set_component_state(&self, component_id: &ComponentId, state: Vec<u8>) -> Result<(), RuntimeError>;
 }
@@ -0,0 +1,144 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::sync::Arc;
+
+use digest::Digest;
+use tari_template_abi::decode;
+use tari_template_lib::{
+    args::LogLevel,
+    models::{Component, ComponentId, ComponentInstance},
+};
+
+use crate::{
+    crypto,
+    runtime::{RuntimeError, RuntimeInterface},
+    state_store::{AtomicDb, StateReader, StateStoreError, StateWriter},
+};
+
+const NEXT_COMPONENT_ID_KEY: &[u8] = b"runtime.next_component_id";
+const COMPONENT_ID_LEN: usize = 32;
+
+/// A runtime interface that keeps components in a state store. The component id counter is kept in the store as
+/// well, so a persistent store can be reopened and used to create further components.
+#[derive(Debug)]
+pub struct StateStoreRuntimeInterface<S> {
+    store: Arc<S>,
+}
+
+impl<S> StateStoreRuntimeInterface<S> {
+    pub fn new(store: S) -> Self {
+        Self { store: Arc::new(store) }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+}
+
+impl<S> Clone for StateStoreRuntimeInterface<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+        }
+    }
+}
+
+fn db_error<E: Into<anyhow::Error>>(err: E) -> RuntimeError {
+    RuntimeError::StateDbError(err.into())
+}
+
+impl<S> RuntimeInterface for StateStoreRuntimeInterface<S>
+where
+    S: for<'a> AtomicDb<'a> + Send + Sync,
+    for<'a> <S as AtomicDb<'a>>::ReadAccess: StateReader,
+    for<'a> <S as AtomicDb<'a>>::WriteAccess: StateWriter,
+    for<'a> <S as AtomicDb<'a>>::Error: Into<anyhow::Error>,
+{
+    fn emit_log(&self, level: LogLevel, message: &str) {
+        let level = match level {
+            LogLevel::Error => log::Level::Error,
+            LogLevel::Warn => log::Level::Warn,
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Debug => log::Level::Debug,
+        };
+        eprintln!("[{:?}] {}", level, message);
+        log::log!(target: "tari::dan::engine::runtime", level, "{}", message);
+    }
+
+    fn create_component(&self, new_component: Component) -> Result<ComponentId, RuntimeError> {
+        let mut tx = self.store.write_access().map_err(db_error)?;
+        let next_id = tx.get_state::<_, u64>(&NEXT_COMPONENT_ID_KEY)?.unwrap_or(0);
+        let component_id: [u8; 32] = crypto::hasher("component")
+            .chain(next_id.to_le_bytes())
+            .finalize()
+            .into();
+
+        let component = ComponentInstance::new(component_id.into(), new_component);
+        tx.set_state(&component_id, component)?;
+        tx.set_state(&NEXT_COMPONENT_ID_KEY, next_id + 1)?;
+        self.store.commit(tx).map_err(db_error)?;
+
+        Ok(component_id.into())
+    }
+
+    fn get_component(&self, component_id: &ComponentId) -> Result<ComponentInstance, RuntimeError> {
+        let component = self
+            .store
+            .read_access()
+            .map_err(db_error)?
+            .get_state(component_id)?
+            .ok_or(RuntimeError::ComponentNotFound { id: *component_id })?;
+        Ok(component)
+    }
+
+    fn get_components_in_range(
+        &self,
+        start: &ComponentId,
+        end: Option<&ComponentId>,
+    ) -> Result<Vec<ComponentInstance>, RuntimeError> {
+        let components = self
+            .store
+            .read_access()
+            .map_err(db_error)?
+            .iter_range(&start[..], end.map(|end| &end[..]))?
+            .into_iter()
+            // Component ids are the only 32 byte keys, the component id counter key is longer
+            .filter(|(key, _)| key.len() == COMPONENT_ID_LEN)
+            .map(|(_, value)| decode(&value))
+            .collect::<Result<_, _>>()
+            .map_err(StateStoreError::from)?;
+        Ok(components)
+    }
+
+    fn set_component_state(&self, component_id: &ComponentId, state: Vec<u8>) -> Result<(), RuntimeError> {
+        let mut tx = self.store.write_access().map_err(db_error)?;
+        let mut component: ComponentInstance = tx
+            .get_state(component_id)?
+            .ok_or(RuntimeError::ComponentNotFound { id: *component_id })?;
+        component.state = state;
+        tx.set_state(component_id, component)?;
+        self.store.commit(tx).map_err(db_error)?;
+
+        Ok(())
+    }
+}
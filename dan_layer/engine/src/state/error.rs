@@ -1,6 +1,6 @@
 // Copyright 2022 The Tari Project
 // SPDX-License-Identifier: BSD-3-Clause
-use std::sync::PoisonError;
+use std::{io, sync::PoisonError};
 
 use tari_mmr::error::MerkleMountainRangeError;
 use thiserror::Error;
@@ -17,10 +17,34 @@ pub enum StateStorageError {
     QueryError { reason: String },
     #[error("Migration error: {reason}")]
     MigrationError { reason: String },
+    #[error("State tree node {hash} is missing")]
+    MissingTreeNode { hash: String },
+    #[error("State at height {height} has been pruned, the earliest readable height is {retained_from_height}")]
+    StatePruned { height: u64, retained_from_height: u64 },
     #[error("General storage error: {details}")]
     General { details: String },
 }
 
+#[derive(Debug, Error)]
+pub enum StateExportError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Storage error: {0}")]
+    StorageError(#[from] StateStorageError),
+    #[error("Not a state export")]
+    InvalidMagic,
+    #[error("Unsupported state export version {0}")]
+    UnsupportedVersion(u8),
+    #[error("State export is for contract {actual} but contract {expected} was expected")]
+    ContractIdMismatch { expected: String, actual: String },
+    #[error("Malformed state export: {details}")]
+    Malformed { details: String },
+    #[error("State export checksum does not match its contents")]
+    ChecksumMismatch,
+    #[error("State root {actual} recomputed from the export does not match the exported state root {expected}")]
+    StateRootMismatch { expected: String, actual: String },
+}
+
 impl<T> From<PoisonError<T>> for StateStorageError {
     fn from(_err: PoisonError<T>) -> Self {
         Self::LockError
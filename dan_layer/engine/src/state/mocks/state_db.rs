@@ -1,70 +1,308 @@
 // Copyright 2022 The Tari Project
 // SPDX-License-Identifier: BSD-3-Clause
 
-use crate::state::{error::StateStorageError, DbKeyValue, DbStateOpLogEntry, StateDbBackendAdapter};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Bound,
+    sync::{Arc, RwLock},
+};
 
+use tari_common_types::types::FixedHash;
+
+use crate::state::{
+    error::StateStorageError,
+    models::StateRoot,
+    sparse_merkle_tree::TreeNode,
+    DbKeyValue,
+    DbStateOpLogEntry,
+    StateDbBackendAdapter,
+};
+
+/// An in-memory state db backend. Writes are applied immediately, so transactions cannot be rolled back.
 #[derive(Debug, Clone, Default)]
-pub struct MockStateDbBackupAdapter;
+pub struct MockStateDbBackupAdapter {
+    db: Arc<RwLock<MemoryStateDb>>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryStateDb {
+    /// Values by schema and key, ordered the same way that the sqlite backend returns them
+    values: BTreeMap<(String, Vec<u8>), Vec<u8>>,
+    op_log: Vec<DbStateOpLogEntry>,
+    tree_nodes: HashMap<FixedHash, TreeNode>,
+    /// The lowest height that state can be read at after pruning
+    retained_from_height: u64,
+}
+
+fn clone_entry(entry: &DbStateOpLogEntry) -> DbStateOpLogEntry {
+    DbStateOpLogEntry {
+        height: entry.height,
+        merkle_root: entry.merkle_root,
+        operation: entry.operation,
+        schema: entry.schema.clone(),
+        key: entry.key.clone(),
+        value: entry.value.clone(),
+    }
+}
 
 impl StateDbBackendAdapter for MockStateDbBackupAdapter {
     type BackendTransaction = ();
     type Error = StateStorageError;
 
     fn create_transaction(&self) -> Result<Self::BackendTransaction, Self::Error> {
-        todo!()
+        Ok(())
     }
 
     fn update_key_value(
         &self,
-        _schema: &str,
-        _key: &[u8],
-        _value: &[u8],
+        schema: &str,
+        key: &[u8],
+        value: &[u8],
         _tx: &Self::BackendTransaction,
     ) -> Result<(), Self::Error> {
-        todo!()
+        self.db
+            .write()?
+            .values
+            .insert((schema.to_string(), key.to_vec()), value.to_vec());
+        Ok(())
     }
 
-    fn get(&self, _schema: &str, _key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
-        todo!()
+    fn delete_key_value(&self, schema: &str, key: &[u8], _tx: &Self::BackendTransaction) -> Result<(), Self::Error> {
+        self.db.write()?.values.remove(&(schema.to_string(), key.to_vec()));
+        Ok(())
     }
 
-    fn find_keys_by_value(&self, _schema: &str, _value: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
-        todo!()
+    fn get(&self, schema: &str, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self.db.read()?.values.get(&(schema.to_string(), key.to_vec())).cloned())
+    }
+
+    fn get_committed_value(
+        &self,
+        schema: &str,
+        key: &[u8],
+        _tx: &Self::BackendTransaction,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.get(schema, key)
+    }
+
+    fn get_value_at(&self, schema: &str, key: &[u8], height: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        let db = self.db.read()?;
+        if height < db.retained_from_height {
+            return Err(StateStorageError::StatePruned {
+                height,
+                retained_from_height: db.retained_from_height,
+            });
+        }
+        Ok(db
+            .op_log
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.height <= height && entry.schema == schema && entry.key == key)
+            .max_by_key(|(index, entry)| (entry.height, *index))
+            .and_then(|(_, entry)| entry.value.clone()))
+    }
+
+    fn find_keys_by_value(&self, schema: &str, value: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(self
+            .db
+            .read()?
+            .values
+            .iter()
+            .filter(|((s, _), v)| s == schema && v.as_slice() == value)
+            .map(|((_, key), _)| key.clone())
+            .collect())
     }
 
     fn commit(&self, _tx: &Self::BackendTransaction) -> Result<(), Self::Error> {
-        todo!()
+        Ok(())
     }
 
     fn get_all_schemas(&self, _tx: &Self::BackendTransaction) -> Result<Vec<String>, Self::Error> {
-        todo!()
+        let mut schemas = self
+            .db
+            .read()?
+            .values
+            .keys()
+            .map(|(schema, _)| schema.clone())
+            .collect::<Vec<_>>();
+        schemas.dedup();
+        Ok(schemas)
     }
 
     fn get_all_values_for_schema(
         &self,
-        _schema: &str,
+        schema: &str,
+        _tx: &Self::BackendTransaction,
+    ) -> Result<Vec<DbKeyValue>, Self::Error> {
+        Ok(self
+            .db
+            .read()?
+            .values
+            .iter()
+            .filter(|((s, _), _)| s == schema)
+            .map(|((schema, key), value)| DbKeyValue {
+                schema: schema.clone(),
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect())
+    }
+
+    fn get_values_in_range(
+        &self,
+        schema: &str,
+        start: &[u8],
+        end: Option<&[u8]>,
         _tx: &Self::BackendTransaction,
     ) -> Result<Vec<DbKeyValue>, Self::Error> {
-        todo!()
+        let start = (schema.to_string(), start.to_vec());
+        let end = match end {
+            Some(end) => Bound::Excluded((schema.to_string(), end.to_vec())),
+            None => Bound::Unbounded,
+        };
+        Ok(self
+            .db
+            .read()?
+            .values
+            .range((Bound::Included(start), end))
+            .take_while(|((s, _), _)| s == schema)
+            .map(|((schema, key), value)| DbKeyValue {
+                schema: schema.clone(),
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect())
     }
 
     fn get_state_op_logs_by_height(
         &self,
-        _height: u64,
+        height: u64,
         _tx: &Self::BackendTransaction,
     ) -> Result<Vec<DbStateOpLogEntry>, Self::Error> {
-        todo!()
+        let mut entries = self
+            .db
+            .read()?
+            .op_log
+            .iter()
+            .filter(|entry| entry.height == height)
+            .map(clone_entry)
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(entries)
     }
 
     fn add_state_oplog_entry(
         &self,
-        _entry: DbStateOpLogEntry,
+        entry: DbStateOpLogEntry,
         _tx: &Self::BackendTransaction,
     ) -> Result<(), Self::Error> {
-        todo!()
+        self.db.write()?.op_log.push(entry);
+        Ok(())
     }
 
     fn clear_all_state(&self, _tx: &Self::BackendTransaction) -> Result<(), Self::Error> {
-        todo!()
+        let mut db = self.db.write()?;
+        db.values.clear();
+        db.op_log.clear();
+        db.tree_nodes.clear();
+        db.retained_from_height = 0;
+        Ok(())
+    }
+
+    fn get_current_height(&self, _tx: &Self::BackendTransaction) -> Result<u64, Self::Error> {
+        Ok(self
+            .db
+            .read()?
+            .op_log
+            .iter()
+            .map(|entry| entry.height)
+            .max()
+            .unwrap_or(0))
+    }
+
+    fn get_current_state_root(&self, _tx: &Self::BackendTransaction) -> Result<StateRoot, Self::Error> {
+        Ok(self
+            .db
+            .read()?
+            .op_log
+            .iter()
+            .rev()
+            .find_map(|entry| entry.merkle_root)
+            .map(StateRoot::new)
+            .unwrap_or_else(StateRoot::initial))
+    }
+
+    fn get_tree_node(&self, hash: &FixedHash, _tx: &Self::BackendTransaction) -> Result<Option<TreeNode>, Self::Error> {
+        Ok(self.db.read()?.tree_nodes.get(hash).cloned())
+    }
+
+    fn insert_tree_node(
+        &self,
+        hash: &FixedHash,
+        node: &TreeNode,
+        _tx: &Self::BackendTransaction,
+    ) -> Result<(), Self::Error> {
+        self.db.write()?.tree_nodes.entry(*hash).or_insert_with(|| node.clone());
+        Ok(())
+    }
+
+    fn get_state_roots_from_height(
+        &self,
+        height: u64,
+        _tx: &Self::BackendTransaction,
+    ) -> Result<Vec<StateRoot>, Self::Error> {
+        let db = self.db.read()?;
+        let mut roots = Vec::<FixedHash>::new();
+        for root in db
+            .op_log
+            .iter()
+            .filter(|entry| entry.height >= height)
+            .filter_map(|entry| entry.merkle_root)
+        {
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+        roots.extend(
+            db.op_log
+                .iter()
+                .rev()
+                .filter(|entry| entry.height < height)
+                .find_map(|entry| entry.merkle_root),
+        );
+        Ok(roots.into_iter().map(StateRoot::new).collect())
+    }
+
+    fn get_tree_node_hashes(&self, _tx: &Self::BackendTransaction) -> Result<Vec<FixedHash>, Self::Error> {
+        Ok(self.db.read()?.tree_nodes.keys().copied().collect())
+    }
+
+    fn delete_tree_nodes(&self, hashes: &[FixedHash], _tx: &Self::BackendTransaction) -> Result<(), Self::Error> {
+        let mut db = self.db.write()?;
+        for hash in hashes {
+            db.tree_nodes.remove(hash);
+        }
+        Ok(())
+    }
+
+    fn delete_state_op_logs_below(&self, height: u64, _tx: &Self::BackendTransaction) -> Result<u64, Self::Error> {
+        let mut db = self.db.write()?;
+        // The latest entry of each key below the height is kept, so that the key can still be read as of the height
+        let mut latest_below = HashMap::new();
+        for (index, entry) in db.op_log.iter().enumerate() {
+            if entry.height < height {
+                latest_below.insert((entry.schema.clone(), entry.key.clone()), index);
+            }
+        }
+        let num_entries = db.op_log.len();
+        let mut index = 0;
+        db.op_log.retain(|entry| {
+            let keep =
+                entry.height >= height || latest_below.get(&(entry.schema.clone(), entry.key.clone())) == Some(&index);
+            index += 1;
+            keep
+        });
+        db.retained_from_height = db.retained_from_height.max(height);
+        Ok((num_entries - db.op_log.len()) as u64)
     }
 }
@@ -10,13 +10,27 @@ pub use db_key_value::DbKeyValue;
 mod state_db;
 pub use state_db::StateDb;
 
+mod state_db_snapshot;
+pub use state_db_snapshot::StateDbSnapshot;
+
 mod state_db_backend_adapter;
 pub use state_db_backend_adapter::StateDbBackendAdapter;
 
+mod state_export;
+pub use state_export::{export_state, import_state, StateExportHeader, StateExportReader, StateExportWriter};
+
+mod state_pruning;
+pub use state_pruning::{prune_state, StatePruningStats};
+
+mod state_tree_rebuild;
+pub use state_tree_rebuild::rebuild_state_tree_if_missing;
+
 mod state_op_log;
 pub use state_op_log::{DbStateOpLogEntry, DbStateOperation};
 
 pub mod models;
 
+pub mod sparse_merkle_tree;
+
 pub mod error;
 pub mod mocks;
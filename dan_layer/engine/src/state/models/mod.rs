@@ -7,8 +7,6 @@ pub use key_value::KeyValue;
 mod schema_state;
 pub use schema_state::SchemaState;
 
-mod state_root;
-pub use state_root::StateRoot;
-
 mod op_log;
 pub use op_log::{StateOpLogEntry, StateOperation};
+pub use tari_dan_common_types::state_proof::{StateProof, StateRoot, StateValueProof};
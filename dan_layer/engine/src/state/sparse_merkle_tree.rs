@@ -0,0 +1,402 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! A sparse Merkle tree over the hashes of (schema, key) pairs.
+//!
+//! Subtrees that contain a single leaf are collapsed into that leaf and empty subtrees hash to zero, so the root is
+//! independent of insertion order and an update only touches the nodes on the path to the updated leaf. Nodes are
+//! content-addressed and never removed, so proofs can be generated against any root that has been committed.
+
+use std::{collections::HashMap, convert::TryFrom};
+
+use tari_common_types::types::FixedHash;
+use tari_dan_common_types::state_proof::{hash_internal, hash_leaf, path_bit};
+pub use tari_dan_common_types::state_proof::{state_tree_key, state_tree_value_hash};
+use tari_utilities::hex::to_hex;
+
+use crate::state::{error::StateStorageError, models::StateProof};
+
+const NODE_TAG_INTERNAL: u8 = 0;
+const NODE_TAG_LEAF: u8 = 1;
+const HASH_SIZE: usize = 32;
+const MAX_DEPTH: usize = HASH_SIZE * 8;
+
+pub trait TreeNodeReader {
+    fn get_tree_node(&self, hash: &FixedHash) -> Result<Option<TreeNode>, StateStorageError>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeNode {
+    Internal { left: FixedHash, right: FixedHash },
+    Leaf { key: FixedHash, value_hash: FixedHash },
+}
+
+impl TreeNode {
+    pub fn hash(&self) -> FixedHash {
+        match self {
+            TreeNode::Internal { left, right } => hash_internal(left, right),
+            TreeNode::Leaf { key, value_hash } => hash_leaf(key, value_hash),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (tag, a, b) = match self {
+            TreeNode::Internal { left, right } => (NODE_TAG_INTERNAL, left, right),
+            TreeNode::Leaf { key, value_hash } => (NODE_TAG_LEAF, key, value_hash),
+        };
+        let mut buf = Vec::with_capacity(1 + 2 * HASH_SIZE);
+        buf.push(tag);
+        buf.extend_from_slice(a.as_slice());
+        buf.extend_from_slice(b.as_slice());
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 1 + 2 * HASH_SIZE {
+            return None;
+        }
+        let a = FixedHash::try_from(&bytes[1..=HASH_SIZE]).ok()?;
+        let b = FixedHash::try_from(&bytes[1 + HASH_SIZE..]).ok()?;
+        match bytes[0] {
+            NODE_TAG_INTERNAL => Some(TreeNode::Internal { left: a, right: b }),
+            NODE_TAG_LEAF => Some(TreeNode::Leaf { key: a, value_hash: b }),
+            _ => None,
+        }
+    }
+}
+
+/// Applies updates on top of a committed root. New nodes are kept in memory until they are taken with
+/// [SparseMerkleTree::into_new_nodes] and persisted.
+pub struct SparseMerkleTree<'a, TReader> {
+    reader: &'a TReader,
+    root: FixedHash,
+    new_nodes: HashMap<FixedHash, TreeNode>,
+}
+
+impl<'a, TReader: TreeNodeReader> SparseMerkleTree<'a, TReader> {
+    pub fn new(reader: &'a TReader, root: FixedHash) -> Self {
+        Self {
+            reader,
+            root,
+            new_nodes: HashMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> FixedHash {
+        self.root
+    }
+
+    /// Sets the value hash of the leaf at `key`, returning the new root
+    pub fn insert(&mut self, key: FixedHash, value_hash: FixedHash) -> Result<FixedHash, StateStorageError> {
+        self.root = self.insert_at(self.root, 0, &key, value_hash)?;
+        Ok(self.root)
+    }
+
+    /// Removes the leaf at `key`, returning the new root. The tree is left in the shape it would have had if the key
+    /// had never been inserted, so the root only depends on the keys that remain. Removing a missing key is a no-op.
+    pub fn remove(&mut self, key: &FixedHash) -> Result<FixedHash, StateStorageError> {
+        self.root = self.remove_at(self.root, 0, key)?;
+        Ok(self.root)
+    }
+
+    /// Returns the nodes created since this tree was loaded, keyed by hash. Nodes that are no longer reachable from
+    /// the current root are included, since they may be part of a root that was committed in between.
+    pub fn into_new_nodes(self) -> HashMap<FixedHash, TreeNode> {
+        self.new_nodes
+    }
+
+    pub fn get_proof(&self, key: &FixedHash) -> Result<StateProof, StateStorageError> {
+        let mut siblings = Vec::new();
+        let mut current = self.root;
+        loop {
+            if current == FixedHash::zero() {
+                return Ok(StateProof::new(siblings, None));
+            }
+            match self.get_node(&current)? {
+                TreeNode::Leaf { key, value_hash } => return Ok(StateProof::new(siblings, Some((key, value_hash)))),
+                TreeNode::Internal { left, right } => {
+                    if path_bit(key, siblings.len()) {
+                        siblings.push(left);
+                        current = right;
+                    } else {
+                        siblings.push(right);
+                        current = left;
+                    }
+                },
+            }
+        }
+    }
+
+    fn insert_at(
+        &mut self,
+        node_hash: FixedHash,
+        depth: usize,
+        key: &FixedHash,
+        value_hash: FixedHash,
+    ) -> Result<FixedHash, StateStorageError> {
+        if node_hash == FixedHash::zero() {
+            return Ok(self.put(TreeNode::Leaf { key: *key, value_hash }));
+        }
+        match self.get_node(&node_hash)? {
+            TreeNode::Leaf { key: existing_key, .. } if existing_key == *key => {
+                Ok(self.put(TreeNode::Leaf { key: *key, value_hash }))
+            },
+            TreeNode::Leaf { key: existing_key, .. } => {
+                let leaf = self.put(TreeNode::Leaf { key: *key, value_hash });
+                self.split(depth, (key, leaf), (&existing_key, node_hash))
+            },
+            TreeNode::Internal { left, right } => {
+                if path_bit(key, depth) {
+                    let right = self.insert_at(right, depth + 1, key, value_hash)?;
+                    Ok(self.put(TreeNode::Internal { left, right }))
+                } else {
+                    let left = self.insert_at(left, depth + 1, key, value_hash)?;
+                    Ok(self.put(TreeNode::Internal { left, right }))
+                }
+            },
+        }
+    }
+
+    fn remove_at(
+        &mut self,
+        node_hash: FixedHash,
+        depth: usize,
+        key: &FixedHash,
+    ) -> Result<FixedHash, StateStorageError> {
+        if node_hash == FixedHash::zero() {
+            return Ok(node_hash);
+        }
+        match self.get_node(&node_hash)? {
+            TreeNode::Leaf { key: existing_key, .. } if existing_key == *key => Ok(FixedHash::zero()),
+            TreeNode::Leaf { .. } => Ok(node_hash),
+            TreeNode::Internal { left, right } => {
+                let (new_left, new_right) = if path_bit(key, depth) {
+                    (left, self.remove_at(right, depth + 1, key)?)
+                } else {
+                    (self.remove_at(left, depth + 1, key)?, right)
+                };
+                if new_left == left && new_right == right {
+                    return Ok(node_hash);
+                }
+                // A subtree that is left with a single leaf collapses into that leaf
+                let remaining = match (new_left == FixedHash::zero(), new_right == FixedHash::zero()) {
+                    (true, true) => return Ok(FixedHash::zero()),
+                    (true, false) => Some(new_right),
+                    (false, true) => Some(new_left),
+                    (false, false) => None,
+                };
+                if let Some(remaining) = remaining {
+                    if let TreeNode::Leaf { .. } = self.get_node(&remaining)? {
+                        return Ok(remaining);
+                    }
+                }
+                Ok(self.put(TreeNode::Internal {
+                    left: new_left,
+                    right: new_right,
+                }))
+            },
+        }
+    }
+
+    /// Builds the internal nodes that separate two leaves whose paths are equal up to `depth`
+    fn split(
+        &mut self,
+        depth: usize,
+        (key_a, hash_a): (&FixedHash, FixedHash),
+        (key_b, hash_b): (&FixedHash, FixedHash),
+    ) -> Result<FixedHash, StateStorageError> {
+        if depth >= MAX_DEPTH {
+            return Err(StateStorageError::General {
+                details: "State tree keys collide".to_string(),
+            });
+        }
+        let bit_a = path_bit(key_a, depth);
+        let node = if bit_a == path_bit(key_b, depth) {
+            let child = self.split(depth + 1, (key_a, hash_a), (key_b, hash_b))?;
+            if bit_a {
+                TreeNode::Internal {
+                    left: FixedHash::zero(),
+                    right: child,
+                }
+            } else {
+                TreeNode::Internal {
+                    left: child,
+                    right: FixedHash::zero(),
+                }
+            }
+        } else if bit_a {
+            TreeNode::Internal {
+                left: hash_b,
+                right: hash_a,
+            }
+        } else {
+            TreeNode::Internal {
+                left: hash_a,
+                right: hash_b,
+            }
+        };
+        Ok(self.put(node))
+    }
+
+    fn get_node(&self, hash: &FixedHash) -> Result<TreeNode, StateStorageError> {
+        if let Some(node) = self.new_nodes.get(hash) {
+            return Ok(node.clone());
+        }
+        self.reader
+            .get_tree_node(hash)?
+            .ok_or_else(|| StateStorageError::MissingTreeNode {
+                hash: to_hex(hash.as_slice()),
+            })
+    }
+
+    fn put(&mut self, node: TreeNode) -> FixedHash {
+        let hash = node.hash();
+        self.new_nodes.insert(hash, node);
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::models::StateRoot;
+
+    #[derive(Default)]
+    struct MemoryNodes(HashMap<FixedHash, TreeNode>);
+
+    impl TreeNodeReader for MemoryNodes {
+        fn get_tree_node(&self, hash: &FixedHash) -> Result<Option<TreeNode>, StateStorageError> {
+            Ok(self.0.get(hash).cloned())
+        }
+    }
+
+    fn build(nodes: &mut MemoryNodes, root: FixedHash, items: &[(&str, &[u8], &[u8])]) -> FixedHash {
+        let mut tree = SparseMerkleTree::new(&*nodes, root);
+        for (schema, key, value) in items {
+            tree.insert(state_tree_key(schema, key), state_tree_value_hash(value))
+                .unwrap();
+        }
+        let root = tree.root();
+        let new_nodes = tree.into_new_nodes();
+        nodes.0.extend(new_nodes);
+        root
+    }
+
+    #[test]
+    fn root_is_independent_of_insertion_order() {
+        let items: Vec<(&str, &[u8], &[u8])> = vec![
+            ("a", b"1", b"one"),
+            ("a", b"2", b"two"),
+            ("b", b"1", b"three"),
+            ("c", b"xyz", b"four"),
+        ];
+        let mut nodes = MemoryNodes::default();
+        let root_a = build(&mut nodes, FixedHash::zero(), &items);
+        let reversed = items.iter().rev().cloned().collect::<Vec<_>>();
+        let root_b = build(&mut nodes, FixedHash::zero(), &reversed);
+        assert_eq!(root_a, root_b);
+
+        // Applying the same items on top of the committed root changes nothing
+        let root_c = build(&mut nodes, root_a, &items[1..3]);
+        assert_eq!(root_a, root_c);
+        assert_ne!(root_a, FixedHash::zero());
+    }
+
+    #[test]
+    fn update_changes_root() {
+        let mut nodes = MemoryNodes::default();
+        let root = build(&mut nodes, FixedHash::zero(), &[
+            ("a", b"1", b"one"),
+            ("a", b"2", b"two"),
+        ]);
+        let updated = build(&mut nodes, root, &[("a", b"1", b"uno")]);
+        assert_ne!(root, updated);
+        let expected = build(&mut nodes, FixedHash::zero(), &[
+            ("a", b"2", b"two"),
+            ("a", b"1", b"uno"),
+        ]);
+        assert_eq!(updated, expected);
+    }
+
+    #[test]
+    fn remove_restores_the_root_without_the_key() {
+        let mut nodes = MemoryNodes::default();
+        let items = (0u8..20).map(|i| (vec![i], vec![i; 3])).collect::<Vec<_>>();
+        let refs = items
+            .iter()
+            .map(|(k, v)| ("s", k.as_slice(), v.as_slice()))
+            .collect::<Vec<_>>();
+        let root = build(&mut nodes, FixedHash::zero(), &refs);
+
+        let mut tree = SparseMerkleTree::new(&nodes, root);
+        for (key, _) in items.iter().step_by(2) {
+            tree.remove(&state_tree_key("s", key)).unwrap();
+        }
+        // Removing a key that is not in the tree changes nothing
+        let removed_root = tree.root();
+        assert_eq!(tree.remove(&state_tree_key("s", b"missing")).unwrap(), removed_root);
+        nodes.0.extend(tree.into_new_nodes());
+
+        let remaining = refs.iter().skip(1).step_by(2).cloned().collect::<Vec<_>>();
+        assert_eq!(removed_root, build(&mut nodes, FixedHash::zero(), &remaining));
+
+        let mut tree = SparseMerkleTree::new(&nodes, removed_root);
+        for (key, _) in items.iter().skip(1).step_by(2) {
+            tree.remove(&state_tree_key("s", key)).unwrap();
+        }
+        assert_eq!(tree.root(), FixedHash::zero());
+    }
+
+    #[test]
+    fn node_bytes_round_trip() {
+        let node = TreeNode::Leaf {
+            key: state_tree_key("a", b"1"),
+            value_hash: state_tree_value_hash(b"one"),
+        };
+        assert_eq!(TreeNode::from_bytes(&node.to_bytes()), Some(node));
+        assert_eq!(TreeNode::from_bytes(&[2u8; 65]), None);
+        assert_eq!(TreeNode::from_bytes(&[0u8; 3]), None);
+    }
+
+    #[test]
+    fn inclusion_and_exclusion_proofs() {
+        let mut nodes = MemoryNodes::default();
+        let items = (0u8..20).map(|i| (vec![i], vec![i; 3])).collect::<Vec<_>>();
+        let refs = items
+            .iter()
+            .map(|(k, v)| ("s", k.as_slice(), v.as_slice()))
+            .collect::<Vec<_>>();
+        let root = build(&mut nodes, FixedHash::zero(), &refs);
+        let state_root = StateRoot::new(root);
+        let tree = SparseMerkleTree::new(&nodes, root);
+
+        for (key, value) in &items {
+            let proof = tree.get_proof(&state_tree_key("s", key)).unwrap();
+            assert!(proof.verify_inclusion(&state_root, "s", key, value));
+            assert!(!proof.verify_inclusion(&state_root, "s", key, b"wrong"));
+            assert!(!proof.verify_exclusion(&state_root, "s", key));
+        }
+
+        for key in [b"missing".as_ref(), &[100u8], &[0, 0]] {
+            let proof = tree.get_proof(&state_tree_key("s", key)).unwrap();
+            assert!(proof.verify_exclusion(&state_root, "s", key));
+            assert!(!proof.verify_inclusion(&state_root, "s", key, b""));
+        }
+        // Same key in another schema is excluded
+        let proof = tree.get_proof(&state_tree_key("t", &[1])).unwrap();
+        assert!(proof.verify_exclusion(&state_root, "t", &[1]));
+
+        // Proofs do not verify against a different root
+        let proof = tree.get_proof(&state_tree_key("s", &[1])).unwrap();
+        let other_root = StateRoot::new(build(&mut nodes, root, &[("s", b"new", b"value")]));
+        assert!(!proof.verify_inclusion(&other_root, "s", &[1], &[1, 1, 1]));
+    }
+
+    #[test]
+    fn empty_tree_excludes_everything() {
+        let nodes = MemoryNodes::default();
+        let tree = SparseMerkleTree::new(&nodes, FixedHash::zero());
+        let proof = tree.get_proof(&state_tree_key("a", b"1")).unwrap();
+        assert!(proof.verify_exclusion(&StateRoot::initial(), "a", b"1"));
+    }
+}
@@ -1,11 +1,18 @@
 // Copyright 2022 The Tari Project
 // SPDX-License-Identifier: BSD-3-Clause
 
+use std::io::{Read, Write};
+
 use tari_common_types::types::FixedHash;
 
 use crate::state::{
+    error::{StateExportError, StateStorageError},
     state_db_unit_of_work::{StateDbUnitOfWorkImpl, StateDbUnitOfWorkReader, UnitOfWorkContext},
+    state_export::{export_state, import_state, StateExportHeader},
+    state_pruning::{prune_state, StatePruningStats},
+    state_tree_rebuild::rebuild_state_tree_if_missing,
     StateDbBackendAdapter,
+    StateDbSnapshot,
 };
 
 pub struct StateDb<TStateDbBackendAdapter> {
@@ -36,4 +43,41 @@ impl<TStateDbBackendAdapter: StateDbBackendAdapter> StateDb<TStateDbBackendAdapt
             self.backend_adapter.clone(),
         )
     }
+
+    /// Returns a read-only view of the committed state pinned at `height`
+    pub fn snapshot_at(&self, height: u64) -> StateDbSnapshot<TStateDbBackendAdapter> {
+        StateDbSnapshot::new(self.backend_adapter.clone(), height)
+    }
+
+    /// Writes the committed state to `writer` in the state export format
+    pub fn export_state<W: Write>(&self, writer: W) -> Result<StateExportHeader, StateExportError> {
+        export_state(&self.backend_adapter, self.contract_id, writer)
+    }
+
+    /// Replaces the state with a verified state export read from `reader`
+    pub fn import_state<R: Read>(&self, reader: R) -> Result<StateExportHeader, StateExportError> {
+        import_state(&self.backend_adapter, &self.contract_id, reader)
+    }
+
+    /// Returns the height of the most recent commit, or 0 if nothing has been committed
+    pub fn get_current_height(&self) -> Result<u64, StateStorageError> {
+        let tx = self
+            .backend_adapter
+            .create_transaction()
+            .map_err(TStateDbBackendAdapter::Error::into)?;
+        self.backend_adapter
+            .get_current_height(&tx)
+            .map_err(TStateDbBackendAdapter::Error::into)
+    }
+
+    /// Builds the state tree from the committed state if the database has state but no recorded state root, as is the
+    /// case for databases written before the state tree was stored. Returns true if the tree was rebuilt.
+    pub fn rebuild_state_tree_if_missing(&self) -> Result<bool, StateStorageError> {
+        rebuild_state_tree_if_missing(&self.backend_adapter)
+    }
+
+    /// Removes the state history that is not needed to read state as of `retain_from_height` or later
+    pub fn prune(&self, retain_from_height: u64) -> Result<StatePruningStats, StateStorageError> {
+        prune_state(&self.backend_adapter, retain_from_height)
+    }
 }
@@ -1,9 +1,17 @@
 // Copyright 2022 The Tari Project
 // SPDX-License-Identifier: BSD-3-Clause
 
-use crate::state::{db_key_value::DbKeyValue, error::StateStorageError, DbStateOpLogEntry};
+use tari_common_types::types::FixedHash;
 
-pub trait StateDbBackendAdapter: Send + Sync + Clone {
+use crate::state::{
+    db_key_value::DbKeyValue,
+    error::StateStorageError,
+    models::StateRoot,
+    sparse_merkle_tree::TreeNode,
+    DbStateOpLogEntry,
+};
+
+pub trait StateDbBackendAdapter: Send + Sync + Clone + 'static {
     type BackendTransaction;
     type Error: Into<StateStorageError>;
 
@@ -15,7 +23,17 @@ pub trait StateDbBackendAdapter: Send + Sync + Clone {
         value: &[u8],
         tx: &Self::BackendTransaction,
     ) -> Result<(), Self::Error>;
+    fn delete_key_value(&self, schema: &str, key: &[u8], tx: &Self::BackendTransaction) -> Result<(), Self::Error>;
     fn get(&self, schema: &str, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error>;
+    /// Returns the committed value of the key as seen by `tx`
+    fn get_committed_value(
+        &self,
+        schema: &str,
+        key: &[u8],
+        tx: &Self::BackendTransaction,
+    ) -> Result<Option<Vec<u8>>, Self::Error>;
+    /// Returns the value of the key as it was committed at `height`, using the state op log
+    fn get_value_at(&self, schema: &str, key: &[u8], height: u64) -> Result<Option<Vec<u8>>, Self::Error>;
     fn find_keys_by_value(&self, schema: &str, value: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error>;
     fn commit(&self, tx: &Self::BackendTransaction) -> Result<(), Self::Error>;
     fn get_all_schemas(&self, tx: &Self::BackendTransaction) -> Result<Vec<String>, Self::Error>;
@@ -24,6 +42,15 @@ pub trait StateDbBackendAdapter: Send + Sync + Clone {
         schema: &str,
         tx: &Self::BackendTransaction,
     ) -> Result<Vec<DbKeyValue>, Self::Error>;
+    /// Returns the committed values of the schema with keys from `start` (inclusive) to `end` (exclusive), in ascending
+    /// key order. If `end` is None, all keys from `start` onwards are returned.
+    fn get_values_in_range(
+        &self,
+        schema: &str,
+        start: &[u8],
+        end: Option<&[u8]>,
+        tx: &Self::BackendTransaction,
+    ) -> Result<Vec<DbKeyValue>, Self::Error>;
     fn get_state_op_logs_by_height(
         &self,
         height: u64,
@@ -32,4 +59,26 @@ pub trait StateDbBackendAdapter: Send + Sync + Clone {
     fn add_state_oplog_entry(&self, entry: DbStateOpLogEntry, tx: &Self::BackendTransaction)
         -> Result<(), Self::Error>;
     fn clear_all_state(&self, tx: &Self::BackendTransaction) -> Result<(), Self::Error>;
+    /// Returns the height of the most recent commit, or 0 if nothing has been committed
+    fn get_current_height(&self, tx: &Self::BackendTransaction) -> Result<u64, Self::Error>;
+    /// Returns the state root recorded by the most recent commit, or the initial root if nothing has been committed
+    fn get_current_state_root(&self, tx: &Self::BackendTransaction) -> Result<StateRoot, Self::Error>;
+    fn get_tree_node(&self, hash: &FixedHash, tx: &Self::BackendTransaction) -> Result<Option<TreeNode>, Self::Error>;
+    fn insert_tree_node(
+        &self,
+        hash: &FixedHash,
+        node: &TreeNode,
+        tx: &Self::BackendTransaction,
+    ) -> Result<(), Self::Error>;
+    /// Returns the distinct state roots committed at or above `height`, along with the last root committed below it
+    fn get_state_roots_from_height(
+        &self,
+        height: u64,
+        tx: &Self::BackendTransaction,
+    ) -> Result<Vec<StateRoot>, Self::Error>;
+    fn get_tree_node_hashes(&self, tx: &Self::BackendTransaction) -> Result<Vec<FixedHash>, Self::Error>;
+    fn delete_tree_nodes(&self, hashes: &[FixedHash], tx: &Self::BackendTransaction) -> Result<(), Self::Error>;
+    /// Deletes the op log entries below `height`, except for the latest entry of each key, and returns the number of
+    /// entries deleted
+    fn delete_state_op_logs_below(&self, height: u64, tx: &Self::BackendTransaction) -> Result<u64, Self::Error>;
 }
@@ -0,0 +1,42 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use crate::state::{error::StateStorageError, StateDbBackendAdapter};
+
+/// A read-only view of the committed state as it was at a given height
+#[derive(Debug, Clone)]
+pub struct StateDbSnapshot<TBackendAdapter> {
+    backend_adapter: TBackendAdapter,
+    height: u64,
+}
+
+impl<TBackendAdapter: StateDbBackendAdapter> StateDbSnapshot<TBackendAdapter> {
+    pub fn new(backend_adapter: TBackendAdapter, height: u64) -> Self {
+        Self {
+            backend_adapter,
+            height,
+        }
+    }
+
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn get_value(&self, schema: &str, key: &[u8]) -> Result<Option<Vec<u8>>, StateStorageError> {
+        self.backend_adapter
+            .get_value_at(schema, key, self.height)
+            .map_err(TBackendAdapter::Error::into)
+    }
+
+    pub fn get_u64(&self, schema: &str, key: &[u8]) -> Result<Option<u64>, StateStorageError> {
+        let data = self.get_value(schema, key)?;
+        match data {
+            Some(data) => {
+                let mut data2: [u8; 8] = [0; 8];
+                data2.copy_from_slice(&data);
+                Ok(Some(u64::from_le_bytes(data2)))
+            },
+            None => Ok(None),
+        }
+    }
+}
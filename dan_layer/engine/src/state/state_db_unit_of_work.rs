@@ -2,23 +2,19 @@
 // SPDX-License-Identifier: BSD-3-Clause
 
 use std::{
-    convert::TryInto,
-    ops::Deref,
-    sync::{Arc, RwLock, RwLockReadGuard},
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
 };
 
-use digest::Digest;
 use log::*;
 use tari_common_types::types::FixedHash;
-use tari_crypto::hash::blake2::Blake256;
 use tari_dan_common_types::storage::UnitOfWorkTracker;
-use tari_mmr::{MemBackendVec, MerkleMountainRange};
-use tari_utilities::hex::Hex;
 
 use crate::state::{
     db_key_value::DbKeyValue,
     error::StateStorageError,
-    models::{KeyValue, SchemaState, StateOpLogEntry, StateRoot},
+    models::{KeyValue, SchemaState, StateOpLogEntry, StateProof, StateRoot, StateValueProof},
+    sparse_merkle_tree::{state_tree_key, state_tree_value_hash, SparseMerkleTree, TreeNode, TreeNodeReader},
     DbStateOpLogEntry,
     StateDbBackendAdapter,
 };
@@ -28,16 +24,34 @@ const LOG_TARGET: &str = "tari::dan::state_db";
 pub trait StateDbUnitOfWork: StateDbUnitOfWorkReader {
     fn set_value(&mut self, schema: String, key: Vec<u8>, value: Vec<u8>) -> Result<(), StateStorageError>;
     fn set_u64(&mut self, schema: &str, key: &[u8], value: u64) -> Result<(), StateStorageError>;
+    fn delete_value(&mut self, schema: &str, key: &[u8]) -> Result<(), StateStorageError>;
     fn commit(&mut self) -> Result<(), StateStorageError>;
     fn clear_all_state(&self) -> Result<(), StateStorageError>;
 }
 
-pub trait StateDbUnitOfWorkReader: Clone + Send + Sync {
+pub trait StateDbUnitOfWorkReader: Clone + Send + Sync + 'static {
     fn context(&self) -> &UnitOfWorkContext;
     fn get_value(&self, schema: &str, key: &[u8]) -> Result<Option<Vec<u8>>, StateStorageError>;
+    /// Returns the value of the key as it was at `height`. Uncommitted changes are included if `height` is at or above
+    /// the height of this unit of work.
+    fn get_value_at(&self, schema: &str, key: &[u8], height: u64) -> Result<Option<Vec<u8>>, StateStorageError>;
     fn get_u64(&self, schema: &str, key: &[u8]) -> Result<Option<u64>, StateStorageError>;
     fn find_keys_by_value(&self, schema: &str, value: &[u8]) -> Result<Vec<Vec<u8>>, StateStorageError>;
+    /// Returns the values in the schema with keys from `start` (inclusive) to `end` (exclusive), in ascending key
+    /// order. If `end` is None, all keys from `start` onwards are returned. Uncommitted changes are included.
+    fn get_values_in_range(
+        &self,
+        schema: &str,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> Result<Vec<KeyValue>, StateStorageError>;
+    /// Returns the root of the committed state tree with any uncommitted changes applied
     fn calculate_root(&self) -> Result<StateRoot, StateStorageError>;
+    /// Returns an inclusion or exclusion proof for the key against a committed state root
+    fn get_state_proof(&self, root: &StateRoot, schema: &str, key: &[u8]) -> Result<StateProof, StateStorageError>;
+    /// Returns the committed value of the key with a proof against the latest committed state root. The root, value
+    /// and proof are read in a single backend transaction so that they always agree with each other.
+    fn get_committed_value_proof(&self, schema: &str, key: &[u8]) -> Result<StateValueProof, StateStorageError>;
     fn get_all_state(&self) -> Result<Vec<SchemaState>, StateStorageError>;
     fn get_op_logs_for_height(&self, height: u64) -> Result<Vec<StateOpLogEntry>, StateStorageError>;
 }
@@ -90,7 +104,7 @@ impl<TBackendAdapter: StateDbBackendAdapter> StateDbUnitOfWork for StateDbUnitOf
         let mut inner = self.inner.write()?;
         inner
             .updates
-            .push(UnitOfWorkTracker::new(DbKeyValue { schema, key, value }, true));
+            .push(UnitOfWorkTracker::new(PendingUpdate::set(schema, key, value), true));
 
         Ok(())
     }
@@ -99,6 +113,15 @@ impl<TBackendAdapter: StateDbBackendAdapter> StateDbUnitOfWork for StateDbUnitOf
         self.set_value(schema.to_string(), Vec::from(key), Vec::from(value.to_le_bytes()))
     }
 
+    fn delete_value(&mut self, schema: &str, key: &[u8]) -> Result<(), StateStorageError> {
+        let mut inner = self.inner.write()?;
+        inner
+            .updates
+            .push(UnitOfWorkTracker::new(PendingUpdate::delete(schema, key), true));
+
+        Ok(())
+    }
+
     fn commit(&mut self) -> Result<(), StateStorageError> {
         let mut inner = self.inner.write()?;
         if !inner.is_dirty() {
@@ -108,34 +131,59 @@ impl<TBackendAdapter: StateDbBackendAdapter> StateDbUnitOfWork for StateDbUnitOf
             .backend_adapter
             .create_transaction()
             .map_err(TBackendAdapter::Error::into)?;
-        // let mut current_tree = inner
-        //     .backend_adapter
-        //     .get_current_state_tree(&tx)
-        //     .map_err(TBackendAdapter::Error::into)?;
-        debug!(target: LOG_TARGET, "Committing {} state update(s)", inner.updates.len());
+        let current_root = inner
+            .backend_adapter
+            .get_current_state_root(&tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        let tree_nodes = BackendTreeNodes::new(&inner.backend_adapter, &tx);
+        let mut tree = SparseMerkleTree::new(&tree_nodes, current_root.into());
         for item in &inner.updates {
-            let i = item.get();
+            item.get().apply_to_tree(&mut tree)?;
+        }
+        let new_root = tree.root();
+        let new_nodes = tree.into_new_nodes();
+
+        debug!(
+            target: LOG_TARGET,
+            "Committing {} state update(s) ({} new state tree node(s))",
+            inner.updates.len(),
+            new_nodes.len()
+        );
+        for (hash, node) in &new_nodes {
             inner
                 .backend_adapter
-                .update_key_value(&i.schema, &i.key, &i.value, &tx)
+                .insert_tree_node(hash, node, &tx)
                 .map_err(TBackendAdapter::Error::into)?;
-
+        }
+        for item in &inner.updates {
+            let i = item.get();
+            let mut entry = match i.value {
+                Some(ref value) => {
+                    inner
+                        .backend_adapter
+                        .update_key_value(&i.schema, &i.key, value, &tx)
+                        .map_err(TBackendAdapter::Error::into)?;
+                    DbStateOpLogEntry::set_operation(self.context.height, DbKeyValue {
+                        schema: i.schema.clone(),
+                        key: i.key.clone(),
+                        value: value.clone(),
+                    })
+                },
+                None => {
+                    inner
+                        .backend_adapter
+                        .delete_key_value(&i.schema, &i.key, &tx)
+                        .map_err(TBackendAdapter::Error::into)?;
+                    DbStateOpLogEntry::delete_operation(self.context.height, i.schema.clone(), i.key.clone())
+                },
+            };
+            entry.merkle_root = Some(new_root);
             inner
                 .backend_adapter
-                .add_state_oplog_entry(
-                    DbStateOpLogEntry::set_operation(self.context.height, i.deref().clone()),
-                    &tx,
-                )
+                .add_state_oplog_entry(entry, &tx)
                 .map_err(TBackendAdapter::Error::into)?;
-            // let key = format!("{}.{}", &i.schema, bs58::encode(&i.key).into_string());
-            // current_tree.insert(key, i.value.clone());
         }
 
-        // inner
-        //     .backend_adapter
-        //     .set_current_state_tree(current_tree, &tx)
-        //     .map_err(TBackendAdapter::Error::into)?;
-
         inner
             .backend_adapter
             .commit(&tx)
@@ -167,6 +215,10 @@ impl<TBackendAdapter: StateDbBackendAdapter> StateDbUnitOfWorkReader for StateDb
 
     fn get_value(&self, schema: &str, key: &[u8]) -> Result<Option<Vec<u8>>, StateStorageError> {
         let inner = self.inner.read()?;
+        // Uncommitted updates take precedence
+        if let Some(value) = inner.get_pending_value(schema, key) {
+            return Ok(value);
+        }
         // Hit the DB.
         inner
             .backend_adapter
@@ -174,6 +226,19 @@ impl<TBackendAdapter: StateDbBackendAdapter> StateDbUnitOfWorkReader for StateDb
             .map_err(TBackendAdapter::Error::into)
     }
 
+    fn get_value_at(&self, schema: &str, key: &[u8], height: u64) -> Result<Option<Vec<u8>>, StateStorageError> {
+        let inner = self.inner.read()?;
+        if height >= self.context.height {
+            if let Some(value) = inner.get_pending_value(schema, key) {
+                return Ok(value);
+            }
+        }
+        inner
+            .backend_adapter
+            .get_value_at(schema, key, height)
+            .map_err(TBackendAdapter::Error::into)
+    }
+
     fn get_u64(&self, schema: &str, key: &[u8]) -> Result<Option<u64>, StateStorageError> {
         let data = self.get_value(schema, key)?;
         match data {
@@ -194,60 +259,96 @@ impl<TBackendAdapter: StateDbBackendAdapter> StateDbUnitOfWorkReader for StateDb
             .map_err(TBackendAdapter::Error::into)
     }
 
-    // TODO: Needs to keep a merkle proof of the latest state and append all updates onto that to get the merkle root
-    // TODO: This does not include _new_ keys that are to be added in the updates
-    fn calculate_root(&self) -> Result<StateRoot, StateStorageError> {
+    fn get_values_in_range(
+        &self,
+        schema: &str,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> Result<Vec<KeyValue>, StateStorageError> {
+        let in_range = |key: &[u8]| key >= start && end.map_or(true, |end| key < end);
         let inner = self.inner.read()?;
         let tx = inner
             .backend_adapter
             .create_transaction()
             .map_err(TBackendAdapter::Error::into)?;
-        // let root_node : Node<Vec<u8>> = inner.backend_adapter.get_current_state_tree(&tx).into();
+        let mut values = inner
+            .backend_adapter
+            .get_values_in_range(schema, start, end, &tx)
+            .map_err(TBackendAdapter::Error::into)?
+            .into_iter()
+            .map(|kv| (kv.key, Some(kv.value)))
+            .collect::<BTreeMap<_, _>>();
+        for update in &inner.updates {
+            let update = update.get();
+            if update.schema == schema && in_range(&update.key) {
+                values.insert(update.key.clone(), update.value.clone());
+            }
+        }
+        Ok(values
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| KeyValue { key, value }))
+            .collect())
+    }
 
-        // omg it's an MMR of MMRs
-        let mut top_level_mmr = MerkleMountainRange::<Blake256, _>::new(MemBackendVec::new());
-        let schemas = inner
+    fn calculate_root(&self) -> Result<StateRoot, StateStorageError> {
+        let inner = self.inner.read()?;
+        let tx = inner
             .backend_adapter
-            .get_all_schemas(&tx)
+            .create_transaction()
+            .map_err(TBackendAdapter::Error::into)?;
+        let current_root = inner
+            .backend_adapter
+            .get_current_state_root(&tx)
             .map_err(TBackendAdapter::Error::into)?;
+        if !inner.is_dirty() {
+            return Ok(current_root);
+        }
+
+        let tree_nodes = BackendTreeNodes::new(&inner.backend_adapter, &tx);
+        let mut tree = SparseMerkleTree::new(&tree_nodes, current_root.into());
+        for update in &inner.updates {
+            update.get().apply_to_tree(&mut tree)?;
+        }
         debug!(
             target: LOG_TARGET,
-            "calculate_root: {} key value schemas loaded",
-            schemas.len()
+            "calculate_root: applied {} pending update(s) to the committed state root",
+            inner.updates.len()
         );
+        Ok(StateRoot::new(tree.root()))
+    }
 
-        for schema in schemas {
-            let mut mmr = MerkleMountainRange::<Blake256, _>::new(MemBackendVec::new());
-            for key_value in inner
-                .backend_adapter
-                .get_all_values_for_schema(&schema, &tx)
-                .map_err(TBackendAdapter::Error::into)?
-            {
-                debug!(
-                    target: LOG_TARGET,
-                    "schema = {}, key = {}, value = {}",
-                    schema,
-                    key_value.key.to_hex(),
-                    key_value.value.to_hex()
-                );
-                if let Some(updated_value) = find_update(&inner, &schema, &key_value.key) {
-                    let hasher = Blake256::new();
-                    mmr.push(hasher.chain(&key_value.key).chain(updated_value).finalize().to_vec())?;
-                } else {
-                    let hasher = Blake256::new();
-                    mmr.push(hasher.chain(&key_value.key).chain(&key_value.value).finalize().to_vec())?;
-                }
-            }
-            let hasher = Blake256::new();
-            top_level_mmr.push(hasher.chain(schema).chain(mmr.get_merkle_root()?).finalize().to_vec())?;
-        }
+    fn get_state_proof(&self, root: &StateRoot, schema: &str, key: &[u8]) -> Result<StateProof, StateStorageError> {
+        let inner = self.inner.read()?;
+        let tx = inner
+            .backend_adapter
+            .create_transaction()
+            .map_err(TBackendAdapter::Error::into)?;
+        let tree_nodes = BackendTreeNodes::new(&inner.backend_adapter, &tx);
+        let tree = SparseMerkleTree::new(&tree_nodes, (*root).into());
+        tree.get_proof(&state_tree_key(schema, key))
+    }
 
-        Ok(StateRoot::new(
-            top_level_mmr
-                .get_merkle_root()?
-                .try_into()
-                .expect("MMR output incorrect size"),
-        ))
+    fn get_committed_value_proof(&self, schema: &str, key: &[u8]) -> Result<StateValueProof, StateStorageError> {
+        let inner = self.inner.read()?;
+        let tx = inner
+            .backend_adapter
+            .create_transaction()
+            .map_err(TBackendAdapter::Error::into)?;
+        let state_root = inner
+            .backend_adapter
+            .get_current_state_root(&tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        let height = inner
+            .backend_adapter
+            .get_current_height(&tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        let value = inner
+            .backend_adapter
+            .get_committed_value(schema, key, &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+        let tree_nodes = BackendTreeNodes::new(&inner.backend_adapter, &tx);
+        let proof = SparseMerkleTree::new(&tree_nodes, state_root.into()).get_proof(&state_tree_key(schema, key))?;
+        Ok(StateValueProof::new(state_root, height, value, proof))
     }
 
     fn get_all_state(&self) -> Result<Vec<SchemaState>, StateStorageError> {
@@ -270,9 +371,12 @@ impl<TBackendAdapter: StateDbBackendAdapter> StateDbUnitOfWorkReader for StateDb
 
             let key_values = key_values
                 .into_iter()
-                .map(|kv| {
-                    let value = find_update(&inner, &schema, &kv.key).unwrap_or(kv.value);
-                    KeyValue { key: kv.key, value }
+                .filter_map(|kv| match inner.get_pending_value(&schema, &kv.key) {
+                    Some(value) => value.map(|value| KeyValue { key: kv.key, value }),
+                    None => Some(KeyValue {
+                        key: kv.key,
+                        value: kv.value,
+                    }),
                 })
                 .collect();
 
@@ -298,23 +402,66 @@ impl<TBackendAdapter: StateDbBackendAdapter> StateDbUnitOfWorkReader for StateDb
     }
 }
 
-fn find_update<TBackendAdapter: StateDbBackendAdapter>(
-    inner: &RwLockReadGuard<StateDbUnitOfWorkInner<TBackendAdapter>>,
-    schema: &str,
-    key: &[u8],
-) -> Option<Vec<u8>> {
-    for update in &inner.updates {
-        let update = update.get();
-        if update.schema == schema && update.key == key {
-            return Some(update.value.clone());
+/// An uncommitted change to a key. A change without a value deletes the key.
+#[derive(Debug, Clone)]
+struct PendingUpdate {
+    schema: String,
+    key: Vec<u8>,
+    value: Option<Vec<u8>>,
+}
+
+impl PendingUpdate {
+    fn set(schema: String, key: Vec<u8>, value: Vec<u8>) -> Self {
+        Self {
+            schema,
+            key,
+            value: Some(value),
+        }
+    }
+
+    fn delete(schema: &str, key: &[u8]) -> Self {
+        Self {
+            schema: schema.to_string(),
+            key: key.to_vec(),
+            value: None,
+        }
+    }
+
+    fn apply_to_tree<TReader: TreeNodeReader>(
+        &self,
+        tree: &mut SparseMerkleTree<'_, TReader>,
+    ) -> Result<FixedHash, StateStorageError> {
+        let tree_key = state_tree_key(&self.schema, &self.key);
+        match self.value {
+            Some(ref value) => tree.insert(tree_key, state_tree_value_hash(value)),
+            None => tree.remove(&tree_key),
         }
     }
-    None
+}
+
+/// Reads state tree nodes from the backend within a transaction
+pub(crate) struct BackendTreeNodes<'a, TBackendAdapter: StateDbBackendAdapter> {
+    backend_adapter: &'a TBackendAdapter,
+    tx: &'a TBackendAdapter::BackendTransaction,
+}
+
+impl<'a, TBackendAdapter: StateDbBackendAdapter> BackendTreeNodes<'a, TBackendAdapter> {
+    pub fn new(backend_adapter: &'a TBackendAdapter, tx: &'a TBackendAdapter::BackendTransaction) -> Self {
+        Self { backend_adapter, tx }
+    }
+}
+
+impl<'a, TBackendAdapter: StateDbBackendAdapter> TreeNodeReader for BackendTreeNodes<'a, TBackendAdapter> {
+    fn get_tree_node(&self, hash: &FixedHash) -> Result<Option<TreeNode>, StateStorageError> {
+        self.backend_adapter
+            .get_tree_node(hash, self.tx)
+            .map_err(TBackendAdapter::Error::into)
+    }
 }
 
 pub struct StateDbUnitOfWorkInner<TBackendAdapter> {
     backend_adapter: TBackendAdapter,
-    updates: Vec<UnitOfWorkTracker<DbKeyValue>>,
+    updates: Vec<UnitOfWorkTracker<PendingUpdate>>,
 }
 
 impl<TBackendAdapter: StateDbBackendAdapter> StateDbUnitOfWorkInner<TBackendAdapter> {
@@ -328,4 +475,105 @@ impl<TBackendAdapter: StateDbBackendAdapter> StateDbUnitOfWorkInner<TBackendAdap
     pub fn is_dirty(&self) -> bool {
         !self.updates.is_empty()
     }
+
+    /// Returns the value of the latest uncommitted update to the key, if any. `Some(None)` means that the key is
+    /// deleted.
+    fn get_pending_value(&self, schema: &str, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        self.updates
+            .iter()
+            .rev()
+            .map(|update| update.get())
+            .find(|update| update.schema == schema && update.key == key)
+            .map(|update| update.value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::mocks::state_db::MockStateDbBackupAdapter;
+
+    fn new_unit_of_work(
+        height: u64,
+        backend_adapter: MockStateDbBackupAdapter,
+    ) -> StateDbUnitOfWorkImpl<MockStateDbBackupAdapter> {
+        StateDbUnitOfWorkImpl::new(UnitOfWorkContext::new(height, FixedHash::zero()), backend_adapter)
+    }
+
+    #[test]
+    fn delete_value_removes_the_key_from_the_state_and_state_root() {
+        let backend_adapter = MockStateDbBackupAdapter::default();
+        let mut uow = new_unit_of_work(1, backend_adapter.clone());
+        uow.set_value("s".to_string(), b"a".to_vec(), b"1".to_vec()).unwrap();
+        uow.set_value("s".to_string(), b"b".to_vec(), b"2".to_vec()).unwrap();
+        uow.commit().unwrap();
+
+        let mut only_b = new_unit_of_work(1, MockStateDbBackupAdapter::default());
+        only_b.set_value("s".to_string(), b"b".to_vec(), b"2".to_vec()).unwrap();
+        let expected_root = only_b.calculate_root().unwrap();
+
+        let mut uow = new_unit_of_work(2, backend_adapter.clone());
+        uow.delete_value("s", b"a").unwrap();
+        assert_eq!(uow.get_value("s", b"a").unwrap(), None);
+        assert_eq!(uow.calculate_root().unwrap(), expected_root);
+        uow.commit().unwrap();
+
+        let uow = new_unit_of_work(3, backend_adapter);
+        assert_eq!(uow.get_value("s", b"a").unwrap(), None);
+        assert_eq!(uow.get_value("s", b"b").unwrap(), Some(b"2".to_vec()));
+        assert_eq!(uow.calculate_root().unwrap(), expected_root);
+        assert_eq!(uow.get_value_at("s", b"a", 1).unwrap(), Some(b"1".to_vec()));
+        assert_eq!(uow.get_value_at("s", b"a", 2).unwrap(), None);
+        assert!(uow
+            .get_state_proof(&expected_root, "s", b"a")
+            .unwrap()
+            .verify_exclusion(&expected_root, "s", b"a"));
+    }
+
+    #[test]
+    fn get_values_in_range_includes_uncommitted_changes() {
+        let backend_adapter = MockStateDbBackupAdapter::default();
+        let mut uow = new_unit_of_work(1, backend_adapter.clone());
+        for key in [b"a", b"b", b"c", b"d"] {
+            uow.set_value("s".to_string(), key.to_vec(), key.to_vec()).unwrap();
+        }
+        uow.set_value("other".to_string(), b"b".to_vec(), b"x".to_vec())
+            .unwrap();
+        uow.commit().unwrap();
+
+        let mut uow = new_unit_of_work(2, backend_adapter);
+        uow.delete_value("s", b"b").unwrap();
+        uow.set_value("s".to_string(), b"bb".to_vec(), b"bb".to_vec()).unwrap();
+        uow.set_value("s".to_string(), b"c".to_vec(), b"c2".to_vec()).unwrap();
+        let kvs = uow.get_values_in_range("s", b"b", Some(b"d")).unwrap();
+        let kvs = kvs.into_iter().map(|kv| (kv.key, kv.value)).collect::<Vec<_>>();
+        assert_eq!(kvs, vec![
+            (b"bb".to_vec(), b"bb".to_vec()),
+            (b"c".to_vec(), b"c2".to_vec())
+        ]);
+        assert_eq!(uow.get_values_in_range("s", b"c", None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn committed_value_proof_is_pinned_to_the_committed_root() {
+        let backend_adapter = MockStateDbBackupAdapter::default();
+        let mut uow = new_unit_of_work(1, backend_adapter.clone());
+        uow.set_value("s".to_string(), b"a".to_vec(), b"1".to_vec()).unwrap();
+        uow.set_value("s".to_string(), b"b".to_vec(), b"2".to_vec()).unwrap();
+        uow.commit().unwrap();
+        let committed_root = uow.calculate_root().unwrap();
+
+        // Uncommitted changes are not part of the proof
+        let mut uow = new_unit_of_work(2, backend_adapter);
+        uow.set_value("s".to_string(), b"a".to_vec(), b"3".to_vec()).unwrap();
+        let value_proof = uow.get_committed_value_proof("s", b"a").unwrap();
+        assert_eq!(*value_proof.state_root(), committed_root);
+        assert_eq!(value_proof.height(), 1);
+        assert_eq!(value_proof.value(), Some(b"1".as_slice()));
+        assert!(value_proof.verify(&committed_root, "s", b"a"));
+
+        let missing = uow.get_committed_value_proof("s", b"c").unwrap();
+        assert_eq!(missing.value(), None);
+        assert!(missing.verify(&committed_root, "s", b"c"));
+    }
 }
@@ -0,0 +1,471 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! A portable, verifiable format for exporting and importing the state of a contract.
+//!
+//! An export consists of a header (magic bytes, format version, contract ID, height and state root), followed by
+//! chunks of (schema, key, value) records and a trailer. Each chunk starts with the number of records it contains and
+//! the records end with an empty chunk. The trailer is the Blake256 checksum of all preceding bytes. All integers are
+//! little-endian.
+//!
+//! Importing verifies the checksum and that the state root recomputed from the records matches the header before the
+//! imported state replaces the current state.
+
+use std::{
+    convert::TryFrom,
+    io,
+    io::{Read, Write},
+};
+
+use digest::Digest;
+use log::*;
+use tari_common_types::types::FixedHash;
+use tari_crypto::hash::blake2::Blake256;
+use tari_utilities::hex::to_hex;
+
+use crate::state::{
+    error::{StateExportError, StateStorageError},
+    models::StateRoot,
+    state_tree_rebuild::{record_state_root, StateTreeBuilder},
+    DbKeyValue,
+    StateDbBackendAdapter,
+};
+
+const LOG_TARGET: &str = "tari::dan::state_export";
+
+const MAGIC: &[u8; 4] = b"TDSE";
+pub const STATE_EXPORT_VERSION: u8 = 1;
+const RECORDS_PER_CHUNK: u32 = 1000;
+const MAX_FIELD_LEN: u32 = 64 * 1024 * 1024;
+const HASH_SIZE: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateExportHeader {
+    pub contract_id: FixedHash,
+    pub height: u64,
+    pub state_root: StateRoot,
+}
+
+/// Writes the committed state of the backend to `writer`
+pub fn export_state<TBackendAdapter: StateDbBackendAdapter, W: Write>(
+    backend_adapter: &TBackendAdapter,
+    contract_id: FixedHash,
+    writer: W,
+) -> Result<StateExportHeader, StateExportError> {
+    let tx = backend_adapter.create_transaction().map_err(into_storage_error)?;
+    let header = StateExportHeader {
+        contract_id,
+        height: backend_adapter.get_current_height(&tx).map_err(into_storage_error)?,
+        state_root: backend_adapter
+            .get_current_state_root(&tx)
+            .map_err(into_storage_error)?,
+    };
+
+    let mut export = StateExportWriter::new(writer, &header)?;
+    for schema in backend_adapter.get_all_schemas(&tx).map_err(into_storage_error)? {
+        for kv in backend_adapter
+            .get_all_values_for_schema(&schema, &tx)
+            .map_err(into_storage_error)?
+        {
+            export.write_record(&kv.schema, &kv.key, &kv.value)?;
+        }
+    }
+    let mut writer = export.finish()?;
+    writer.flush()?;
+
+    Ok(header)
+}
+
+/// Replaces the state of the backend with the state read from `reader`. The imported state is only committed once the
+/// checksum is verified and the recomputed state root matches the root in the header.
+pub fn import_state<TBackendAdapter: StateDbBackendAdapter, R: Read>(
+    backend_adapter: &TBackendAdapter,
+    contract_id: &FixedHash,
+    reader: R,
+) -> Result<StateExportHeader, StateExportError> {
+    let mut import = StateExportReader::new(reader)?;
+    let header = import.header().clone();
+    if header.contract_id != *contract_id {
+        return Err(StateExportError::ContractIdMismatch {
+            expected: to_hex(contract_id.as_slice()),
+            actual: to_hex(header.contract_id.as_slice()),
+        });
+    }
+
+    let tx = backend_adapter.create_transaction().map_err(into_storage_error)?;
+    backend_adapter.clear_all_state(&tx).map_err(into_storage_error)?;
+
+    // Tree nodes are written to the backend in batches, so memory use does not grow with the size of the state
+    let mut tree = StateTreeBuilder::new(backend_adapter, &tx, StateRoot::initial());
+    let mut num_records = 0usize;
+    while let Some(kv) = import.next_record()? {
+        tree.insert(&kv.schema, &kv.key, &kv.value)?;
+        backend_adapter
+            .update_key_value(&kv.schema, &kv.key, &kv.value, &tx)
+            .map_err(into_storage_error)?;
+        num_records += 1;
+    }
+
+    let state_root = tree.finish()?;
+    if state_root != header.state_root {
+        return Err(StateExportError::StateRootMismatch {
+            expected: to_hex(header.state_root.as_bytes()),
+            actual: to_hex(state_root.as_bytes()),
+        });
+    }
+
+    // The op log records the state root and height of the imported state
+    record_state_root(backend_adapter, &tx, header.height, state_root)?;
+    // There is no history before the imported height, so reads below it report the state as pruned. Nothing is deleted
+    // as every op log entry is at the imported height.
+    backend_adapter
+        .delete_state_op_logs_below(header.height, &tx)
+        .map_err(into_storage_error)?;
+    backend_adapter.commit(&tx).map_err(into_storage_error)?;
+
+    info!(
+        target: LOG_TARGET,
+        "Imported {} state record(s) for contract {} at height {}",
+        num_records,
+        to_hex(header.contract_id.as_slice()),
+        header.height
+    );
+    Ok(header)
+}
+
+fn into_storage_error<E: Into<StateStorageError>>(err: E) -> StateStorageError {
+    err.into()
+}
+
+/// Writes a state export, buffering records into chunks
+pub struct StateExportWriter<W> {
+    writer: HashingWriter<W>,
+    chunk: Vec<u8>,
+    chunk_len: u32,
+}
+
+impl<W: Write> StateExportWriter<W> {
+    pub fn new(writer: W, header: &StateExportHeader) -> Result<Self, StateExportError> {
+        let mut writer = HashingWriter::new(writer);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[STATE_EXPORT_VERSION])?;
+        writer.write_all(header.contract_id.as_slice())?;
+        writer.write_all(&header.height.to_le_bytes())?;
+        writer.write_all(header.state_root.as_bytes())?;
+        Ok(Self {
+            writer,
+            chunk: Vec::new(),
+            chunk_len: 0,
+        })
+    }
+
+    pub fn write_record(&mut self, schema: &str, key: &[u8], value: &[u8]) -> Result<(), StateExportError> {
+        let schema_len = u16::try_from(schema.len()).map_err(|_| StateExportError::Malformed {
+            details: format!("Schema name is {} bytes long", schema.len()),
+        })?;
+        self.chunk.extend_from_slice(&schema_len.to_le_bytes());
+        self.chunk.extend_from_slice(schema.as_bytes());
+        write_field(&mut self.chunk, key)?;
+        write_field(&mut self.chunk, value)?;
+        self.chunk_len += 1;
+        if self.chunk_len == RECORDS_PER_CHUNK {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the final chunk, the end marker and the checksum, and returns the underlying writer
+    pub fn finish(mut self) -> Result<W, StateExportError> {
+        self.flush_chunk()?;
+        self.writer.write_all(&0u32.to_le_bytes())?;
+        let (mut writer, checksum) = self.writer.finalize();
+        writer.write_all(&checksum)?;
+        Ok(writer)
+    }
+
+    fn flush_chunk(&mut self) -> Result<(), StateExportError> {
+        if self.chunk_len == 0 {
+            return Ok(());
+        }
+        self.writer.write_all(&self.chunk_len.to_le_bytes())?;
+        self.writer.write_all(&self.chunk)?;
+        self.chunk.clear();
+        self.chunk_len = 0;
+        Ok(())
+    }
+}
+
+fn write_field(buf: &mut Vec<u8>, field: &[u8]) -> Result<(), StateExportError> {
+    let len = u32::try_from(field.len())
+        .ok()
+        .filter(|len| *len <= MAX_FIELD_LEN)
+        .ok_or_else(|| StateExportError::Malformed {
+            details: format!("Field of {} bytes exceeds the maximum length", field.len()),
+        })?;
+    buf.extend_from_slice(&len.to_le_bytes());
+    buf.extend_from_slice(field);
+    Ok(())
+}
+
+/// Reads the records of a state export. The checksum is verified once the last record has been read.
+pub struct StateExportReader<R> {
+    reader: Option<HashingReader<R>>,
+    header: StateExportHeader,
+    remaining_in_chunk: u32,
+}
+
+impl<R: Read> StateExportReader<R> {
+    pub fn new(reader: R) -> Result<Self, StateExportError> {
+        let mut reader = HashingReader::new(reader);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != *MAGIC {
+            return Err(StateExportError::InvalidMagic);
+        }
+        let [version] = read_array::<_, 1>(&mut reader)?;
+        if version != STATE_EXPORT_VERSION {
+            return Err(StateExportError::UnsupportedVersion(version));
+        }
+        let contract_id = read_hash(&mut reader)?;
+        let height = u64::from_le_bytes(read_array(&mut reader)?);
+        let state_root = StateRoot::new(read_hash(&mut reader)?);
+
+        Ok(Self {
+            reader: Some(reader),
+            header: StateExportHeader {
+                contract_id,
+                height,
+                state_root,
+            },
+            remaining_in_chunk: 0,
+        })
+    }
+
+    pub fn header(&self) -> &StateExportHeader {
+        &self.header
+    }
+
+    /// Returns the next record, or None once all records have been read and the checksum has been verified
+    pub fn next_record(&mut self) -> Result<Option<DbKeyValue>, StateExportError> {
+        let reader = match self.reader.as_mut() {
+            Some(reader) => reader,
+            None => return Ok(None),
+        };
+        if self.remaining_in_chunk == 0 {
+            self.remaining_in_chunk = u32::from_le_bytes(read_array(reader)?);
+            if self.remaining_in_chunk == 0 {
+                self.verify_trailer()?;
+                return Ok(None);
+            }
+        }
+
+        let schema_len = u16::from_le_bytes(read_array(reader)?);
+        let schema =
+            String::from_utf8(read_vec(reader, u32::from(schema_len))?).map_err(|_| StateExportError::Malformed {
+                details: "Schema name is not valid UTF-8".to_string(),
+            })?;
+        let key_len = u32::from_le_bytes(read_array(reader)?);
+        let key = read_vec(reader, key_len)?;
+        let value_len = u32::from_le_bytes(read_array(reader)?);
+        let value = read_vec(reader, value_len)?;
+        self.remaining_in_chunk -= 1;
+
+        Ok(Some(DbKeyValue { schema, key, value }))
+    }
+
+    fn verify_trailer(&mut self) -> Result<(), StateExportError> {
+        let (mut reader, checksum) = self
+            .reader
+            .take()
+            .expect("verify_trailer called after the trailer was read")
+            .finalize();
+        let expected = read_array::<_, HASH_SIZE>(&mut reader)?;
+        if checksum != expected {
+            return Err(StateExportError::ChecksumMismatch);
+        }
+        if reader.read(&mut [0u8; 1])? != 0 {
+            return Err(StateExportError::Malformed {
+                details: "Unexpected data after the checksum".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], StateExportError> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_hash<R: Read>(reader: &mut R) -> Result<FixedHash, StateExportError> {
+    let bytes = read_array::<_, HASH_SIZE>(reader)?;
+    Ok(FixedHash::try_from(&bytes[..]).expect("HASH_SIZE is the size of a FixedHash"))
+}
+
+fn read_vec<R: Read>(reader: &mut R, len: u32) -> Result<Vec<u8>, StateExportError> {
+    if len > MAX_FIELD_LEN {
+        return Err(StateExportError::Malformed {
+            details: format!("Field of {} bytes exceeds the maximum length", len),
+        });
+    }
+    let mut buf = vec![0u8; len as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Blake256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Blake256::new(),
+        }
+    }
+
+    fn finalize(self) -> (W, [u8; HASH_SIZE]) {
+        let mut checksum = [0u8; HASH_SIZE];
+        checksum.copy_from_slice(&self.hasher.finalize());
+        (self.inner, checksum)
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+struct HashingReader<R> {
+    inner: R,
+    hasher: Blake256,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Blake256::new(),
+        }
+    }
+
+    fn finalize(self) -> (R, [u8; HASH_SIZE]) {
+        let mut checksum = [0u8; HASH_SIZE];
+        checksum.copy_from_slice(&self.hasher.finalize());
+        (self.inner, checksum)
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::mocks::state_db::MockStateDbBackupAdapter;
+
+    fn header() -> StateExportHeader {
+        StateExportHeader {
+            contract_id: FixedHash::try_from(&[1u8; 32][..]).unwrap(),
+            height: 123,
+            state_root: StateRoot::new(FixedHash::try_from(&[2u8; 32][..]).unwrap()),
+        }
+    }
+
+    fn write_export(num_records: u32) -> Vec<u8> {
+        let mut export = StateExportWriter::new(Vec::new(), &header()).unwrap();
+        for i in 0..num_records {
+            export
+                .write_record("schema", &i.to_le_bytes(), format!("value {}", i).as_bytes())
+                .unwrap();
+        }
+        export.finish().unwrap()
+    }
+
+    fn read_all(bytes: &[u8]) -> Result<Vec<DbKeyValue>, StateExportError> {
+        let mut reader = StateExportReader::new(bytes)?;
+        let mut records = Vec::new();
+        while let Some(record) = reader.next_record()? {
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    #[test]
+    fn records_round_trip_across_chunks() {
+        let bytes = write_export(RECORDS_PER_CHUNK * 2 + 5);
+        let reader = StateExportReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(*reader.header(), header());
+
+        let records = read_all(&bytes).unwrap();
+        assert_eq!(records.len(), (RECORDS_PER_CHUNK * 2 + 5) as usize);
+        assert_eq!(records[1001].schema, "schema");
+        assert_eq!(records[1001].key, 1001u32.to_le_bytes());
+        assert_eq!(records[1001].value, b"value 1001");
+    }
+
+    #[test]
+    fn empty_export() {
+        let bytes = write_export(0);
+        assert!(read_all(&bytes).unwrap().is_empty());
+    }
+
+    #[test]
+    fn detects_tampering() {
+        let mut bytes = write_export(3);
+        // Change the last byte of the last value
+        let pos = bytes.len() - HASH_SIZE - 5;
+        bytes[pos] ^= 1;
+        assert!(matches!(read_all(&bytes), Err(StateExportError::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn rejects_truncated_and_foreign_data() {
+        let bytes = write_export(3);
+        assert!(matches!(
+            read_all(&bytes[..bytes.len() - 1]),
+            Err(StateExportError::Io(_))
+        ));
+
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(matches!(read_all(&extended), Err(StateExportError::Malformed { .. })));
+
+        assert!(matches!(
+            read_all(b"not a state export"),
+            Err(StateExportError::InvalidMagic)
+        ));
+    }
+
+    #[test]
+    fn import_rejects_a_state_root_mismatch() {
+        // The header claims a state root that the records do not hash to
+        let bytes = write_export(3);
+        let backend_adapter = MockStateDbBackupAdapter::default();
+        let err = import_state(&backend_adapter, &header().contract_id, bytes.as_slice()).unwrap_err();
+        assert!(matches!(err, StateExportError::StateRootMismatch { .. }));
+    }
+
+    #[test]
+    fn import_rejects_an_export_for_another_contract() {
+        let bytes = write_export(3);
+        let backend_adapter = MockStateDbBackupAdapter::default();
+        let err = import_state(&backend_adapter, &FixedHash::zero(), bytes.as_slice()).unwrap_err();
+        assert!(matches!(err, StateExportError::ContractIdMismatch { .. }));
+    }
+}
@@ -28,6 +28,17 @@ impl DbStateOpLogEntry {
             value: Some(key_value.value),
         }
     }
+
+    pub fn delete_operation(height: u64, schema: String, key: Vec<u8>) -> Self {
+        Self {
+            height,
+            merkle_root: None,
+            operation: DbStateOperation::Delete,
+            schema,
+            key,
+            value: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -0,0 +1,85 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::collections::HashSet;
+
+use log::*;
+use tari_common_types::types::FixedHash;
+use tari_utilities::hex::to_hex;
+
+use crate::state::{error::StateStorageError, sparse_merkle_tree::TreeNode, StateDbBackendAdapter};
+
+const LOG_TARGET: &str = "tari::dan::state_pruning";
+const DELETE_BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatePruningStats {
+    pub op_logs_removed: u64,
+    pub tree_nodes_removed: u64,
+}
+
+/// Removes the state history that is not needed to read state, or prove it, as of `retain_from_height` or later.
+///
+/// Op log entries below the height are deleted, except for the latest entry of each key, and state tree nodes that are
+/// not reachable from a retained state root are deleted. Reading state as of an earlier height fails with
+/// [StateStorageError::StatePruned] after pruning.
+pub fn prune_state<TBackendAdapter: StateDbBackendAdapter>(
+    backend_adapter: &TBackendAdapter,
+    retain_from_height: u64,
+) -> Result<StatePruningStats, StateStorageError> {
+    let tx = backend_adapter
+        .create_transaction()
+        .map_err(TBackendAdapter::Error::into)?;
+
+    let mut reachable = HashSet::new();
+    let roots = backend_adapter
+        .get_state_roots_from_height(retain_from_height, &tx)
+        .map_err(TBackendAdapter::Error::into)?;
+    let mut pending = roots.into_iter().map(FixedHash::from).collect::<Vec<_>>();
+    while let Some(hash) = pending.pop() {
+        if hash == FixedHash::zero() || !reachable.insert(hash) {
+            continue;
+        }
+        let node = backend_adapter
+            .get_tree_node(&hash, &tx)
+            .map_err(TBackendAdapter::Error::into)?
+            .ok_or_else(|| StateStorageError::MissingTreeNode {
+                hash: to_hex(hash.as_slice()),
+            })?;
+        if let TreeNode::Internal { left, right } = node {
+            pending.push(left);
+            pending.push(right);
+        }
+    }
+
+    let unreachable = backend_adapter
+        .get_tree_node_hashes(&tx)
+        .map_err(TBackendAdapter::Error::into)?
+        .into_iter()
+        .filter(|hash| !reachable.contains(hash))
+        .collect::<Vec<_>>();
+    for batch in unreachable.chunks(DELETE_BATCH_SIZE) {
+        backend_adapter
+            .delete_tree_nodes(batch, &tx)
+            .map_err(TBackendAdapter::Error::into)?;
+    }
+
+    let op_logs_removed = backend_adapter
+        .delete_state_op_logs_below(retain_from_height, &tx)
+        .map_err(TBackendAdapter::Error::into)?;
+    backend_adapter.commit(&tx).map_err(TBackendAdapter::Error::into)?;
+
+    let stats = StatePruningStats {
+        op_logs_removed,
+        tree_nodes_removed: unreachable.len() as u64,
+    };
+    debug!(
+        target: LOG_TARGET,
+        "Pruned state below height {}: {} op log entries and {} tree nodes removed, {} tree nodes retained",
+        retain_from_height,
+        stats.op_logs_removed,
+        stats.tree_nodes_removed,
+        reachable.len()
+    );
+    Ok(stats)
+}
@@ -0,0 +1,198 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use log::*;
+use tari_common_types::types::FixedHash;
+
+use crate::state::{
+    error::StateStorageError,
+    models::StateRoot,
+    sparse_merkle_tree::{state_tree_key, state_tree_value_hash, SparseMerkleTree},
+    state_db_unit_of_work::BackendTreeNodes,
+    DbStateOpLogEntry,
+    StateDbBackendAdapter,
+};
+
+const LOG_TARGET: &str = "tari::dan::state_tree_rebuild";
+const INSERT_BATCH_SIZE: usize = 1000;
+
+/// Inserts state into the state tree of a backend, writing the new tree nodes to the backend after every batch so that
+/// the nodes of a large state are not all held in memory
+pub(crate) struct StateTreeBuilder<'a, TBackendAdapter: StateDbBackendAdapter> {
+    backend_adapter: &'a TBackendAdapter,
+    tx: &'a TBackendAdapter::BackendTransaction,
+    root: FixedHash,
+    pending: Vec<(FixedHash, FixedHash)>,
+}
+
+impl<'a, TBackendAdapter: StateDbBackendAdapter> StateTreeBuilder<'a, TBackendAdapter> {
+    pub fn new(
+        backend_adapter: &'a TBackendAdapter,
+        tx: &'a TBackendAdapter::BackendTransaction,
+        root: StateRoot,
+    ) -> Self {
+        Self {
+            backend_adapter,
+            tx,
+            root: root.into(),
+            pending: Vec::with_capacity(INSERT_BATCH_SIZE),
+        }
+    }
+
+    pub fn insert(&mut self, schema: &str, key: &[u8], value: &[u8]) -> Result<(), StateStorageError> {
+        self.pending
+            .push((state_tree_key(schema, key), state_tree_value_hash(value)));
+        if self.pending.len() >= INSERT_BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the remaining nodes and returns the root of the tree
+    pub fn finish(mut self) -> Result<StateRoot, StateStorageError> {
+        self.flush()?;
+        Ok(StateRoot::new(self.root))
+    }
+
+    fn flush(&mut self) -> Result<(), StateStorageError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let tree_nodes = BackendTreeNodes::new(self.backend_adapter, self.tx);
+        let mut tree = SparseMerkleTree::new(&tree_nodes, self.root);
+        for (key, value_hash) in self.pending.drain(..) {
+            tree.insert(key, value_hash)?;
+        }
+        self.root = tree.root();
+        for (hash, node) in &tree.into_new_nodes() {
+            self.backend_adapter
+                .insert_tree_node(hash, node, self.tx)
+                .map_err(TBackendAdapter::Error::into)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records `state_root` as the root of the committed state at `height`, by adding an op log entry for every key
+pub(crate) fn record_state_root<TBackendAdapter: StateDbBackendAdapter>(
+    backend_adapter: &TBackendAdapter,
+    tx: &TBackendAdapter::BackendTransaction,
+    height: u64,
+    state_root: StateRoot,
+) -> Result<(), StateStorageError> {
+    for schema in backend_adapter
+        .get_all_schemas(tx)
+        .map_err(TBackendAdapter::Error::into)?
+    {
+        for kv in backend_adapter
+            .get_all_values_for_schema(&schema, tx)
+            .map_err(TBackendAdapter::Error::into)?
+        {
+            let mut entry = DbStateOpLogEntry::set_operation(height, kv);
+            entry.merkle_root = Some(state_root.into());
+            backend_adapter
+                .add_state_oplog_entry(entry, tx)
+                .map_err(TBackendAdapter::Error::into)?;
+        }
+    }
+    Ok(())
+}
+
+/// Builds the state tree from the committed state if no state root has been recorded for it. Databases written before
+/// the state tree was stored have state but no tree nodes, so their root would otherwise leave out all of the existing
+/// state. Returns true if the tree was rebuilt.
+pub fn rebuild_state_tree_if_missing<TBackendAdapter: StateDbBackendAdapter>(
+    backend_adapter: &TBackendAdapter,
+) -> Result<bool, StateStorageError> {
+    let tx = backend_adapter
+        .create_transaction()
+        .map_err(TBackendAdapter::Error::into)?;
+    let current_root = backend_adapter
+        .get_current_state_root(&tx)
+        .map_err(TBackendAdapter::Error::into)?;
+    if current_root != StateRoot::initial() {
+        return Ok(false);
+    }
+    let schemas = backend_adapter
+        .get_all_schemas(&tx)
+        .map_err(TBackendAdapter::Error::into)?;
+    if schemas.is_empty() {
+        return Ok(false);
+    }
+
+    let mut builder = StateTreeBuilder::new(backend_adapter, &tx, current_root);
+    let mut num_records = 0usize;
+    for schema in &schemas {
+        for kv in backend_adapter
+            .get_all_values_for_schema(schema, &tx)
+            .map_err(TBackendAdapter::Error::into)?
+        {
+            builder.insert(&kv.schema, &kv.key, &kv.value)?;
+            num_records += 1;
+        }
+    }
+    let state_root = builder.finish()?;
+    let height = backend_adapter
+        .get_current_height(&tx)
+        .map_err(TBackendAdapter::Error::into)?;
+    record_state_root(backend_adapter, &tx, height, state_root)?;
+    backend_adapter.commit(&tx).map_err(TBackendAdapter::Error::into)?;
+
+    info!(
+        target: LOG_TARGET,
+        "Rebuilt the state tree from {} existing state record(s) at height {}", num_records, height
+    );
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        mocks::state_db::MockStateDbBackupAdapter,
+        DbKeyValue,
+        StateDbUnitOfWork,
+        StateDbUnitOfWorkImpl,
+        StateDbUnitOfWorkReader,
+        UnitOfWorkContext,
+    };
+
+    #[test]
+    fn it_rebuilds_the_tree_of_state_written_without_one() {
+        let expected = {
+            let mut uow = StateDbUnitOfWorkImpl::new(
+                UnitOfWorkContext::new(3, FixedHash::zero()),
+                MockStateDbBackupAdapter::default(),
+            );
+            uow.set_value("s".to_string(), b"a".to_vec(), b"1".to_vec()).unwrap();
+            uow.set_value("s".to_string(), b"b".to_vec(), b"2".to_vec()).unwrap();
+            uow.commit().unwrap();
+            uow.calculate_root().unwrap()
+        };
+
+        // State written before the state tree was stored has op log entries without a merkle root
+        let backend = MockStateDbBackupAdapter::default();
+        let tx = backend.create_transaction().unwrap();
+        for (key, value) in [(b"a", b"1"), (b"b", b"2")] {
+            let kv = DbKeyValue {
+                schema: "s".to_string(),
+                key: key.to_vec(),
+                value: value.to_vec(),
+            };
+            backend.update_key_value(&kv.schema, &kv.key, &kv.value, &tx).unwrap();
+            backend
+                .add_state_oplog_entry(DbStateOpLogEntry::set_operation(3, kv), &tx)
+                .unwrap();
+        }
+        assert_eq!(backend.get_current_state_root(&tx).unwrap(), StateRoot::initial());
+
+        assert!(rebuild_state_tree_if_missing(&backend).unwrap());
+        assert_eq!(backend.get_current_state_root(&tx).unwrap(), expected);
+        let uow = StateDbUnitOfWorkImpl::new(UnitOfWorkContext::new(3, FixedHash::zero()), backend.clone());
+        let proof = uow.get_state_proof(&expected, "s", b"a").unwrap();
+        assert!(proof.verify_inclusion(&expected, "s", b"a", b"1"));
+
+        // A tree is only built once
+        assert!(!rebuild_state_tree_if_missing(&backend).unwrap());
+    }
+}
@@ -20,36 +20,83 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet},
+    ops::{Bound, Deref},
     sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
 
 use anyhow::anyhow;
 
-use crate::state_store::{AtomicDb, StateReader, StateStoreError, StateWriter};
+use crate::state_store::{
+    AtomicDb,
+    StateEntry,
+    StateReader,
+    StateStoreError,
+    StateWriter,
+    VersionedStateReader,
+    VersionedStateWriter,
+};
+
+#[derive(Debug, Default)]
+pub struct MemoryState {
+    values: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// Keys deleted in a pending transaction
+    deleted: BTreeSet<Vec<u8>>,
+    /// The versions of each key by height. A version of None is a tombstone.
+    history: BTreeMap<Vec<u8>, BTreeMap<u64, Option<Vec<u8>>>>,
+}
+
+impl MemoryState {
+    fn get_at(&self, key: &[u8], height: u64) -> Option<(u64, &Option<Vec<u8>>)> {
+        self.history
+            .get(key)
+            .and_then(|versions| versions.range(..=height).next_back())
+            .map(|(height, value)| (*height, value))
+    }
+
+    fn range(&self, start: &[u8], end: Option<&[u8]>) -> impl Iterator<Item = (&Vec<u8>, &Vec<u8>)> {
+        let end = end.map_or(Bound::Unbounded, Bound::Excluded);
+        self.values.range::<[u8], _>((Bound::Included(start), end))
+    }
+
+    fn history_keys(&self, start: &[u8], end: Option<&[u8]>) -> impl Iterator<Item = &Vec<u8>> {
+        let end = end.map_or(Bound::Unbounded, Bound::Excluded);
+        self.history
+            .range::<[u8], _>((Bound::Included(start), end))
+            .map(|(k, _)| k)
+    }
 
-type InnerKvMap = HashMap<Vec<u8>, Vec<u8>>;
+    fn extend(&mut self, other: MemoryState) {
+        for key in other.deleted {
+            self.values.remove(&key);
+        }
+        self.values.extend(other.values);
+        for (key, versions) in other.history {
+            self.history.entry(key).or_default().extend(versions);
+        }
+    }
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct MemoryStateStore {
-    state: Arc<RwLock<InnerKvMap>>,
+    state: Arc<RwLock<MemoryState>>,
 }
 
 pub struct MemoryTransaction<T> {
-    pending: InnerKvMap,
+    pending: MemoryState,
     guard: T,
 }
 
 impl<'a> AtomicDb<'a> for MemoryStateStore {
     type Error = anyhow::Error;
-    type ReadAccess = MemoryTransaction<RwLockReadGuard<'a, InnerKvMap>>;
-    type WriteAccess = MemoryTransaction<RwLockWriteGuard<'a, InnerKvMap>>;
+    type ReadAccess = MemoryTransaction<RwLockReadGuard<'a, MemoryState>>;
+    type WriteAccess = MemoryTransaction<RwLockWriteGuard<'a, MemoryState>>;
 
     fn read_access(&'a self) -> Result<Self::ReadAccess, Self::Error> {
         let guard = self.state.read().map_err(|_| anyhow!("Failed to read state"))?;
 
         Ok(MemoryTransaction {
-            pending: HashMap::default(),
+            pending: MemoryState::default(),
             guard,
         })
     }
@@ -58,49 +105,122 @@ impl<'a> AtomicDb<'a> for MemoryStateStore {
         let guard = self.state.write().map_err(|_| anyhow!("Failed to write state"))?;
 
         Ok(MemoryTransaction {
-            pending: HashMap::default(),
+            pending: MemoryState::default(),
             guard,
         })
     }
 
     fn commit(&self, mut tx: Self::WriteAccess) -> Result<(), Self::Error> {
-        tx.guard.extend(tx.pending.into_iter());
+        tx.guard.extend(tx.pending);
         Ok(())
     }
 }
 
-impl<'a> StateReader for MemoryTransaction<RwLockReadGuard<'a, InnerKvMap>> {
+impl<T: Deref<Target = MemoryState>> StateReader for MemoryTransaction<T> {
     fn get_state_raw(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StateStoreError> {
-        Ok(self.pending.get(key).cloned().or_else(|| self.guard.get(key).cloned()))
+        if self.pending.deleted.contains(key) {
+            return Ok(None);
+        }
+        Ok(self
+            .pending
+            .values
+            .get(key)
+            .cloned()
+            .or_else(|| self.guard.values.get(key).cloned()))
     }
 
     fn exists(&self, key: &[u8]) -> Result<bool, StateStoreError> {
-        Ok(self.pending.contains_key(key) || self.guard.contains_key(key))
+        if self.pending.deleted.contains(key) {
+            return Ok(false);
+        }
+        Ok(self.pending.values.contains_key(key) || self.guard.values.contains_key(key))
+    }
+
+    fn iter_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Vec<StateEntry>, StateStoreError> {
+        let mut entries = self
+            .guard
+            .range(start, end)
+            .filter(|(k, _)| !self.pending.deleted.contains(*k))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<BTreeMap<_, _>>();
+        entries.extend(self.pending.range(start, end).map(|(k, v)| (k.clone(), v.clone())));
+        Ok(entries.into_iter().collect())
     }
 }
 
-impl<'a> StateReader for MemoryTransaction<RwLockWriteGuard<'a, InnerKvMap>> {
-    fn get_state_raw(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StateStoreError> {
-        Ok(self.pending.get(key).cloned().or_else(|| self.guard.get(key).cloned()))
+impl<T: Deref<Target = MemoryState>> VersionedStateReader for MemoryTransaction<T> {
+    fn get_state_raw_at(&self, key: &[u8], height: u64) -> Result<Option<Vec<u8>>, StateStoreError> {
+        let value = match (self.pending.get_at(key, height), self.guard.get_at(key, height)) {
+            // Pending writes replace committed writes at the same height
+            (Some((pending_height, pending)), Some((committed_height, committed))) => {
+                if pending_height >= committed_height {
+                    pending
+                } else {
+                    committed
+                }
+            },
+            (Some((_, value)), None) | (None, Some((_, value))) => value,
+            (None, None) => return Ok(None),
+        };
+        Ok(value.clone())
     }
 
-    fn exists(&self, key: &[u8]) -> Result<bool, StateStoreError> {
-        Ok(self.pending.contains_key(key) || self.guard.contains_key(key))
+    fn iter_range_at(&self, start: &[u8], end: Option<&[u8]>, height: u64) -> Result<Vec<StateEntry>, StateStoreError> {
+        let keys = self
+            .guard
+            .history_keys(start, end)
+            .chain(self.pending.history_keys(start, end))
+            .collect::<BTreeSet<_>>();
+        let mut entries = Vec::with_capacity(keys.len());
+        for key in keys {
+            if let Some(value) = self.get_state_raw_at(key, height)? {
+                entries.push((key.clone(), value));
+            }
+        }
+        Ok(entries)
     }
 }
 
-impl<'a> StateWriter for MemoryTransaction<RwLockWriteGuard<'a, InnerKvMap>> {
+impl<'a> StateWriter for MemoryTransaction<RwLockWriteGuard<'a, MemoryState>> {
     fn set_state_raw(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), StateStoreError> {
-        self.pending.insert(key.to_vec(), value);
+        self.pending.deleted.remove(key);
+        self.pending.values.insert(key.to_vec(), value);
+        Ok(())
+    }
+
+    fn delete_state_raw(&mut self, key: &[u8]) -> Result<(), StateStoreError> {
+        self.pending.values.remove(key);
+        self.pending.deleted.insert(key.to_vec());
         Ok(())
     }
 }
 
+impl<'a> VersionedStateWriter for MemoryTransaction<RwLockWriteGuard<'a, MemoryState>> {
+    fn set_state_raw_at(&mut self, key: &[u8], height: u64, value: Vec<u8>) -> Result<(), StateStoreError> {
+        self.pending
+            .history
+            .entry(key.to_vec())
+            .or_default()
+            .insert(height, Some(value.clone()));
+        self.set_state_raw(key, value)
+    }
+
+    fn delete_state_raw_at(&mut self, key: &[u8], height: u64) -> Result<(), StateStoreError> {
+        self.pending
+            .history
+            .entry(key.to_vec())
+            .or_default()
+            .insert(height, None);
+        self.delete_state_raw(key)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use tari_template_abi::{Decode, Encode};
+    use tari_template_abi::{encode, Decode, Encode};
 
     use super::*;
+    use crate::state_store::{prefix_end, StateSnapshot};
 
     #[test]
     fn read_write() {
@@ -153,4 +273,174 @@ mod tests {
         let res = access.get_state(b"abc").unwrap();
         assert_eq!(res, Some(user_data));
     }
+
+    #[test]
+    fn read_at_height() {
+        let store = MemoryStateStore::default();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_at(b"abc", 1, 1u32).unwrap();
+            access.set_state_at(b"abc", 5, 5u32).unwrap();
+            store.commit(access).unwrap();
+        }
+
+        let mut access = store.write_access().unwrap();
+        access.set_state_at(b"abc", 8, 8u32).unwrap();
+        assert_eq!(access.get_state_at::<_, u32>(b"abc", 0).unwrap(), None);
+        assert_eq!(access.get_state_at(b"abc", 1).unwrap(), Some(1u32));
+        assert_eq!(access.get_state_at(b"abc", 4).unwrap(), Some(1u32));
+        assert_eq!(access.get_state_at(b"abc", 7).unwrap(), Some(5u32));
+        assert_eq!(access.get_state_at(b"abc", 100).unwrap(), Some(8u32));
+        assert_eq!(access.get_state(b"abc").unwrap(), Some(8u32));
+        drop(access);
+
+        let snapshot = StateSnapshot::new(store.read_access().unwrap(), 6);
+        assert_eq!(snapshot.get_state(b"abc").unwrap(), Some(5u32));
+        assert!(!StateSnapshot::new(store.read_access().unwrap(), 0)
+            .exists(&encode(b"abc").unwrap())
+            .unwrap());
+    }
+
+    #[test]
+    fn iterate_prefix_and_range() {
+        let store = MemoryStateStore::default();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_raw(b"a1", vec![1]).unwrap();
+            access.set_state_raw(b"b1", vec![2]).unwrap();
+            access.set_state_raw(b"b3", vec![3]).unwrap();
+            access.set_state_raw(&[b'b', 0xff], vec![4]).unwrap();
+            access.set_state_raw(b"c1", vec![5]).unwrap();
+            store.commit(access).unwrap();
+        }
+
+        let mut access = store.write_access().unwrap();
+        // Pending writes are merged in key order and replace committed values
+        access.set_state_raw(b"b2", vec![6]).unwrap();
+        access.set_state_raw(b"b3", vec![7]).unwrap();
+
+        let keys = |entries: Vec<StateEntry>| entries.into_iter().map(|(k, _)| k).collect::<Vec<_>>();
+        let entries = access.iter_prefix(b"b").unwrap();
+        assert_eq!(entries, vec![
+            (b"b1".to_vec(), vec![2]),
+            (b"b2".to_vec(), vec![6]),
+            (b"b3".to_vec(), vec![7]),
+            (vec![b'b', 0xff], vec![4])
+        ]);
+        assert_eq!(keys(access.iter_range(b"b2", Some(b"c1")).unwrap()), vec![
+            b"b2".to_vec(),
+            b"b3".to_vec(),
+            vec![b'b', 0xff]
+        ]);
+        assert_eq!(keys(access.iter_range(b"b4", None).unwrap()), vec![
+            vec![b'b', 0xff],
+            b"c1".to_vec()
+        ]);
+        assert_eq!(access.iter_prefix(b"").unwrap().len(), 6);
+        assert!(access.iter_prefix(b"d").unwrap().is_empty());
+    }
+
+    #[test]
+    fn snapshot_iterates_the_state_at_its_height() {
+        let store = MemoryStateStore::default();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_raw_at(b"a", 1, vec![1]).unwrap();
+            access.set_state_raw_at(b"b", 1, vec![2]).unwrap();
+            access.set_state_raw_at(b"c", 3, vec![3]).unwrap();
+            store.commit(access).unwrap();
+        }
+        {
+            let mut access = store.write_access().unwrap();
+            // Changed and removed from the latest state after the snapshot height
+            access.set_state_raw_at(b"a", 4, vec![4]).unwrap();
+            access.delete_state_raw(b"b").unwrap();
+            store.commit(access).unwrap();
+        }
+
+        let snapshot = StateSnapshot::new(store.read_access().unwrap(), 2);
+        assert_eq!(snapshot.iter_prefix(b"").unwrap(), vec![
+            (b"a".to_vec(), vec![1]),
+            (b"b".to_vec(), vec![2])
+        ]);
+        assert_eq!(snapshot.iter_range(b"b", None).unwrap(), vec![(b"b".to_vec(), vec![2])]);
+        let snapshot = StateSnapshot::new(store.read_access().unwrap(), 4);
+        assert_eq!(snapshot.iter_range(b"a", Some(b"b")).unwrap(), vec![(
+            b"a".to_vec(),
+            vec![4]
+        )]);
+        assert_eq!(snapshot.iter_range(b"c", None).unwrap(), vec![(b"c".to_vec(), vec![3])]);
+    }
+
+    #[test]
+    fn delete_at_height_records_a_tombstone() {
+        let store = MemoryStateStore::default();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_raw_at(b"a", 1, vec![1]).unwrap();
+            access.set_state_raw_at(b"b", 1, vec![2]).unwrap();
+            store.commit(access).unwrap();
+        }
+        {
+            let mut access = store.write_access().unwrap();
+            access.delete_state_raw_at(b"a", 3).unwrap();
+            assert_eq!(access.get_state_raw_at(b"a", 3).unwrap(), None);
+            store.commit(access).unwrap();
+        }
+
+        let access = store.read_access().unwrap();
+        assert!(!access.exists(b"a").unwrap());
+        assert_eq!(access.get_state_raw_at(b"a", 2).unwrap(), Some(vec![1]));
+        assert_eq!(access.get_state_raw_at(b"a", 3).unwrap(), None);
+        assert_eq!(access.iter_range_at(b"", None, 2).unwrap().len(), 2);
+        assert_eq!(access.iter_range_at(b"", None, 3).unwrap(), vec![(
+            b"b".to_vec(),
+            vec![2]
+        )]);
+        drop(access);
+
+        // The key can be written again after it was deleted
+        let mut access = store.write_access().unwrap();
+        access.set_state_raw_at(b"a", 5, vec![5]).unwrap();
+        store.commit(access).unwrap();
+        let snapshot = StateSnapshot::new(store.read_access().unwrap(), 4);
+        assert!(!snapshot.exists(b"a").unwrap());
+        let snapshot = StateSnapshot::new(store.read_access().unwrap(), 5);
+        assert_eq!(snapshot.get_state_raw(b"a").unwrap(), Some(vec![5]));
+    }
+
+    #[test]
+    fn prefix_end_of_max_bytes() {
+        assert_eq!(prefix_end(b"ab"), Some(b"ac".to_vec()));
+        assert_eq!(prefix_end(&[1, 0xff]), Some(vec![2]));
+        assert_eq!(prefix_end(&[0xff, 0xff]), None);
+        assert_eq!(prefix_end(&[]), None);
+    }
+
+    #[test]
+    fn delete() {
+        let store = MemoryStateStore::default();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_raw(b"a", vec![1]).unwrap();
+            access.set_state_raw(b"b", vec![2]).unwrap();
+            store.commit(access).unwrap();
+        }
+
+        let mut access = store.write_access().unwrap();
+        access.delete_state_raw(b"a").unwrap();
+        access.delete_state_raw(b"missing").unwrap();
+        assert_eq!(access.get_state_raw(b"a").unwrap(), None);
+        assert!(!access.exists(b"a").unwrap());
+        assert_eq!(access.iter_prefix(b"").unwrap(), vec![(b"b".to_vec(), vec![2])]);
+        // Setting a deleted key in the same transaction restores it
+        access.set_state_raw(b"b", vec![3]).unwrap();
+        access.delete_state_raw(b"b").unwrap();
+        access.set_state_raw(b"b", vec![4]).unwrap();
+        store.commit(access).unwrap();
+
+        let access = store.read_access().unwrap();
+        assert!(!access.exists(b"a").unwrap());
+        assert_eq!(access.get_state_raw(b"b").unwrap(), Some(vec![4]));
+    }
 }
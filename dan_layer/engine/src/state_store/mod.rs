@@ -41,6 +41,9 @@ pub trait AtomicDb<'a> {
     fn commit(&self, tx: Self::WriteAccess) -> Result<(), Self::Error>;
 }
 
+/// A raw key and its value
+pub type StateEntry = (Vec<u8>, Vec<u8>);
+
 pub trait StateReader {
     fn get_state_raw(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StateStoreError>;
 
@@ -51,6 +54,28 @@ pub trait StateReader {
     }
 
     fn exists(&self, key: &[u8]) -> Result<bool, StateStoreError>;
+
+    /// Returns the key-value pairs with keys from `start` (inclusive) to `end` (exclusive), in ascending key order. If
+    /// `end` is None, all keys from `start` onwards are returned. Uncommitted writes in the transaction are included.
+    fn iter_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Vec<StateEntry>, StateStoreError>;
+
+    /// Returns the key-value pairs with keys that start with `prefix`, in ascending key order
+    fn iter_prefix(&self, prefix: &[u8]) -> Result<Vec<StateEntry>, StateStoreError> {
+        self.iter_range(prefix, prefix_end(prefix).as_deref())
+    }
+}
+
+/// Returns the smallest key that is greater than every key starting with `prefix`, or None if there is no such key
+/// (i.e. the prefix is empty or consists only of 0xff bytes)
+pub fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
 }
 
 pub trait StateWriter: StateReader {
@@ -58,6 +83,78 @@ pub trait StateWriter: StateReader {
     fn set_state<K: Encode, V: Encode>(&mut self, key: &K, value: V) -> Result<(), StateStoreError> {
         self.set_state_raw(&encode(key)?, encode(&value)?)
     }
+
+    /// Removes the key. Deleting a key that does not exist is not an error.
+    fn delete_state_raw(&mut self, key: &[u8]) -> Result<(), StateStoreError>;
+    fn delete_state<K: Encode>(&mut self, key: &K) -> Result<(), StateStoreError> {
+        self.delete_state_raw(&encode(key)?)
+    }
+}
+
+/// Read access to a store that keeps the value of every key as of each height at which it was written. A key that was
+/// deleted at a height has a tombstone in its history, so it has no value from that height onwards.
+pub trait VersionedStateReader {
+    /// Returns the value of the key as it was at `height`, i.e. the value of the last write at or below that height
+    fn get_state_raw_at(&self, key: &[u8], height: u64) -> Result<Option<Vec<u8>>, StateStoreError>;
+
+    /// Returns the key-value pairs with keys from `start` (inclusive) to `end` (exclusive) as they were at `height`, in
+    /// ascending key order. Keys that have been deleted or overwritten since `height` are returned with the value
+    /// they had at `height`.
+    fn iter_range_at(&self, start: &[u8], end: Option<&[u8]>, height: u64) -> Result<Vec<StateEntry>, StateStoreError>;
+
+    fn get_state_at<K: Encode, V: Decode>(&self, key: &K, height: u64) -> Result<Option<V>, StateStoreError> {
+        let value = self.get_state_raw_at(&encode(key)?, height)?;
+        let value = value.map(|v| V::deserialize(&mut v.as_slice())).transpose()?;
+        Ok(value)
+    }
+}
+
+pub trait VersionedStateWriter: StateWriter + VersionedStateReader {
+    /// Sets the latest value of the key and records it as the value from `height` onwards
+    fn set_state_raw_at(&mut self, key: &[u8], height: u64, value: Vec<u8>) -> Result<(), StateStoreError>;
+
+    fn set_state_at<K: Encode, V: Encode>(&mut self, key: &K, height: u64, value: V) -> Result<(), StateStoreError> {
+        self.set_state_raw_at(&encode(key)?, height, encode(&value)?)
+    }
+
+    /// Removes the key from the latest state and records a tombstone at `height`, so that the key has no value from
+    /// `height` onwards. Versioned state must be deleted through this rather than [StateWriter::delete_state_raw],
+    /// which does not touch the history.
+    fn delete_state_raw_at(&mut self, key: &[u8], height: u64) -> Result<(), StateStoreError>;
+
+    fn delete_state_at<K: Encode>(&mut self, key: &K, height: u64) -> Result<(), StateStoreError> {
+        self.delete_state_raw_at(&encode(key)?, height)
+    }
+}
+
+/// A [StateReader] that reads every key as it was at a fixed height
+pub struct StateSnapshot<R> {
+    reader: R,
+    height: u64,
+}
+
+impl<R: StateReader + VersionedStateReader> StateSnapshot<R> {
+    pub fn new(reader: R, height: u64) -> Self {
+        Self { reader, height }
+    }
+
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+}
+
+impl<R: StateReader + VersionedStateReader> StateReader for StateSnapshot<R> {
+    fn get_state_raw(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StateStoreError> {
+        self.reader.get_state_raw_at(key, self.height)
+    }
+
+    fn exists(&self, key: &[u8]) -> Result<bool, StateStoreError> {
+        Ok(self.get_state_raw(key)?.is_some())
+    }
+
+    fn iter_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Vec<StateEntry>, StateStoreError> {
+        self.reader.iter_range_at(start, end, self.height)
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -0,0 +1,212 @@
+// Copyright 2022 The Tari Project
+
+use thiserror::Error;
+use wasmparser::{BinaryReaderError, ImportSectionEntryType, Operator, Parser, Payload, Validator, WasmFeatures};
+
+/// The host functions that a template is permitted to import. Any other import would not be resolvable by the engine
+/// and could be used to introduce behaviour that differs between validators.
+const PERMITTED_IMPORTS: &[(&str, &str)] = &[("env", "tari_engine"), ("env", "debug")];
+
+/// Checks that the given WASM code only uses features and instructions that produce the same result on every
+/// validator. Floating point instructions, SIMD and threads are rejected, as are imports that the engine does not
+/// provide.
+pub fn validate_determinism(code: &[u8]) -> Result<(), DeterminismError> {
+    let mut validator = Validator::new();
+    validator.wasm_features(WasmFeatures {
+        simd: false,
+        relaxed_simd: false,
+        threads: false,
+        module_linking: false,
+        multi_memory: false,
+        memory64: false,
+        exceptions: false,
+        tail_call: false,
+        ..Default::default()
+    });
+    validator.validate_all(code)?;
+
+    let mut num_imported_functions = 0;
+    let mut function_index = 0;
+    for payload in Parser::new(0).parse_all(code) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    let name = import.field.unwrap_or_default();
+                    if !PERMITTED_IMPORTS.contains(&(import.module, name)) {
+                        return Err(DeterminismError::UnexpectedImport {
+                            module: import.module.to_string(),
+                            name: name.to_string(),
+                        });
+                    }
+                    if let ImportSectionEntryType::Function(_) = import.ty {
+                        num_imported_functions += 1;
+                    }
+                }
+            },
+            Payload::CodeSectionEntry(body) => {
+                let mut reader = body.get_operators_reader()?;
+                while !reader.eof() {
+                    let op = reader.read()?;
+                    if is_float_operator(&op) {
+                        return Err(DeterminismError::FloatingPointInstruction {
+                            function_index: num_imported_functions + function_index,
+                            instruction: format!("{:?}", op),
+                        });
+                    }
+                }
+                function_index += 1;
+            },
+            _ => {},
+        }
+    }
+
+    Ok(())
+}
+
+fn is_float_operator(op: &Operator<'_>) -> bool {
+    #[allow(clippy::enum_glob_use)]
+    use Operator::*;
+    matches!(
+        op,
+        F32Load { .. } |
+            F64Load { .. } |
+            F32Store { .. } |
+            F64Store { .. } |
+            F32Const { .. } |
+            F64Const { .. } |
+            F32Eq |
+            F32Ne |
+            F32Lt |
+            F32Gt |
+            F32Le |
+            F32Ge |
+            F64Eq |
+            F64Ne |
+            F64Lt |
+            F64Gt |
+            F64Le |
+            F64Ge |
+            F32Abs |
+            F32Neg |
+            F32Ceil |
+            F32Floor |
+            F32Trunc |
+            F32Nearest |
+            F32Sqrt |
+            F32Add |
+            F32Sub |
+            F32Mul |
+            F32Div |
+            F32Min |
+            F32Max |
+            F32Copysign |
+            F64Abs |
+            F64Neg |
+            F64Ceil |
+            F64Floor |
+            F64Trunc |
+            F64Nearest |
+            F64Sqrt |
+            F64Add |
+            F64Sub |
+            F64Mul |
+            F64Div |
+            F64Min |
+            F64Max |
+            F64Copysign |
+            I32TruncF32S |
+            I32TruncF32U |
+            I32TruncF64S |
+            I32TruncF64U |
+            I64TruncF32S |
+            I64TruncF32U |
+            I64TruncF64S |
+            I64TruncF64U |
+            F32ConvertI32S |
+            F32ConvertI32U |
+            F32ConvertI64S |
+            F32ConvertI64U |
+            F32DemoteF64 |
+            F64ConvertI32S |
+            F64ConvertI32U |
+            F64ConvertI64S |
+            F64ConvertI64U |
+            F64PromoteF32 |
+            I32ReinterpretF32 |
+            I64ReinterpretF64 |
+            F32ReinterpretI32 |
+            F64ReinterpretI64 |
+            I32TruncSatF32S |
+            I32TruncSatF32U |
+            I32TruncSatF64S |
+            I32TruncSatF64U |
+            I64TruncSatF32S |
+            I64TruncSatF32U |
+            I64TruncSatF64S |
+            I64TruncSatF64U
+    )
+}
+
+#[derive(Debug, Error)]
+pub enum DeterminismError {
+    #[error("Invalid WASM module: {0}")]
+    InvalidModule(#[from] BinaryReaderError),
+    #[error("Template imports '{module}.{name}' which is not provided by the engine")]
+    UnexpectedImport { module: String, name: String },
+    #[error("Function {function_index} contains non-deterministic floating point instruction {instruction}")]
+    FloatingPointInstruction { function_index: u32, instruction: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate_wat(wat: &str) -> Result<(), DeterminismError> {
+        let code = wat::parse_str(wat).unwrap();
+        validate_determinism(&code)
+    }
+
+    #[test]
+    fn it_accepts_integer_only_modules() {
+        validate_wat(
+            r#"(module
+                (import "env" "tari_engine" (func $engine (param i32 i32 i32) (result i32)))
+                (func (export "add") (param i32 i32) (result i32)
+                    local.get 0
+                    local.get 1
+                    i32.add))"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn it_rejects_floating_point_instructions() {
+        let err = validate_wat(
+            r#"(module
+                (import "env" "debug" (func $debug (param i32 i32)))
+                (func (export "add") (param f32 f32) (result f32)
+                    local.get 0
+                    local.get 1
+                    f32.add))"#,
+        )
+        .unwrap_err();
+        assert!(matches!(err, DeterminismError::FloatingPointInstruction {
+            function_index: 1,
+            ..
+        }));
+    }
+
+    #[test]
+    fn it_rejects_unknown_imports() {
+        let err = validate_wat(r#"(module (import "wasi_snapshot_preview1" "random_get" (func (param i32 i32))))"#)
+            .unwrap_err();
+        assert!(matches!(err, DeterminismError::UnexpectedImport { .. }));
+    }
+
+    #[test]
+    fn it_rejects_threads() {
+        let err = validate_wat(r#"(module (memory 1 1 shared))"#).unwrap_err();
+        assert!(matches!(err, DeterminismError::InvalidModule(_)));
+    }
+}
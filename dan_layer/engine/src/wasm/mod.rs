@@ -15,7 +15,10 @@ pub use error::{WasmError, WasmExecutionError};
 mod environment;
 
 mod module;
-pub use module::{LoadedWasmModule, WasmModule};
+pub use module::{LoadedWasmModule, WasmModule, MAX_SUPPORTED_ABI_VERSION, MIN_SUPPORTED_ABI_VERSION};
 
 mod process;
 pub use process::{ExecutionResult, Process};
+
+mod determinism;
+pub use determinism::{validate_determinism, DeterminismError};
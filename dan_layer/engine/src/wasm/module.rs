@@ -25,7 +25,7 @@ use std::sync::{
     Arc,
 };
 
-use tari_template_abi::{FunctionDef, TemplateDef};
+use tari_template_abi::{FunctionDef, TemplateDef, ABI_VERSION};
 use wasmer::{Extern, Function, Instance, Module, Store, Val, WasmerEnv};
 
 use crate::{
@@ -33,6 +33,11 @@ use crate::{
     wasm::{environment::WasmEnv, WasmExecutionError},
 };
 
+/// The oldest template ABI version that this engine is able to load
+pub const MIN_SUPPORTED_ABI_VERSION: u16 = 1;
+/// The newest template ABI version that this engine is able to load
+pub const MAX_SUPPORTED_ABI_VERSION: u16 = ABI_VERSION;
+
 #[derive(Debug, Clone)]
 pub struct WasmModule {
     code: Vec<u8>,
@@ -81,7 +86,7 @@ impl PackageModuleLoader for WasmModule {
 fn initialize_and_load_template_abi(
     instance: &Instance,
     env: &WasmEnv<Arc<AtomicBool>>,
-) -> Result<TemplateDef, WasmExecutionError> {
+) -> Result<TemplateDef, PackageError> {
     let abi_func = instance
         .exports
         .iter()
@@ -92,14 +97,25 @@ fn initialize_and_load_template_abi(
         .ok_or(WasmExecutionError::NoAbiDefinition)?;
 
     // Initialize ABI memory
-    let ret = abi_func.call(&[])?;
+    let ret = abi_func.call(&[]).map_err(WasmExecutionError::from)?;
     let ptr = match ret.get(0) {
         Some(Val::I32(ptr)) => *ptr as u32,
-        Some(_) | None => return Err(WasmExecutionError::InvalidReturnTypeFromAbiFunc),
+        Some(_) | None => return Err(WasmExecutionError::InvalidReturnTypeFromAbiFunc.into()),
     };
 
     // Load ABI from memory
     let data = env.read_memory_with_embedded_len(ptr)?;
+
+    // The version is always encoded first, so we check it before attempting to decode the rest of the definition
+    let abi_version = tari_template_abi::decode::<u16>(&data).map_err(|_| WasmExecutionError::AbiDecodeError)?;
+    if !(MIN_SUPPORTED_ABI_VERSION..=MAX_SUPPORTED_ABI_VERSION).contains(&abi_version) {
+        return Err(PackageError::UnsupportedAbiVersion {
+            version: abi_version,
+            min: MIN_SUPPORTED_ABI_VERSION,
+            max: MAX_SUPPORTED_ABI_VERSION,
+        });
+    }
+
     let decoded = tari_template_abi::decode(&data).map_err(|_| WasmExecutionError::AbiDecodeError)?;
     Ok(decoded)
 }
@@ -26,7 +26,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use tari_template_abi::{decode, encode, encode_into, encode_with_len, CallInfo, Type};
 use tari_template_lib::{
     abi_context::AbiContext,
-    args::{CreateComponentArg, EmitLogArg, GetComponentArg, SetComponentStateArg},
+    args::{CreateComponentArg, EmitLogArg, GetComponentArg, GetComponentsInRangeArg, SetComponentStateArg},
     models::{Component, Contract, ContractAddress, Package, PackageId},
     ops,
 };
@@ -99,6 +99,7 @@ impl Process {
                 Result::<_, WasmExecutionError>::Ok(())
             }),
             ops::OP_CREATE_COMPONENT => Self::handle(env, arg, |env, arg: CreateComponentArg| {
+                env.state().check_write_permitted("create_component")?;
                 env.state().interface().create_component(Component {
                     contract_address: arg.contract_address,
                     package_id: arg.package_id,
@@ -109,7 +110,13 @@ impl Process {
             ops::OP_GET_COMPONENT => Self::handle(env, arg, |env, arg: GetComponentArg| {
                 env.state().interface().get_component(&arg.component_id)
             }),
+            ops::OP_GET_COMPONENTS_IN_RANGE => Self::handle(env, arg, |env, arg: GetComponentsInRangeArg| {
+                env.state()
+                    .interface()
+                    .get_components_in_range(&arg.start, arg.end.as_ref())
+            }),
             ops::OP_SET_COMPONENT_STATE => Self::handle(env, arg, |env, arg: SetComponentStateArg| {
+                env.state().check_write_permitted("set_component_state")?;
                 env.state()
                     .interface()
                     .set_component_state(&arg.component_id, arg.state)
@@ -119,6 +126,11 @@ impl Process {
 
         result.unwrap_or_else(|err| {
             log::error!(target: LOG_TARGET, "{}", err);
+            if let WasmExecutionError::RuntimeError(err) = err {
+                if err.is_storage_error() {
+                    env.state().set_storage_error(err);
+                }
+            }
             0
         })
     }
@@ -173,7 +185,12 @@ impl Invokable for Process {
         let func = self.instance.exports.get_function(&main_name)?;
 
         let call_info_ptr = self.alloc_and_write(&call_info)?;
-        let res = func.call(&[call_info_ptr.as_i32().into(), Val::I32(call_info_ptr.len() as i32)])?;
+        let res = func.call(&[call_info_ptr.as_i32().into(), Val::I32(call_info_ptr.len() as i32)]);
+        // A host call that failed because of local storage fails the template, but the storage error is the cause
+        if let Some(err) = self.env.state().take_storage_error() {
+            return Err(err.into());
+        }
+        let res = res?;
         self.env.free(call_info_ptr)?;
         let ptr = res
             .get(0)
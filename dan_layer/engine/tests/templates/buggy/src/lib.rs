@@ -38,11 +38,23 @@ pub extern "C" fn Buggy_abi() -> *mut u8 {
         tari_engine(123, ptr::null_mut(), 0)
     };
     wrap_ptr(encode_with_len(&TemplateDef {
+        abi_version: ABI_VERSION,
         template_name: "".to_string(),
         functions: vec![],
     }))
 }
 
+#[cfg(feature = "unsupported_abi_version")]
+#[no_mangle]
+pub extern "C" fn Buggy_abi() -> *mut u8 {
+    use tari_template_abi::*;
+    wrap_ptr(encode_with_len(&TemplateDef {
+        abi_version: u16::MAX,
+        template_name: "Buggy".to_string(),
+        functions: vec![],
+    }))
+}
+
 #[cfg(feature = "return_null_abi")]
 #[no_mangle]
 pub extern "C" fn Buggy_abi() -> *mut u8 {
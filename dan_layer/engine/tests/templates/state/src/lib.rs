@@ -38,6 +38,7 @@ mod state_template {
             self.value = value;
         }
 
+        #[view]
         pub fn get(&self) -> u32 {
             self.value
         }
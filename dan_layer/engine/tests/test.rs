@@ -23,10 +23,18 @@
 mod tooling;
 
 use tari_dan_engine::{
+    instruction::InstructionError,
     packager::{Package, PackageError},
     state_store::{AtomicDb, StateReader},
-    wasm::{compile::compile_template, WasmExecutionError},
+    wasm::{
+        compile::compile_template,
+        validate_determinism,
+        WasmExecutionError,
+        MAX_SUPPORTED_ABI_VERSION,
+        MIN_SUPPORTED_ABI_VERSION,
+    },
 };
+use tari_template_abi::{ReceiverKind, ABI_VERSION};
 use tari_template_lib::{
     args,
     models::{ComponentId, ComponentInstance},
@@ -79,6 +87,43 @@ fn test_state() {
     assert_eq!(value, new_value);
 }
 
+#[test]
+fn test_read_only_call() {
+    let template_test = TemplateTest::new(vec!["tests/templates/state"]);
+
+    let get_def = template_test
+        .get_module("State")
+        .template_def()
+        .get_function("get")
+        .unwrap();
+    assert!(get_def.is_view);
+    assert_eq!(get_def.receiver, ReceiverKind::Ref);
+    let set_def = template_test
+        .get_module("State")
+        .template_def()
+        .get_function("set")
+        .unwrap();
+    assert!(!set_def.is_view);
+    assert_eq!(set_def.receiver, ReceiverKind::Mut);
+
+    let component_id: ComponentId = template_test.call_function("State", "new", args![]);
+    template_test.call_method::<()>(component_id, "set", args![20_u32]);
+
+    let value: u32 = template_test
+        .call_method_read_only(component_id, "get", args![])
+        .unwrap();
+    assert_eq!(value, 20);
+
+    let err = template_test
+        .call_method_read_only::<()>(component_id, "set", args![1_u32])
+        .unwrap_err();
+    assert!(matches!(err, InstructionError::FunctionNotReadOnly { .. }));
+
+    // the state was not changed
+    let value: u32 = template_test.call_method(component_id, "get", args![]);
+    assert_eq!(value, 20);
+}
+
 #[test]
 fn test_composed() {
     let template_test = TemplateTest::new(vec!["tests/templates/state", "tests/templates/hello_world"]);
@@ -137,3 +182,53 @@ fn test_dodgy_template() {
         PackageError::WasmModuleError(WasmExecutionError::UnexpectedAbiFunction { .. })
     ));
 }
+
+#[test]
+fn test_storage_errors_in_host_calls_are_returned() {
+    let template_test = TemplateTest::new(vec!["tests/templates/state"]);
+    template_test.runtime_interface().set_fail_writes(true);
+
+    // The template only sees a failed engine call, but the execution must fail with the storage error
+    let err = template_test
+        .try_call_function::<ComponentId>("State", "new", args![])
+        .unwrap_err();
+    assert!(err.is_storage_error(), "unexpected error: {}", err);
+
+    template_test.runtime_interface().set_fail_writes(false);
+    let err = template_test
+        .try_call_function::<ComponentId>("Missing", "new", args![])
+        .unwrap_err();
+    assert!(!err.is_storage_error());
+    let _component_id: ComponentId = template_test.call_function("State", "new", args![]);
+}
+
+#[test]
+fn test_templates_pass_determinism_validation() {
+    // Templates built by rustc must not be rejected, e.g. because core emits floating point instructions
+    for path in &["tests/templates/state", "tests/templates/hello_world"] {
+        let wasm = compile_template(path, &[]).unwrap();
+        if let Err(err) = validate_determinism(wasm.code()) {
+            panic!("Template at {} failed determinism validation: {}", path, err);
+        }
+    }
+}
+
+#[test]
+fn test_abi_version_compatibility() {
+    // Templates compiled against the current ABI must always be loadable by the engine built with it
+    assert!((MIN_SUPPORTED_ABI_VERSION..=MAX_SUPPORTED_ABI_VERSION).contains(&ABI_VERSION));
+    assert!(MIN_SUPPORTED_ABI_VERSION <= MAX_SUPPORTED_ABI_VERSION);
+
+    let template_test = TemplateTest::new(vec!["tests/templates/state"]);
+    assert_eq!(
+        template_test.get_module("State").template_def().abi_version,
+        ABI_VERSION
+    );
+
+    let wasm = compile_template("tests/templates/buggy", &["unsupported_abi_version"]).unwrap();
+    let err = Package::builder().add_wasm_module(wasm).build().unwrap_err();
+    assert!(matches!(
+        err,
+        PackageError::UnsupportedAbiVersion { version, .. } if version == u16::MAX
+    ));
+}
@@ -20,41 +20,41 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::sync::{atomic::AtomicU32, Arc, RwLock};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+    RwLock,
+};
 
-use digest::Digest;
+use anyhow::anyhow;
 use tari_dan_engine::{
-    crypto,
-    runtime::{RuntimeError, RuntimeInterface},
-    state_store::{memory::MemoryStateStore, AtomicDb, StateReader, StateWriter},
+    runtime::{RuntimeError, RuntimeInterface, StateStoreRuntimeInterface},
+    state_store::memory::MemoryStateStore,
 };
 use tari_template_lib::{
     args::LogLevel,
     models::{Component, ComponentId, ComponentInstance},
 };
 
-#[derive(Debug, Clone, Default)]
+/// An in-memory runtime interface that records the calls made to it
+#[derive(Debug, Clone)]
 pub struct MockRuntimeInterface {
-    ids: Arc<AtomicU32>,
-    state: MemoryStateStore,
+    inner: StateStoreRuntimeInterface<MemoryStateStore>,
     calls: Arc<RwLock<Vec<&'static str>>>,
+    fail_writes: Arc<AtomicBool>,
 }
 
 impl MockRuntimeInterface {
     pub fn new() -> Self {
         Self {
-            ids: Arc::new(AtomicU32::new(0)),
-            state: MemoryStateStore::default(),
+            inner: StateStoreRuntimeInterface::new(MemoryStateStore::default()),
             calls: Arc::new(RwLock::new(vec![])),
+            fail_writes: Arc::new(AtomicBool::new(false)),
         }
     }
 
     pub fn state_store(&self) -> MemoryStateStore {
-        self.state.clone()
-    }
-
-    pub fn next_id(&self) -> u32 {
-        self.ids.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        self.inner.store().clone()
     }
 
     pub fn get_calls(&self) -> Vec<&'static str> {
@@ -65,60 +65,58 @@ impl MockRuntimeInterface {
         self.calls.write().unwrap().clear();
     }
 
+    /// Makes every subsequent write fail with a storage error, as a failing database would
+    pub fn set_fail_writes(&self, fail_writes: bool) {
+        self.fail_writes.store(fail_writes, Ordering::Relaxed);
+    }
+
     fn add_call(&self, call: &'static str) {
         self.calls.write().unwrap().push(call);
     }
+
+    fn check_write(&self) -> Result<(), RuntimeError> {
+        if self.fail_writes.load(Ordering::Relaxed) {
+            return Err(RuntimeError::StateDbError(anyhow!("Mock storage write failure")));
+        }
+        Ok(())
+    }
+}
+
+impl Default for MockRuntimeInterface {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RuntimeInterface for MockRuntimeInterface {
     fn emit_log(&self, level: LogLevel, message: &str) {
         self.add_call("emit_log");
-        let level = match level {
-            LogLevel::Error => log::Level::Error,
-            LogLevel::Warn => log::Level::Warn,
-            LogLevel::Info => log::Level::Info,
-            LogLevel::Debug => log::Level::Debug,
-        };
-        eprintln!("[{:?}] {}", level, message);
-        log::log!(target: "tari::dan::engine::runtime", level, "{}", message);
+        self.inner.emit_log(level, message);
     }
 
     fn create_component(&self, new_component: Component) -> Result<ComponentId, RuntimeError> {
         self.add_call("create_component");
-        let component_id: [u8; 32] = crypto::hasher("component")
-            .chain(self.next_id().to_le_bytes())
-            .finalize()
-            .into();
-
-        let component = ComponentInstance::new(component_id.into(), new_component);
-        let mut tx = self.state.write_access().map_err(RuntimeError::StateDbError)?;
-        tx.set_state(&component_id, component)?;
-        self.state.commit(tx).map_err(RuntimeError::StateDbError)?;
-
-        Ok(component_id.into())
+        self.check_write()?;
+        self.inner.create_component(new_component)
     }
 
     fn get_component(&self, component_id: &ComponentId) -> Result<ComponentInstance, RuntimeError> {
         self.add_call("get_component");
-        let component = self
-            .state
-            .read_access()
-            .map_err(RuntimeError::StateDbError)?
-            .get_state(component_id)?
-            .ok_or(RuntimeError::ComponentNotFound { id: *component_id })?;
-        Ok(component)
+        self.inner.get_component(component_id)
+    }
+
+    fn get_components_in_range(
+        &self,
+        start: &ComponentId,
+        end: Option<&ComponentId>,
+    ) -> Result<Vec<ComponentInstance>, RuntimeError> {
+        self.add_call("get_components_in_range");
+        self.inner.get_components_in_range(start, end)
     }
 
     fn set_component_state(&self, component_id: &ComponentId, state: Vec<u8>) -> Result<(), RuntimeError> {
         self.add_call("set_component_state");
-        let mut tx = self.state.write_access().map_err(RuntimeError::StateDbError)?;
-        let mut component: ComponentInstance = tx
-            .get_state(component_id)?
-            .ok_or(RuntimeError::ComponentNotFound { id: *component_id })?;
-        component.state = state;
-        tx.set_state(&component_id, component)?;
-        self.state.commit(tx).map_err(RuntimeError::StateDbError)?;
-
-        Ok(())
+        self.check_write()?;
+        self.inner.set_component_state(component_id, state)
     }
 }
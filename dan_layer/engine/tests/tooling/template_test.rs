@@ -26,7 +26,7 @@ use borsh::BorshDeserialize;
 use tari_crypto::ristretto::RistrettoSecretKey;
 use tari_dan_engine::{
     crypto::create_key_pair,
-    instruction::{Instruction, InstructionBuilder, InstructionProcessor},
+    instruction::{Instruction, InstructionBuilder, InstructionError, InstructionProcessor},
     packager::Package,
     state_store::memory::MemoryStateStore,
     wasm::{compile::compile_template, LoadedWasmModule},
@@ -82,8 +82,24 @@ impl TemplateTest {
         self.package.get_module_by_name(module_name).unwrap()
     }
 
+    pub fn runtime_interface(&self) -> &MockRuntimeInterface {
+        &self.runtime_interface
+    }
+
     pub fn call_function<T>(&self, template_name: &str, func_name: &str, args: Vec<Vec<u8>>) -> T
     where T: BorshDeserialize {
+        self.try_call_function(template_name, func_name, args).unwrap()
+    }
+
+    pub fn try_call_function<T>(
+        &self,
+        template_name: &str,
+        func_name: &str,
+        args: Vec<Vec<u8>>,
+    ) -> Result<T, InstructionError>
+    where
+        T: BorshDeserialize,
+    {
         let instruction = InstructionBuilder::new()
             .add_instruction(Instruction::CallFunction {
                 package_id: self.package.id(),
@@ -93,9 +109,9 @@ impl TemplateTest {
             })
             .sign(&self.secret_key)
             .build();
-        let result = self.processor.execute(instruction).unwrap();
+        let result = self.processor.execute(instruction)?;
 
-        result[0].decode::<T>().unwrap()
+        Ok(result[0].decode::<T>().unwrap())
     }
 
     pub fn call_method<T>(&self, component_id: ComponentId, method_name: &str, args: Vec<Vec<u8>>) -> T
@@ -113,4 +129,27 @@ impl TemplateTest {
 
         result[0].decode::<T>().unwrap()
     }
+
+    pub fn call_method_read_only<T>(
+        &self,
+        component_id: ComponentId,
+        method_name: &str,
+        args: Vec<Vec<u8>>,
+    ) -> Result<T, InstructionError>
+    where
+        T: BorshDeserialize,
+    {
+        let instruction = InstructionBuilder::new()
+            .add_instruction(Instruction::CallMethod {
+                package_id: self.package.id(),
+                component_id,
+                method: method_name.to_owned(),
+                args,
+            })
+            .sign(&self.secret_key)
+            .build();
+        let result = self.processor.execute_read_only(instruction)?;
+
+        Ok(result[0].decode::<T>().unwrap())
+    }
 }
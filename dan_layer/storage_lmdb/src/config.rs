@@ -0,0 +1,82 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::time::Duration;
+
+use lmdb_zero::open;
+
+const MIB: usize = 1024 * 1024;
+
+/// Configuration for an [LmdbStateStore](crate::LmdbStateStore) environment
+#[derive(Debug, Clone)]
+pub struct LmdbConfig {
+    /// The initial size of the memory map in bytes
+    pub map_size: usize,
+    /// The number of bytes the memory map grows by each time it becomes full
+    pub grow_size: usize,
+    /// How long a write that fills the memory map waits for open transactions to close so that the map can grow
+    pub grow_timeout: Duration,
+    /// The maximum number of concurrent read transactions
+    pub max_readers: u32,
+    /// The maximum number of named databases in the environment. Each state store uses two.
+    pub max_databases: u32,
+    pub sync_mode: SyncMode,
+    /// The name of the database that the store is opened on. Use a different name per contract or schema to keep their
+    /// state apart within one environment.
+    pub database_name: String,
+}
+
+impl Default for LmdbConfig {
+    fn default() -> Self {
+        Self {
+            map_size: 64 * MIB,
+            grow_size: 64 * MIB,
+            grow_timeout: Duration::from_secs(30),
+            max_readers: 126,
+            max_databases: 32,
+            sync_mode: SyncMode::Full,
+            database_name: "state".to_string(),
+        }
+    }
+}
+
+/// How durable a commit is when it returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Flush data and metadata to disk on every commit
+    Full,
+    /// Flush data but not metadata on commit. The last commit may be lost if the system crashes, but the database is
+    /// not corrupted.
+    NoMetaSync,
+    /// Leave flushing to the operating system. Commits are fast, but recent commits may be lost if the system crashes.
+    NoSync,
+}
+
+impl SyncMode {
+    pub(crate) fn env_flags(self) -> open::Flags {
+        match self {
+            SyncMode::Full => open::Flags::empty(),
+            SyncMode::NoMetaSync => open::NOMETASYNC,
+            SyncMode::NoSync => open::NOSYNC,
+        }
+    }
+}
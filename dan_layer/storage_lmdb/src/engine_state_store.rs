@@ -20,71 +20,291 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::{ops::Deref, path::Path, sync::Arc};
+use std::{collections::BTreeMap, convert::TryInto, ops::Deref, path::Path, sync::Arc, time::Duration};
 
-use lmdb_zero::{db, put, ConstTransaction, LmdbResultExt, ReadTransaction, WriteTransaction};
-use tari_dan_engine::state_store::{AtomicDb, StateReader, StateStoreError, StateWriter};
-use tari_storage::lmdb_store::{DatabaseRef, LMDBBuilder};
+use lmdb_zero::{
+    db,
+    error,
+    put,
+    ConstTransaction,
+    Database,
+    DatabaseOptions,
+    EnvBuilder,
+    Environment,
+    LmdbResultExt,
+    ReadTransaction,
+    WriteTransaction,
+};
+use tari_dan_engine::state_store::{
+    AtomicDb,
+    StateEntry,
+    StateReader,
+    StateStoreError,
+    StateWriter,
+    VersionedStateReader,
+    VersionedStateWriter,
+};
+
+use crate::{
+    transaction_tracker::{TransactionGuard, TransactionTracker},
+    LmdbConfig,
+    LmdbStorageError,
+};
+
+const HEIGHT_LEN: usize = 8;
+const KEY_LEN_LEN: usize = 4;
+/// History values start with a tag that distinguishes a written value from a deletion
+const HISTORY_TAG_TOMBSTONE: u8 = 0;
+const HISTORY_TAG_VALUE: u8 = 1;
+
+type DatabaseRef = Arc<Database<'static>>;
 
 pub struct LmdbTransaction<T> {
+    env: Arc<Environment>,
     db: DatabaseRef,
-    tx: T,
+    history_db: DatabaseRef,
+    /// None while a write transaction is being replaced after the map was resized, or if that failed
+    tx: Option<T>,
+    tracker: Arc<TransactionTracker>,
+    /// Keeps the transaction registered with the tracker, so that the map is not resized while it is open
+    guard: Option<TransactionGuard>,
+    grow_size: usize,
+    grow_timeout: Duration,
+    /// The writes made in this transaction, replayed into a new transaction if the map has to grow
+    write_log: Vec<WriteOp>,
+}
+
+impl<T> LmdbTransaction<T> {
+    /// Returns the underlying transaction, or an error if it was lost to a failed resize of the map
+    fn tx(&self) -> Result<&T, StateStoreError> {
+        self.tx
+            .as_ref()
+            .ok_or_else(|| StateStoreError::custom(LmdbStorageError::TransactionAborted))
+    }
 }
 
 pub struct LmdbStateStore {
-    pub env: Arc<lmdb_zero::Environment>,
+    pub env: Arc<Environment>,
     pub db: DatabaseRef,
+    /// Holds every write made through [VersionedStateWriter], keyed by [history_key]
+    pub history_db: DatabaseRef,
+    /// Shared by every store opened on the environment
+    tracker: Arc<TransactionTracker>,
+    config: LmdbConfig,
 }
 
 impl LmdbStateStore {
-    pub fn new<P: AsRef<Path>>(path: P) -> Self {
-        let flags = db::CREATE;
-        std::fs::create_dir_all(&path).unwrap();
+    /// Opens the store with the default configuration
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, LmdbStorageError> {
+        Self::open(path, LmdbConfig::default())
+    }
+
+    /// Opens (creating if necessary) the environment at `path` and the database named in the config
+    pub fn open<P: AsRef<Path>>(path: P, config: LmdbConfig) -> Result<Self, LmdbStorageError> {
+        let path = path.as_ref();
+        std::fs::create_dir_all(path)?;
+        let path_str = path.to_str().ok_or_else(|| LmdbStorageError::InvalidPath {
+            path: path.display().to_string(),
+        })?;
+
+        let mut builder = EnvBuilder::new()?;
+        builder.set_mapsize(config.map_size)?;
+        builder.set_maxreaders(config.max_readers)?;
+        builder.set_maxdbs(config.max_databases)?;
+        // Safety: the environment is not opened more than once by this store
+        let env = unsafe { builder.open(path_str, config.sync_mode.env_flags(), 0o600)? };
+
+        Self::open_database_in(Arc::new(env), Arc::new(TransactionTracker::default()), config)
+    }
+
+    /// Opens another named database in the same environment, e.g. for a different contract
+    pub fn open_database(&self, name: &str) -> Result<Self, LmdbStorageError> {
+        let config = LmdbConfig {
+            database_name: name.to_string(),
+            ..self.config.clone()
+        };
+        Self::open_database_in(self.env.clone(), self.tracker.clone(), config)
+    }
+
+    pub fn config(&self) -> &LmdbConfig {
+        &self.config
+    }
+
+    fn open_database_in(
+        env: Arc<Environment>,
+        tracker: Arc<TransactionTracker>,
+        config: LmdbConfig,
+    ) -> Result<Self, LmdbStorageError> {
+        let options = DatabaseOptions::new(db::CREATE);
+        let db = Database::open(env.clone(), Some(config.database_name.as_str()), &options)?;
+        let history_name = format!("{}.history", config.database_name);
+        let history_db = Database::open(env.clone(), Some(history_name.as_str()), &options)?;
+        Ok(Self {
+            env,
+            db: Arc::new(db),
+            history_db: Arc::new(history_db),
+            tracker,
+            config,
+        })
+    }
+
+    fn transaction<T>(&self, tx: T, guard: TransactionGuard) -> LmdbTransaction<T> {
+        LmdbTransaction {
+            env: self.env.clone(),
+            db: self.db.clone(),
+            history_db: self.history_db.clone(),
+            tx: Some(tx),
+            tracker: self.tracker.clone(),
+            guard: Some(guard),
+            grow_size: self.config.grow_size,
+            grow_timeout: self.config.grow_timeout,
+            write_log: Vec::new(),
+        }
+    }
+}
+
+/// Encodes a history key so that all versions of a key are adjacent and ordered by height. The key is length-prefixed
+/// so that the versions of one key cannot interleave with those of a longer key that shares its prefix.
+fn history_key(key: &[u8], height: u64) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(KEY_LEN_LEN + key.len() + HEIGHT_LEN);
+    buf.extend_from_slice(&(key.len() as u32).to_be_bytes());
+    buf.extend_from_slice(key);
+    buf.extend_from_slice(&height.to_be_bytes());
+    buf
+}
+
+/// Splits a history key into the state key and the height
+fn split_history_key(history_key: &[u8]) -> Option<(&[u8], u64)> {
+    let (key_len, rest) = history_key.split_at(KEY_LEN_LEN.min(history_key.len()));
+    let key_len = u32::from_be_bytes(key_len.try_into().ok()?) as usize;
+    if rest.len() != key_len + HEIGHT_LEN {
+        return None;
+    }
+    let (key, height) = rest.split_at(key_len);
+    Some((key, u64::from_be_bytes(height.try_into().ok()?)))
+}
+
+fn encode_history_value(value: Option<&[u8]>) -> Vec<u8> {
+    match value {
+        Some(value) => {
+            let mut buf = Vec::with_capacity(1 + value.len());
+            buf.push(HISTORY_TAG_VALUE);
+            buf.extend_from_slice(value);
+            buf
+        },
+        None => vec![HISTORY_TAG_TOMBSTONE],
+    }
+}
+
+/// Decodes a history value, returning None for a tombstone
+fn decode_history_value(value: &[u8]) -> Result<Option<Vec<u8>>, StateStoreError> {
+    match value.split_first() {
+        Some((&HISTORY_TAG_VALUE, value)) => Ok(Some(value.to_vec())),
+        Some((&HISTORY_TAG_TOMBSTONE, [])) => Ok(None),
+        _ => Err(StateStoreError::custom_str("Malformed state history value")),
+    }
+}
+
+enum WriteOp {
+    Put { key: Vec<u8>, value: Vec<u8> },
+    PutHistory { key: Vec<u8>, value: Vec<u8> },
+    Delete { key: Vec<u8> },
+}
+
+fn is_map_full(err: &lmdb_zero::Error) -> bool {
+    matches!(err, lmdb_zero::Error::Code(code) if *code == error::MAP_FULL)
+}
 
-        let store = LMDBBuilder::new()
-            .set_path(path)
-            // .set_env_config(config)
-            .set_max_number_of_databases(1)
-            .add_database("test_db", flags )
-            .build().unwrap();
+/// Grows the memory map of the environment.
+///
+/// LMDB requires that no transactions are active in this process while the map is resized, so this must only be
+/// called through [TransactionTracker::resize].
+fn grow_map(env: &Environment, grow_size: usize) -> Result<(), LmdbStorageError> {
+    let info = env.info()?;
+    // Safety: the tracker has waited for every transaction on the environment to close and holds back new ones
+    unsafe { env.set_mapsize(info.mapsize + grow_size)? };
+    Ok(())
+}
+
+impl<'a> LmdbTransaction<WriteTransaction<'a>> {
+    fn apply(&mut self, op: WriteOp) -> Result<(), StateStoreError> {
+        match self.apply_to(self.tx()?, &op) {
+            Ok(()) => {},
+            // The transaction cannot be used after MDB_MAP_FULL, so all of its writes are replayed in a larger map
+            Err(err) if is_map_full(&err) => {
+                self.write_log.push(op);
+                self.grow_and_replay().map_err(StateStoreError::custom)?;
+                return Ok(());
+            },
+            Err(err) => return Err(StateStoreError::custom(err)),
+        }
+        self.write_log.push(op);
+        Ok(())
+    }
+
+    fn apply_to(&self, tx: &WriteTransaction<'a>, op: &WriteOp) -> Result<(), lmdb_zero::Error> {
+        let mut access = tx.access();
+        match op {
+            WriteOp::Put { key, value } => access.put(&*self.db, key, value, put::Flags::empty()),
+            WriteOp::PutHistory { key, value } => access.put(&*self.history_db, key, value, put::Flags::empty()),
+            WriteOp::Delete { key } => access.del_key(&*self.db, key).to_opt().map(|_| ()),
+        }
+    }
 
-        let handle = store.get_handle("test_db").unwrap();
-        let db = handle.db();
-        Self { env: store.env(), db }
+    /// Aborts the current transaction, grows the map and replays the write log into a new transaction, repeating until
+    /// the writes fit
+    fn grow_and_replay(&mut self) -> Result<(), LmdbStorageError> {
+        'grow: loop {
+            drop(self.tx.take());
+            drop(self.guard.take());
+            self.tracker
+                .resize(self.grow_timeout, || grow_map(&self.env, self.grow_size))?;
+            self.guard = Some(self.tracker.begin()?);
+            let tx = WriteTransaction::new(self.env.clone())?;
+            for op in &self.write_log {
+                match self.apply_to(&tx, op) {
+                    Ok(()) => {},
+                    Err(err) if is_map_full(&err) => continue 'grow,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            self.tx = Some(tx);
+            return Ok(());
+        }
     }
 }
 
 impl<'a> AtomicDb<'a> for LmdbStateStore {
-    type Error = lmdb_zero::Error;
+    type Error = LmdbStorageError;
     type ReadAccess = LmdbTransaction<ReadTransaction<'a>>;
     type WriteAccess = LmdbTransaction<WriteTransaction<'a>>;
 
     fn read_access(&'a self) -> Result<Self::ReadAccess, Self::Error> {
+        let guard = self.tracker.begin()?;
         let tx = ReadTransaction::new(self.env.clone())?;
-
-        Ok(LmdbTransaction {
-            db: self.db.clone(),
-            tx,
-        })
+        Ok(self.transaction(tx, guard))
     }
 
     fn write_access(&'a self) -> Result<Self::WriteAccess, Self::Error> {
+        let guard = self.tracker.begin()?;
         let tx = WriteTransaction::new(self.env.clone())?;
-
-        Ok(LmdbTransaction {
-            db: self.db.clone(),
-            tx,
-        })
+        Ok(self.transaction(tx, guard))
     }
 
-    fn commit(&self, tx: Self::WriteAccess) -> Result<(), Self::Error> {
-        tx.tx.commit()
+    fn commit(&self, mut tx: Self::WriteAccess) -> Result<(), Self::Error> {
+        loop {
+            let result = tx.tx.take().ok_or(LmdbStorageError::TransactionAborted)?.commit();
+            match result {
+                Err(err) if is_map_full(&err) => tx.grow_and_replay()?,
+                result => return Ok(result?),
+            }
+        }
     }
 }
 
 impl<'a, T: Deref<Target = ConstTransaction<'a>>> StateReader for LmdbTransaction<T> {
     fn get_state_raw(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StateStoreError> {
-        let access = self.tx.access();
+        let access = self.tx()?.access();
         access
             .get::<_, [u8]>(&*self.db, key)
             .map(|data| data.to_vec())
@@ -95,14 +315,118 @@ impl<'a, T: Deref<Target = ConstTransaction<'a>>> StateReader for LmdbTransactio
     fn exists(&self, key: &[u8]) -> Result<bool, StateStoreError> {
         Ok(self.get_state_raw(key)?.is_some())
     }
+
+    fn iter_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Vec<StateEntry>, StateStoreError> {
+        let tx = self.tx()?;
+        let access = tx.access();
+        let mut cursor = tx.cursor(&*self.db).map_err(StateStoreError::custom)?;
+        let mut entries = Vec::new();
+        let mut next = cursor
+            .seek_range_k::<[u8], [u8]>(&access, start)
+            .to_opt()
+            .map_err(StateStoreError::custom)?;
+        while let Some((k, v)) = next {
+            if end.map_or(false, |end| k >= end) {
+                break;
+            }
+            entries.push((k.to_vec(), v.to_vec()));
+            next = cursor
+                .next::<[u8], [u8]>(&access)
+                .to_opt()
+                .map_err(StateStoreError::custom)?;
+        }
+        Ok(entries)
+    }
+}
+
+impl<'a, T: Deref<Target = ConstTransaction<'a>>> VersionedStateReader for LmdbTransaction<T> {
+    fn get_state_raw_at(&self, key: &[u8], height: u64) -> Result<Option<Vec<u8>>, StateStoreError> {
+        let tx = self.tx()?;
+        let access = tx.access();
+        let mut cursor = tx.cursor(&*self.history_db).map_err(StateStoreError::custom)?;
+        let target = history_key(key, height);
+
+        // Find the last entry at or before the target: seek to the first entry at or after it and step back if needed
+        let entry = match cursor
+            .seek_range_k::<[u8], [u8]>(&access, &target)
+            .to_opt()
+            .map_err(StateStoreError::custom)?
+        {
+            Some((k, v)) if k == target.as_slice() => Some((k, v)),
+            Some(_) => cursor
+                .prev::<[u8], [u8]>(&access)
+                .to_opt()
+                .map_err(StateStoreError::custom)?,
+            None => cursor
+                .last::<[u8], [u8]>(&access)
+                .to_opt()
+                .map_err(StateStoreError::custom)?,
+        };
+
+        let prefix = &target[..target.len() - HEIGHT_LEN];
+        match entry {
+            Some((k, v)) if k.len() == target.len() && k.starts_with(prefix) => decode_history_value(v),
+            _ => Ok(None),
+        }
+    }
+
+    /// History keys are ordered by key length before key bytes, so this scans the whole history
+    fn iter_range_at(&self, start: &[u8], end: Option<&[u8]>, height: u64) -> Result<Vec<StateEntry>, StateStoreError> {
+        let tx = self.tx()?;
+        let access = tx.access();
+        let mut cursor = tx.cursor(&*self.history_db).map_err(StateStoreError::custom)?;
+        // The versions of each key are ordered by height, so the last version seen at or below the height wins
+        let mut versions = BTreeMap::new();
+        let mut next = cursor
+            .first::<[u8], [u8]>(&access)
+            .to_opt()
+            .map_err(StateStoreError::custom)?;
+        while let Some((k, v)) = next {
+            let (key, version_height) =
+                split_history_key(k).ok_or_else(|| StateStoreError::custom_str("Malformed state history key"))?;
+            if version_height <= height && key >= start && end.map_or(true, |end| key < end) {
+                versions.insert(key.to_vec(), decode_history_value(v)?);
+            }
+            next = cursor
+                .next::<[u8], [u8]>(&access)
+                .to_opt()
+                .map_err(StateStoreError::custom)?;
+        }
+        Ok(versions
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .collect())
+    }
 }
 
 impl<'a> StateWriter for LmdbTransaction<WriteTransaction<'a>> {
     fn set_state_raw(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), StateStoreError> {
-        let mut access = self.tx.access();
-        access
-            .put(&*self.db, key, &value, put::Flags::empty())
-            .map_err(StateStoreError::custom)
+        self.apply(WriteOp::Put {
+            key: key.to_vec(),
+            value,
+        })
+    }
+
+    fn delete_state_raw(&mut self, key: &[u8]) -> Result<(), StateStoreError> {
+        self.apply(WriteOp::Delete { key: key.to_vec() })
+    }
+}
+
+impl<'a> VersionedStateWriter for LmdbTransaction<WriteTransaction<'a>> {
+    fn set_state_raw_at(&mut self, key: &[u8], height: u64, value: Vec<u8>) -> Result<(), StateStoreError> {
+        self.apply(WriteOp::PutHistory {
+            key: history_key(key, height),
+            value: encode_history_value(Some(&value)),
+        })?;
+        self.set_state_raw(key, value)
+    }
+
+    fn delete_state_raw_at(&mut self, key: &[u8], height: u64) -> Result<(), StateStoreError> {
+        self.apply(WriteOp::PutHistory {
+            key: history_key(key, height),
+            value: encode_history_value(None),
+        })?;
+        self.delete_state_raw(key)
     }
 }
 
@@ -110,6 +434,7 @@ impl<'a> StateWriter for LmdbTransaction<WriteTransaction<'a>> {
 mod tests {
 
     use borsh::{BorshDeserialize, BorshSerialize};
+    use tari_dan_engine::state_store::StateSnapshot;
     use tempfile::tempdir;
 
     use super::*;
@@ -128,7 +453,7 @@ mod tests {
         };
 
         let path = tempdir().unwrap();
-        let store = LmdbStateStore::new(&path);
+        let store = LmdbStateStore::new(&path).unwrap();
         {
             let mut access = store.write_access().unwrap();
             access.set_state(b"abc", user_data.clone()).unwrap();
@@ -157,4 +482,207 @@ mod tests {
         let res = access.get_state(b"abc").unwrap();
         assert_eq!(res, Some(user_data));
     }
+
+    #[test]
+    fn read_at_height() {
+        let path = tempdir().unwrap();
+        let store = LmdbStateStore::new(&path).unwrap();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_at(b"abc", 1, 1u32).unwrap();
+            access.set_state_at(b"abc", 5, 5u32).unwrap();
+            // A longer key sharing a prefix must not be mistaken for a version of "abc"
+            access.set_state_at(b"abcd", 3, 3u32).unwrap();
+            store.commit(access).unwrap();
+        }
+
+        let access = store.read_access().unwrap();
+        assert_eq!(access.get_state_at::<_, u32>(b"abc", 0).unwrap(), None);
+        assert_eq!(access.get_state_at(b"abc", 1).unwrap(), Some(1u32));
+        assert_eq!(access.get_state_at(b"abc", 4).unwrap(), Some(1u32));
+        assert_eq!(access.get_state_at(b"abc", 100).unwrap(), Some(5u32));
+        assert_eq!(access.get_state_at::<_, u32>(b"abcd", 2).unwrap(), None);
+        assert_eq!(access.get_state(b"abc").unwrap(), Some(5u32));
+
+        let snapshot = StateSnapshot::new(access, 4);
+        assert_eq!(snapshot.get_state(b"abc").unwrap(), Some(1u32));
+    }
+
+    #[test]
+    fn iterate_prefix_and_range() {
+        let path = tempdir().unwrap();
+        let store = LmdbStateStore::new(&path).unwrap();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_raw(b"a1", vec![1]).unwrap();
+            access.set_state_raw(b"b1", vec![2]).unwrap();
+            access.set_state_raw(b"b3", vec![3]).unwrap();
+            access.set_state_raw(b"c1", vec![4]).unwrap();
+            store.commit(access).unwrap();
+        }
+
+        let mut access = store.write_access().unwrap();
+        access.set_state_raw(b"b2", vec![5]).unwrap();
+        access.set_state_raw(b"b3", vec![6]).unwrap();
+        assert_eq!(access.iter_prefix(b"b").unwrap(), vec![
+            (b"b1".to_vec(), vec![2]),
+            (b"b2".to_vec(), vec![5]),
+            (b"b3".to_vec(), vec![6])
+        ]);
+        assert_eq!(access.iter_range(b"b2", None).unwrap().len(), 3);
+        assert!(access.iter_prefix(b"d").unwrap().is_empty());
+        drop(access);
+
+        let access = store.read_access().unwrap();
+        assert_eq!(access.iter_range(b"a", Some(b"b2")).unwrap(), vec![
+            (b"a1".to_vec(), vec![1]),
+            (b"b1".to_vec(), vec![2])
+        ]);
+    }
+
+    #[test]
+    fn snapshot_iterates_the_state_at_its_height() {
+        let path = tempdir().unwrap();
+        let store = LmdbStateStore::new(&path).unwrap();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_raw_at(b"a", 1, vec![1]).unwrap();
+            access.set_state_raw_at(b"b", 1, vec![2]).unwrap();
+            // Sorts after "b" in the history, which is ordered by key length first
+            access.set_state_raw_at(b"ab", 1, vec![3]).unwrap();
+            access.set_state_raw_at(b"c", 3, vec![4]).unwrap();
+            store.commit(access).unwrap();
+        }
+        {
+            let mut access = store.write_access().unwrap();
+            // Changed and removed from the latest state after the snapshot height
+            access.set_state_raw_at(b"a", 4, vec![5]).unwrap();
+            access.delete_state_raw(b"b").unwrap();
+            store.commit(access).unwrap();
+        }
+
+        let snapshot = StateSnapshot::new(store.read_access().unwrap(), 2);
+        assert_eq!(snapshot.iter_prefix(b"").unwrap(), vec![
+            (b"a".to_vec(), vec![1]),
+            (b"ab".to_vec(), vec![3]),
+            (b"b".to_vec(), vec![2])
+        ]);
+        assert_eq!(snapshot.iter_prefix(b"a").unwrap().len(), 2);
+        let snapshot = StateSnapshot::new(store.read_access().unwrap(), 4);
+        assert_eq!(snapshot.iter_range(b"a", Some(b"ab")).unwrap(), vec![(
+            b"a".to_vec(),
+            vec![5]
+        )]);
+        assert_eq!(snapshot.iter_range(b"c", None).unwrap(), vec![(b"c".to_vec(), vec![4])]);
+    }
+
+    #[test]
+    fn delete() {
+        let path = tempdir().unwrap();
+        let store = LmdbStateStore::new(&path).unwrap();
+        let mut access = store.write_access().unwrap();
+        access.set_state_raw(b"a", vec![1]).unwrap();
+        access.delete_state_raw(b"a").unwrap();
+        access.delete_state_raw(b"missing").unwrap();
+        assert!(!access.exists(b"a").unwrap());
+        store.commit(access).unwrap();
+        assert!(!store.read_access().unwrap().exists(b"a").unwrap());
+    }
+
+    #[test]
+    fn delete_at_height_records_a_tombstone() {
+        let path = tempdir().unwrap();
+        let store = LmdbStateStore::new(&path).unwrap();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_raw_at(b"a", 1, vec![1]).unwrap();
+            access.set_state_raw_at(b"b", 1, vec![2]).unwrap();
+            store.commit(access).unwrap();
+        }
+        {
+            let mut access = store.write_access().unwrap();
+            access.delete_state_raw_at(b"a", 3).unwrap();
+            store.commit(access).unwrap();
+        }
+
+        let access = store.read_access().unwrap();
+        assert!(!access.exists(b"a").unwrap());
+        assert_eq!(access.get_state_raw_at(b"a", 2).unwrap(), Some(vec![1]));
+        assert_eq!(access.get_state_raw_at(b"a", 3).unwrap(), None);
+        assert_eq!(access.iter_range_at(b"", None, 2).unwrap().len(), 2);
+        assert_eq!(access.iter_range_at(b"", None, 3).unwrap(), vec![(
+            b"b".to_vec(),
+            vec![2]
+        )]);
+        let snapshot = StateSnapshot::new(access, 3);
+        assert!(!snapshot.exists(b"a").unwrap());
+    }
+
+    #[test]
+    fn grow_fails_while_a_reader_is_open() {
+        let path = tempdir().unwrap();
+        let config = LmdbConfig {
+            map_size: 256 * 1024,
+            grow_size: 256 * 1024,
+            grow_timeout: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let store = LmdbStateStore::open(&path, config).unwrap();
+        let reader = store.read_access().unwrap();
+        let mut access = store.write_access().unwrap();
+        let result = (0u32..1000).try_for_each(|i| access.set_state_raw(&i.to_be_bytes(), vec![0xaa; 1024]));
+        assert!(result.is_err());
+        // The transaction was lost with the failed resize, so it reports an error instead of panicking
+        assert!(access.get_state_raw(b"a").is_err());
+        assert!(access.set_state_raw(b"a", vec![1]).is_err());
+        assert!(matches!(
+            store.commit(access),
+            Err(LmdbStorageError::TransactionAborted)
+        ));
+        drop(reader);
+
+        // Once the reader is closed the map can grow
+        let mut access = store.write_access().unwrap();
+        for i in 0u32..1000 {
+            access.set_state_raw(&i.to_be_bytes(), vec![0xaa; 1024]).unwrap();
+        }
+        store.commit(access).unwrap();
+    }
+
+    #[test]
+    fn grows_map_when_full() {
+        let path = tempdir().unwrap();
+        let config = LmdbConfig {
+            map_size: 256 * 1024,
+            grow_size: 256 * 1024,
+            ..Default::default()
+        };
+        let store = LmdbStateStore::open(&path, config).unwrap();
+        let mut access = store.write_access().unwrap();
+        for i in 0u32..1000 {
+            access.set_state_raw(&i.to_be_bytes(), vec![0xaa; 1024]).unwrap();
+        }
+        store.commit(access).unwrap();
+
+        let access = store.read_access().unwrap();
+        assert_eq!(access.iter_prefix(b"").unwrap().len(), 1000);
+        assert_eq!(
+            access.get_state_raw(&999u32.to_be_bytes()).unwrap(),
+            Some(vec![0xaa; 1024])
+        );
+    }
+
+    #[test]
+    fn named_databases_are_isolated() {
+        let path = tempdir().unwrap();
+        let store_a = LmdbStateStore::new(&path).unwrap();
+        let store_b = store_a.open_database("contract_b").unwrap();
+        {
+            let mut access = store_a.write_access().unwrap();
+            access.set_state_raw(b"key", vec![1]).unwrap();
+            store_a.commit(access).unwrap();
+        }
+        assert!(store_a.read_access().unwrap().exists(b"key").unwrap());
+        assert!(!store_b.read_access().unwrap().exists(b"key").unwrap());
+    }
 }
@@ -20,4 +20,13 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+mod config;
+pub use config::{LmdbConfig, SyncMode};
+
 pub mod engine_state_store;
+pub use engine_state_store::LmdbStateStore;
+
+mod error;
+pub use error::LmdbStorageError;
+
+mod transaction_tracker;
@@ -0,0 +1,136 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use crate::LmdbStorageError;
+
+/// Counts the transactions that are open on an environment in this process.
+///
+/// LMDB requires that no transactions are open in the process while the memory map is resized. A resize waits for the
+/// open transactions to close and new transactions wait for the resize to finish.
+#[derive(Debug, Default)]
+pub(crate) struct TransactionTracker {
+    state: Mutex<TrackerState>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    open_transactions: usize,
+    is_resizing: bool,
+}
+
+impl TransactionTracker {
+    /// Registers a new transaction, waiting for a resize in progress to finish. The transaction is open until the
+    /// returned guard is dropped.
+    pub fn begin(self: &Arc<Self>) -> Result<TransactionGuard, LmdbStorageError> {
+        let state = self.lock()?;
+        let mut state = self
+            .changed
+            .wait_while(state, |state| state.is_resizing)
+            .map_err(|_| LmdbStorageError::TransactionTrackerPoisoned)?;
+        state.open_transactions += 1;
+        Ok(TransactionGuard { tracker: self.clone() })
+    }
+
+    /// Runs `resize` once no transactions are open. Fails if transactions are still open after `timeout`, e.g. because
+    /// the thread that is resizing also holds a read transaction.
+    pub fn resize<T>(
+        &self,
+        timeout: Duration,
+        resize: impl FnOnce() -> Result<T, LmdbStorageError>,
+    ) -> Result<T, LmdbStorageError> {
+        let state = self.lock()?;
+        // Only one resize at a time
+        let mut state = self
+            .changed
+            .wait_while(state, |state| state.is_resizing)
+            .map_err(|_| LmdbStorageError::TransactionTrackerPoisoned)?;
+        state.is_resizing = true;
+        let (mut state, wait) = self
+            .changed
+            .wait_timeout_while(state, timeout, |state| state.open_transactions > 0)
+            .map_err(|_| LmdbStorageError::TransactionTrackerPoisoned)?;
+        let result = if wait.timed_out() {
+            Err(LmdbStorageError::ResizeTimedOut {
+                open_transactions: state.open_transactions,
+            })
+        } else {
+            resize()
+        };
+        state.is_resizing = false;
+        self.changed.notify_all();
+        result
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, TrackerState>, LmdbStorageError> {
+        self.state
+            .lock()
+            .map_err(|_| LmdbStorageError::TransactionTrackerPoisoned)
+    }
+}
+
+/// Marks a transaction as open until dropped
+#[derive(Debug)]
+pub(crate) struct TransactionGuard {
+    tracker: Arc<TransactionTracker>,
+}
+
+impl Drop for TransactionGuard {
+    fn drop(&mut self) {
+        // A poisoned lock means that a thread panicked while holding it, but the count is still consistent
+        let mut state = self.tracker.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.open_transactions -= 1;
+        self.tracker.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Instant};
+
+    use super::*;
+
+    #[test]
+    fn resize_waits_for_open_transactions() {
+        let tracker = Arc::new(TransactionTracker::default());
+        let guard = tracker.begin().unwrap();
+        let err = tracker.resize(Duration::from_millis(10), || Ok(())).unwrap_err();
+        assert!(matches!(err, LmdbStorageError::ResizeTimedOut { open_transactions: 1 }));
+
+        let start = Instant::now();
+        let reader = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            drop(guard);
+        });
+        tracker.resize(Duration::from_secs(10), || Ok(())).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        reader.join().unwrap();
+
+        // New transactions can begin after the resize
+        drop(tracker.begin().unwrap());
+    }
+}
@@ -0,0 +1 @@
+drop index state_op_log_schema_key_height_index;
@@ -0,0 +1 @@
+create index state_op_log_schema_key_height_index on state_op_log (schema, key, height);
@@ -0,0 +1 @@
+drop table state_tree_nodes;
@@ -0,0 +1,5 @@
+create table state_tree_nodes
+(
+    hash blob(32) primary key not null,
+    node blob              not null
+);
@@ -0,0 +1,2 @@
+alter table locked_qc drop column signer_bitmap;
+alter table prepare_qc drop column signer_bitmap;
@@ -0,0 +1,3 @@
+-- The signature column holds the signatures of the signers in the bitmap, in committee order
+alter table locked_qc add column signer_bitmap blob null;
+alter table prepare_qc add column signer_bitmap blob null;
@@ -0,0 +1 @@
+drop table misbehaviour_evidence;
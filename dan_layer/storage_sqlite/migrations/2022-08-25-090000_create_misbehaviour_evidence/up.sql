@@ -0,0 +1,17 @@
+-- Two conflicting messages signed by the offender in the same view and phase. The signatures hold the public nonce
+-- followed by the signature of each message.
+create table misbehaviour_evidence
+(
+    id               integer primary key autoincrement not null,
+    contract_id      blob    not null,
+    kind             integer not null,
+    offender         blob    not null,
+    view_number      bigint  not null,
+    message_type     integer not null,
+    first_node_hash  blob    not null,
+    first_signature  blob    not null,
+    second_node_hash blob    not null,
+    second_signature blob    not null
+);
+
+create index misbehaviour_evidence_offender_index on misbehaviour_evidence (offender);
@@ -0,0 +1 @@
+drop table last_vote;
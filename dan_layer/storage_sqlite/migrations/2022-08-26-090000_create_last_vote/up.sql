@@ -0,0 +1,9 @@
+-- The last vote cast by this node. It is written before the vote is sent, so that the node does not vote twice in
+-- the same view and phase after a restart.
+create table last_vote
+(
+    id           integer primary key not null, -- should always be 1 row
+    message_type integer not null,
+    view_number  bigint  not null,
+    node_hash    blob    not null
+);
@@ -0,0 +1 @@
+drop table state_pruning_horizon;
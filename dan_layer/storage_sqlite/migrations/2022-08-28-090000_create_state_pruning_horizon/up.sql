@@ -0,0 +1,6 @@
+-- The lowest height that state can still be read at. History below it has been pruned from the state op log.
+create table state_pruning_horizon
+(
+    id                   integer primary key not null, -- should always be 1 row
+    retained_from_height bigint  not null
+);
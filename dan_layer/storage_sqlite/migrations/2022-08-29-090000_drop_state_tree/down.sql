@@ -0,0 +1,7 @@
+create table state_tree
+(
+    id         integer primary key autoincrement not null,
+    version    integer not null,
+    is_current boolean not null,
+    data       blob    not null
+);
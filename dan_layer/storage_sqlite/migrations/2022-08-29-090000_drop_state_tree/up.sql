@@ -0,0 +1,2 @@
+-- The state tree is stored as content-addressed nodes in state_tree_nodes, so the serialized tree is no longer used
+drop table state_tree;
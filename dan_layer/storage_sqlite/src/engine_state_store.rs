@@ -20,86 +20,138 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use diesel::{connection::TransactionManager, Connection, OptionalExtension, QueryDsl, RunQueryDsl, SqliteConnection};
-use tari_dan_engine::state_store::{AtomicDb, StateReader, StateStoreError, StateWriter};
+use std::{
+    ops::Deref,
+    sync::{Mutex, MutexGuard},
+};
+
+use diesel::{
+    connection::{SimpleConnection, TransactionManager},
+    r2d2::{ConnectionManager, CustomizeConnection, Pool, PooledConnection},
+    Connection,
+    OptionalExtension,
+    QueryDsl,
+    RunQueryDsl,
+    SqliteConnection,
+};
+use tari_dan_engine::state_store::{AtomicDb, StateEntry, StateReader, StateStoreError, StateWriter};
 
 use crate::{diesel::ExpressionMethods, error::SqliteStorageError, schema::metadata};
+
+const DEFAULT_MAX_READERS: u32 = 8;
+const BUSY_TIMEOUT_MS: u32 = 5000;
+
+type PooledSqliteConnection = PooledConnection<ConnectionManager<SqliteConnection>>;
+
+/// State store backed by a SQLite database file in WAL mode. All writes go through a single connection, while read
+/// transactions are served from a pool of connections so that readers are not blocked by an open write transaction.
+///
+/// The database must be a file: each connection to `:memory:` opens its own private database.
 pub struct SqliteStateStore {
-    conn: SqliteConnection,
+    readers: Pool<ConnectionManager<SqliteConnection>>,
+    writer: Mutex<SqliteConnection>,
 }
 
 impl SqliteStateStore {
     pub fn try_connect(url: &str) -> Result<Self, SqliteStorageError> {
-        let conn = SqliteConnection::establish(url)?;
-        conn.execute("PRAGMA foreign_keys = ON;")
-            .map_err(|source| SqliteStorageError::DieselError {
-                source,
-                operation: "set pragma".to_string(),
-            })?;
-        Ok(Self { conn })
+        Self::try_connect_with_max_readers(url, DEFAULT_MAX_READERS)
+    }
+
+    pub fn try_connect_with_max_readers(url: &str, max_readers: u32) -> Result<Self, SqliteStorageError> {
+        let writer = SqliteConnection::establish(url)?;
+        configure_connection(&writer).map_err(|source| SqliteStorageError::DieselError {
+            source,
+            operation: "set pragma".to_string(),
+        })?;
+        let readers = Pool::builder()
+            .max_size(max_readers)
+            .connection_customizer(Box::new(ConnectionPragmas))
+            .build(ConnectionManager::new(url))?;
+        Ok(Self {
+            readers,
+            writer: Mutex::new(writer),
+        })
     }
 
     pub fn migrate(&self) -> Result<(), SqliteStorageError> {
         embed_migrations!("./migrations");
-        embedded_migrations::run(&self.conn)?;
+        embedded_migrations::run(&*self.lock_writer()?)?;
         Ok(())
     }
 
-    fn access(&self) -> Result<SqliteTransaction<'_>, SqliteStorageError> {
-        let manager = self.conn.transaction_manager();
-        manager
-            .begin_transaction(&self.conn)
-            .map_err(|err| SqliteStorageError::DieselError {
-                source: err,
-                operation: "begin transaction".to_string(),
-            })?;
-        Ok(SqliteTransaction::new(&self.conn))
+    fn lock_writer(&self) -> Result<MutexGuard<'_, SqliteConnection>, SqliteStorageError> {
+        self.writer.lock().map_err(|_| SqliteStorageError::WriterLockPoisoned)
+    }
+}
+
+fn configure_connection(conn: &SqliteConnection) -> Result<(), diesel::result::Error> {
+    conn.batch_execute(&format!(
+        "PRAGMA journal_mode = WAL; PRAGMA busy_timeout = {}; PRAGMA foreign_keys = ON;",
+        BUSY_TIMEOUT_MS
+    ))
+}
+
+#[derive(Debug)]
+struct ConnectionPragmas;
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionPragmas {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        configure_connection(conn).map_err(diesel::r2d2::Error::QueryError)
     }
 }
 
 impl<'a> AtomicDb<'a> for SqliteStateStore {
     type Error = SqliteStorageError;
-    type ReadAccess = SqliteTransaction<'a>;
-    type WriteAccess = SqliteTransaction<'a>;
+    type ReadAccess = SqliteTransaction<PooledSqliteConnection>;
+    type WriteAccess = SqliteTransaction<MutexGuard<'a, SqliteConnection>>;
 
     fn read_access(&'a self) -> Result<Self::ReadAccess, Self::Error> {
-        self.access()
+        SqliteTransaction::begin(self.readers.get()?, "BEGIN DEFERRED")
     }
 
     fn write_access(&'a self) -> Result<Self::WriteAccess, Self::Error> {
-        self.access()
+        // Take the write lock when the transaction begins, rather than failing to upgrade a read lock later on
+        SqliteTransaction::begin(self.lock_writer()?, "BEGIN IMMEDIATE")
     }
 
-    fn commit(&self, tx: Self::WriteAccess) -> Result<(), Self::Error> {
-        self.conn
+    fn commit(&self, mut tx: Self::WriteAccess) -> Result<(), Self::Error> {
+        tx.conn
             .transaction_manager()
-            .commit_transaction(tx.conn)
+            .commit_transaction(&*tx.conn)
             .map_err(|err| SqliteStorageError::DieselError {
                 source: err,
                 operation: "commit transaction".to_string(),
             })?;
+        tx.is_open = false;
 
         Ok(())
     }
 }
 
-pub struct SqliteTransaction<'a> {
-    conn: &'a SqliteConnection,
+pub struct SqliteTransaction<C: Deref<Target = SqliteConnection>> {
+    conn: C,
+    is_open: bool,
 }
 
-impl<'a> SqliteTransaction<'a> {
-    fn new(conn: &'a SqliteConnection) -> Self {
-        Self { conn }
+impl<C: Deref<Target = SqliteConnection>> SqliteTransaction<C> {
+    fn begin(conn: C, sql: &str) -> Result<Self, SqliteStorageError> {
+        conn.transaction_manager()
+            .begin_transaction_sql(&*conn, sql)
+            .map_err(|err| SqliteStorageError::DieselError {
+                source: err,
+                operation: "begin transaction".to_string(),
+            })?;
+        Ok(Self { conn, is_open: true })
     }
 }
 
-impl<'a> StateReader for SqliteTransaction<'a> {
+impl<C: Deref<Target = SqliteConnection>> StateReader for SqliteTransaction<C> {
     fn get_state_raw(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StateStoreError> {
         use crate::schema::metadata::dsl;
         let val = dsl::metadata
             .select(metadata::value)
             .filter(metadata::key.eq(key))
-            .first::<Vec<u8>>(self.conn)
+            .first::<Vec<u8>>(&*self.conn)
             .optional()
             .map_err(|source| {
                 StateStoreError::custom(SqliteStorageError::DieselError {
@@ -117,7 +169,7 @@ impl<'a> StateReader for SqliteTransaction<'a> {
             .count()
             .filter(metadata::key.eq(key))
             .limit(1)
-            .first::<i64>(self.conn)
+            .first::<i64>(&*self.conn)
             .map_err(|source| {
                 StateStoreError::custom(SqliteStorageError::DieselError {
                     source,
@@ -127,9 +179,28 @@ impl<'a> StateReader for SqliteTransaction<'a> {
 
         Ok(val > 0)
     }
+
+    fn iter_range(&self, start: &[u8], end: Option<&[u8]>) -> Result<Vec<StateEntry>, StateStoreError> {
+        use crate::schema::metadata::dsl;
+        // SQLite compares blobs with memcmp, which gives the same order as the other state stores
+        let mut query = dsl::metadata
+            .select((metadata::key, metadata::value))
+            .filter(metadata::key.ge(start))
+            .order_by(metadata::key.asc())
+            .into_boxed();
+        if let Some(end) = end {
+            query = query.filter(metadata::key.lt(end));
+        }
+        query.load(&*self.conn).map_err(|source| {
+            StateStoreError::custom(SqliteStorageError::DieselError {
+                source,
+                operation: "iter_range".to_string(),
+            })
+        })
+    }
 }
 
-impl<'a> StateWriter for SqliteTransaction<'a> {
+impl<'a> StateWriter for SqliteTransaction<MutexGuard<'a, SqliteConnection>> {
     fn set_state_raw(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), StateStoreError> {
         use crate::schema::metadata::dsl;
 
@@ -137,7 +208,7 @@ impl<'a> StateWriter for SqliteTransaction<'a> {
         match self.get_state_raw(key) {
             Ok(Some(_)) => diesel::update(dsl::metadata.filter(metadata::key.eq(key)))
                 .set(metadata::value.eq(value))
-                .execute(self.conn)
+                .execute(&*self.conn)
                 .map_err(|source| {
                     StateStoreError::custom(SqliteStorageError::DieselError {
                         source,
@@ -146,7 +217,7 @@ impl<'a> StateWriter for SqliteTransaction<'a> {
                 })?,
             Ok(None) => diesel::insert_into(metadata::table)
                 .values((metadata::key.eq(key), metadata::value.eq(value)))
-                .execute(self.conn)
+                .execute(&*self.conn)
                 .map_err(|source| {
                     StateStoreError::custom(SqliteStorageError::DieselError {
                         source,
@@ -158,11 +229,29 @@ impl<'a> StateWriter for SqliteTransaction<'a> {
 
         Ok(())
     }
+
+    fn delete_state_raw(&mut self, key: &[u8]) -> Result<(), StateStoreError> {
+        use crate::schema::metadata::dsl;
+
+        diesel::delete(dsl::metadata.filter(metadata::key.eq(key)))
+            .execute(&*self.conn)
+            .map_err(|source| {
+                StateStoreError::custom(SqliteStorageError::DieselError {
+                    source,
+                    operation: "delete::metadata".to_string(),
+                })
+            })?;
+
+        Ok(())
+    }
 }
 
-impl Drop for SqliteTransaction<'_> {
+impl<C: Deref<Target = SqliteConnection>> Drop for SqliteTransaction<C> {
     fn drop(&mut self) {
-        if let Err(err) = self.conn.transaction_manager().rollback_transaction(self.conn) {
+        if !self.is_open {
+            return;
+        }
+        if let Err(err) = self.conn.transaction_manager().rollback_transaction(&*self.conn) {
             log::error!("Error rolling back transaction: {:?}", err);
         }
     }
@@ -171,9 +260,17 @@ impl Drop for SqliteTransaction<'_> {
 #[cfg(test)]
 mod tests {
     use borsh::{BorshDeserialize, BorshSerialize};
+    use tempfile::{tempdir, TempDir};
 
     use super::*;
 
+    fn create_store() -> (TempDir, SqliteStateStore) {
+        let temp_dir = tempdir().unwrap();
+        let store = SqliteStateStore::try_connect(temp_dir.path().join("state.sqlite").to_str().unwrap()).unwrap();
+        store.migrate().unwrap();
+        (temp_dir, store)
+    }
+
     #[test]
     fn read_write_rollback_commit() {
         #[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone)]
@@ -187,8 +284,7 @@ mod tests {
             age: 99,
         };
 
-        let store = SqliteStateStore::try_connect(":memory:").unwrap();
-        store.migrate().unwrap();
+        let (_temp_dir, store) = create_store();
         {
             let mut access = store.write_access().unwrap();
             access.set_state(b"abc", user_data.clone()).unwrap();
@@ -217,4 +313,69 @@ mod tests {
         let res = access.get_state(b"abc").unwrap();
         assert_eq!(res, Some(user_data));
     }
+
+    #[test]
+    fn iterate_prefix_and_range() {
+        let (_temp_dir, store) = create_store();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_raw(b"a1", vec![1]).unwrap();
+            access.set_state_raw(b"b1", vec![2]).unwrap();
+            access.set_state_raw(b"b3", vec![3]).unwrap();
+            access.set_state_raw(b"c1", vec![4]).unwrap();
+            store.commit(access).unwrap();
+        }
+
+        let mut access = store.write_access().unwrap();
+        access.set_state_raw(b"b2", vec![5]).unwrap();
+        access.set_state_raw(b"b3", vec![6]).unwrap();
+        assert_eq!(access.iter_prefix(b"b").unwrap(), vec![
+            (b"b1".to_vec(), vec![2]),
+            (b"b2".to_vec(), vec![5]),
+            (b"b3".to_vec(), vec![6])
+        ]);
+        assert_eq!(access.iter_range(b"b2", None).unwrap().len(), 3);
+        assert!(access.iter_prefix(b"d").unwrap().is_empty());
+    }
+
+    #[test]
+    fn delete() {
+        let (_temp_dir, store) = create_store();
+        let mut access = store.write_access().unwrap();
+        access.set_state_raw(b"a", vec![1]).unwrap();
+        access.delete_state_raw(b"a").unwrap();
+        access.delete_state_raw(b"missing").unwrap();
+        assert!(!access.exists(b"a").unwrap());
+        assert_eq!(access.get_state_raw(b"a").unwrap(), None);
+    }
+
+    #[test]
+    fn reads_are_not_blocked_by_an_open_write() {
+        let (_temp_dir, store) = create_store();
+        {
+            let mut access = store.write_access().unwrap();
+            access.set_state_raw(b"a", vec![1]).unwrap();
+            store.commit(access).unwrap();
+        }
+
+        let mut write = store.write_access().unwrap();
+        write.set_state_raw(b"a", vec![2]).unwrap();
+        write.set_state_raw(b"b", vec![3]).unwrap();
+
+        // Readers see the last committed state while the write transaction is open
+        let read = store.read_access().unwrap();
+        assert_eq!(read.get_state_raw(b"a").unwrap(), Some(vec![1]));
+        let other_read = store.read_access().unwrap();
+        assert!(!other_read.exists(b"b").unwrap());
+
+        store.commit(write).unwrap();
+        // A read transaction keeps its snapshot until it ends
+        assert_eq!(read.get_state_raw(b"a").unwrap(), Some(vec![1]));
+        drop(read);
+        drop(other_read);
+
+        let read = store.read_access().unwrap();
+        assert_eq!(read.get_state_raw(b"a").unwrap(), Some(vec![2]));
+        assert_eq!(read.get_state_raw(b"b").unwrap(), Some(vec![3]));
+    }
 }
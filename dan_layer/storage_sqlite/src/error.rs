@@ -32,6 +32,13 @@ pub enum SqliteStorageError {
         #[from]
         source: diesel::ConnectionError,
     },
+    #[error("Could not get a connection from the pool: {source}")]
+    PoolError {
+        #[from]
+        source: diesel::r2d2::PoolError,
+    },
+    #[error("The writer connection lock was poisoned")]
+    WriterLockPoisoned,
     #[error("General diesel error during operation {operation}: {source}")]
     DieselError {
         source: diesel::result::Error,
@@ -52,13 +59,17 @@ pub enum SqliteStorageError {
     ConversionError { reason: String },
     #[error("Malformed metadata for key '{key}'")]
     MalformedMetadata { key: String },
+    #[error("State at height {height} has been pruned, the earliest readable height is {retained_from_height}")]
+    StatePruned { height: u64, retained_from_height: u64 },
 }
 
 impl From<SqliteStorageError> for StorageError {
     fn from(source: SqliteStorageError) -> Self {
         match source {
-            SqliteStorageError::ConnectionError { .. } => StorageError::ConnectionError {
-                reason: source.to_string(),
+            SqliteStorageError::ConnectionError { .. } | SqliteStorageError::PoolError { .. } => {
+                StorageError::ConnectionError {
+                    reason: source.to_string(),
+                }
             },
             SqliteStorageError::DieselError { .. } => StorageError::QueryError {
                 reason: source.to_string(),
@@ -76,8 +87,10 @@ impl From<SqliteStorageError> for StorageError {
 impl From<SqliteStorageError> for StateStorageError {
     fn from(source: SqliteStorageError) -> Self {
         match source {
-            SqliteStorageError::ConnectionError { .. } => StateStorageError::ConnectionError {
-                reason: source.to_string(),
+            SqliteStorageError::ConnectionError { .. } | SqliteStorageError::PoolError { .. } => {
+                StateStorageError::ConnectionError {
+                    reason: source.to_string(),
+                }
             },
             SqliteStorageError::DieselError { .. } => StateStorageError::QueryError {
                 reason: source.to_string(),
@@ -85,6 +98,13 @@ impl From<SqliteStorageError> for StateStorageError {
             SqliteStorageError::MigrationError { .. } => StateStorageError::MigrationError {
                 reason: source.to_string(),
             },
+            SqliteStorageError::StatePruned {
+                height,
+                retained_from_height,
+            } => StateStorageError::StatePruned {
+                height,
+                retained_from_height,
+            },
             other => StateStorageError::General {
                 details: other.to_string(),
             },
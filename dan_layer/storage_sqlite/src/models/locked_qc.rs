@@ -27,4 +27,5 @@ pub struct LockedQc {
     pub view_number: i64,
     pub node_hash: Vec<u8>,
     pub signature: Option<Vec<u8>>,
+    pub signer_bitmap: Option<Vec<u8>>,
 }
@@ -21,8 +21,10 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 pub mod instruction;
+pub mod last_vote;
 pub mod locked_qc;
 pub mod metadata;
+pub mod misbehaviour_evidence;
 pub mod node;
 pub mod prepare_qc;
 pub mod state_key;
@@ -32,6 +32,15 @@ table! {
     }
 }
 
+table! {
+    last_vote (id) {
+        id -> Integer,
+        message_type -> Integer,
+        view_number -> BigInt,
+        node_hash -> Binary,
+    }
+}
+
 table! {
     locked_qc (id) {
         id -> Integer,
@@ -39,6 +48,7 @@ table! {
         view_number -> BigInt,
         node_hash -> Binary,
         signature -> Nullable<Binary>,
+        signer_bitmap -> Nullable<Binary>,
     }
 }
 
@@ -49,6 +59,21 @@ table! {
     }
 }
 
+table! {
+    misbehaviour_evidence (id) {
+        id -> Integer,
+        contract_id -> Binary,
+        kind -> Integer,
+        offender -> Binary,
+        view_number -> BigInt,
+        message_type -> Integer,
+        first_node_hash -> Binary,
+        first_signature -> Binary,
+        second_node_hash -> Binary,
+        second_signature -> Binary,
+    }
+}
+
 table! {
     nodes (id) {
         id -> Integer,
@@ -66,6 +91,7 @@ table! {
         view_number -> BigInt,
         node_hash -> Binary,
         signature -> Nullable<Binary>,
+        signer_bitmap -> Nullable<Binary>,
     }
 }
 
@@ -90,11 +116,16 @@ table! {
 }
 
 table! {
-    state_tree (id) {
+    state_pruning_horizon (id) {
         id -> Integer,
-        version -> Integer,
-        is_current -> Bool,
-        data -> Binary,
+        retained_from_height -> BigInt,
+    }
+}
+
+table! {
+    state_tree_nodes (hash) {
+        hash -> Binary,
+        node -> Binary,
     }
 }
 
@@ -102,11 +133,14 @@ joinable!(instructions -> nodes (node_id));
 
 allow_tables_to_appear_in_same_query!(
     instructions,
+    last_vote,
     locked_qc,
     metadata,
+    misbehaviour_evidence,
     nodes,
     prepare_qc,
     state_keys,
     state_op_log,
-    state_tree,
+    state_pruning_horizon,
+    state_tree_nodes,
 );
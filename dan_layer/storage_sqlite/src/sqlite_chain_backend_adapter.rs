@@ -27,10 +27,22 @@ use std::{
 
 use diesel::{prelude::*, Connection, SqliteConnection};
 use log::*;
+use tari_common_types::types::{FixedHash, PrivateKey, PublicKey, Signature};
 use tari_dan_core::{
-    models::{HotStuffMessageType, QuorumCertificate, TariDanPayload, TreeNodeHash, ValidatorSignature, ViewId},
+    models::{
+        HotStuffMessageType,
+        MisbehaviourEvidence,
+        MisbehaviourKind,
+        QuorumCertificate,
+        QuorumSignatures,
+        SignedConsensusMessage,
+        TariDanPayload,
+        TreeNodeHash,
+        ValidatorSignature,
+        ViewId,
+    },
     storage::{
-        chain::{ChainDbBackendAdapter, DbInstruction, DbNode, DbQc},
+        chain::{ChainDbBackendAdapter, ChainPruningStats, DbInstruction, DbNode, DbQc, DbVote},
         AsKeyBytes,
         AtomicDb,
         MetadataBackendAdapter,
@@ -42,8 +54,10 @@ use crate::{
     error::SqliteStorageError,
     models::{
         instruction::{Instruction, NewInstruction},
+        last_vote::LastVote,
         locked_qc::LockedQc,
         metadata::Metadata,
+        misbehaviour_evidence::{self as db_evidence, NewMisbehaviourEvidence},
         node::{NewNode, Node},
         prepare_qc::PrepareQc,
     },
@@ -161,6 +175,53 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
         }
     }
 
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn get_committed_node_heights(&self, from_height: u32, to_height: u32) -> Result<Vec<u32>, Self::Error> {
+        use crate::schema::nodes::dsl;
+
+        let connection = self.get_connection()?;
+        let heights = dsl::nodes
+            .select(dsl::height)
+            .filter(dsl::is_committed.eq(true))
+            .filter(dsl::height.ge(from_height as i32))
+            .filter(dsl::height.lt(to_height as i32))
+            .order_by(dsl::height.asc())
+            .load::<i32>(&connection)
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_committed_node_heights".to_string(),
+            })?;
+
+        Ok(heights.into_iter().map(|height| height as u32).collect())
+    }
+
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn find_lowest_committed_node_from(&self, height: u32) -> Result<Option<(Self::Id, DbNode)>, Self::Error> {
+        use crate::schema::nodes::dsl;
+
+        let connection = self.get_connection()?;
+        let node = dsl::nodes
+            .filter(dsl::is_committed.eq(true))
+            .filter(dsl::height.ge(height as i32))
+            .order_by(dsl::height.asc())
+            .first::<Node>(&connection)
+            .optional()
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "find_lowest_committed_node_from".to_string(),
+            })?;
+
+        match node {
+            Some(node) => Ok(Some((node.id, DbNode {
+                hash: node.hash.try_into()?,
+                parent: node.parent.try_into()?,
+                height: node.height as u32,
+                is_committed: node.is_committed,
+            }))),
+            None => Ok(None),
+        }
+    }
+
     fn insert_node(&self, item: &DbNode, transaction: &Self::DbTransaction) -> Result<(), Self::Error> {
         debug!(target: LOG_TARGET, "Inserting {:?}", item);
         #[allow(clippy::cast_possible_wrap)]
@@ -209,7 +270,8 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
                         dsl::message_type.eq(message_type),
                         dsl::view_number.eq(item.view_number.0 as i64),
                         dsl::node_hash.eq(item.node_hash.as_bytes()),
-                        dsl::signature.eq(item.signature.as_ref().map(|s| s.to_bytes())),
+                        dsl::signature.eq(signatures_to_bytes(&item.signatures)),
+                        dsl::signer_bitmap.eq(item.signatures.signer_bitmap()),
                     ))
                     .execute(transaction.connection())
                     .map_err(|source| SqliteStorageError::DieselError {
@@ -224,7 +286,8 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
                         dsl::message_type.eq(message_type),
                         dsl::view_number.eq(item.view_number.0 as i64),
                         dsl::node_hash.eq(item.node_hash.as_bytes()),
-                        dsl::signature.eq(item.signature.as_ref().map(|s| s.to_bytes())),
+                        dsl::signature.eq(signatures_to_bytes(&item.signatures)),
+                        dsl::signer_bitmap.eq(item.signatures.signer_bitmap()),
                     ))
                     .execute(transaction.connection())
                     .map_err(|source| SqliteStorageError::DieselError {
@@ -248,7 +311,8 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
                         dsl::message_type.eq(message_type),
                         dsl::view_number.eq(item.view_number.0 as i64),
                         dsl::node_hash.eq(item.node_hash.as_bytes()),
-                        dsl::signature.eq(item.signature.as_ref().map(|s| s.to_bytes())),
+                        dsl::signature.eq(signatures_to_bytes(&item.signatures)),
+                        dsl::signer_bitmap.eq(item.signatures.signer_bitmap()),
                     ))
                     .execute(transaction.connection())
                     .map_err(|source| SqliteStorageError::DieselError {
@@ -263,7 +327,8 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
                         dsl::message_type.eq(message_type),
                         dsl::view_number.eq(item.view_number.0 as i64),
                         dsl::node_hash.eq(item.node_hash.as_bytes()),
-                        dsl::signature.eq(item.signature.as_ref().map(|s| s.to_bytes())),
+                        dsl::signature.eq(signatures_to_bytes(&item.signatures)),
+                        dsl::signer_bitmap.eq(item.signatures.signer_bitmap()),
                     ))
                     .execute(transaction.connection())
                     .map_err(|source| SqliteStorageError::DieselError {
@@ -275,6 +340,66 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
         Ok(())
     }
 
+    #[allow(clippy::cast_sign_loss)]
+    fn get_last_vote(&self) -> Result<Option<DbVote>, Self::Error> {
+        use crate::schema::last_vote::dsl;
+        let connection = self.get_connection()?;
+        let vote: Option<LastVote> =
+            dsl::last_vote
+                .find(1)
+                .first(&connection)
+                .optional()
+                .map_err(|source| SqliteStorageError::DieselError {
+                    source,
+                    operation: "get_last_vote".to_string(),
+                })?;
+        vote.map(|vote| {
+            Ok(DbVote {
+                message_type: HotStuffMessageType::try_from(u8::try_from(vote.message_type).unwrap()).unwrap(),
+                view_number: ViewId::from(vote.view_number as u64),
+                node_hash: vote.node_hash.try_into()?,
+            })
+        })
+        .transpose()
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn update_last_vote(&self, vote: &DbVote, transaction: &Self::DbTransaction) -> Result<(), Self::Error> {
+        use crate::schema::last_vote::dsl;
+        let message_type = i32::from(vote.message_type.as_u8());
+        let existing: Result<LastVote, _> = dsl::last_vote.find(1).first(transaction.connection());
+        match existing {
+            Ok(_) => {
+                diesel::update(dsl::last_vote.find(1))
+                    .set((
+                        dsl::message_type.eq(message_type),
+                        dsl::view_number.eq(vote.view_number.0 as i64),
+                        dsl::node_hash.eq(vote.node_hash.as_bytes()),
+                    ))
+                    .execute(transaction.connection())
+                    .map_err(|source| SqliteStorageError::DieselError {
+                        source,
+                        operation: "update::last_vote".to_string(),
+                    })?;
+            },
+            Err(_) => {
+                diesel::insert_into(last_vote::table)
+                    .values((
+                        dsl::id.eq(1),
+                        dsl::message_type.eq(message_type),
+                        dsl::view_number.eq(vote.view_number.0 as i64),
+                        dsl::node_hash.eq(vote.node_hash.as_bytes()),
+                    ))
+                    .execute(transaction.connection())
+                    .map_err(|source| SqliteStorageError::DieselError {
+                        source,
+                        operation: "insert::last_vote".to_string(),
+                    })?;
+            },
+        }
+        Ok(())
+    }
+
     #[allow(clippy::cast_sign_loss)]
     fn get_prepare_qc(&self) -> Result<Option<QuorumCertificate>, Self::Error> {
         let connection = self.get_connection()?;
@@ -292,7 +417,7 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
                 HotStuffMessageType::try_from(u8::try_from(qc.message_type).unwrap()).unwrap(),
                 ViewId::from(qc.view_number as u64),
                 qc.node_hash.try_into()?,
-                qc.signature.map(|s| ValidatorSignature::from_bytes(s.as_slice())),
+                signatures_from_db(qc.signer_bitmap, qc.signature)?,
             ))
         })
         .transpose()
@@ -335,6 +460,7 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
                     view_number: l.view_number,
                     node_hash: l.node_hash.clone(),
                     signature: l.signature,
+                    signer_bitmap: l.signer_bitmap,
                 }
             },
         };
@@ -343,7 +469,7 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
             HotStuffMessageType::try_from(u8::try_from(qc.message_type).unwrap()).unwrap(),
             ViewId::from(qc.view_number as u64),
             qc.node_hash.try_into()?,
-            qc.signature.map(|s| ValidatorSignature::from_bytes(s.as_slice())),
+            signatures_from_db(qc.signer_bitmap, qc.signature)?,
         ))
     }
 
@@ -362,7 +488,7 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
             HotStuffMessageType::try_from(u8::try_from(qc.message_type).unwrap()).unwrap(),
             ViewId::from(qc.view_number as u64),
             qc.node_hash.try_into()?,
-            qc.signature.map(|s| ValidatorSignature::from_bytes(s.as_slice())),
+            signatures_from_db(qc.signer_bitmap, qc.signature)?,
         ))
     }
 
@@ -413,6 +539,31 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
         }
     }
 
+    #[allow(clippy::cast_sign_loss)]
+    fn find_uncommitted_nodes(&self) -> Result<Vec<(Self::Id, DbNode)>, Self::Error> {
+        use crate::schema::nodes::dsl;
+        let connection = self.get_connection()?;
+        let nodes = dsl::nodes
+            .filter(dsl::is_committed.eq(false))
+            .order_by(dsl::height.asc())
+            .load::<Node>(&connection)
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "find_uncommitted_nodes".to_string(),
+            })?;
+
+        let mut uncommitted_nodes = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            uncommitted_nodes.push((node.id, DbNode {
+                hash: node.hash.try_into()?,
+                parent: node.parent.try_into()?,
+                height: node.height as u32,
+                is_committed: node.is_committed,
+            }));
+        }
+        Ok(uncommitted_nodes)
+    }
+
     fn insert_instruction(&self, item: &DbInstruction, transaction: &Self::DbTransaction) -> Result<(), Self::Error> {
         use crate::schema::nodes::dsl;
         // TODO: this could be made more efficient
@@ -471,6 +622,81 @@ impl ChainDbBackendAdapter for SqliteChainBackendAdapter {
 
         Ok(instructions)
     }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn delete_committed_nodes_below(
+        &self,
+        height: u32,
+        transaction: &Self::DbTransaction,
+    ) -> Result<ChainPruningStats, Self::Error> {
+        use crate::schema::{instructions::dsl as instructions_dsl, nodes::dsl as nodes_dsl};
+        let pruned_nodes = nodes_dsl::nodes
+            .select(nodes_dsl::id)
+            .filter(nodes_dsl::is_committed.eq(true))
+            .filter(nodes_dsl::height.lt(height as i32));
+        let instructions_removed =
+            diesel::delete(instructions_dsl::instructions.filter(instructions_dsl::node_id.eq_any(pruned_nodes)))
+                .execute(transaction.connection())
+                .map_err(|source| SqliteStorageError::DieselError {
+                    source,
+                    operation: "delete_committed_nodes_below::instructions".to_string(),
+                })?;
+        let nodes_removed = diesel::delete(
+            nodes_dsl::nodes
+                .filter(nodes_dsl::is_committed.eq(true))
+                .filter(nodes_dsl::height.lt(height as i32)),
+        )
+        .execute(transaction.connection())
+        .map_err(|source| SqliteStorageError::DieselError {
+            source,
+            operation: "delete_committed_nodes_below::nodes".to_string(),
+        })?;
+
+        Ok(ChainPruningStats {
+            nodes_removed: nodes_removed as u64,
+            instructions_removed: instructions_removed as u64,
+        })
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn insert_misbehaviour_evidence(
+        &self,
+        evidence: &MisbehaviourEvidence,
+        transaction: &Self::DbTransaction,
+    ) -> Result<(), Self::Error> {
+        let new_evidence = NewMisbehaviourEvidence {
+            contract_id: evidence.contract_id.as_slice().to_vec(),
+            kind: i32::from(evidence.kind.as_u8()),
+            offender: evidence.offender().to_vec(),
+            view_number: evidence.view_number().as_u64() as i64,
+            message_type: i32::from(evidence.first.message_type.as_u8()),
+            first_node_hash: evidence.first.node_hash.as_bytes().to_vec(),
+            first_signature: signature_to_bytes(evidence.first.signature.signature()),
+            second_node_hash: evidence.second.node_hash.as_bytes().to_vec(),
+            second_signature: signature_to_bytes(evidence.second.signature.signature()),
+        };
+        diesel::insert_into(misbehaviour_evidence::table)
+            .values(new_evidence)
+            .execute(transaction.connection())
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "insert_misbehaviour_evidence".to_string(),
+            })?;
+        Ok(())
+    }
+
+    fn get_misbehaviour_evidence(&self) -> Result<Vec<MisbehaviourEvidence>, Self::Error> {
+        use crate::schema::misbehaviour_evidence::dsl;
+        let connection = self.get_connection()?;
+        let rows = dsl::misbehaviour_evidence
+            .order_by(dsl::id.asc())
+            .load::<db_evidence::MisbehaviourEvidence>(&connection)
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_misbehaviour_evidence".to_string(),
+            })?;
+        rows.into_iter().map(evidence_from_db).collect()
+    }
 }
 
 impl<K: AsKeyBytes + Display + Copy> MetadataBackendAdapter<K> for SqliteChainBackendAdapter {
@@ -545,3 +771,86 @@ impl<K: AsKeyBytes + Display + Copy> MetadataBackendAdapter<K> for SqliteChainBa
         Ok(v.is_some())
     }
 }
+
+const SIGNATURE_SIZE: usize = 64;
+
+fn signature_to_bytes(signature: &Signature) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(SIGNATURE_SIZE);
+    bytes.extend_from_slice(signature.get_public_nonce().as_bytes());
+    bytes.extend_from_slice(signature.get_signature().as_bytes());
+    bytes
+}
+
+fn signature_from_bytes(bytes: &[u8]) -> Result<Signature, SqliteStorageError> {
+    if bytes.len() != SIGNATURE_SIZE {
+        return Err(SqliteStorageError::MalformedDbData(format!(
+            "signature has invalid length {}",
+            bytes.len()
+        )));
+    }
+    let (public_nonce, signature) = bytes.split_at(SIGNATURE_SIZE / 2);
+    Ok(Signature::new(
+        PublicKey::from_bytes(public_nonce).map_err(|err| SqliteStorageError::MalformedDbData(err.to_string()))?,
+        PrivateKey::from_bytes(signature).map_err(|err| SqliteStorageError::MalformedDbData(err.to_string()))?,
+    ))
+}
+
+#[allow(clippy::cast_sign_loss)]
+fn evidence_from_db(row: db_evidence::MisbehaviourEvidence) -> Result<MisbehaviourEvidence, SqliteStorageError> {
+    let kind = u8::try_from(row.kind)
+        .map_err(|err| err.to_string())
+        .and_then(MisbehaviourKind::try_from)
+        .map_err(|reason| SqliteStorageError::ConversionError { reason })?;
+    let message_type = u8::try_from(row.message_type)
+        .map_err(|err| err.to_string())
+        .and_then(HotStuffMessageType::try_from)
+        .map_err(|reason| SqliteStorageError::ConversionError { reason })?;
+    let offender =
+        PublicKey::from_bytes(&row.offender).map_err(|err| SqliteStorageError::MalformedDbData(err.to_string()))?;
+    let view_number = ViewId::from(row.view_number as u64);
+    Ok(MisbehaviourEvidence {
+        contract_id: FixedHash::try_from(row.contract_id)?,
+        kind,
+        first: SignedConsensusMessage {
+            message_type,
+            view_number,
+            node_hash: row.first_node_hash.try_into()?,
+            signature: ValidatorSignature::new(offender.clone(), signature_from_bytes(&row.first_signature)?),
+        },
+        second: SignedConsensusMessage {
+            message_type,
+            view_number,
+            node_hash: row.second_node_hash.try_into()?,
+            signature: ValidatorSignature::new(offender, signature_from_bytes(&row.second_signature)?),
+        },
+    })
+}
+
+fn signatures_to_bytes(signatures: &QuorumSignatures) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(signatures.len() * SIGNATURE_SIZE);
+    for signature in signatures.signatures() {
+        bytes.extend(signature_to_bytes(signature));
+    }
+    bytes
+}
+
+fn signatures_from_db(
+    signer_bitmap: Option<Vec<u8>>,
+    signatures: Option<Vec<u8>>,
+) -> Result<QuorumSignatures, SqliteStorageError> {
+    let signatures = signatures.unwrap_or_default();
+    if signatures.len() % SIGNATURE_SIZE != 0 {
+        return Err(SqliteStorageError::MalformedDbData(format!(
+            "quorum certificate signatures have invalid length {}",
+            signatures.len()
+        )));
+    }
+    let signatures = signatures
+        .chunks_exact(SIGNATURE_SIZE)
+        .map(signature_from_bytes)
+        .collect::<Result<_, SqliteStorageError>>()?;
+    Ok(QuorumSignatures::try_new(
+        signer_bitmap.unwrap_or_default(),
+        signatures,
+    )?)
+}
@@ -20,9 +20,9 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::{fs::create_dir_all, path::PathBuf};
+use std::{convert::TryFrom, fs::create_dir_all, path::PathBuf};
 
-use diesel::{Connection, ConnectionError, SqliteConnection};
+use diesel::{Connection, ConnectionError, RunQueryDsl, SqliteConnection};
 use diesel_migrations::embed_migrations;
 use log::*;
 use tari_common_types::types::FixedHash;
@@ -37,6 +37,12 @@ use crate::{
     SqliteChainBackendAdapter,
 };
 
+#[derive(QueryableByName)]
+struct FreeSpace {
+    #[sql_type = "diesel::sql_types::BigInt"]
+    free_bytes: i64,
+}
+
 #[derive(Clone)]
 pub struct SqliteDbFactory {
     data_dir: PathBuf,
@@ -65,6 +71,24 @@ impl SqliteDbFactory {
             .expect("Should not fail")
     }
 
+    /// Returns the number of bytes in the contract database that are free to be reused, or None if the database does
+    /// not exist
+    pub fn get_free_space(&self, contract_id: &FixedHash) -> Result<Option<u64>, StorageError> {
+        let connection = match self.try_connect(&self.database_url_for(contract_id))? {
+            Some(connection) => connection,
+            None => return Ok(None),
+        };
+        let free_space: FreeSpace = diesel::sql_query(
+            "SELECT freelist_count * page_size AS free_bytes FROM pragma_freelist_count(), pragma_page_size()",
+        )
+        .get_result(&connection)
+        .map_err(|source| SqliteStorageError::DieselError {
+            source,
+            operation: "get_free_space".to_string(),
+        })?;
+        Ok(Some(u64::try_from(free_space.free_bytes).unwrap_or(0)))
+    }
+
     fn try_connect(&self, url: &str) -> Result<Option<SqliteConnection>, StorageError> {
         match SqliteConnection::establish(url) {
             Ok(connection) => {
@@ -150,10 +174,14 @@ impl DbFactory for SqliteDbFactory {
             })?;
         embed_migrations!("./migrations");
         embedded_migrations::run(&connection).map_err(SqliteStorageError::from)?;
-        Ok(StateDb::new(
-            *contract_id,
-            SqliteStateDbBackendAdapter::new(database_url),
-        ))
+        let state_db = StateDb::new(*contract_id, SqliteStateDbBackendAdapter::new(database_url));
+        // Databases created before the state tree was stored need their tree built from the existing state
+        state_db
+            .rebuild_state_tree_if_missing()
+            .map_err(|err| StorageError::General {
+                details: err.to_string(),
+            })?;
+        Ok(state_db)
     }
 
     fn get_or_create_global_db(&self) -> Result<GlobalDb<Self::GlobalDbBackendAdapter>, StorageError> {
@@ -20,11 +20,18 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 
-use diesel::{prelude::*, Connection, SqliteConnection};
+use diesel::{prelude::*, sql_types::BigInt, Connection, SqliteConnection};
 use log::*;
-use tari_dan_engine::state::{DbKeyValue, DbStateOpLogEntry, StateDbBackendAdapter};
+use tari_common_types::types::FixedHash;
+use tari_dan_engine::state::{
+    models::StateRoot,
+    sparse_merkle_tree::TreeNode,
+    DbKeyValue,
+    DbStateOpLogEntry,
+    StateDbBackendAdapter,
+};
 
 use crate::{
     error::SqliteStorageError,
@@ -112,6 +119,17 @@ impl StateDbBackendAdapter for SqliteStateDbBackendAdapter {
         Ok(())
     }
 
+    fn delete_key_value(&self, schema: &str, key: &[u8], tx: &Self::BackendTransaction) -> Result<(), Self::Error> {
+        use crate::schema::state_keys::dsl;
+        diesel::delete(dsl::state_keys.find((schema, key)))
+            .execute(tx.connection())
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "delete::state_key".to_string(),
+            })?;
+        Ok(())
+    }
+
     fn get(&self, schema: &str, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
         use crate::schema::state_keys::dsl;
         let connection = SqliteConnection::establish(self.database_url.as_str())?;
@@ -126,6 +144,49 @@ impl StateDbBackendAdapter for SqliteStateDbBackendAdapter {
         Ok(row.map(|r| r.value))
     }
 
+    fn get_committed_value(
+        &self,
+        schema: &str,
+        key: &[u8],
+        tx: &Self::BackendTransaction,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        use crate::schema::state_keys::dsl;
+        let row: Option<StateKey> = dsl::state_keys
+            .find((schema, key))
+            .first(tx.connection())
+            .optional()
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_committed_value::state_key".to_string(),
+            })?;
+        Ok(row.map(|r| r.value))
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn get_value_at(&self, schema: &str, key: &[u8], height: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        use crate::schema::state_op_log::dsl;
+        let connection = SqliteConnection::establish(self.database_url.as_str())?;
+        let retained_from_height = get_retained_from_height(&connection)?;
+        if height < retained_from_height {
+            return Err(SqliteStorageError::StatePruned {
+                height,
+                retained_from_height,
+            });
+        }
+        let entry: Option<StateOpLogEntry> = dsl::state_op_log
+            .filter(dsl::schema.eq(schema))
+            .filter(dsl::key.eq(key))
+            .filter(dsl::height.le(height as i64))
+            .order_by((dsl::height.desc(), dsl::id.desc()))
+            .first(&connection)
+            .optional()
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_value_at::state_op_log".to_string(),
+            })?;
+        Ok(entry.and_then(|e| e.value))
+    }
+
     fn find_keys_by_value(&self, schema: &str, value: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
         use crate::schema::state_keys::dsl;
         let connection = SqliteConnection::establish(self.database_url.as_str())?;
@@ -187,6 +248,36 @@ impl StateDbBackendAdapter for SqliteStateDbBackendAdapter {
             .collect())
     }
 
+    fn get_values_in_range(
+        &self,
+        schema: &str,
+        start: &[u8],
+        end: Option<&[u8]>,
+        tx: &Self::BackendTransaction,
+    ) -> Result<Vec<DbKeyValue>, Self::Error> {
+        use crate::schema::state_keys::dsl;
+        let mut query = dsl::state_keys
+            .filter(state_keys::schema_name.eq(schema))
+            .filter(state_keys::key_name.ge(start))
+            .into_boxed();
+        if let Some(end) = end {
+            query = query.filter(state_keys::key_name.lt(end));
+        }
+        let values: Vec<(String, Vec<u8>, Vec<u8>)> = query
+            .select((state_keys::schema_name, state_keys::key_name, state_keys::value))
+            .order_by(state_keys::key_name.asc())
+            .load(tx.connection())
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_values_in_range".to_string(),
+            })?;
+
+        Ok(values
+            .into_iter()
+            .map(|(schema, key, value)| DbKeyValue { schema, key, value })
+            .collect())
+    }
+
     #[allow(clippy::cast_sign_loss)]
     #[allow(clippy::cast_possible_wrap)]
     fn get_state_op_logs_by_height(
@@ -239,6 +330,423 @@ impl StateDbBackendAdapter for SqliteStateDbBackendAdapter {
                 operation: "clear_all_state::state_op_logs".to_string(),
             })?;
 
+        diesel::delete(state_tree_nodes::dsl::state_tree_nodes)
+            .execute(tx.connection())
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "clear_all_state::state_tree_nodes".to_string(),
+            })?;
+
+        diesel::delete(state_pruning_horizon::table)
+            .execute(tx.connection())
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "clear_all_state::state_pruning_horizon".to_string(),
+            })?;
+
         Ok(())
     }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn get_current_height(&self, tx: &Self::BackendTransaction) -> Result<u64, Self::Error> {
+        use crate::schema::state_op_log::dsl;
+        let height: Option<i64> = dsl::state_op_log
+            .select(diesel::dsl::max(dsl::height))
+            .first(tx.connection())
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_current_height".to_string(),
+            })?;
+
+        Ok(height.map(|h| h as u64).unwrap_or(0))
+    }
+
+    fn get_current_state_root(&self, tx: &Self::BackendTransaction) -> Result<StateRoot, Self::Error> {
+        use crate::schema::state_op_log::dsl;
+        let root: Option<Option<Vec<u8>>> = dsl::state_op_log
+            .select(dsl::merkle_root)
+            .filter(dsl::merkle_root.is_not_null())
+            .order_by(dsl::id.desc())
+            .first(tx.connection())
+            .optional()
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_current_state_root".to_string(),
+            })?;
+
+        match root.flatten() {
+            Some(root) => Ok(StateRoot::new(
+                FixedHash::try_from(root).map_err(|_| SqliteStorageError::MalformedHashData)?,
+            )),
+            None => Ok(StateRoot::initial()),
+        }
+    }
+
+    fn get_tree_node(&self, hash: &FixedHash, tx: &Self::BackendTransaction) -> Result<Option<TreeNode>, Self::Error> {
+        use crate::schema::state_tree_nodes::dsl;
+        let node: Option<Vec<u8>> = dsl::state_tree_nodes
+            .select(dsl::node)
+            .find(hash.as_slice())
+            .first(tx.connection())
+            .optional()
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_tree_node".to_string(),
+            })?;
+
+        node.map(|node| {
+            TreeNode::from_bytes(&node)
+                .ok_or_else(|| SqliteStorageError::MalformedDbData("Invalid state tree node".to_string()))
+        })
+        .transpose()
+    }
+
+    fn insert_tree_node(
+        &self,
+        hash: &FixedHash,
+        node: &TreeNode,
+        tx: &Self::BackendTransaction,
+    ) -> Result<(), Self::Error> {
+        use crate::schema::state_tree_nodes::dsl;
+        // Nodes are content-addressed, so an existing node with the same hash is identical
+        diesel::insert_or_ignore_into(dsl::state_tree_nodes)
+            .values((dsl::hash.eq(hash.as_slice()), dsl::node.eq(node.to_bytes())))
+            .execute(tx.connection())
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "insert_tree_node".to_string(),
+            })?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn get_state_roots_from_height(
+        &self,
+        height: u64,
+        tx: &Self::BackendTransaction,
+    ) -> Result<Vec<StateRoot>, Self::Error> {
+        use crate::schema::state_op_log::dsl;
+        let mut roots: Vec<Option<Vec<u8>>> = dsl::state_op_log
+            .select(dsl::merkle_root)
+            .filter(dsl::merkle_root.is_not_null())
+            .filter(dsl::height.ge(height as i64))
+            .distinct()
+            .load(tx.connection())
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_state_roots_from_height".to_string(),
+            })?;
+        let last_root_below: Option<Option<Vec<u8>>> = dsl::state_op_log
+            .select(dsl::merkle_root)
+            .filter(dsl::merkle_root.is_not_null())
+            .filter(dsl::height.lt(height as i64))
+            .order_by(dsl::id.desc())
+            .first(tx.connection())
+            .optional()
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_state_roots_from_height".to_string(),
+            })?;
+        roots.extend(last_root_below);
+
+        roots
+            .into_iter()
+            .flatten()
+            .map(|root| {
+                FixedHash::try_from(root)
+                    .map(StateRoot::new)
+                    .map_err(|_| SqliteStorageError::MalformedHashData)
+            })
+            .collect()
+    }
+
+    fn get_tree_node_hashes(&self, tx: &Self::BackendTransaction) -> Result<Vec<FixedHash>, Self::Error> {
+        use crate::schema::state_tree_nodes::dsl;
+        let hashes: Vec<Vec<u8>> = dsl::state_tree_nodes
+            .select(dsl::hash)
+            .load(tx.connection())
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "get_tree_node_hashes".to_string(),
+            })?;
+
+        hashes
+            .into_iter()
+            .map(|hash| FixedHash::try_from(hash).map_err(|_| SqliteStorageError::MalformedHashData))
+            .collect()
+    }
+
+    fn delete_tree_nodes(&self, hashes: &[FixedHash], tx: &Self::BackendTransaction) -> Result<(), Self::Error> {
+        use crate::schema::state_tree_nodes::dsl;
+        diesel::delete(dsl::state_tree_nodes.filter(dsl::hash.eq_any(hashes.iter().map(|h| h.as_slice()))))
+            .execute(tx.connection())
+            .map_err(|source| SqliteStorageError::DieselError {
+                source,
+                operation: "delete_tree_nodes".to_string(),
+            })?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn delete_state_op_logs_below(&self, height: u64, tx: &Self::BackendTransaction) -> Result<u64, Self::Error> {
+        // The latest entry of each key below the height is kept, so that the key can still be read as of the height
+        let num_deleted = diesel::sql_query(
+            "DELETE FROM state_op_log WHERE height < ? AND id NOT IN (SELECT MAX(id) FROM state_op_log WHERE height < \
+             ? GROUP BY schema, key)",
+        )
+        .bind::<BigInt, _>(height as i64)
+        .bind::<BigInt, _>(height as i64)
+        .execute(tx.connection())
+        .map_err(|source| SqliteStorageError::DieselError {
+            source,
+            operation: "delete_state_op_logs_below".to_string(),
+        })?;
+
+        if height > get_retained_from_height(tx.connection())? {
+            set_retained_from_height(height, tx.connection())?;
+        }
+
+        Ok(num_deleted as u64)
+    }
+}
+
+/// Returns the lowest height that state can be read at, which is 0 until the state has been pruned
+#[allow(clippy::cast_sign_loss)]
+fn get_retained_from_height(connection: &SqliteConnection) -> Result<u64, SqliteStorageError> {
+    use crate::schema::state_pruning_horizon::dsl;
+    let height: Option<i64> = dsl::state_pruning_horizon
+        .find(1)
+        .select(dsl::retained_from_height)
+        .first(connection)
+        .optional()
+        .map_err(|source| SqliteStorageError::DieselError {
+            source,
+            operation: "get_retained_from_height".to_string(),
+        })?;
+    Ok(height.map(|h| h as u64).unwrap_or(0))
+}
+
+#[allow(clippy::cast_possible_wrap)]
+fn set_retained_from_height(height: u64, connection: &SqliteConnection) -> Result<(), SqliteStorageError> {
+    use crate::schema::state_pruning_horizon::dsl;
+    diesel::replace_into(state_pruning_horizon::table)
+        .values((dsl::id.eq(1), dsl::retained_from_height.eq(height as i64)))
+        .execute(connection)
+        .map_err(|source| SqliteStorageError::DieselError {
+            source,
+            operation: "set_retained_from_height".to_string(),
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel_migrations::embed_migrations;
+    use tari_dan_engine::state::{
+        error::StateExportError,
+        DbStateOperation,
+        StateDb,
+        StateDbUnitOfWork,
+        StateDbUnitOfWorkReader,
+        StateExportWriter,
+    };
+    use tempfile::{tempdir, TempDir};
+
+    use super::*;
+
+    fn create_adapter() -> (TempDir, SqliteStateDbBackendAdapter) {
+        let temp_dir = tempdir().unwrap();
+        let database_url = temp_dir.path().join("state.sqlite").to_str().unwrap().to_string();
+        let connection = SqliteConnection::establish(&database_url).unwrap();
+        embed_migrations!("./migrations");
+        embedded_migrations::run(&connection).unwrap();
+        (temp_dir, SqliteStateDbBackendAdapter::new(database_url))
+    }
+
+    fn op_log_entry(height: u64, operation: DbStateOperation, value: Option<Vec<u8>>) -> DbStateOpLogEntry {
+        DbStateOpLogEntry {
+            height,
+            merkle_root: None,
+            operation,
+            schema: "test".to_string(),
+            key: b"key".to_vec(),
+            value,
+        }
+    }
+
+    #[test]
+    fn get_value_at_reads_the_value_as_of_the_height() {
+        let (_temp_dir, adapter) = create_adapter();
+        let tx = adapter.create_transaction().unwrap();
+        adapter
+            .add_state_oplog_entry(op_log_entry(1, DbStateOperation::Set, Some(vec![1])), &tx)
+            .unwrap();
+        adapter
+            .add_state_oplog_entry(op_log_entry(3, DbStateOperation::Set, Some(vec![3])), &tx)
+            .unwrap();
+        adapter
+            .add_state_oplog_entry(op_log_entry(5, DbStateOperation::Delete, None), &tx)
+            .unwrap();
+        adapter.commit(&tx).unwrap();
+
+        assert_eq!(adapter.get_value_at("test", b"key", 0).unwrap(), None);
+        assert_eq!(adapter.get_value_at("test", b"key", 1).unwrap(), Some(vec![1]));
+        assert_eq!(adapter.get_value_at("test", b"key", 2).unwrap(), Some(vec![1]));
+        assert_eq!(adapter.get_value_at("test", b"key", 4).unwrap(), Some(vec![3]));
+        assert_eq!(adapter.get_value_at("test", b"key", 5).unwrap(), None);
+        assert_eq!(adapter.get_value_at("test", b"other", 4).unwrap(), None);
+    }
+
+    #[test]
+    fn get_value_at_rejects_heights_below_the_pruning_horizon() {
+        let (_temp_dir, adapter) = create_adapter();
+        let tx = adapter.create_transaction().unwrap();
+        adapter
+            .add_state_oplog_entry(op_log_entry(1, DbStateOperation::Set, Some(vec![1])), &tx)
+            .unwrap();
+        adapter
+            .add_state_oplog_entry(op_log_entry(2, DbStateOperation::Set, Some(vec![2])), &tx)
+            .unwrap();
+        adapter.commit(&tx).unwrap();
+
+        let tx = adapter.create_transaction().unwrap();
+        assert_eq!(adapter.delete_state_op_logs_below(3, &tx).unwrap(), 1);
+        adapter.commit(&tx).unwrap();
+
+        assert_eq!(adapter.get_value_at("test", b"key", 3).unwrap(), Some(vec![2]));
+        assert!(matches!(
+            adapter.get_value_at("test", b"key", 1),
+            Err(SqliteStorageError::StatePruned {
+                height: 1,
+                retained_from_height: 3
+            })
+        ));
+
+        // Pruning to a lower height does not move the horizon back
+        let tx = adapter.create_transaction().unwrap();
+        adapter.delete_state_op_logs_below(2, &tx).unwrap();
+        adapter.commit(&tx).unwrap();
+        assert!(adapter.get_value_at("test", b"key", 2).is_err());
+    }
+
+    #[test]
+    fn get_values_in_range_only_reads_keys_in_the_range() {
+        let (_temp_dir, adapter) = create_adapter();
+        let tx = adapter.create_transaction().unwrap();
+        for key in [&b"a"[..], b"b", b"b\x00", b"c", b"d"] {
+            adapter.update_key_value("s", key, key, &tx).unwrap();
+        }
+        adapter.update_key_value("t", b"b", b"t", &tx).unwrap();
+        adapter.commit(&tx).unwrap();
+
+        let tx = adapter.create_transaction().unwrap();
+        let keys = |values: Vec<DbKeyValue>| values.into_iter().map(|kv| kv.key).collect::<Vec<_>>();
+        assert_eq!(
+            keys(adapter.get_values_in_range("s", b"b", Some(b"d"), &tx).unwrap()),
+            vec![b"b".to_vec(), b"b\x00".to_vec(), b"c".to_vec()]
+        );
+        assert_eq!(keys(adapter.get_values_in_range("s", b"c", None, &tx).unwrap()), vec![
+            b"c".to_vec(),
+            b"d".to_vec()
+        ]);
+        assert_eq!(keys(adapter.get_values_in_range("t", b"a", None, &tx).unwrap()), vec![
+            b"b".to_vec()
+        ]);
+    }
+
+    fn create_state_db(contract_id: FixedHash) -> (TempDir, StateDb<SqliteStateDbBackendAdapter>) {
+        let (temp_dir, adapter) = create_adapter();
+        (temp_dir, StateDb::new(contract_id, adapter))
+    }
+
+    #[test]
+    fn export_then_import_restores_an_identical_state_root() {
+        let contract_id = FixedHash::try_from(&[1u8; 32][..]).unwrap();
+        let (_source_dir, source) = create_state_db(contract_id);
+        let mut uow = source.new_unit_of_work(1);
+        uow.set_value("s".to_string(), b"a".to_vec(), b"1".to_vec()).unwrap();
+        uow.set_value("s".to_string(), b"b".to_vec(), b"2".to_vec()).unwrap();
+        uow.commit().unwrap();
+        let mut uow = source.new_unit_of_work(2);
+        uow.set_value("t".to_string(), b"c".to_vec(), b"3".to_vec()).unwrap();
+        uow.delete_value("s", b"a").unwrap();
+        uow.commit().unwrap();
+
+        let mut export = Vec::new();
+        let exported = source.export_state(&mut export).unwrap();
+        assert_eq!(exported.height, 2);
+        assert_eq!(exported.state_root, source.reader().calculate_root().unwrap());
+
+        let (_destination_dir, destination) = create_state_db(contract_id);
+        let imported = destination.import_state(export.as_slice()).unwrap();
+        assert_eq!(imported, exported);
+
+        let reader = destination.reader();
+        assert_eq!(reader.calculate_root().unwrap(), exported.state_root);
+        assert_eq!(destination.get_current_height().unwrap(), 2);
+        assert_eq!(reader.get_value("s", b"a").unwrap(), None);
+        assert_eq!(reader.get_value("s", b"b").unwrap(), Some(b"2".to_vec()));
+        assert_eq!(reader.get_value("t", b"c").unwrap(), Some(b"3".to_vec()));
+        // The imported tree nodes are usable for proofs against the imported root
+        let proof = reader.get_committed_value_proof("t", b"c").unwrap();
+        assert_eq!(proof.state_root(), &exported.state_root);
+        assert!(proof.verify(&exported.state_root, "t", b"c"));
+    }
+
+    #[test]
+    fn import_resets_the_pruning_horizon_to_the_imported_height() {
+        let contract_id = FixedHash::try_from(&[1u8; 32][..]).unwrap();
+        let (_source_dir, source) = create_state_db(contract_id);
+        let mut uow = source.new_unit_of_work(2);
+        uow.set_value("s".to_string(), b"a".to_vec(), b"1".to_vec()).unwrap();
+        uow.commit().unwrap();
+        let mut export = Vec::new();
+        source.export_state(&mut export).unwrap();
+
+        // The destination was pruned above the height of the export
+        let (_destination_dir, adapter) = create_adapter();
+        let destination = StateDb::new(contract_id, adapter.clone());
+        let mut uow = destination.new_unit_of_work(5);
+        uow.set_value("s".to_string(), b"b".to_vec(), b"2".to_vec()).unwrap();
+        uow.commit().unwrap();
+        destination.prune(5).unwrap();
+
+        destination.import_state(export.as_slice()).unwrap();
+        assert_eq!(adapter.get_value_at("s", b"a", 2).unwrap(), Some(b"1".to_vec()));
+        assert_eq!(adapter.get_value_at("s", b"b", 5).unwrap(), None);
+        assert!(matches!(
+            adapter.get_value_at("s", b"a", 1),
+            Err(SqliteStorageError::StatePruned {
+                height: 1,
+                retained_from_height: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn import_with_a_state_root_mismatch_leaves_the_state_unchanged() {
+        let contract_id = FixedHash::try_from(&[1u8; 32][..]).unwrap();
+        let (_temp_dir, state_db) = create_state_db(contract_id);
+        let mut uow = state_db.new_unit_of_work(1);
+        uow.set_value("s".to_string(), b"a".to_vec(), b"1".to_vec()).unwrap();
+        uow.commit().unwrap();
+        let root = state_db.reader().calculate_root().unwrap();
+
+        // An export whose records do not hash to the state root in its header
+        let mut header = state_db.export_state(Vec::new()).unwrap();
+        header.state_root = StateRoot::new(FixedHash::try_from(&[2u8; 32][..]).unwrap());
+        let mut export = StateExportWriter::new(Vec::new(), &header).unwrap();
+        export.write_record("s", b"x", b"forged").unwrap();
+        let export = export.finish().unwrap();
+
+        let err = state_db.import_state(export.as_slice()).unwrap_err();
+        assert!(matches!(err, StateExportError::StateRootMismatch { .. }));
+
+        let reader = state_db.reader();
+        assert_eq!(reader.calculate_root().unwrap(), root);
+        assert_eq!(reader.get_value("s", b"a").unwrap(), Some(b"1".to_vec()));
+        assert_eq!(reader.get_value("s", b"x").unwrap(), None);
+    }
 }
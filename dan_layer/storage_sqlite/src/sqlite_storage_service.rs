@@ -22,7 +22,7 @@
 
 use async_trait::async_trait;
 use tari_dan_core::{
-    models::{HotStuffTreeNode, SidechainMetadata, TariDanPayload},
+    models::{HotStuffTreeNode, SideChainBlock, SidechainMetadata, TariDanPayload},
     storage::{chain::ChainDbUnitOfWork, ChainStorageService, StorageError},
 };
 
@@ -48,4 +48,9 @@ impl ChainStorageService<TariDanPayload> for SqliteStorageService {
         db.add_node(*node.hash(), *node.parent(), node.height())?;
         Ok(())
     }
+
+    fn payload_from_block(&self, block: SideChainBlock) -> TariDanPayload {
+        let (_, instructions) = block.destruct();
+        TariDanPayload::new(instructions, None)
+    }
 }
@@ -26,6 +26,18 @@
 //!
 //! This library provides types and encoding that allow low-level communication between the Tari WASM runtime and the
 //! WASM modules.
+//!
+//! ## ABI compatibility policy
+//!
+//! Every template embeds the [ABI_VERSION] it was compiled against in the [TemplateDef] returned from its `_abi`
+//! export. The engine reads this version before decoding anything else and refuses to load templates whose version
+//! falls outside of the range it supports.
+//!
+//! - `abi_version` is always the first field of [TemplateDef], encoded as a little-endian `u16`. This never changes.
+//! - [ABI_VERSION] is incremented for any change to the encoding of [TemplateDef], [CallInfo] or the engine operation
+//!   arguments. Additive changes that older engines would mis-decode are breaking changes.
+//! - An engine supports a contiguous range of versions, ending at the version of the `tari_template_abi` crate it is
+//!   built with. Support for older versions is dropped by raising the minimum of that range.
 
 mod abi;
 pub use abi::*;
@@ -26,8 +26,15 @@ use crate::{
     Encode,
 };
 
+/// The version of the template ABI implemented by this crate. See the crate documentation for the compatibility
+/// policy.
+pub const ABI_VERSION: u16 = 2;
+
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct TemplateDef {
+    /// The ABI version the template was compiled against. This MUST remain the first field so that the engine is able
+    /// to read it from any template, regardless of how the rest of the definition is encoded.
+    pub abi_version: u16,
     pub template_name: String,
     pub functions: Vec<FunctionDef>,
 }
@@ -43,6 +50,27 @@ pub struct FunctionDef {
     pub name: String,
     pub arguments: Vec<Type>,
     pub output: Type,
+    pub receiver: ReceiverKind,
+    /// True if the function is annotated with `#[view]` and so is guaranteed never to change state
+    pub is_view: bool,
+}
+
+impl FunctionDef {
+    /// Returns true if calling this function may change component state
+    pub fn is_mutable(&self) -> bool {
+        self.receiver == ReceiverKind::Mut
+    }
+}
+
+/// The kind of `self` receiver taken by a template function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+pub enum ReceiverKind {
+    /// A function without a receiver (e.g. a constructor)
+    None,
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    Mut,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
@@ -60,6 +88,8 @@ pub enum Type {
     U64,
     U128,
     String,
+    /// The id of a component. This is also the type of a `self` receiver and of a constructor's return value.
+    ComponentId,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
@@ -68,3 +98,21 @@ pub struct CallInfo {
     pub args: Vec<Vec<u8>>,
     pub abi_context: Vec<u8>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode};
+
+    #[test]
+    fn abi_version_is_encoded_first() {
+        let template = TemplateDef {
+            abi_version: ABI_VERSION,
+            template_name: "Test".into(),
+            functions: vec![],
+        };
+        let encoded = encode(&template).unwrap();
+        assert_eq!(encoded[..2], ABI_VERSION.to_le_bytes());
+        assert_eq!(decode::<u16>(&encoded).unwrap(), ABI_VERSION);
+    }
+}
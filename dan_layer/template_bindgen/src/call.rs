@@ -0,0 +1,95 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{io, marker::PhantomData};
+
+use tari_common_types::types::PrivateKey;
+use tari_dan_engine::{
+    instruction::{Instruction, InstructionBuilder, InstructionError, InstructionProcessor},
+    runtime::RuntimeInterface,
+    wasm::ExecutionResult,
+};
+use tari_template_abi::Decode;
+
+/// An instruction that calls a template function, along with the type that the function returns
+#[derive(Debug, Clone)]
+pub struct TemplateCall<T> {
+    instruction: Instruction,
+    _return_type: PhantomData<T>,
+}
+
+impl<T: Decode> TemplateCall<T> {
+    pub fn new(instruction: Instruction) -> Self {
+        Self {
+            instruction,
+            _return_type: PhantomData,
+        }
+    }
+
+    pub fn instruction(&self) -> &Instruction {
+        &self.instruction
+    }
+
+    pub fn into_instruction(self) -> Instruction {
+        self.instruction
+    }
+
+    /// Decodes the result of executing this call
+    pub fn decode_result(&self, result: &ExecutionResult) -> io::Result<T> {
+        result.decode()
+    }
+
+    /// Signs and executes this call on its own, returning the decoded result
+    pub fn execute<TRuntimeInterface>(
+        self,
+        processor: &InstructionProcessor<TRuntimeInterface>,
+        secret_key: &PrivateKey,
+    ) -> Result<T, TemplateCallError>
+    where
+        TRuntimeInterface: RuntimeInterface + Clone + 'static,
+    {
+        let instruction_set = InstructionBuilder::new()
+            .add_instruction(self.instruction)
+            .sign(secret_key)
+            .build();
+        let results = processor.execute(instruction_set)?;
+        let result = results.first().ok_or(TemplateCallError::NoResult)?;
+        let decoded = result.decode().map_err(TemplateCallError::DecodeError)?;
+        Ok(decoded)
+    }
+}
+
+impl<T> From<TemplateCall<T>> for Instruction {
+    fn from(call: TemplateCall<T>) -> Self {
+        call.instruction
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateCallError {
+    #[error(transparent)]
+    InstructionError(#[from] InstructionError),
+    #[error("The instruction did not return a result")]
+    NoResult,
+    #[error("Failed to decode the result: {0}")]
+    DecodeError(io::Error),
+}
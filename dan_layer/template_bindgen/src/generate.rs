@@ -0,0 +1,267 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use tari_template_abi::{FunctionDef, ReceiverKind, TemplateDef, Type};
+
+use crate::BindgenError;
+
+/// The names of the inherent methods of every generated client, which template functions cannot use
+const RESERVED_FUNCTION_NAMES: &[&str] = &["for_package", "package_id"];
+
+/// Generates a typed client for the template. The client has one method per template function, each of which builds
+/// the instruction to call the function and knows the type of its return value.
+pub fn generate_bindings(template_def: &TemplateDef) -> Result<TokenStream, BindgenError> {
+    let template_name = &template_def.template_name;
+    if let Some(function) = template_def
+        .functions
+        .iter()
+        .find(|f| RESERVED_FUNCTION_NAMES.contains(&f.name.as_str()))
+    {
+        return Err(BindgenError::ReservedFunctionName {
+            template_name: template_name.clone(),
+            function_name: function.name.clone(),
+        });
+    }
+    let client_name = format_ident!("{}Client", template_name);
+    let doc = format!("Typed client for the `{}` template", template_name);
+    let functions = template_def.functions.iter().map(generate_function);
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy)]
+        pub struct #client_name {
+            package_id: ::tari_template_bindgen::tari_template_lib::models::PackageId,
+        }
+
+        #[allow(clippy::new_ret_no_self, clippy::too_many_arguments)]
+        impl #client_name {
+            pub const TEMPLATE_NAME: &'static str = #template_name;
+
+            pub fn for_package(package_id: ::tari_template_bindgen::tari_template_lib::models::PackageId) -> Self {
+                Self { package_id }
+            }
+
+            pub fn package_id(&self) -> ::tari_template_bindgen::tari_template_lib::models::PackageId {
+                self.package_id
+            }
+
+            #(#functions)*
+        }
+    })
+}
+
+fn generate_function(function: &FunctionDef) -> TokenStream {
+    let func_ident = format_ident!("{}", function.name);
+    let func_name = &function.name;
+    let output = generate_rust_type(&function.output);
+
+    // The first argument of a method is the component, which is passed by id
+    let is_method = function.receiver != ReceiverKind::None;
+    let skip = if is_method { 1 } else { 0 };
+    let arg_idents = (skip..function.arguments.len())
+        .map(|i| format_ident!("arg_{}", i))
+        .collect::<Vec<_>>();
+    let arg_types = function.arguments.iter().skip(skip).map(generate_rust_type);
+    let args = quote! {
+        vec![ #(::tari_template_bindgen::tari_template_abi::encode(&#arg_idents).unwrap()),* ]
+    };
+
+    let instruction = if is_method {
+        quote! {
+            ::tari_template_bindgen::tari_dan_engine::instruction::Instruction::CallMethod {
+                package_id: self.package_id,
+                component_id,
+                method: #func_name.to_string(),
+                args: #args,
+            }
+        }
+    } else {
+        quote! {
+            ::tari_template_bindgen::tari_dan_engine::instruction::Instruction::CallFunction {
+                package_id: self.package_id,
+                template: Self::TEMPLATE_NAME.to_string(),
+                function: #func_name.to_string(),
+                args: #args,
+            }
+        }
+    };
+
+    let mut params = Vec::with_capacity(arg_idents.len() + 1);
+    if is_method {
+        params.push(quote! { component_id: ::tari_template_bindgen::tari_template_lib::models::ComponentId });
+    }
+    params.extend(
+        arg_idents
+            .iter()
+            .zip(arg_types)
+            .map(|(ident, ty)| quote! { #ident: #ty }),
+    );
+
+    quote! {
+        pub fn #func_ident(&self #(, #params)*) -> ::tari_template_bindgen::TemplateCall<#output> {
+            ::tari_template_bindgen::TemplateCall::new(#instruction)
+        }
+    }
+}
+
+fn generate_rust_type(abi_type: &Type) -> TokenStream {
+    match abi_type {
+        Type::Unit => quote!(()),
+        Type::Bool => quote!(bool),
+        Type::I8 => quote!(i8),
+        Type::I16 => quote!(i16),
+        Type::I32 => quote!(i32),
+        Type::I64 => quote!(i64),
+        Type::I128 => quote!(i128),
+        Type::U8 => quote!(u8),
+        Type::U16 => quote!(u16),
+        Type::U32 => quote!(u32),
+        Type::U64 => quote!(u64),
+        Type::U128 => quote!(u128),
+        Type::String => quote!(::std::string::String),
+        Type::ComponentId => quote!(::tari_template_bindgen::tari_template_lib::models::ComponentId),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::TokenStream;
+    use quote::quote;
+    use tari_template_abi::{FunctionDef, ReceiverKind, TemplateDef, Type, ABI_VERSION};
+
+    use super::generate_bindings;
+    use crate::BindgenError;
+
+    #[test]
+    #[allow(clippy::too_many_lines)]
+    fn test_state() {
+        let template_def = TemplateDef {
+            abi_version: ABI_VERSION,
+            template_name: "State".to_string(),
+            functions: vec![
+                FunctionDef {
+                    name: "new".to_string(),
+                    arguments: vec![],
+                    output: Type::ComponentId,
+                    receiver: ReceiverKind::None,
+                    is_view: false,
+                },
+                FunctionDef {
+                    name: "get".to_string(),
+                    arguments: vec![Type::ComponentId],
+                    output: Type::U32,
+                    receiver: ReceiverKind::Ref,
+                    is_view: true,
+                },
+                FunctionDef {
+                    name: "set".to_string(),
+                    arguments: vec![Type::ComponentId, Type::U32],
+                    output: Type::Unit,
+                    receiver: ReceiverKind::Mut,
+                    is_view: false,
+                },
+            ],
+        };
+
+        let output = generate_bindings(&template_def).unwrap();
+
+        assert_code_eq(output, quote! {
+            #[doc = "Typed client for the `State` template"]
+            #[derive(Debug, Clone, Copy)]
+            pub struct StateClient {
+                package_id: ::tari_template_bindgen::tari_template_lib::models::PackageId,
+            }
+
+            #[allow(clippy::new_ret_no_self, clippy::too_many_arguments)]
+            impl StateClient {
+                pub const TEMPLATE_NAME: &'static str = "State";
+
+                pub fn for_package(package_id: ::tari_template_bindgen::tari_template_lib::models::PackageId) -> Self {
+                    Self { package_id }
+                }
+
+                pub fn package_id(&self) -> ::tari_template_bindgen::tari_template_lib::models::PackageId {
+                    self.package_id
+                }
+
+                pub fn new(&self) -> ::tari_template_bindgen::TemplateCall<::tari_template_bindgen::tari_template_lib::models::ComponentId> {
+                    ::tari_template_bindgen::TemplateCall::new(
+                        ::tari_template_bindgen::tari_dan_engine::instruction::Instruction::CallFunction {
+                            package_id: self.package_id,
+                            template: Self::TEMPLATE_NAME.to_string(),
+                            function: "new".to_string(),
+                            args: vec![],
+                        }
+                    )
+                }
+
+                pub fn get(&self, component_id: ::tari_template_bindgen::tari_template_lib::models::ComponentId) -> ::tari_template_bindgen::TemplateCall<u32> {
+                    ::tari_template_bindgen::TemplateCall::new(
+                        ::tari_template_bindgen::tari_dan_engine::instruction::Instruction::CallMethod {
+                            package_id: self.package_id,
+                            component_id,
+                            method: "get".to_string(),
+                            args: vec![],
+                        }
+                    )
+                }
+
+                pub fn set(&self, component_id: ::tari_template_bindgen::tari_template_lib::models::ComponentId, arg_1: u32) -> ::tari_template_bindgen::TemplateCall<()> {
+                    ::tari_template_bindgen::TemplateCall::new(
+                        ::tari_template_bindgen::tari_dan_engine::instruction::Instruction::CallMethod {
+                            package_id: self.package_id,
+                            component_id,
+                            method: "set".to_string(),
+                            args: vec![::tari_template_bindgen::tari_template_abi::encode(&arg_1).unwrap()],
+                        }
+                    )
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_reserved_function_name() {
+        let template_def = TemplateDef {
+            abi_version: ABI_VERSION,
+            template_name: "State".to_string(),
+            functions: vec![FunctionDef {
+                name: "package_id".to_string(),
+                arguments: vec![Type::ComponentId],
+                output: Type::U32,
+                receiver: ReceiverKind::Ref,
+                is_view: true,
+            }],
+        };
+
+        let err = generate_bindings(&template_def).unwrap_err();
+        assert!(
+            matches!(err, BindgenError::ReservedFunctionName { function_name, .. } if function_name == "package_id")
+        );
+    }
+
+    fn assert_code_eq(a: TokenStream, b: TokenStream) {
+        assert_eq!(a.to_string(), b.to_string());
+    }
+}
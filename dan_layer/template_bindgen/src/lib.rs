@@ -0,0 +1,65 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Tari template bindings generator
+//!
+//! Generates typed Rust clients for compiled templates from the `TemplateDef` embedded in the WASM. Each client has
+//! one method per template function that builds the [Instruction](tari_dan_engine::instruction::Instruction) for the
+//! call and returns a [TemplateCall] that decodes the function's return value.
+//!
+//! In a build script:
+//! ```ignore
+//! tari_template_bindgen::write_bindings("templates/state.wasm", std::env::var("OUT_DIR").unwrap()).unwrap();
+//! ```
+//! and in the crate:
+//! ```ignore
+//! tari_template_bindgen::include_template_bindings!("State");
+//!
+//! let state = StateClient::for_package(package_id);
+//! let component_id = state.new().execute(&processor, &secret_key)?;
+//! state.set(component_id, 20).execute(&processor, &secret_key)?;
+//! ```
+
+// Re-exported for use by generated code
+pub use tari_dan_engine;
+pub use tari_template_abi;
+pub use tari_template_lib;
+
+mod call;
+pub use call::{TemplateCall, TemplateCallError};
+
+mod error;
+pub use error::BindgenError;
+
+mod generate;
+pub use generate::generate_bindings;
+
+mod wasm;
+pub use wasm::{generate_bindings_from_wasm, write_bindings};
+
+/// Includes the bindings for a template generated by [write_bindings] in the build script
+#[macro_export]
+macro_rules! include_template_bindings {
+    ($template_name:literal) => {
+        include!(concat!(env!("OUT_DIR"), "/", $template_name, ".rs"));
+    };
+}
@@ -0,0 +1,48 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use proc_macro2::TokenStream;
+use tari_dan_engine::{packager::PackageModuleLoader, wasm::WasmModule};
+
+use crate::{generate::generate_bindings, BindgenError};
+
+/// Loads the compiled template and generates a typed client from its ABI
+pub fn generate_bindings_from_wasm(code: Vec<u8>) -> Result<TokenStream, BindgenError> {
+    let loaded = WasmModule::from_code(code).load_module()?;
+    generate_bindings(loaded.template_def())
+}
+
+/// Generates a typed client for the compiled template at `wasm_path` and writes it to `<TemplateName>.rs` in
+/// `out_dir`, returning the path of the written file. Intended to be called from a build script, with the bindings
+/// included using [include_template_bindings](crate::include_template_bindings).
+pub fn write_bindings<P1: AsRef<Path>, P2: AsRef<Path>>(wasm_path: P1, out_dir: P2) -> Result<PathBuf, BindgenError> {
+    let loaded = WasmModule::from_code(fs::read(wasm_path)?).load_module()?;
+    let bindings = generate_bindings(loaded.template_def())?;
+    let path = out_dir.as_ref().join(format!("{}.rs", loaded.template_name()));
+    fs::write(&path, bindings.to_string())?;
+    Ok(path)
+}
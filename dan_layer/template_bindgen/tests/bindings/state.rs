@@ -0,0 +1,85 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+// Bindings generated from the `state` test template of the engine. `test_bindings_match_generator_output` fails if
+// this file no longer matches the output of `generate_bindings_from_wasm`.
+
+#[doc = "Typed client for the `State` template"]
+#[derive(Debug, Clone, Copy)]
+pub struct StateClient {
+    package_id: ::tari_template_bindgen::tari_template_lib::models::PackageId,
+}
+
+#[allow(clippy::new_ret_no_self, clippy::too_many_arguments)]
+impl StateClient {
+    pub const TEMPLATE_NAME: &'static str = "State";
+
+    pub fn for_package(package_id: ::tari_template_bindgen::tari_template_lib::models::PackageId) -> Self {
+        Self { package_id }
+    }
+
+    pub fn package_id(&self) -> ::tari_template_bindgen::tari_template_lib::models::PackageId {
+        self.package_id
+    }
+
+    pub fn new(
+        &self,
+    ) -> ::tari_template_bindgen::TemplateCall<::tari_template_bindgen::tari_template_lib::models::ComponentId> {
+        ::tari_template_bindgen::TemplateCall::new(
+            ::tari_template_bindgen::tari_dan_engine::instruction::Instruction::CallFunction {
+                package_id: self.package_id,
+                template: Self::TEMPLATE_NAME.to_string(),
+                function: "new".to_string(),
+                args: vec![],
+            },
+        )
+    }
+
+    pub fn set(
+        &self,
+        component_id: ::tari_template_bindgen::tari_template_lib::models::ComponentId,
+        arg_1: u32,
+    ) -> ::tari_template_bindgen::TemplateCall<()> {
+        ::tari_template_bindgen::TemplateCall::new(
+            ::tari_template_bindgen::tari_dan_engine::instruction::Instruction::CallMethod {
+                package_id: self.package_id,
+                component_id,
+                method: "set".to_string(),
+                args: vec![::tari_template_bindgen::tari_template_abi::encode(&arg_1).unwrap()],
+            },
+        )
+    }
+
+    pub fn get(
+        &self,
+        component_id: ::tari_template_bindgen::tari_template_lib::models::ComponentId,
+    ) -> ::tari_template_bindgen::TemplateCall<u32> {
+        ::tari_template_bindgen::TemplateCall::new(
+            ::tari_template_bindgen::tari_dan_engine::instruction::Instruction::CallMethod {
+                package_id: self.package_id,
+                component_id,
+                method: "get".to_string(),
+                args: vec![],
+            },
+        )
+    }
+}
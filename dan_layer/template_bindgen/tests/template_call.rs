@@ -0,0 +1,73 @@
+//  Copyright 2022. The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::str::FromStr;
+
+use proc_macro2::TokenStream;
+use tari_dan_engine::{
+    crypto::create_key_pair,
+    instruction::InstructionProcessor,
+    packager::Package,
+    runtime::StateStoreRuntimeInterface,
+    state_store::memory::MemoryStateStore,
+    wasm::{compile::compile_template, WasmModule},
+};
+use tari_template_bindgen::generate_bindings_from_wasm;
+
+include!("bindings/state.rs");
+
+const STATE_TEMPLATE_PATH: &str = "../engine/tests/templates/state";
+
+#[test]
+fn test_bindings_match_generator_output() {
+    let wasm = compile_template(STATE_TEMPLATE_PATH, &[]).unwrap();
+    let generated = generate_bindings_from_wasm(wasm.code().to_vec()).unwrap();
+    let checked_in = TokenStream::from_str(include_str!("bindings/state.rs")).unwrap();
+    assert_eq!(generated.to_string(), checked_in.to_string());
+}
+
+#[test]
+fn test_execute_calls_through_generated_bindings() {
+    let wasm = compile_template(STATE_TEMPLATE_PATH, &[]).unwrap();
+    let package = build_package(wasm);
+    let processor = InstructionProcessor::new(
+        StateStoreRuntimeInterface::new(MemoryStateStore::default()),
+        package.clone(),
+    );
+    let (secret_key, _) = create_key_pair();
+
+    let client = StateClient::for_package(package.id());
+    assert_eq!(client.package_id(), package.id());
+    let component_id = client.new().execute(&processor, &secret_key).unwrap();
+    let value = client.get(component_id).execute(&processor, &secret_key).unwrap();
+    assert_eq!(value, 0);
+
+    client.set(component_id, 42).execute(&processor, &secret_key).unwrap();
+    let value = client.get(component_id).execute(&processor, &secret_key).unwrap();
+    assert_eq!(value, 42);
+}
+
+fn build_package(wasm: WasmModule) -> Package {
+    let mut builder = Package::builder();
+    builder.add_wasm_module(wasm);
+    builder.build().unwrap()
+}
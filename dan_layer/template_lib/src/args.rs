@@ -50,6 +50,14 @@ pub struct GetComponentArg {
     pub component_id: ComponentId,
 }
 
+/// Requests the components with ids from `start` (inclusive) to `end` (exclusive), or to the last component if `end` is
+/// None
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct GetComponentsInRangeArg {
+    pub start: ComponentId,
+    pub end: Option<ComponentId>,
+}
+
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct SetComponentStateArg {
     pub component_id: ComponentId,
@@ -20,13 +20,15 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::fmt;
+
 use tari_template_abi::{call_engine, decode, encode, Decode, Encode};
 
 use crate::{
-    args::{CreateComponentArg, EmitLogArg, GetComponentArg, LogLevel, SetComponentStateArg},
+    args::{CreateComponentArg, EmitLogArg, GetComponentArg, GetComponentsInRangeArg, LogLevel, SetComponentStateArg},
     context::Context,
     get_context,
-    models::{Component, ComponentId},
+    models::{Component, ComponentId, ComponentInstance},
     ops::*,
 };
 
@@ -75,8 +77,36 @@ impl TariEngine {
         decode(&component.state).expect("Failed to decode component state")
     }
 
-    pub fn set_component_state<T: Encode>(&self, component_id: ComponentId, state: T) {
-        let state = encode(&state).unwrap();
-        call_engine::<_, ()>(OP_SET_COMPONENT_STATE, &SetComponentStateArg { component_id, state });
+    /// Returns the components with ids from `start` (inclusive) to `end` (exclusive), in ascending id order. If `end`
+    /// is None, all components from `start` onwards are returned.
+    pub fn get_components_in_range(&self, start: ComponentId, end: Option<ComponentId>) -> Vec<ComponentInstance> {
+        call_engine::<_, Vec<ComponentInstance>>(OP_GET_COMPONENTS_IN_RANGE, &GetComponentsInRangeArg { start, end })
+            .expect("get_components_in_range returned no components")
+    }
+
+    /// Replaces the component state. Fails if the state cannot be encoded or the engine rejects the change, e.g.
+    /// because the component does not exist or the call is running in a read-only runtime.
+    pub fn set_component_state<T: Encode>(&self, component_id: ComponentId, state: T) -> Result<(), EngineError> {
+        let state = encode(&state).map_err(|_| EngineError::EncodingFailed)?;
+        call_engine::<_, ()>(OP_SET_COMPONENT_STATE, &SetComponentStateArg { component_id, state }).ok_or(
+            EngineError::OperationFailed {
+                operation: "set_component_state",
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    EncodingFailed,
+    OperationFailed { operation: &'static str },
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::EncodingFailed => write!(f, "Failed to encode the engine call arguments"),
+            EngineError::OperationFailed { operation } => write!(f, "The engine rejected operation '{}'", operation),
+        }
     }
 }
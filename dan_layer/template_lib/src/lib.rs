@@ -43,4 +43,4 @@ pub use context::{get_context, set_context_from_call_info};
 mod engine;
 
 #[cfg(target_arch = "wasm32")]
-pub use engine::engine;
+pub use engine::{engine, EngineError};
@@ -25,3 +25,4 @@ pub const OP_CREATE_COMPONENT: i32 = 0x01;
 pub const OP_GET_COMPONENT: i32 = 0x02;
 pub const OP_SET_COMPONENT_STATE: i32 = 0x03;
 pub const OP_RESOURCE_INVOKE: i32 = 0x04;
+pub const OP_GET_COMPONENTS_IN_RANGE: i32 = 0x05;
@@ -24,11 +24,13 @@ use syn::{
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
     token::Comma,
+    Attribute,
     Error,
     FnArg,
     Ident,
     ImplItem,
     ImplItemMethod,
+    Item,
     ItemImpl,
     ItemMod,
     ItemStruct,
@@ -36,13 +38,21 @@ use syn::{
     ReturnType,
     Signature,
     Stmt,
+    Visibility,
 };
 
+/// Reported for argument and return types of template functions that the ABI cannot describe
+pub const UNSUPPORTED_TYPE_MESSAGE: &str =
+    "template functions can only take and return bool, integers, String, ComponentId and Self";
+
 #[allow(dead_code)]
 pub struct TemplateAst {
     pub template_name: Ident,
     pub struct_section: ItemStruct,
-    pub impl_section: ItemImpl,
+    /// All inherent `impl` blocks of the template struct
+    pub impl_sections: Vec<ItemImpl>,
+    /// Every other item of the module (uses, consts, helper functions, types and trait impls), in declaration order
+    pub other_items: Vec<Item>,
 }
 
 impl Parse for TemplateAst {
@@ -56,56 +66,196 @@ impl Parse for TemplateAst {
             None => return Err(Error::new(module.ident.span(), "empty module")),
         };
 
-        // there should be two items: the "struct" and the "impl" blocks
-        if items.len() != 2 {
-            return Err(Error::new(module.ident.span(), "invalid number of module sections"));
-        }
-
-        // get the "struct" block
-        let struct_section = match &items[0] {
-            syn::Item::Struct(struct_item) => struct_item.clone(),
-            _ => return Err(Error::new(module.ident.span(), "the first section is not a 'struct'")),
-        };
+        let struct_section = select_template_struct(&module.ident, &items)?;
+        let template_name = struct_section.ident.clone();
 
-        // get the "impl" block
-        let impl_section = match &items[1] {
-            syn::Item::Impl(impl_item) => impl_item.clone(),
-            _ => return Err(Error::new(module.ident.span(), "the second section is not an 'impl'")),
-        };
+        let mut impl_sections = vec![];
+        let mut other_items = vec![];
+        for item in items {
+            match item {
+                Item::Impl(impl_item) if is_inherent_impl_of_ident(&impl_item, &template_name) => {
+                    impl_sections.push(impl_item)
+                },
+                Item::Struct(ref struct_item) if struct_item.ident == template_name => {},
+                item => other_items.push(item),
+            }
+        }
 
-        let template_name = struct_section.ident.clone();
+        validate_view_functions(&impl_sections)?;
 
         Ok(Self {
             template_name,
             struct_section,
-            impl_section,
+            impl_sections,
+            other_items,
+        })
+    }
+}
+
+/// Returns true if the attribute is the `#[template_struct]` marker that selects the template struct of the module
+pub fn is_template_struct_attribute(attr: &Attribute) -> bool {
+    attr.path.is_ident("template_struct")
+}
+
+/// Returns true if the attribute is the `#[view]` marker of a read-only template function
+pub fn is_view_attribute(attr: &Attribute) -> bool {
+    attr.path.is_ident("view")
+}
+
+/// Selects the template struct of the module. In order of precedence, this is the struct marked with
+/// `#[template_struct]`, the struct named after the module (e.g. `HelloWorld` in `mod hello_world`) or the only struct
+/// that has an inherent `impl` block. Any other case is ambiguous and is reported as a compile error.
+fn select_template_struct(module_ident: &Ident, items: &[Item]) -> Result<ItemStruct> {
+    let structs = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(struct_item) => Some(struct_item),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let marked = structs
+        .iter()
+        .copied()
+        .filter(|struct_item| struct_item.attrs.iter().any(is_template_struct_attribute))
+        .collect::<Vec<_>>();
+    let selected = match marked.as_slice() {
+        [struct_item] => *struct_item,
+        [] => {
+            let candidates = structs
+                .iter()
+                .copied()
+                .filter(|struct_item| items.iter().any(|item| is_inherent_impl_of(item, &struct_item.ident)))
+                .collect::<Vec<_>>();
+            let module_name = to_camel_case(&module_ident.to_string());
+            match candidates.iter().find(|struct_item| struct_item.ident == module_name) {
+                Some(struct_item) => *struct_item,
+                None => match candidates.as_slice() {
+                    [struct_item] => *struct_item,
+                    [] => {
+                        return Err(Error::new(
+                            module_ident.span(),
+                            "the module does not contain a 'struct' with an 'impl' block",
+                        ))
+                    },
+                    _ => {
+                        return Err(Error::new(
+                            module_ident.span(),
+                            "the module contains several structs with an 'impl' block, mark the template struct with \
+                             #[template_struct]",
+                        ))
+                    },
+                },
+            }
+        },
+        [_, second, ..] => {
+            return Err(Error::new_spanned(
+                &second.ident,
+                "only one struct can be marked with #[template_struct]",
+            ))
+        },
+    };
+
+    if !items.iter().any(|item| is_inherent_impl_of(item, &selected.ident)) {
+        return Err(Error::new_spanned(
+            &selected.ident,
+            "the template struct does not have an 'impl' block",
+        ));
+    }
+
+    let mut struct_section = selected.clone();
+    struct_section.attrs.retain(|attr| !is_template_struct_attribute(attr));
+    Ok(struct_section)
+}
+
+fn to_camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
         })
+        .collect()
+}
+
+fn validate_view_functions(impl_sections: &[ItemImpl]) -> Result<()> {
+    let methods = impl_sections
+        .iter()
+        .flat_map(|impl_section| impl_section.items.iter())
+        .filter_map(|item| match item {
+            ImplItem::Method(m) => Some(m),
+            _ => None,
+        });
+
+    for method in methods {
+        if !method.attrs.iter().any(is_view_attribute) {
+            continue;
+        }
+        let has_mut_receiver = method
+            .sig
+            .inputs
+            .iter()
+            .any(|arg| matches!(arg, FnArg::Receiver(r) if r.mutability.is_some()));
+        if has_mut_receiver {
+            return Err(Error::new_spanned(
+                &method.sig,
+                "a #[view] function cannot take '&mut self'",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn is_inherent_impl_of(item: &Item, ident: &Ident) -> bool {
+    match item {
+        Item::Impl(impl_item) => is_inherent_impl_of_ident(impl_item, ident),
+        _ => false,
+    }
+}
+
+fn is_inherent_impl_of_ident(impl_item: &ItemImpl, ident: &Ident) -> bool {
+    if impl_item.trait_.is_some() {
+        return false;
+    }
+    match impl_item.self_ty.as_ref() {
+        syn::Type::Path(type_path) => type_path.path.is_ident(ident),
+        _ => false,
     }
 }
 
 impl TemplateAst {
-    pub fn get_functions(&self) -> Vec<FunctionAst> {
-        self.impl_section
-            .items
+    /// Returns the public functions of the template, which are the only ones exposed in the ABI and dispatcher
+    pub fn get_functions(&self) -> Result<Vec<FunctionAst>> {
+        self.impl_sections
             .iter()
-            .map(Self::get_function_from_item)
+            .flat_map(|impl_section| impl_section.items.iter())
+            .filter_map(Self::get_function_from_item)
             .collect()
     }
 
-    fn get_function_from_item(item: &ImplItem) -> FunctionAst {
+    fn get_function_from_item(item: &ImplItem) -> Option<Result<FunctionAst>> {
         match item {
-            ImplItem::Method(m) => FunctionAst {
-                name: m.sig.ident.to_string(),
-                input_types: Self::get_input_types(&m.sig.inputs),
-                output_type: Self::get_output_type_token(&m.sig.output),
-                statements: Self::get_statements(m),
-                is_constructor: Self::is_constructor(&m.sig),
-            },
-            _ => todo!(),
+            ImplItem::Method(m) if matches!(m.vis, Visibility::Public(_)) => Some(Self::get_function(m)),
+            // private methods, associated constants and types are not part of the template ABI
+            _ => None,
         }
     }
 
-    fn get_input_types(inputs: &Punctuated<FnArg, Comma>) -> Vec<TypeAst> {
+    fn get_function(method: &ImplItemMethod) -> Result<FunctionAst> {
+        Ok(FunctionAst {
+            name: method.sig.ident.to_string(),
+            input_types: Self::get_input_types(&method.sig.inputs)?,
+            output_type: Self::get_output_type_token(&method.sig.output)?,
+            statements: Self::get_statements(method),
+            is_constructor: Self::is_constructor(&method.sig),
+            is_view: method.attrs.iter().any(is_view_attribute),
+        })
+    }
+
+    fn get_input_types(inputs: &Punctuated<FnArg, Comma>) -> Result<Vec<TypeAst>> {
         inputs
             .iter()
             .map(|arg| match arg {
@@ -114,28 +264,27 @@ impl TemplateAst {
                     // TODO: validate that it's indeed a reference ("&") to self
 
                     let mutability = r.mutability.is_some();
-                    TypeAst::Receiver { mutability }
+                    Ok(TypeAst::Receiver { mutability })
                 },
                 syn::FnArg::Typed(t) => Self::get_type_ast(&t.ty),
             })
             .collect()
     }
 
-    fn get_output_type_token(ast_type: &ReturnType) -> Option<TypeAst> {
+    fn get_output_type_token(ast_type: &ReturnType) -> Result<Option<TypeAst>> {
         match ast_type {
-            syn::ReturnType::Default => None, // the function does not return anything
-            syn::ReturnType::Type(_, t) => Some(Self::get_type_ast(t)),
+            syn::ReturnType::Default => Ok(None), // the function does not return anything
+            syn::ReturnType::Type(_, t) => Self::get_type_ast(t).map(Some),
         }
     }
 
-    fn get_type_ast(syn_type: &syn::Type) -> TypeAst {
+    fn get_type_ast(syn_type: &syn::Type) -> Result<TypeAst> {
         match syn_type {
             syn::Type::Path(type_path) => {
-                // TODO: handle "Self"
                 // TODO: detect more complex types
-                TypeAst::Typed(type_path.path.segments[0].ident.clone())
+                Ok(TypeAst::Typed(type_path.path.segments[0].ident.clone()))
             },
-            _ => todo!(),
+            _ => Err(Error::new_spanned(syn_type, UNSUPPORTED_TYPE_MESSAGE)),
         }
     }
 
@@ -160,6 +309,16 @@ pub struct FunctionAst {
     pub output_type: Option<TypeAst>,
     pub statements: Vec<Stmt>,
     pub is_constructor: bool,
+    pub is_view: bool,
+}
+
+impl FunctionAst {
+    /// Returns the receiver of the function, if it has one
+    pub fn receiver(&self) -> Option<&TypeAst> {
+        self.input_types
+            .first()
+            .filter(|input| matches!(input, TypeAst::Receiver { .. }))
+    }
 }
 
 pub enum TypeAst {
@@ -22,21 +22,28 @@
 
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use syn::{parse_quote, Expr, Result};
+use syn::{parse_quote, Error, Expr, Result};
 
-use crate::ast::{FunctionAst, TemplateAst, TypeAst};
+use crate::ast::{FunctionAst, TemplateAst, TypeAst, UNSUPPORTED_TYPE_MESSAGE};
 
 pub fn generate_abi(ast: &TemplateAst) -> Result<TokenStream> {
     let abi_function_name = format_ident!("{}_abi", ast.struct_section.ident);
     let template_name_as_str = ast.template_name.to_string();
-    let function_defs: Vec<Expr> = ast.get_functions().iter().map(generate_function_def).collect();
+    let function_defs = ast
+        .get_functions()?
+        .iter()
+        .map(generate_function_def)
+        .collect::<Result<Vec<_>>>()?;
 
     let output = quote! {
         #[no_mangle]
         pub extern "C" fn #abi_function_name() -> *mut u8 {
-            use ::tari_template_abi::{encode_with_len, FunctionDef, TemplateDef, Type, wrap_ptr};
+            use ::tari_template_abi::{
+                encode_with_len, FunctionDef, ReceiverKind, TemplateDef, Type, wrap_ptr, ABI_VERSION,
+            };
 
             let template = TemplateDef {
+                abi_version: ABI_VERSION,
                 template_name: #template_name_as_str.to_string(),
                 functions: vec![ #(#function_defs),* ],
             };
@@ -49,27 +56,40 @@ pub fn generate_abi(ast: &TemplateAst) -> Result<TokenStream> {
     Ok(output)
 }
 
-fn generate_function_def(f: &FunctionAst) -> Expr {
+fn generate_function_def(f: &FunctionAst) -> Result<Expr> {
     let name = f.name.clone();
 
-    let arguments: Vec<Expr> = f.input_types.iter().map(generate_abi_type).collect();
+    let arguments = f
+        .input_types
+        .iter()
+        .map(generate_abi_type)
+        .collect::<Result<Vec<_>>>()?;
 
     let output = match &f.output_type {
-        Some(type_ast) => generate_abi_type(type_ast),
+        Some(type_ast) => generate_abi_type(type_ast)?,
         None => parse_quote!(Type::Unit),
     };
 
-    parse_quote!(
+    let receiver: Expr = match f.receiver() {
+        Some(TypeAst::Receiver { mutability: true }) => parse_quote!(ReceiverKind::Mut),
+        Some(_) => parse_quote!(ReceiverKind::Ref),
+        None => parse_quote!(ReceiverKind::None),
+    };
+    let is_view = f.is_view;
+
+    Ok(parse_quote!(
         FunctionDef {
             name: #name.to_string(),
             arguments: vec![ #(#arguments),* ],
             output: #output,
+            receiver: #receiver,
+            is_view: #is_view,
         }
-    )
+    ))
 }
 
-fn generate_abi_type(rust_type: &TypeAst) -> Expr {
-    match rust_type {
+fn generate_abi_type(rust_type: &TypeAst) -> Result<Expr> {
+    let abi_type = match rust_type {
         // on "&self" we want to pass the component id
         TypeAst::Receiver { .. } => get_component_id_type(),
         // basic type
@@ -88,14 +108,17 @@ fn generate_abi_type(rust_type: &TypeAst) -> Expr {
             "u64" => parse_quote!(Type::U64),
             "u128" => parse_quote!(Type::U128),
             "String" => parse_quote!(Type::String),
+            "ComponentId" => get_component_id_type(),
             "Self" => get_component_id_type(),
-            _ => todo!(),
+            // helper types of the template cannot be described by the ABI, so callers could not encode them
+            _ => return Err(Error::new_spanned(ident, UNSUPPORTED_TYPE_MESSAGE)),
         },
-    }
+    };
+    Ok(abi_type)
 }
 
 fn get_component_id_type() -> Expr {
-    parse_quote!(Type::U32)
+    parse_quote!(Type::ComponentId)
 }
 
 #[cfg(test)]
@@ -137,35 +160,140 @@ mod tests {
         assert_code_eq(output, quote! {
             #[no_mangle]
             pub extern "C" fn Foo_abi() -> *mut u8 {
-                use ::tari_template_abi::{encode_with_len, FunctionDef, TemplateDef, Type, wrap_ptr};
+                use ::tari_template_abi::{
+                    encode_with_len, FunctionDef, ReceiverKind, TemplateDef, Type, wrap_ptr, ABI_VERSION,
+                };
 
                 let template = TemplateDef {
+
+                    abi_version: ABI_VERSION,
                     template_name: "Foo".to_string(),
                     functions: vec![
                         FunctionDef {
                             name: "no_args_function".to_string(),
                             arguments: vec![],
                             output: Type::String,
+                            receiver: ReceiverKind::None,
+                            is_view: false,
                         },
                         FunctionDef {
                             name: "some_args_function".to_string(),
                             arguments: vec![Type::I8, Type::String],
                             output: Type::U32,
+                            receiver: ReceiverKind::None,
+                            is_view: false,
                         },
                         FunctionDef {
                             name: "no_return_function".to_string(),
                             arguments: vec![],
                             output: Type::Unit,
+                            receiver: ReceiverKind::None,
+                            is_view: false,
                         },
                         FunctionDef {
                             name: "constructor".to_string(),
                             arguments: vec![],
-                            output: Type::U32,
+                            output: Type::ComponentId,
+                            receiver: ReceiverKind::None,
+                            is_view: false,
                         },
                         FunctionDef {
                             name: "method".to_string(),
-                            arguments: vec![Type::U32],
+                            arguments: vec![Type::ComponentId],
+                            output: Type::Unit,
+                            receiver: ReceiverKind::Ref,
+                            is_view: false,
+                        }
+                    ],
+                };
+
+                let buf = encode_with_len(&template);
+                wrap_ptr(buf)
+            }
+        });
+    }
+
+    #[test]
+    fn test_only_public_template_functions_are_exposed() {
+        let input = TokenStream::from_str(indoc! {"
+            mod foo {
+                use std::collections::HashMap;
+
+                const MAX_VALUE: u32 = 100;
+
+                enum Mode {
+                    On,
+                    Off,
+                }
+
+                struct Foo {}
+
+                impl Foo {
+                    pub fn first_impl_function() -> u32 {
+                        helper()
+                    }
+
+                    fn private_function() {}
+                }
+
+                impl Foo {
+                    const BAR: u32 = 1;
+
+                    pub fn second_impl_function(&self) {}
+                }
+
+                impl Default for Foo {
+                    fn default() -> Self {
+                        Self {}
+                    }
+                }
+
+                struct Args {
+                    value: u32
+                }
+
+                impl Args {
+                    pub fn args_function() {}
+                }
+
+                fn helper() -> u32 {
+                    MAX_VALUE
+                }
+            }
+        "})
+        .unwrap();
+
+        let ast = parse2::<TemplateAst>(input).unwrap();
+        assert_eq!(ast.impl_sections.len(), 2);
+        assert_eq!(ast.other_items.len(), 7);
+
+        let output = generate_abi(&ast).unwrap();
+
+        assert_code_eq(output, quote! {
+            #[no_mangle]
+            pub extern "C" fn Foo_abi() -> *mut u8 {
+                use ::tari_template_abi::{
+                    encode_with_len, FunctionDef, ReceiverKind, TemplateDef, Type, wrap_ptr, ABI_VERSION,
+                };
+
+                let template = TemplateDef {
+
+                    abi_version: ABI_VERSION,
+                    template_name: "Foo".to_string(),
+                    functions: vec![
+                        FunctionDef {
+                            name: "first_impl_function".to_string(),
+                            arguments: vec![],
+                            output: Type::U32,
+                            receiver: ReceiverKind::None,
+                            is_view: false,
+                        },
+                        FunctionDef {
+                            name: "second_impl_function".to_string(),
+                            arguments: vec![Type::ComponentId],
                             output: Type::Unit,
+                            receiver: ReceiverKind::Ref,
+                            is_view: false,
                         }
                     ],
                 };
@@ -22,25 +22,40 @@
 
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
+use syn::{ImplItem, ItemImpl};
 
-use crate::ast::TemplateAst;
+use crate::ast::{is_view_attribute, TemplateAst};
 
 pub fn generate_definition(ast: &TemplateAst) -> TokenStream {
     let template_name = format_ident!("{}", ast.struct_section.ident);
+    let template_attrs = &ast.struct_section.attrs;
     let template_fields = &ast.struct_section.fields;
     let semi_token = &ast.struct_section.semi_token;
-    let functions = &ast.impl_section.items;
+    let impl_sections = ast.impl_sections.iter().map(strip_view_attributes);
+    let other_items = &ast.other_items;
 
     quote! {
         pub mod template {
             use super::*;
 
+            #(#other_items)*
+
+            #(#template_attrs)*
             #[derive(Decode, Encode)]
             pub struct #template_name #template_fields #semi_token
 
-            impl #template_name {
-                #(#functions)*
-            }
+            #(#impl_sections)*
+        }
+    }
+}
+
+/// The `#[view]` marker is only meaningful to the template macro, so it is removed from the emitted code
+fn strip_view_attributes(impl_section: &ItemImpl) -> ItemImpl {
+    let mut impl_section = impl_section.clone();
+    for item in &mut impl_section.items {
+        if let ImplItem::Method(method) = item {
+            method.attrs.retain(|attr| !is_view_attribute(attr));
         }
     }
+    impl_section
 }
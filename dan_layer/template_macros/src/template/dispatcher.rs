@@ -28,8 +28,12 @@ use crate::ast::{FunctionAst, TemplateAst, TypeAst};
 
 pub fn generate_dispatcher(ast: &TemplateAst) -> Result<TokenStream> {
     let dispatcher_function_name = format_ident!("{}_main", ast.struct_section.ident);
-    let function_names = get_function_names(ast);
-    let function_blocks = get_function_blocks(ast);
+    let functions = ast.get_functions()?;
+    let function_names = functions.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+    let function_blocks = functions
+        .into_iter()
+        .map(|function| get_function_block(&ast.template_name, function))
+        .collect::<Vec<_>>();
 
     let output = quote! {
         #[no_mangle]
@@ -61,21 +65,6 @@ pub fn generate_dispatcher(ast: &TemplateAst) -> Result<TokenStream> {
     Ok(output)
 }
 
-fn get_function_names(ast: &TemplateAst) -> Vec<String> {
-    ast.get_functions().iter().map(|f| f.name.clone()).collect()
-}
-
-fn get_function_blocks(ast: &TemplateAst) -> Vec<Expr> {
-    let mut blocks = vec![];
-
-    for function in ast.get_functions() {
-        let block = get_function_block(&ast.template_name, function);
-        blocks.push(block);
-    }
-
-    blocks
-}
-
 fn get_function_block(template_ident: &Ident, ast: FunctionAst) -> Expr {
     let mut args: Vec<Expr> = vec![];
     let mut stmts = vec![];
@@ -138,7 +127,7 @@ fn get_function_block(template_ident: &Ident, ast: FunctionAst) -> Expr {
     // after user function invocation, update the component state
     if should_set_state {
         stmts.push(parse_quote! {
-            engine().set_component_state(component.id(), state);
+            engine().set_component_state(component.id(), state).expect("failed to set component state");
         });
     }
 
@@ -38,7 +38,7 @@ use self::{
 use crate::ast::TemplateAst;
 
 pub fn generate_template(input: TokenStream) -> Result<TokenStream> {
-    let ast = parse2::<TemplateAst>(input).unwrap();
+    let ast = parse2::<TemplateAst>(input)?;
 
     let dependencies = generate_dependencies();
     let definition = generate_definition(&ast);
@@ -65,8 +65,10 @@ mod tests {
     use indoc::indoc;
     use proc_macro2::TokenStream;
     use quote::quote;
+    use syn::parse2;
 
     use super::generate_template;
+    use crate::ast::TemplateAst;
 
     #[test]
     #[allow(clippy::too_many_lines)]
@@ -80,6 +82,7 @@ mod tests {
                     pub fn new() -> Self {
                         Self { value: 0 }
                     }
+                    #[view]
                     pub fn get(&self) -> u32 {
                         self.value
                     }
@@ -119,25 +122,35 @@ mod tests {
 
             #[no_mangle]
             pub extern "C" fn State_abi() -> *mut u8 {
-                use ::tari_template_abi::{encode_with_len, FunctionDef, TemplateDef, Type, wrap_ptr};
+                use ::tari_template_abi::{
+                    encode_with_len, FunctionDef, ReceiverKind, TemplateDef, Type, wrap_ptr, ABI_VERSION,
+                };
 
                 let template = TemplateDef {
+
+                    abi_version: ABI_VERSION,
                     template_name: "State".to_string(),
                     functions: vec![
                         FunctionDef {
                             name: "new".to_string(),
                             arguments: vec![],
-                            output: Type::U32,
+                            output: Type::ComponentId,
+                            receiver: ReceiverKind::None,
+                            is_view: false,
                         },
                         FunctionDef {
                             name: "get".to_string(),
-                            arguments: vec![Type::U32],
+                            arguments: vec![Type::ComponentId],
                             output: Type::U32,
+                            receiver: ReceiverKind::Ref,
+                            is_view: true,
                         },
                         FunctionDef {
                             name: "set".to_string(),
-                            arguments: vec![Type::U32, Type::U32],
+                            arguments: vec![Type::ComponentId, Type::U32],
                             output: Type::Unit,
+                            receiver: ReceiverKind::Mut,
+                            is_view: false,
                         }
                     ],
                 };
@@ -180,7 +193,7 @@ mod tests {
                         let arg_1 = decode::<u32>(&call_info.args[1usize]).unwrap();
                         let rtn = template::State::set(&mut state, arg_1);
                         result = encode_with_len(&rtn);
-                        engine().set_component_state(component.id(), state);
+                        engine().set_component_state(component.id(), state).expect("failed to set component state");
                     },
                     _ => panic!("invalid function name")
                 };
@@ -190,6 +203,154 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_view_function_cannot_take_mut_self() {
+        let input = TokenStream::from_str(indoc! {"
+            mod test {
+                struct State {
+                    value: u32
+                }
+                impl State {
+                    #[view]
+                    pub fn set(&mut self, value: u32) {
+                        self.value = value;
+                    }
+                }
+            }
+        "})
+        .unwrap();
+
+        let err = generate_template(input).unwrap_err();
+        assert_eq!(err.to_string(), "a #[view] function cannot take '&mut self'");
+    }
+
+    #[test]
+    fn test_helper_types_cannot_be_used_in_template_function_signatures() {
+        let argument = TokenStream::from_str(indoc! {"
+            mod test {
+                pub struct Args {
+                    value: u32
+                }
+                struct State {}
+                impl State {
+                    pub fn new(args: Args) -> Self {
+                        Self {}
+                    }
+                }
+            }
+        "})
+        .unwrap();
+        let err = generate_template(argument).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "template functions can only take and return bool, integers, String, ComponentId and Self"
+        );
+
+        let output = TokenStream::from_str(indoc! {"
+            mod test {
+                struct State {}
+                impl State {
+                    pub fn pair(&self) -> (u32, u32) {
+                        (1, 2)
+                    }
+                }
+            }
+        "})
+        .unwrap();
+        let err = generate_template(output).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "template functions can only take and return bool, integers, String, ComponentId and Self"
+        );
+    }
+
+    #[test]
+    fn test_template_struct_selection_is_not_ambiguous() {
+        let input = TokenStream::from_str(indoc! {"
+            mod test {
+                struct Helper {}
+                impl Helper {
+                    pub fn help() {}
+                }
+                struct State {}
+                impl State {
+                    pub fn new() -> Self {
+                        Self {}
+                    }
+                }
+            }
+        "})
+        .unwrap();
+
+        let err = generate_template(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the module contains several structs with an 'impl' block, mark the template struct with \
+             #[template_struct]"
+        );
+    }
+
+    #[test]
+    fn test_template_struct_selection() {
+        let marked = parse2::<TemplateAst>(
+            TokenStream::from_str(indoc! {"
+                mod test {
+                    struct Helper {}
+                    impl Helper {
+                        pub fn help() {}
+                    }
+                    #[template_struct]
+                    struct State {}
+                    impl State {
+                        pub fn new() -> Self {
+                            Self {}
+                        }
+                    }
+                }
+            "})
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(marked.template_name, "State");
+        assert!(marked.struct_section.attrs.is_empty());
+
+        let named_after_module = parse2::<TemplateAst>(
+            TokenStream::from_str(indoc! {"
+                mod my_state {
+                    struct Helper {}
+                    impl Helper {
+                        pub fn help() {}
+                    }
+                    struct MyState {}
+                    impl MyState {
+                        pub fn new() -> Self {
+                            Self {}
+                        }
+                    }
+                }
+            "})
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(named_after_module.template_name, "MyState");
+
+        let err = parse2::<TemplateAst>(
+            TokenStream::from_str(indoc! {"
+                mod test {
+                    #[template_struct]
+                    struct Helper {}
+                    #[template_struct]
+                    struct State {}
+                    impl State {}
+                }
+            "})
+            .unwrap(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.to_string(), "only one struct can be marked with #[template_struct]");
+    }
+
     fn assert_code_eq(a: TokenStream, b: TokenStream) {
         assert_eq!(a.to_string(), b.to_string());
     }